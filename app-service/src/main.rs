@@ -48,7 +48,8 @@ async fn root(headers: HeaderMap) -> impl IntoResponse {
 }
 
 async fn protected(jar: CookieJar) -> impl IntoResponse {
-    let jwt_cookie = match jar.get("jwt") {
+    let cookie_name = env::var("JWT_COOKIE_NAME").unwrap_or("jwt".to_owned());
+    let jwt_cookie = match jar.get(&cookie_name) {
         Some(cookie) => cookie,
         None => {
             return StatusCode::UNAUTHORIZED.into_response();
@@ -52,7 +52,7 @@ loginButton.addEventListener("click", (e) => {
                 if (response.status === 206) {
                         TwoFAForm.email.value = email;
                         response.json().then(data => {
-                                TwoFAForm.login_attempt_id.value = data.loginAttemptId;
+                                TwoFAForm.challenge_token.value = data.challengeToken;
                         });
 
                         loginForm.email.value = "";
@@ -130,7 +130,7 @@ TwoFAButton.addEventListener("click", (e) => {
     e.preventDefault();
 
     const email = TwoFAForm.email.value;
-    const loginAttemptId = TwoFAForm.login_attempt_id.value;
+    const challengeToken = TwoFAForm.challenge_token.value;
     const code = TwoFAForm.email_code.value;
 
     fetch('/verify-2fa', {
@@ -138,12 +138,12 @@ TwoFAButton.addEventListener("click", (e) => {
         headers: {
             'Content-Type': 'application/json',
         },
-        body: JSON.stringify({ email, loginAttemptId, code }),
+        body: JSON.stringify({ email, challengeToken, code }),
     }).then(response => {
         if (response.ok) {
             TwoFAForm.email.value = "";
             TwoFAForm.email_code.value = "";
-            TwoFAForm.login_attempt_id.value = "";
+            TwoFAForm.challenge_token.value = "";
             TwoFAErrAlter.style.display = "none";
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
@@ -151,6 +151,15 @@ TwoFAButton.addEventListener("click", (e) => {
             signupSection.style.display = "none";
         } else {
             response.json().then(data => {
+                // The pending code is gone (expired or lost), so another code can't help
+                if (data.code === "no_active_2fa_challenge") {
+                    TwoFAErrAlter.style.display = "none";
+                    alert("Your sign-in attempt has expired. Please log in again.");
+                    loginSection.style.display = "block";
+                    twoFASection.style.display = "none";
+                    signupSection.style.display = "none";
+                    return;
+                }
                 let error_msg = data.error;
                 if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
                     TwoFAErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
@@ -0,0 +1,2 @@
+-- NULL means the account is active; a timestamp marks when it was soft-deleted.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
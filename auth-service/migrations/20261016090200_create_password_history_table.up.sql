@@ -0,0 +1,9 @@
+-- Previous password hashes per user, trimmed to PASSWORD_HISTORY_DEPTH on every change.
+CREATE TABLE IF NOT EXISTS password_history (
+   id BIGSERIAL PRIMARY KEY,
+   email VARCHAR(255) NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   password_hash VARCHAR(255) NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS password_history_email_idx ON password_history (email, id DESC);
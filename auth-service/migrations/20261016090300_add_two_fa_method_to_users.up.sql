@@ -0,0 +1,2 @@
+-- 'email' or 'totp'; only meaningful when requires_2fa is set.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS two_fa_method VARCHAR(16) NOT NULL DEFAULT 'email';
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS users_last_login_at_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS last_login_at;
@@ -0,0 +1,5 @@
+-- Starts at account creation (or at this migration for existing rows) so an account that never
+-- logs in still gets the full INACTIVITY_EXPIRY_DAYS window before it is expired.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS users_last_login_at_idx ON users (last_login_at) WHERE deleted_at IS NULL;
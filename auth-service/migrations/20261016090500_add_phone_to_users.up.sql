@@ -0,0 +1,2 @@
+-- Optional, stored in E.164 form ('+' and up to 15 digits).
+ALTER TABLE users ADD COLUMN IF NOT EXISTS phone VARCHAR(16);
@@ -0,0 +1,2 @@
+-- Optional login name, stored lowercase. Unique across all rows, soft-deleted ones included.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS username VARCHAR(32) UNIQUE;
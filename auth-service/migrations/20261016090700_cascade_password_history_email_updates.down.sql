@@ -0,0 +1,3 @@
+ALTER TABLE password_history DROP CONSTRAINT IF EXISTS password_history_email_fkey;
+ALTER TABLE password_history ADD CONSTRAINT password_history_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE;
@@ -0,0 +1,2 @@
+-- Optional name the UI greets the user by; free-form and not unique, unlike username.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name VARCHAR(64);
@@ -0,0 +1,3 @@
+-- Version of the pepper each hash was made with; 0 (no pepper) for every existing hash.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pepper_version SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE password_history ADD COLUMN IF NOT EXISTS pepper_version SMALLINT NOT NULL DEFAULT 0;
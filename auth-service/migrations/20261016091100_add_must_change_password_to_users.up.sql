@@ -0,0 +1,2 @@
+-- Set by an admin's forced password reset; the next login must choose a new password.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Backstop for emails differing only in case: at most one row per lowercased address,
+-- soft-deleted ones included, like the primary key. Fails on existing case-variant duplicates,
+-- which have to be merged by hand first.
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_lower_key ON users (LOWER(email));
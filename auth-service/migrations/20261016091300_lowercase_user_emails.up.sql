@@ -0,0 +1,4 @@
+-- Emails are lowercased when parsed, so rows stored before that could no longer be looked up.
+-- `users_email_lower_key` guarantees no two rows collide once lowercased, and
+-- `password_history` follows through its ON UPDATE CASCADE foreign key.
+UPDATE users SET email = LOWER(email) WHERE email <> LOWER(email);
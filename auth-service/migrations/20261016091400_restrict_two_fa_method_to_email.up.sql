@@ -0,0 +1,3 @@
+-- Nothing can verify an authenticator app's code yet, so a TOTP account could never finish
+-- logging in. Drop this once TOTP codes are checked.
+ALTER TABLE users ADD CONSTRAINT users_two_fa_method_check CHECK (two_fa_method = 'email');
@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+
+/// Server-side check of a CAPTCHA token produced by the signup form
+#[async_trait]
+pub trait CaptchaVerifier {
+        /// `Ok` when the provider accepts `token`; `Err` describes why it was rejected
+        async fn verify(&self, token: &str) -> Result<(), String>;
+}
@@ -1,20 +1,81 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
-use crate::domain::{
-        login_attempt_id::LoginAttemptId, two_fa_code::TwoFACode, Email, HashedPassword,
+use crate::{
+        domain::{
+                login_attempt_id::LoginAttemptId, two_fa_code::TwoFACode, Email, HashedPassword,
+                Username,
+        },
+        utils::{
+                auth::Token,
+                constants::{CHECK_EMAIL_WINDOW_SECONDS, MAGIC_LINK_REQUEST_WINDOW_SECONDS},
+        },
 };
 
 use super::User;
 
 #[async_trait]
 pub trait UserStore: Send + Sync {
+        /// Fails with `UserAlreadyExists` if the email or the username is taken, including by a
+        /// soft-deleted user
         async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
+        /// Soft-deleted users are reported as `UserNotFound`
         async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
+        /// Look a user up by username; soft-deleted users are reported as `UserNotFound`
+        async fn get_user_by_username(&self, username: &Username) -> Result<User, UserStoreError>;
+        /// Like `get_user`, but also returns soft-deleted users
+        async fn get_user_including_deleted(&self, email: &Email) -> Result<User, UserStoreError>;
         async fn validate_user(
                 &self,
                 email: &Email,
                 raw_password: &str,
         ) -> Result<(), UserStoreError>;
+        /// Mark an active user as deleted without removing their row
+        async fn soft_delete(&mut self, email: &Email) -> Result<(), UserStoreError>;
+        /// Clear the deleted marker of a user, active or soft-deleted, and restart their
+        /// inactivity window
+        async fn reactivate(&mut self, email: &Email) -> Result<(), UserStoreError>;
+        /// Stamp an active user's `last_login_at` with the current time
+        async fn record_login(&mut self, email: &Email) -> Result<(), UserStoreError>;
+        /// Soft-delete up to `limit` active users whose last login is older than `cutoff`,
+        /// returning how many accounts were expired
+        async fn expire_inactive(
+                &mut self,
+                cutoff: DateTime<Utc>,
+                limit: u64,
+        ) -> Result<u64, UserStoreError>;
+        /// Replace the user's password, moving the old hash into the password history and
+        /// trimming the history to the `history_depth` most recent entries. Clears
+        /// `must_change_password`.
+        async fn update_password(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+                history_depth: usize,
+        ) -> Result<(), UserStoreError>;
+        /// Replace an active user's password with a temporary one set by an admin and require
+        /// a new one at the next login. The replaced hash is not added to the history.
+        async fn force_password_reset(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+        ) -> Result<(), UserStoreError>;
+        /// Move an active user, password history included, to a new email. Fails with
+        /// `UserAlreadyExists` if `new_email` belongs to any user, soft-deleted ones included
+        async fn update_email(
+                &mut self,
+                old_email: &Email,
+                new_email: &Email,
+        ) -> Result<(), UserStoreError>;
+        /// Previous password hashes, most recent first (the current password is not included)
+        async fn get_password_history(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<HashedPassword>, UserStoreError>;
+        /// Cheap reachability check for readiness probes; in-memory stores are always reachable
+        async fn ping(&self) -> Result<(), UserStoreError> {
+                Ok(())
+        }
 }
 
 #[derive(Debug, PartialEq)]
@@ -27,8 +88,8 @@ pub enum UserStoreError {
 
 #[async_trait]
 pub trait BannedTokenStore: Send + Sync {
-        async fn ban_token(&mut self, token: String) -> Result<(), BannedTokenStoreError>;
-        async fn is_banned(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+        async fn ban_token(&mut self, token: Token) -> Result<(), BannedTokenStoreError>;
+        async fn is_banned(&self, token: &Token) -> Result<bool, BannedTokenStoreError>;
 }
 
 #[derive(Debug, PartialEq)]
@@ -37,6 +98,8 @@ pub enum BannedTokenStoreError {
         UnexpectedError,
 }
 
+/// Pending 2FA codes, one per login attempt, so a user logging in from several devices at
+/// once gets a challenge on each
 #[async_trait]
 pub trait TwoFACodeStore: Send + Sync {
         async fn add_code(
@@ -45,17 +108,311 @@ pub trait TwoFACodeStore: Send + Sync {
                 login_attempt_id: LoginAttemptId,
                 code: TwoFACode,
         ) -> Result<(), TwoFACodeStoreError>;
-        async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
+        async fn remove_code(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<(), TwoFACodeStoreError>;
         async fn get_code(
                 &self,
                 email: &Email,
-        ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<TwoFACode, TwoFACodeStoreError>;
+        /// When the pending code for the login attempt was added, as a Unix timestamp in seconds
+        async fn get_issued_at(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<i64, TwoFACodeStoreError>;
+        /// Count a 2FA email to `email`, returning how many it has been sent within the last
+        /// `TWO_FA_EMAIL_WINDOW_SECONDS` (including this one)
+        async fn record_email_sent(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError>;
+        /// Count a wrong 2FA code for `email`, whichever login attempt it was for, returning how
+        /// many it has had within the last `TWO_FA_FAILURE_WINDOW_SECONDS` (including this one)
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError>;
+        /// Wrong 2FA codes for `email` within the current `TWO_FA_FAILURE_WINDOW_SECONDS`
+        async fn get_failures(&self, email: &Email) -> Result<u32, TwoFACodeStoreError>;
+        /// When the current `TWO_FA_FAILURE_WINDOW_SECONDS` window for `email` ends, as a Unix
+        /// timestamp in seconds, or `None` when no wrong code is being counted
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, TwoFACodeStoreError>;
+        /// Count a check of the pending code for the login attempt, returning how many checks it
+        /// has had (including this one). Adding or removing the code resets the count.
+        async fn record_attempt(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<u32, TwoFACodeStoreError>;
+        /// Codes currently held for login attempts, across all accounts
+        async fn count_pending_codes(&self) -> Result<u64, TwoFACodeStoreError>;
 }
 
 #[derive(Debug, PartialEq)]
 pub enum TwoFACodeStoreError {
         CodeNotFound,
+        /// `get_code` found nothing pending for the login attempt, e.g. because it expired, was
+        /// used, or the store was wiped after the 206 login
+        NoActiveChallenge,
         CodeAlreadyExists,
         LoginAttemptIdNotFound,
         UnexpectedError,
 }
+
+/// Short-lived record of responses to requests that carried an `Idempotency-Key` header,
+/// so a client retry can be answered with the original response
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+        /// The response body recorded for `key` and `email`, if it has not expired
+        async fn get_response(
+                &self,
+                key: &str,
+                email: &Email,
+        ) -> Result<Option<String>, IdempotencyStoreError>;
+        async fn save_response(
+                &mut self,
+                key: &str,
+                email: &Email,
+                response: String,
+        ) -> Result<(), IdempotencyStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IdempotencyStoreError {
+        UnexpectedError,
+}
+
+/// A JWT issued to a user, tracked so the number of concurrent sessions can be capped
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ActiveSession {
+        /// `jti` claim of the token
+        pub jti: String,
+        /// Kept so an evicted session's token can be banned
+        pub token: Token,
+        /// `exp` claim of the token
+        pub expires_at: usize,
+        /// When the session started (Unix timestamp in seconds)
+        #[serde(default)]
+        pub created_at: i64,
+        /// Client IP the login came from, if known
+        #[serde(default)]
+        pub ip: Option<String>,
+        /// `User-Agent` of the login request, if sent
+        #[serde(default)]
+        pub user_agent: Option<String>,
+}
+
+/// Active sessions per user, listed by `GET /sessions` and capped by `MAX_SESSIONS_PER_USER`
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+        async fn add_session(
+                &mut self,
+                email: &Email,
+                session: ActiveSession,
+        ) -> Result<(), SessionStoreError>;
+        /// Sessions of `email`, oldest first
+        async fn get_sessions(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<ActiveSession>, SessionStoreError>;
+        /// Stop tracking the session whose token has the given `jti`
+        async fn remove_session(
+                &mut self,
+                email: &Email,
+                jti: &str,
+        ) -> Result<(), SessionStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SessionStoreError {
+        UnexpectedError,
+}
+
+/// Failed-login counts per account and per client IP, and when each account was last
+/// alerted about them. Counts cover a sliding window: each failure counts for
+/// `FAILED_LOGIN_WINDOW_SECONDS` after it happened, then drops out on its own.
+#[async_trait]
+pub trait FailedLoginStore: Send + Sync {
+        /// Count a failed login, returning how many failures `email` has had within the last
+        /// `FAILED_LOGIN_WINDOW_SECONDS` (including this one)
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, FailedLoginStoreError>;
+        /// Failures of `email` within the last `FAILED_LOGIN_WINDOW_SECONDS`
+        async fn get_failures(&self, email: &Email) -> Result<u32, FailedLoginStoreError>;
+        /// When the oldest failure of `email` still counted leaves the window, lowering the
+        /// count by one, as a Unix timestamp in seconds, or `None` when no failure is counted
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, FailedLoginStoreError>;
+        /// Reset the count after a successful login
+        async fn clear_failures(&mut self, email: &Email) -> Result<(), FailedLoginStoreError>;
+        /// Count a failed login from `ip`, whichever account it was for, returning how many
+        /// failures `ip` has had within the last `FAILED_LOGIN_WINDOW_SECONDS` (including this
+        /// one). A successful login does not reset it.
+        async fn record_ip_failure(&mut self, ip: &str) -> Result<u32, FailedLoginStoreError>;
+        /// Failures from `ip` within the last `FAILED_LOGIN_WINDOW_SECONDS`
+        async fn get_ip_failures(&self, ip: &str) -> Result<u32, FailedLoginStoreError>;
+        /// When the oldest failure from `ip` still counted leaves the window, lowering the
+        /// count by one, as a Unix timestamp in seconds, or `None` when no failure is counted
+        async fn get_ip_failures_reset_at(
+                &self,
+                ip: &str,
+        ) -> Result<Option<i64>, FailedLoginStoreError>;
+        /// Reserve the right to alert `email`. Returns `false` if an alert was already sent
+        /// within the last `FAILED_LOGIN_ALERT_INTERVAL_SECONDS`.
+        async fn claim_alert(&mut self, email: &Email) -> Result<bool, FailedLoginStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FailedLoginStoreError {
+        UnexpectedError,
+}
+
+/// What an `AuditEvent` records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventKind {
+        /// A session was started: a login without 2FA, a completed 2FA login, or an
+        /// auto-login at signup
+        LoginSuccess,
+        /// A wrong password was given for the account
+        LoginFailed,
+}
+
+/// A sign-in event on an account, kept so its owner can review where they signed in from
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AuditEvent {
+        pub kind: AuditEventKind,
+        /// Unix timestamp in seconds
+        pub occurred_at: i64,
+        /// Client IP the request came from, if known
+        pub ip: Option<String>,
+        /// `User-Agent` of the request, if sent
+        pub user_agent: Option<String>,
+}
+
+/// Recent sign-in events per account, listed by `GET /security/login-history`. Only the
+/// newest `AUDIT_LOG_MAX_EVENTS` of each account are kept.
+#[async_trait]
+pub trait AuditLogStore: Send + Sync {
+        async fn record_event(
+                &mut self,
+                email: &Email,
+                event: AuditEvent,
+        ) -> Result<(), AuditLogStoreError>;
+        /// Events of `email`, newest first, skipping the first `offset` and returning at most
+        /// `limit`
+        async fn get_events(
+                &self,
+                email: &Email,
+                offset: usize,
+                limit: usize,
+        ) -> Result<Vec<AuditEvent>, AuditLogStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuditLogStoreError {
+        UnexpectedError,
+}
+
+/// A change of `old_email` to `new_email`, waiting for the new address to confirm it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEmailChange {
+        pub old_email: Email,
+        pub new_email: Email,
+}
+
+/// Email changes awaiting confirmation, keyed by the token mailed to the new address
+#[async_trait]
+pub trait PendingEmailChangeStore: Send + Sync {
+        /// Hold the change for `EMAIL_CHANGE_TOKEN_TTL_SECONDS`
+        async fn add_change(
+                &mut self,
+                token: String,
+                change: PendingEmailChange,
+        ) -> Result<(), PendingEmailChangeStoreError>;
+        /// Remove and return the change for `token`, so each token can be redeemed once.
+        /// Unknown and expired tokens are `TokenNotFound`.
+        async fn take_change(
+                &mut self,
+                token: &str,
+        ) -> Result<PendingEmailChange, PendingEmailChangeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PendingEmailChangeStoreError {
+        TokenNotFound,
+        UnexpectedError,
+}
+
+/// Outstanding magic-link logins, keyed by the token mailed to the account
+#[async_trait]
+pub trait MagicLinkStore: Send + Sync {
+        /// Hold `token` as a login for `email` for `ttl_seconds`
+        async fn add_token(
+                &mut self,
+                token: String,
+                email: Email,
+                ttl_seconds: u64,
+        ) -> Result<(), MagicLinkStoreError>;
+        /// Remove `token` and return the account it logs in, so each link works once.
+        /// Unknown and expired tokens are `TokenNotFound`.
+        async fn take_token(&mut self, token: &str) -> Result<Email, MagicLinkStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MagicLinkStoreError {
+        TokenNotFound,
+        UnexpectedError,
+}
+
+/// What a `RateLimitStore` count is kept for. Each has its own window and keeps its counts
+/// apart from the others'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitScope {
+        /// `/signup/check-email` lookups per client IP
+        CheckEmailIp,
+        /// `/magic-link/request` requests per client IP
+        MagicLinkIp,
+        /// `/magic-link/request` requests per email, whether or not it has an account
+        MagicLinkEmail,
+}
+
+impl RateLimitScope {
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        RateLimitScope::CheckEmailIp => "check_email_ip",
+                        RateLimitScope::MagicLinkIp => "magic_link_ip",
+                        RateLimitScope::MagicLinkEmail => "magic_link_email",
+                }
+        }
+
+        /// How long a request keeps counting after it was made
+        pub fn window_seconds(&self) -> i64 {
+                match self {
+                        RateLimitScope::CheckEmailIp => CHECK_EMAIL_WINDOW_SECONDS,
+                        RateLimitScope::MagicLinkIp | RateLimitScope::MagicLinkEmail => {
+                                MAGIC_LINK_REQUEST_WINDOW_SECONDS
+                        }
+                }
+        }
+}
+
+/// Recent requests per client (or account) for the endpoints rate limited over a sliding
+/// window: each request counts for its scope's window after it was made.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+        /// Count a request by `subject`, returning how many it has made within the scope's
+        /// window (including this one)
+        async fn record_request(
+                &mut self,
+                scope: RateLimitScope,
+                subject: &str,
+        ) -> Result<u32, RateLimitStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitStoreError {
+        UnexpectedError,
+}
@@ -0,0 +1,123 @@
+/// Longest display name allowed, in characters
+const MAX_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayName(String);
+
+impl DisplayName {
+        /// Parse the name shown to the user in the UI. Unlike a username it is free-form and
+        /// need not be unique.
+        ///
+        /// Requirements:
+        /// - Not empty once surrounding whitespace is trimmed
+        /// - At most 64 characters
+        /// - No control characters, so the name cannot break lines in logs or emails
+        pub fn parse(display_name_str: &str) -> Result<Self, DisplayNameError> {
+                let display_name_str = display_name_str.trim();
+
+                if display_name_str.is_empty() {
+                        return Err(DisplayNameError::Empty);
+                }
+                if display_name_str.chars().any(char::is_control) {
+                        return Err(DisplayNameError::ControlCharacter);
+                }
+                if display_name_str.chars().count() > MAX_LEN {
+                        return Err(DisplayNameError::TooLong);
+                }
+
+                Ok(DisplayName(display_name_str.to_owned()))
+        }
+
+        /// Get the display name as a string slice
+        pub fn as_str(&self) -> &str {
+                &self.0
+        }
+}
+
+impl AsRef<str> for DisplayName {
+        fn as_ref(&self) -> &str {
+                &self.0
+        }
+}
+
+impl std::fmt::Display for DisplayName {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+        }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DisplayNameError {
+        Empty,
+        /// More than 64 characters
+        TooLong,
+        /// A newline, tab, or other control character
+        ControlCharacter,
+}
+
+impl std::fmt::Display for DisplayNameError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        DisplayNameError::Empty => write!(f, "Display name cannot be empty"),
+                        DisplayNameError::TooLong => {
+                                write!(f, "Display name must be at most 64 characters")
+                        }
+                        DisplayNameError::ControlCharacter => {
+                                write!(f, "Display name cannot contain control characters")
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        // Valid display name test cases
+        #[test]
+        fn test_valid_display_names() {
+                for display_name in
+                        ["Bob", "Jean-Luc Picard", "Zoë O'Brien", "李小龙", "é".repeat(64).as_str()]
+                {
+                        let parsed = DisplayName::parse(display_name).unwrap();
+                        assert_eq!(parsed.as_str(), display_name);
+                }
+        }
+
+        #[test]
+        fn test_display_name_is_trimmed_but_keeps_case() {
+                let display_name = DisplayName::parse("  Alice Smith ").unwrap();
+                assert_eq!(display_name.as_str(), "Alice Smith");
+        }
+
+        // Invalid display name test cases
+        #[test]
+        fn test_empty_string() {
+                assert_eq!(DisplayName::parse(""), Err(DisplayNameError::Empty));
+                assert_eq!(DisplayName::parse(" \t "), Err(DisplayNameError::Empty));
+        }
+
+        #[test]
+        fn test_too_long() {
+                assert_eq!(DisplayName::parse(&"x".repeat(65)), Err(DisplayNameError::TooLong));
+        }
+
+        #[test]
+        fn test_control_characters() {
+                for display_name in ["Bob\nSmith", "Bob\tSmith", "Bob\u{0}", "Bob\u{7f}Smith"] {
+                        assert_eq!(
+                                DisplayName::parse(display_name),
+                                Err(DisplayNameError::ControlCharacter)
+                        );
+                }
+        }
+
+        // Trait implementation tests
+        #[test]
+        fn test_as_ref_and_display() {
+                let display_name = DisplayName::parse("Bob").unwrap();
+                let display_name_ref: &str = display_name.as_ref();
+                assert_eq!(display_name_ref, "Bob");
+                assert_eq!(format!("{}", display_name), "Bob");
+        }
+}
@@ -11,6 +11,9 @@ impl Email {
         /// - Not empty
         /// - (RFC5321) Max length of the local part is 64 characters
         /// - (RFC5321) Max length of the domain part is 255 characters
+        ///
+        /// The address is lowercased, so the same mailbox typed in another case is the same
+        /// account
         pub fn parse(email_str: &str) -> Result<Self, EmailError> {
                 // Trim whitespace
                 let email_str = email_str.trim();
@@ -25,13 +28,19 @@ impl Email {
                         return Err(EmailError::InvalidFormat);
                 }
 
-                Ok(Email(email_str.to_string()))
+                Ok(Email(email_str.to_lowercase()))
         }
 
         /// Get the email as a string slice
         pub fn as_str(&self) -> &str {
                 &self.0
         }
+
+        /// The part after the last '@'
+        pub fn domain(&self) -> EmailDomain {
+                let domain = self.0.rsplit_once('@').map_or("", |(_, domain)| domain);
+                EmailDomain(domain.to_ascii_lowercase())
+        }
 }
 
 impl AsRef<str> for Email {
@@ -46,10 +55,47 @@ impl std::fmt::Display for Email {
         }
 }
 
+/// Domain of an email address, lowercased since DNS names are case-insensitive
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmailDomain(String);
+
+impl EmailDomain {
+        /// Get the domain as a string slice
+        pub fn as_str(&self) -> &str {
+                &self.0
+        }
+}
+
+impl AsRef<str> for EmailDomain {
+        fn as_ref(&self) -> &str {
+                &self.0
+        }
+}
+
+impl std::fmt::Display for EmailDomain {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+        }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum EmailError {
         Empty,
         InvalidFormat,
+        /// The domain has no MX records, so nothing sent to the address can be delivered
+        UndeliverableDomain,
+}
+
+impl std::fmt::Display for EmailError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        EmailError::Empty => write!(f, "Email cannot be empty"),
+                        EmailError::InvalidFormat => write!(f, "Email has an invalid format"),
+                        EmailError::UndeliverableDomain => {
+                                write!(f, "Email domain cannot receive mail")
+                        }
+                }
+        }
 }
 
 #[cfg(test)]
@@ -189,6 +235,19 @@ mod tests {
                 assert_eq!(format!("{}", email), "user@example.com");
         }
 
+        #[test]
+        fn test_domain_is_the_lowercased_part_after_the_at() {
+                let email = Email::parse("User@Mail.Example.COM").unwrap();
+                assert_eq!(email.domain().as_str(), "mail.example.com");
+        }
+
+        #[test]
+        fn test_valid_email_is_lowercased() {
+                let email = Email::parse("User@Mail.Example.COM").unwrap();
+                assert_eq!(email.as_str(), "user@mail.example.com");
+                assert_eq!(email, Email::parse("user@mail.example.com").unwrap());
+        }
+
         // Clone and PartialEq tests
         #[test]
         fn test_clone_and_equality() {
@@ -9,5 +9,40 @@ pub trait EmailClient {
                 recipient: &Email,
                 subject: &str,
                 content: &str,
-        ) -> Result<(), String>;
+        ) -> Result<(), EmailClientError>;
+}
+
+/// Why an email was not sent, so callers can tell a failure worth retrying from one that
+/// will fail again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailClientError {
+        /// The provider could not be reached or failed on its side; the same email may go
+        /// through on a later try
+        Transient(String),
+        /// The provider refused the email itself, e.g. a bad address; retrying won't help
+        Permanent(String),
+        /// The provider is throttling us; retrying right away only adds to it
+        RateLimited,
+}
+
+impl EmailClientError {
+        pub fn is_retryable(&self) -> bool {
+                matches!(self, EmailClientError::Transient(_))
+        }
+}
+
+impl std::fmt::Display for EmailClientError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        EmailClientError::Transient(reason) => {
+                                write!(f, "Email could not be sent: {}", reason)
+                        }
+                        EmailClientError::Permanent(reason) => {
+                                write!(f, "Email was rejected: {}", reason)
+                        }
+                        EmailClientError::RateLimited => {
+                                write!(f, "Email provider rate limit reached")
+                        }
+                }
+        }
 }
@@ -1,13 +1,42 @@
 use crate::{
-        domain::{EmailError, TwoFACodeStoreError, UserStoreError},
+        domain::{
+                AuditLogStoreError, EmailError, FieldError, MagicLinkStoreError, PasswordError,
+                PendingEmailChangeStoreError, SessionStoreError, TwoFACodeStoreError,
+                UserStoreError, ValidationErrors,
+        },
         routes::{LogoutError, TokenError},
-        utils::auth::GenerateTokenError,
+        utils::{auth::GenerateTokenError, content_negotiation::PlainTextError},
 };
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+        http::{header, HeaderValue, StatusCode},
+        response::IntoResponse,
+        Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ErrorResponse {
         pub error: String,
+        /// Only set on a 500: also logged with the failure, so a user can quote it in a
+        /// bug report and an operator can find the matching log line
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub correlation_id: Option<String>,
+        /// Machine-readable reason, set only where a client should react differently than to
+        /// the status alone
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub code: Option<String>,
+        /// Only set on an `account_locked` 403: seconds until the lock ends, also sent as the
+        /// `Retry-After` header
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub retry_after_seconds: Option<u64>,
+}
+
+/// Body of a 400 or 422 caused by field validation, listing every field that failed
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ValidationErrorResponse {
+        pub error: String,
+        pub fields: Vec<FieldError>,
 }
 
 #[derive(Debug)]
@@ -15,32 +44,164 @@ pub enum AuthAPIError {
         /// 400
         InvalidCredentials,
         /// 400
+        InvalidInput(ValidationErrors),
+        /// 400
         MissingToken,
+        /// 400
+        PasswordRecentlyUsed,
+        /// 400
+        InvalidIdempotencyKey,
+        /// 400
+        CaptchaFailed,
+        /// 400
+        InvalidEmailChangeToken,
         /// 401
         Unauthorized,
         /// 401
         InvalidToken,
+        /// 401 – the magic link is unknown, expired or already used
+        InvalidMagicLink,
+        /// 401 – no 2FA code is pending, so the login has to be restarted rather than the code
+        /// re-entered
+        NoActive2FAChallenge,
+        /// 401 – the code matches, but the login attempt outlived `LOGIN_ATTEMPT_TTL_SECONDS`
+        TwoFAChallengeExpired,
+        /// 403
+        Forbidden,
+        /// 403 – the account's email address has not been verified
+        EmailNotVerified,
+        /// 403 – the account is locked for another `retry_after_seconds`
+        AccountLocked {
+                retry_after_seconds: u64,
+        },
+        /// 403 – the account has been banned
+        AccountBanned,
         /// 404
         UserNotFound,
+        /// 404
+        SessionNotFound,
+        /// 404
+        NotFound,
         /// 409
         UserAlreadyExists,
+        /// 409
+        SessionLimitReached,
+        /// 429
+        TwoFAEmailLimitReached,
+        /// 429
+        TwoFAAttemptLimitReached,
+        /// 429 – too many failed logins from the client's IP; retry after `retry_after_seconds`
+        LoginThrottled {
+                retry_after_seconds: u64,
+        },
+        /// 429 – the client has made too many requests of a rate-limited endpoint
+        TooManyRequests,
         /// 422
         UnprocessableContent,
+        /// 422 – the payload parsed but a required field is blank; see `Validate`
+        UnprocessablePayload(ValidationErrors),
+        /// 503 – the service is in read-only maintenance
+        MaintenanceMode,
+        /// 503 – `MAX_CONCURRENT_REQUESTS` requests are already being handled
+        Overloaded,
         /// 500
         UnexpectedError,
 }
 
+impl AuthAPIError {
+        /// Value of `ErrorResponse::code` for this error
+        pub fn code(&self) -> Option<&'static str> {
+                match self {
+                        AuthAPIError::NoActive2FAChallenge => Some("no_active_2fa_challenge"),
+                        AuthAPIError::TwoFAChallengeExpired => Some("2fa_challenge_expired"),
+                        AuthAPIError::MaintenanceMode => Some("maintenance_mode"),
+                        AuthAPIError::Overloaded => Some("overloaded"),
+                        AuthAPIError::EmailNotVerified => Some("email_not_verified"),
+                        AuthAPIError::AccountLocked {
+                                ..
+                        } => Some("account_locked"),
+                        AuthAPIError::AccountBanned => Some("account_banned"),
+                        AuthAPIError::LoginThrottled {
+                                ..
+                        } => Some("login_throttled"),
+                        AuthAPIError::TooManyRequests => Some("rate_limited"),
+                        _ => None,
+                }
+        }
+
+        /// A lock that lasts until `until`. The wait is rounded up, so a client that waits it
+        /// out is not turned away again.
+        pub fn account_locked_until(until: DateTime<Utc>) -> Self {
+                AuthAPIError::AccountLocked {
+                        retry_after_seconds: seconds_until(until),
+                }
+        }
+
+        /// Logins from the client's IP are refused until `until`, rounded up like
+        /// `account_locked_until`
+        pub fn login_throttled_until(until: DateTime<Utc>) -> Self {
+                AuthAPIError::LoginThrottled {
+                        retry_after_seconds: seconds_until(until),
+                }
+        }
+
+        /// Value of `ErrorResponse::retry_after_seconds` and the `Retry-After` header
+        pub fn retry_after_seconds(&self) -> Option<u64> {
+                match self {
+                        AuthAPIError::AccountLocked {
+                                retry_after_seconds,
+                        }
+                        | AuthAPIError::LoginThrottled {
+                                retry_after_seconds,
+                        } => Some(*retry_after_seconds),
+                        _ => None,
+                }
+        }
+}
+
+/// Whole seconds left until `until`, at least 1
+fn seconds_until(until: DateTime<Utc>) -> u64 {
+        let remaining_ms = (until - Utc::now()).num_milliseconds().max(0) as u64;
+        remaining_ms.div_ceil(1000).max(1)
+}
+
 impl IntoResponse for AuthAPIError {
         fn into_response(self) -> axum::response::Response {
+                let code = self.code().map(str::to_owned);
+                let retry_after_seconds = self.retry_after_seconds();
                 let (status, error_message) = match self {
                         /// 400
                         AuthAPIError::InvalidCredentials => {
                                 (StatusCode::BAD_REQUEST, "Invalid credentials")
                         }
                         /// 400
+                        AuthAPIError::InvalidInput(errors) => {
+                                return validation_error_response(
+                                        StatusCode::BAD_REQUEST,
+                                        "Invalid credentials",
+                                        errors,
+                                );
+                        }
+                        /// 400
                         AuthAPIError::MissingToken => {
                                 (StatusCode::BAD_REQUEST, "Missing JWT auth token")
                         }
+                        /// 400
+                        AuthAPIError::PasswordRecentlyUsed => {
+                                (StatusCode::BAD_REQUEST, "Password was used recently")
+                        }
+                        /// 400
+                        AuthAPIError::InvalidIdempotencyKey => {
+                                (StatusCode::BAD_REQUEST, "Invalid Idempotency-Key header")
+                        }
+                        /// 400
+                        AuthAPIError::CaptchaFailed => {
+                                (StatusCode::BAD_REQUEST, "CAPTCHA verification failed")
+                        }
+                        /// 400
+                        AuthAPIError::InvalidEmailChangeToken => {
+                                (StatusCode::BAD_REQUEST, "Invalid or expired email change token")
+                        }
 
                         /// 401
                         AuthAPIError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
@@ -48,32 +209,161 @@ impl IntoResponse for AuthAPIError {
                         AuthAPIError::InvalidToken => {
                                 (StatusCode::UNAUTHORIZED, "Invalid JWT auth token")
                         }
+                        /// 401
+                        AuthAPIError::InvalidMagicLink => {
+                                (StatusCode::UNAUTHORIZED, "Invalid or expired login link")
+                        }
+                        /// 401
+                        AuthAPIError::NoActive2FAChallenge => (
+                                StatusCode::UNAUTHORIZED,
+                                "No 2FA challenge is pending; log in again",
+                        ),
+                        /// 401
+                        AuthAPIError::TwoFAChallengeExpired => {
+                                (StatusCode::UNAUTHORIZED, "2FA challenge expired; log in again")
+                        }
+
+                        /// 403
+                        AuthAPIError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
+                        /// 403
+                        AuthAPIError::EmailNotVerified => {
+                                (StatusCode::FORBIDDEN, "Email address not verified")
+                        }
+                        /// 403
+                        AuthAPIError::AccountLocked {
+                                ..
+                        } => (StatusCode::FORBIDDEN, "Account is temporarily locked"),
+                        /// 403
+                        AuthAPIError::AccountBanned => (StatusCode::FORBIDDEN, "Account is banned"),
 
                         /// 404
                         AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+                        /// 404
+                        AuthAPIError::SessionNotFound => {
+                                (StatusCode::NOT_FOUND, "Session not found")
+                        }
+                        /// 404
+                        AuthAPIError::NotFound => (StatusCode::NOT_FOUND, "Not found"),
 
                         /// 409
                         AuthAPIError::UserAlreadyExists => {
                                 (StatusCode::CONFLICT, "User already exists")
                         }
+                        /// 409
+                        AuthAPIError::SessionLimitReached => {
+                                (StatusCode::CONFLICT, "Too many active sessions")
+                        }
+
+                        /// 429
+                        AuthAPIError::TwoFAEmailLimitReached => (
+                                StatusCode::TOO_MANY_REQUESTS,
+                                "Too many 2FA emails sent to this address today",
+                        ),
+                        /// 429
+                        AuthAPIError::TwoFAAttemptLimitReached => (
+                                StatusCode::TOO_MANY_REQUESTS,
+                                "Too many 2FA attempts; log in again",
+                        ),
+                        /// 429
+                        AuthAPIError::LoginThrottled {
+                                ..
+                        } => (
+                                StatusCode::TOO_MANY_REQUESTS,
+                                "Too many failed logins from this address; try again later",
+                        ),
+                        /// 429
+                        AuthAPIError::TooManyRequests => (
+                                StatusCode::TOO_MANY_REQUESTS,
+                                "Too many requests; try again later",
+                        ),
 
                         /// 422
                         AuthAPIError::UnprocessableContent => {
                                 (StatusCode::UNPROCESSABLE_ENTITY, "Unprocessable content")
                         }
+                        /// 422
+                        AuthAPIError::UnprocessablePayload(errors) => {
+                                return validation_error_response(
+                                        StatusCode::UNPROCESSABLE_ENTITY,
+                                        "Unprocessable payload",
+                                        errors,
+                                );
+                        }
+
+                        /// 503
+                        AuthAPIError::MaintenanceMode => (
+                                StatusCode::SERVICE_UNAVAILABLE,
+                                "Down for maintenance; only logins are available",
+                        ),
+                        /// 503
+                        AuthAPIError::Overloaded => (
+                                StatusCode::SERVICE_UNAVAILABLE,
+                                "Too many requests in progress; try again shortly",
+                        ),
 
                         /// 500
                         AuthAPIError::UnexpectedError => {
-                                (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
+                                // Logged inside the request span, so the line also carries the
+                                // request's method, URI, and request_id
+                                let correlation_id = Uuid::new_v4().to_string();
+                                tracing::error!(%correlation_id, "Unexpected error");
+
+                                let plain_text = format!(
+                                        "Unexpected error (correlation id: {correlation_id})"
+                                );
+                                let body = Json(ErrorResponse {
+                                        error: "Unexpected error".to_string(),
+                                        correlation_id: Some(correlation_id),
+                                        code: None,
+                                        retry_after_seconds: None,
+                                });
+                                return with_plain_text(
+                                        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response(),
+                                        plain_text,
+                                );
                         }
                 };
                 let body = Json(ErrorResponse {
                         error: error_message.to_string(),
+                        correlation_id: None,
+                        code,
+                        retry_after_seconds,
                 });
-                (status, body).into_response()
+                let mut response = (status, body).into_response();
+                if let Some(seconds) = retry_after_seconds {
+                        response.headers_mut()
+                                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+                }
+                with_plain_text(response, error_message.to_string())
         }
 }
 
+/// Attach the plain-text form of an error, used instead of the JSON body when the client
+/// prefers `text/plain` (see `negotiate_error_format`)
+/// A `ValidationErrorResponse`, with each field on its own line in the plain-text form
+fn validation_error_response(
+        status: StatusCode,
+        error: &str,
+        errors: ValidationErrors,
+) -> axum::response::Response {
+        let plain_text = errors.fields.iter().fold(error.to_string(), |text, field| {
+                format!("{text}\n{}: {}", field.field, field.message)
+        });
+        let body = Json(ValidationErrorResponse {
+                error: error.to_string(),
+                fields: errors.fields,
+        });
+        with_plain_text((status, body).into_response(), plain_text)
+}
+
+fn with_plain_text(
+        mut response: axum::response::Response,
+        message: String,
+) -> axum::response::Response {
+        response.extensions_mut().insert(PlainTextError(message));
+        response
+}
+
 impl From<UserStoreError> for AuthAPIError {
         fn from(err: UserStoreError) -> Self {
                 match err {
@@ -85,12 +375,39 @@ impl From<UserStoreError> for AuthAPIError {
         }
 }
 
+impl From<SessionStoreError> for AuthAPIError {
+        fn from(err: SessionStoreError) -> Self {
+                AuthAPIError::UnexpectedError
+        }
+}
+
+impl From<AuditLogStoreError> for AuthAPIError {
+        fn from(err: AuditLogStoreError) -> Self {
+                AuthAPIError::UnexpectedError
+        }
+}
+
+impl From<ValidationErrors> for AuthAPIError {
+        fn from(errors: ValidationErrors) -> Self {
+                AuthAPIError::InvalidInput(errors)
+        }
+}
+
 impl From<EmailError> for AuthAPIError {
         fn from(err: EmailError) -> Self {
                 AuthAPIError::InvalidCredentials
         }
 }
 
+impl From<PasswordError> for AuthAPIError {
+        fn from(err: PasswordError) -> Self {
+                match err {
+                        PasswordError::RecentlyUsed => AuthAPIError::PasswordRecentlyUsed,
+                        _ => AuthAPIError::InvalidCredentials,
+                }
+        }
+}
+
 impl From<LogoutError> for AuthAPIError {
         fn from(err: LogoutError) -> Self {
                 match err {
@@ -120,6 +437,9 @@ impl From<TwoFACodeStoreError> for AuthAPIError {
         fn from(err: TwoFACodeStoreError) -> Self {
                 match err {
                         TwoFACodeStoreError::CodeNotFound => AuthAPIError::Unauthorized,
+                        TwoFACodeStoreError::NoActiveChallenge => {
+                                AuthAPIError::NoActive2FAChallenge
+                        }
                         TwoFACodeStoreError::CodeAlreadyExists => AuthAPIError::UnexpectedError,
                         TwoFACodeStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
                         TwoFACodeStoreError::LoginAttemptIdNotFound => {
@@ -128,3 +448,115 @@ impl From<TwoFACodeStoreError> for AuthAPIError {
                 }
         }
 }
+
+impl From<MagicLinkStoreError> for AuthAPIError {
+        fn from(err: MagicLinkStoreError) -> Self {
+                match err {
+                        MagicLinkStoreError::TokenNotFound => AuthAPIError::InvalidMagicLink,
+                        MagicLinkStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+                }
+        }
+}
+
+impl From<PendingEmailChangeStoreError> for AuthAPIError {
+        fn from(err: PendingEmailChangeStoreError) -> Self {
+                match err {
+                        PendingEmailChangeStoreError::TokenNotFound => {
+                                AuthAPIError::InvalidEmailChangeToken
+                        }
+                        PendingEmailChangeStoreError::UnexpectedError => {
+                                AuthAPIError::UnexpectedError
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use std::{
+                io::Write,
+                sync::{Arc, Mutex},
+        };
+
+        use super::*;
+
+        /// Log sink shared between the subscriber and the test
+        #[derive(Clone, Default)]
+        struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for CapturedLogs {
+                fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                        self.0.lock().unwrap().write(buf)
+                }
+
+                fn flush(&mut self) -> std::io::Result<()> {
+                        Ok(())
+                }
+        }
+
+        #[tokio::test]
+        async fn test_unexpected_error_body_has_a_logged_correlation_id() {
+                let logs = CapturedLogs::default();
+                let writer = logs.clone();
+                let subscriber = tracing_subscriber::fmt()
+                        .with_writer(move || writer.clone())
+                        .with_ansi(false)
+                        .finish();
+
+                let response = tracing::subscriber::with_default(subscriber, || {
+                        AuthAPIError::UnexpectedError.into_response()
+                });
+                assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body.error, "Unexpected error");
+
+                let correlation_id =
+                        body.correlation_id.expect("500 should carry a correlation id");
+                assert!(!correlation_id.is_empty());
+
+                let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+                assert!(logs.contains(&correlation_id), "correlation id not logged: {logs}");
+        }
+
+        #[test]
+        fn test_other_errors_have_no_correlation_id() {
+                let body = serde_json::to_value(ErrorResponse {
+                        error: "Unauthorized".to_string(),
+                        correlation_id: None,
+                        code: None,
+                        retry_after_seconds: None,
+                })
+                .unwrap();
+
+                assert_eq!(body, serde_json::json!({ "error": "Unauthorized" }));
+        }
+
+        #[tokio::test]
+        async fn test_no_active_2fa_challenge_carries_its_code() {
+                let response = AuthAPIError::NoActive2FAChallenge.into_response();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body.code.as_deref(), Some("no_active_2fa_challenge"));
+        }
+
+        #[tokio::test]
+        async fn test_account_locked_reports_the_remaining_lock_time() {
+                let until = Utc::now() + chrono::Duration::seconds(90);
+                let response = AuthAPIError::account_locked_until(until).into_response();
+                assert_eq!(response.status(), StatusCode::FORBIDDEN);
+                assert_eq!(response.headers()[header::RETRY_AFTER], "90");
+
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body.code.as_deref(), Some("account_locked"));
+                assert_eq!(body.retry_after_seconds, Some(90));
+
+                // A lock about to end still asks for a wait rather than an immediate retry
+                let ending = AuthAPIError::account_locked_until(Utc::now());
+                assert_eq!(ending.retry_after_seconds(), Some(1));
+        }
+}
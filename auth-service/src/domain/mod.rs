@@ -1,17 +1,39 @@
+pub mod captcha_verifier;
 pub mod data_stores;
+pub mod display_name;
 pub mod email;
 pub mod email_client;
 pub mod error;
 pub mod login_attempt_id;
+pub mod mx_resolver;
 pub mod password;
+pub mod password_hasher;
+pub mod password_verifier;
+pub mod pepper;
+pub mod phone_number;
+pub mod role;
 pub mod two_fa_code;
+pub mod two_fa_method;
 pub mod user;
+pub mod username;
+pub mod validation;
 
+pub use captcha_verifier::*;
 pub use data_stores::*;
+pub use display_name::*;
 pub use email::*;
 pub use email_client::*;
 pub use error::*;
 pub use login_attempt_id::*;
+pub use mx_resolver::*;
 pub use password::*;
+pub use password_hasher::*;
+pub use password_verifier::*;
+pub use pepper::*;
+pub use phone_number::*;
+pub use role::*;
 pub use two_fa_code::*;
+pub use two_fa_method::*;
 pub use user::*;
+pub use username::*;
+pub use validation::*;
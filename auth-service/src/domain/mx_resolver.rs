@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use crate::domain::EmailDomain;
+
+/// DNS lookup used at signup to turn away email domains that cannot receive mail
+#[async_trait]
+pub trait MxResolver {
+        /// `Ok(true)` when `domain` has at least one MX record and `Ok(false)` when it has none
+        /// or does not exist. `Err` describes a lookup that could not be completed.
+        async fn has_mx_records(&self, domain: &EmailDomain) -> Result<bool, String>;
+}
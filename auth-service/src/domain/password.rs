@@ -1,54 +1,207 @@
-use argon2::{
-        password_hash::{rand_core::OsRng, SaltString},
-        Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+use argon2::{Params, PasswordHash};
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, error::Error, str::FromStr};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+        domain::{
+                password_hasher::{argon2_target_params, verify_password_hash, PasswordHasherKind},
+                Email, Peppers, Username,
+        },
+        utils::constants::{PASSWORD_HASHER, PASSWORD_PEPPERS},
 };
-use std::{error::Error, str::FromStr};
 
+/// Hex characters of the hash's SHA-256 kept as its fingerprint
+const FINGERPRINT_LENGTH: usize = 16;
+
+lazy_static! {
+        /// Embedded list of widely used passwords, normalized once on first use
+        static ref COMMON_PASSWORDS: HashSet<String> = include_str!("common_passwords.txt")
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(normalize_for_comparison)
+                .collect();
+}
+
+/// A password hash, with the version of the pepper the password was mixed with before
+/// hashing (see `Peppers`)
 #[derive(Debug, Clone, Eq, PartialEq, serde::Deserialize)]
-pub struct HashedPassword(String);
+pub struct HashedPassword {
+        hash: String,
+        pepper_version: u16,
+}
 
 impl HashedPassword {
-        /// Parse and hash a raw password
+        /// Parse and hash a raw password under the default `PasswordPolicy`
         pub async fn parse(s: impl Into<String>) -> Result<Self, String> {
+                Self::parse_with_policy(s, &PasswordPolicy::default()).await
+        }
+
+        /// Parse and hash a raw password, rejecting it if it breaks any rule of `policy`
+        pub async fn parse_with_policy(
+                s: impl Into<String>,
+                policy: &PasswordPolicy,
+        ) -> Result<Self, String> {
                 let s: String = s.into();
 
-                validate_raw_password(&s)
-                        .await
-                        .map_err(|e| format!("Error validating password: {}", e))?;
+                if let Some(violation) = policy.violations(&s).first() {
+                        return Err(format!("Error validating password: {}", violation));
+                }
 
                 // Hash the password using the helper function
-                let hashed = compute_password_hash(s)
+                compute_password_hash(s, &PASSWORD_PEPPERS)
                         .await
-                        .map_err(|e| format!("Failed to hash password: {}", e))?;
+                        .map_err(|e| format!("Failed to hash password: {}", e))
+        }
+
+        /// Parse and hash a raw password under `policy`, also rejecting it if it contains the
+        /// account's email local-part or username
+        pub async fn parse_with_context(
+                s: impl Into<String>,
+                policy: &PasswordPolicy,
+                email: &Email,
+                username: Option<&Username>,
+        ) -> Result<Self, String> {
+                let s: String = s.into();
 
-                Ok(Self(hashed))
+                if let Some(violation) = policy.violations_with_context(&s, email, username).first()
+                {
+                        return Err(format!("Error validating password: {}", violation));
+                }
+
+                compute_password_hash(s, &PASSWORD_PEPPERS)
+                        .await
+                        .map_err(|e| format!("Failed to hash password: {}", e))
         }
 
-        /// Parse an existing password hash from the database
+        /// Hash a raw password that was just verified against a stored hash, to store it again
+        /// with the configured hasher, costs, and pepper. No policy applies: the password is
+        /// already in use.
+        pub async fn rehash(raw_password: &str) -> Result<Self, String> {
+                compute_password_hash(raw_password.to_owned(), &PASSWORD_PEPPERS)
+                        .await
+                        .map_err(|e| format!("Failed to hash password: {}", e))
+        }
+
+        /// Parse an existing password hash from the database, made without a pepper; see
+        /// `with_pepper_version` for peppered ones. Besides argon2 and scrypt PHC strings,
+        /// bcrypt hashes imported from a legacy user base are accepted; see `needs_rehash`.
         pub fn parse_password_hash(hash: String) -> Result<HashedPassword, String> {
+                if is_bcrypt_hash(&hash) {
+                        if hash.len() != BCRYPT_HASH_LEN {
+                                return Err("Invalid password hash format: bad bcrypt length"
+                                        .to_owned());
+                        }
+                        return Ok(HashedPassword {
+                                hash,
+                                pepper_version: 0,
+                        });
+                }
+
                 // Validate the hash format using PasswordHash::new
                 PasswordHash::new(&hash)
                         .map_err(|e| format!("Invalid password hash format: {}", e))?;
 
-                Ok(HashedPassword(hash))
+                Ok(HashedPassword {
+                        hash,
+                        pepper_version: 0,
+                })
+        }
+
+        /// The same hash, recorded as made with pepper `version`
+        pub fn with_pepper_version(mut self, version: u16) -> Self {
+                self.pepper_version = version;
+                self
+        }
+
+        /// Version of the pepper the password was mixed with before hashing; 0 for none
+        pub fn pepper_version(&self) -> u16 {
+                self.pepper_version
+        }
+
+        /// Truncated SHA-256 of the hash, for tokens that must stop working once the password
+        /// changes; it says nothing about the password itself
+        pub fn fingerprint(&self) -> String {
+                let mut fingerprint = hex::encode(Sha256::digest(self.hash.as_bytes()));
+                fingerprint.truncate(FINGERPRINT_LENGTH);
+                fingerprint
         }
 
-        /// Verify a raw password against this hashed password
+        /// Whether this is a legacy bcrypt hash, was made with an older pepper than
+        /// `PASSWORD_PEPPER_CURRENT`, by another hasher than `PASSWORD_HASHER`, or is an argon2
+        /// hash computed with a lower memory, iteration, or parallelism cost than new hashes
+        /// are, so it should be recomputed the next time the password is verified. This is the
+        /// one rehash policy: the user stores apply it in `validate_user`.
+        pub fn needs_rehash(&self) -> bool {
+                self.needs_rehash_for(*PASSWORD_HASHER, PASSWORD_PEPPERS.current())
+        }
+
+        fn needs_rehash_for(&self, configured: PasswordHasherKind, current_pepper: u16) -> bool {
+                if is_bcrypt_hash(&self.hash) {
+                        return true;
+                }
+                if self.pepper_version != current_pepper {
+                        return true;
+                }
+                let Ok(hash) = PasswordHash::new(&self.hash) else {
+                        return false;
+                };
+                if hash.algorithm.as_str() != configured.hasher().algorithm() {
+                        return true;
+                }
+                let (Ok(params), Ok(target)) = (Params::try_from(&hash), argon2_target_params())
+                else {
+                        return false;
+                };
+
+                params.m_cost() < target.m_cost()
+                        || params.t_cost() < target.t_cost()
+                        || params.p_cost() < target.p_cost()
+        }
+
+        /// Verify a raw password against this hashed password, peppered the way it was when
+        /// the hash was made
         #[tracing::instrument(name = "Verify raw password", skip_all)]
         pub async fn verify_raw_password(
                 &self,
                 password_candidate: &str,
         ) -> Result<(), Box<dyn Error + Send + Sync>> {
-                let expected_password_hash = self.0.clone();
+                self.verify_raw_password_with(password_candidate, &PASSWORD_PEPPERS).await
+        }
+
+        async fn verify_raw_password_with(
+                &self,
+                password_candidate: &str,
+                peppers: &Peppers,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+                let expected_password_hash = self.hash.clone();
+                let peppered_candidate =
+                        peppers.apply(self.pepper_version, password_candidate.as_bytes())?;
                 let password_candidate = password_candidate.to_owned();
+                let current_span = tracing::Span::current();
 
                 // Spawn blocking task to avoid blocking the async runtime
                 tokio::task::spawn_blocking(move || {
-                        let parsed_hash = PasswordHash::new(&expected_password_hash)?;
-
-                        Argon2::default()
-                                .verify_password(password_candidate.as_bytes(), &parsed_hash)
-                                .map_err(|e| -> Box<dyn Error + Send + Sync> { Box::new(e) })
+                        current_span.in_scope(|| {
+                                if is_bcrypt_hash(&expected_password_hash) {
+                                        return match bcrypt::verify(
+                                                &password_candidate,
+                                                &expected_password_hash,
+                                        )? {
+                                                true => Ok(()),
+                                                false => Err("Invalid password".into()),
+                                        };
+                                }
+
+                                // The algorithm comes from the hash itself, whatever the
+                                // configured hasher
+                                verify_password_hash(&peppered_candidate, &expected_password_hash)
+                                        .map_err(|e| -> Box<dyn Error + Send + Sync> {
+                                                Box::new(e)
+                                        })
+                        })
                 })
                 .await
                 .map_err(|e| -> Box<dyn Error + Send + Sync> {
@@ -57,29 +210,275 @@ impl HashedPassword {
         }
 }
 
+/// Rules a new password must satisfy. Loaded from the environment into `AppConfig`.
+///
+/// Only passwords being set, at signup or a password change, are checked; a login only
+/// compares against the stored hash, so existing passwords that break a rule keep working.
+///
+/// `Default` requires 8 to 128 characters with at least one uppercase letter and one digit,
+/// and rejects passwords on the embedded common-passwords list.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PasswordPolicy {
+        pub min_len: usize,
+        pub max_len: usize,
+        pub require_upper: bool,
+        pub require_lower: bool,
+        pub require_digit: bool,
+        /// Any character that is not an ASCII letter or digit counts as a symbol
+        pub require_symbol: bool,
+        /// Reject passwords on the embedded common-passwords list, ignoring case
+        pub reject_common: bool,
+        /// Reject passwords containing the email local-part or username, ignoring case. Only
+        /// enforced where the account is known (see `violations_with_context`).
+        #[serde(default = "default_reject_identifiers")]
+        pub reject_identifiers: bool,
+}
+
+fn default_reject_identifiers() -> bool {
+        true
+}
+
+impl Default for PasswordPolicy {
+        fn default() -> Self {
+                Self {
+                        min_len: 8,
+                        max_len: 128,
+                        require_upper: true,
+                        require_lower: false,
+                        require_digit: true,
+                        require_symbol: false,
+                        reject_common: true,
+                        reject_identifiers: default_reject_identifiers(),
+                }
+        }
+}
+
+impl PasswordPolicy {
+        /// Every rule `pwd` breaks, so a client can be told about all of them at once
+        pub fn violations(&self, pwd: &str) -> Vec<PasswordError> {
+                if pwd.is_empty() {
+                        return vec![PasswordError::Empty];
+                }
+
+                let len = pwd.chars().count();
+                let mut violations = Vec::new();
+                if len < self.min_len {
+                        violations.push(PasswordError::TooShort(self.min_len));
+                }
+                if len > self.max_len {
+                        violations.push(PasswordError::TooLong(self.max_len));
+                }
+                if self.require_upper && !pwd.chars().any(|c| c.is_ascii_uppercase()) {
+                        violations.push(PasswordError::MissingUppercase);
+                }
+                if self.require_lower && !pwd.chars().any(|c| c.is_ascii_lowercase()) {
+                        violations.push(PasswordError::MissingLowercase);
+                }
+                if self.require_digit && !pwd.chars().any(|c| c.is_ascii_digit()) {
+                        violations.push(PasswordError::MissingDigit);
+                }
+                if self.require_symbol && pwd.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        violations.push(PasswordError::MissingSymbol);
+                }
+                if self.reject_common && is_common_password(pwd) {
+                        violations.push(PasswordError::TooCommon);
+                }
+                violations
+        }
+
+        /// Like `violations`, adding `ContainsIdentifier` when `pwd` contains the local-part
+        /// of `email` or the username. Identifiers shorter than `MIN_IDENTIFIER_LEN` are
+        /// skipped, since almost any password would contain them.
+        pub fn violations_with_context(
+                &self,
+                pwd: &str,
+                email: &Email,
+                username: Option<&Username>,
+        ) -> Vec<PasswordError> {
+                let mut violations = self.violations(pwd);
+                if violations == [PasswordError::Empty] || !self.reject_identifiers {
+                        return violations;
+                }
+
+                let local_part = email.as_str().split('@').next().unwrap_or_default();
+                let pwd = normalize_for_comparison(pwd);
+                let contains_identifier = std::iter::once(local_part)
+                        .chain(username.map(Username::as_str))
+                        .map(normalize_for_comparison)
+                        .filter(|identifier| identifier.chars().count() >= MIN_IDENTIFIER_LEN)
+                        .any(|identifier| pwd.contains(&identifier));
+                if contains_identifier {
+                        violations.push(PasswordError::ContainsIdentifier);
+                }
+                violations
+        }
+}
+
+/// Symbols `generate_temporary_password` draws from
+const TEMPORARY_PASSWORD_SYMBOLS: &[u8] = b"!@#$%^&*-_";
+
+/// Shortest password `generate_temporary_password` makes: one of each character class. A
+/// policy capping passwords below this cannot be met by a temporary password.
+pub const MIN_TEMPORARY_PASSWORD_LEN: usize = 4;
+
+/// Candidates `generate_temporary_password` tries before giving up on a policy
+const TEMPORARY_PASSWORD_ATTEMPTS: usize = 100;
+
+/// A random password for an admin-initiated reset, meeting every rule of `policy` for the
+/// account of `email`. It is at least 20 characters long unless the policy caps it lower.
+///
+/// Returns `None` if no candidate met the policy within `TEMPORARY_PASSWORD_ATTEMPTS`, which
+/// only happens for a policy no password can meet.
+pub fn generate_temporary_password(
+        policy: &PasswordPolicy,
+        email: &Email,
+        username: Option<&Username>,
+) -> Option<String> {
+        use rand::{seq::SliceRandom, Rng};
+
+        const ALPHANUMERIC: &[u8] =
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        let len = policy.min_len.max(20).min(policy.max_len).max(MIN_TEMPORARY_PASSWORD_LEN);
+        let mut rng = rand::rng();
+        for _ in 0..TEMPORARY_PASSWORD_ATTEMPTS {
+                let mut pwd: Vec<u8> = [
+                        &ALPHANUMERIC[..26],
+                        &ALPHANUMERIC[26..52],
+                        &ALPHANUMERIC[52..],
+                        TEMPORARY_PASSWORD_SYMBOLS,
+                ]
+                .iter()
+                .map(|class| class[rng.random_range(0..class.len())])
+                .collect();
+                while pwd.len() < len {
+                        let pool = if rng.random_bool(0.2) {
+                                TEMPORARY_PASSWORD_SYMBOLS
+                        } else {
+                                ALPHANUMERIC
+                        };
+                        pwd.push(pool[rng.random_range(0..pool.len())]);
+                }
+                pwd.shuffle(&mut rng);
+
+                let pwd = String::from_utf8(pwd).expect("temporary password is ASCII");
+                if policy.violations_with_context(&pwd, email, username).is_empty() {
+                        return Some(pwd);
+                }
+        }
+
+        None
+}
+
+/// Shortest email local-part or username `violations_with_context` looks for in a password
+const MIN_IDENTIFIER_LEN: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub enum PasswordError {
+        Empty,
+        /// Shorter than the policy's minimum length
+        TooShort(usize),
+        /// Longer than the policy's maximum length
+        TooLong(usize),
+        MissingUppercase,
+        MissingLowercase,
+        MissingDigit,
+        MissingSymbol,
+        /// On the common-passwords list
+        TooCommon,
+        /// Contains the email local-part or username of the account
+        ContainsIdentifier,
+        /// The candidate matches the current password or one kept in the password history
+        RecentlyUsed,
+}
+
+impl std::fmt::Display for PasswordError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        Self::Empty => write!(f, "Password cannot be empty"),
+                        Self::TooShort(min) => {
+                                write!(f, "Password must be at least {} characters", min)
+                        }
+                        Self::TooLong(max) => {
+                                write!(f, "Password must not exceed {} characters", max)
+                        }
+                        Self::MissingUppercase => {
+                                write!(f, "Password must contain at least one uppercase letter")
+                        }
+                        Self::MissingLowercase => {
+                                write!(f, "Password must contain at least one lowercase letter")
+                        }
+                        Self::MissingDigit => write!(f, "Password must contain at least one digit"),
+                        Self::MissingSymbol => {
+                                write!(f, "Password must contain at least one special character")
+                        }
+                        Self::TooCommon => write!(f, "Password is too common"),
+                        Self::ContainsIdentifier => {
+                                write!(f, "Password must not contain your email or username")
+                        }
+                        Self::RecentlyUsed => write!(f, "Password was used recently"),
+                }
+        }
+}
+
+/// Whether `pwd` is on the common-passwords list. Compatibility forms (full-width letters,
+/// ligatures) and case are folded first, so `Ｐａｓｓｗｏｒｄ１` matches `password1`.
+pub fn is_common_password(pwd: &str) -> bool {
+        COMMON_PASSWORDS.contains(&normalize_for_comparison(pwd))
+}
+
+fn normalize_for_comparison(pwd: &str) -> String {
+        pwd.nfkc().collect::<String>().to_lowercase()
+}
+
+/// Reject `candidate` if it matches any of the given hashes (current password first, then
+/// the user's password history). Every hash is checked so the time taken does not reveal
+/// how far back a match was found.
+pub async fn ensure_not_recently_used(
+        candidate: &str,
+        recent_passwords: &[HashedPassword],
+) -> Result<(), PasswordError> {
+        let mut reused = false;
+        for password in recent_passwords {
+                reused |= password.verify_raw_password(candidate).await.is_ok();
+        }
+
+        match reused {
+                true => Err(PasswordError::RecentlyUsed),
+                false => Ok(()),
+        }
+}
+
+/// Prefixes of the bcrypt variants a legacy user base may have been hashed with
+const BCRYPT_PREFIXES: [&str; 3] = ["$2a$", "$2b$", "$2y$"];
+const BCRYPT_HASH_LEN: usize = 60;
+
+fn is_bcrypt_hash(hash: &str) -> bool {
+        BCRYPT_PREFIXES.iter().any(|prefix| hash.starts_with(prefix))
+}
+
 /// Helper function to compute password hash
 /// NOTE: Hashing is a CPU-intensive operation. To avoid blocking other async tasks, perform hashing on a separate thread pool (tokio::task::spawn_blocking)
 #[tracing::instrument(name = "Compute password hash", skip_all)]
-async fn compute_password_hash(password: String) -> Result<String, Box<dyn Error + Send + Sync>> {
+async fn compute_password_hash(
+        password: String,
+        peppers: &Peppers,
+) -> Result<HashedPassword, Box<dyn Error + Send + Sync>> {
         // This line retrieves the current span from the tracing context.
         // The span represents the execution context for the compute_password_hash function.
         let current_span: tracing::Span = tracing::Span::current(); // New!
-        let password = password.to_owned();
+        let pepper_version = peppers.current();
+        let password = peppers.apply(pepper_version, password.as_bytes())?;
 
         let result = tokio::task::spawn_blocking(move || {
                 // This code block ensures that the operations within the closure are executed within the context of the current span.
                 // This is especially useful for tracing operations that are performed in a different thread or task, such as within tokio::task::spawn_blocking.
                 current_span.in_scope(|| {
-                        let salt: SaltString = SaltString::generate(&mut OsRng);
-                        let password_hash = Argon2::new(
-                                Algorithm::Argon2id,
-                                Version::V0x13,
-                                Params::new(15000, 2, 1, None)?,
-                        )
-                        .hash_password(password.as_bytes(), &salt)?
-                        .to_string();
-
-                        Ok(password_hash)
+                        let hash = PASSWORD_HASHER.hasher().hash(&password)?;
+
+                        Ok(HashedPassword {
+                                hash,
+                                pepper_version,
+                        })
                 })
         })
         .await;
@@ -87,28 +486,9 @@ async fn compute_password_hash(password: String) -> Result<String, Box<dyn Error
         result?
 }
 
-async fn validate_raw_password(pwd: &str) -> Result<(), String> {
-        // Validate password length (adjust min/max as needed)
-        if pwd.is_empty() {
-                return Err("Password cannot be empty".to_string());
-        }
-        if pwd.chars().count() < 8 {
-                return Err("Password must be at least 8 characters".to_string());
-        }
-        if pwd.chars().count() > 128 {
-                return Err("Password must not exceed 128 characters".to_string());
-        }
-        // Validate password contains an uppercase letter, a digit, and a special characer
-        if !pwd.chars().any(|c| c.is_ascii_uppercase()) {
-                return Err("Password must contain at least one uppercase letter".to_string());
-        }
-        if !pwd.chars().any(|c| c.is_ascii_digit()) {
-                return Err("Password must contain at least one digit".to_string());
-        }
-        if !pwd.chars().any(|c| c.is_ascii_alphanumeric()) {
-                return Err("Password must contain at least one special character".to_string());
-        }
-        Ok(())
+/// Every rule of the default policy that `pwd` breaks
+pub fn password_rule_violations(pwd: &str) -> Vec<PasswordError> {
+        PasswordPolicy::default().violations(pwd)
 }
 
 impl std::fmt::Display for HashedPassword {
@@ -120,19 +500,27 @@ impl std::fmt::Display for HashedPassword {
 
 impl PartialEq<str> for HashedPassword {
         fn eq(&self, other: &str) -> bool {
-                self.0.as_str() == other
+                self.hash.as_str() == other
         }
 }
 
 impl AsRef<str> for HashedPassword {
         fn as_ref(&self) -> &str {
-                &self.0
+                &self.hash
         }
 }
 
 #[cfg(test)]
 mod tests {
-        use super::HashedPassword;
+        use super::{
+                compute_password_hash, ensure_not_recently_used, generate_temporary_password,
+                is_common_password, password_rule_violations, HashedPassword, PasswordError,
+                PasswordPolicy,
+        };
+        use crate::domain::{
+                Argon2PasswordHasher, Email, PasswordHasher as _, PasswordHasherKind, Peppers,
+                ScryptPasswordHasher, Username,
+        };
         use argon2::{
                 password_hash::{rand_core::OsRng, SaltString},
                 Algorithm, Argon2, Params, PasswordHasher, Version,
@@ -142,6 +530,14 @@ mod tests {
         use quickcheck::Gen;
         use quickcheck_macros::quickcheck;
         use rand::SeedableRng;
+        use std::{
+                sync::{
+                        atomic::{AtomicU64, Ordering},
+                        Arc,
+                },
+                time::{Duration, Instant},
+        };
+        use tokio::task::JoinSet;
 
         #[tokio::test]
         async fn empty_string_is_rejected() {
@@ -155,6 +551,236 @@ mod tests {
                 assert!(HashedPassword::parse(password).await.is_err());
         }
 
+        #[tokio::test]
+        async fn fingerprint_changes_with_the_hash() {
+                let first = HashedPassword::parse("ValidPassword123").await.unwrap();
+                let second = HashedPassword::parse("ValidPassword123").await.unwrap();
+
+                assert_eq!(first.fingerprint(), first.clone().fingerprint());
+                assert_eq!(first.fingerprint().len(), 16);
+                // Same password, fresh salt
+                assert_ne!(first.fingerprint(), second.fingerprint());
+        }
+
+        // `#[tokio::test]` runs on a single thread: a hash computed on it would stall the
+        // ticker for the whole hash, hundreds of milliseconds in a debug build
+        #[tokio::test]
+        async fn concurrent_hashing_does_not_starve_other_tasks() {
+                let max_gap_ms = Arc::new(AtomicU64::new(0));
+                let ticker = tokio::spawn({
+                        let max_gap_ms = Arc::clone(&max_gap_ms);
+                        async move {
+                                let mut interval = tokio::time::interval(Duration::from_millis(10));
+                                let mut last = Instant::now();
+                                loop {
+                                        interval.tick().await;
+                                        let gap = last.elapsed().as_millis() as u64;
+                                        max_gap_ms.fetch_max(gap, Ordering::Relaxed);
+                                        last = Instant::now();
+                                }
+                        }
+                });
+
+                let mut hashes = JoinSet::new();
+                for _ in 0..8 {
+                        hashes.spawn(HashedPassword::parse("ValidPassword123"));
+                }
+                while let Some(hashed) = hashes.join_next().await {
+                        assert!(hashed.unwrap().is_ok());
+                }
+                ticker.abort();
+
+                let max_gap_ms = max_gap_ms.load(Ordering::Relaxed);
+                assert!(max_gap_ms < 250, "ticker stalled for {max_gap_ms}ms");
+        }
+
+        #[test]
+        fn every_broken_rule_is_reported() {
+                assert_eq!(
+                        password_rule_violations("short")
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>(),
+                        vec![
+                                "Password must be at least 8 characters",
+                                "Password must contain at least one uppercase letter",
+                                "Password must contain at least one digit",
+                        ]
+                );
+                assert!(password_rule_violations("ValidPassword123").is_empty());
+        }
+
+        #[tokio::test]
+        async fn symbol_requiring_policy_rejects_alphanumeric_passwords() {
+                let policy = PasswordPolicy {
+                        require_symbol: true,
+                        ..PasswordPolicy::default()
+                };
+
+                assert_eq!(
+                        policy.violations("ValidPassword123"),
+                        vec![PasswordError::MissingSymbol]
+                );
+                assert!(HashedPassword::parse_with_policy("ValidPassword123", &policy)
+                        .await
+                        .is_err());
+                assert!(HashedPassword::parse_with_policy("ValidPassword123!", &policy)
+                        .await
+                        .is_ok());
+        }
+
+        #[tokio::test]
+        async fn longer_minimum_policy_rejects_default_length_passwords() {
+                let policy = PasswordPolicy {
+                        min_len: 12,
+                        require_lower: true,
+                        ..PasswordPolicy::default()
+                };
+
+                assert_eq!(policy.violations("Passwor1"), vec![PasswordError::TooShort(12)]);
+                assert_eq!(
+                        PasswordError::TooShort(12).to_string(),
+                        "Password must be at least 12 characters"
+                );
+                assert_eq!(
+                        policy.violations("SUNFLOWER1234"),
+                        vec![PasswordError::MissingLowercase]
+                );
+                assert!(HashedPassword::parse_with_policy("Sunflower1234", &policy).await.is_ok());
+        }
+
+        #[test]
+        fn temporary_password_meets_a_strict_policy() {
+                let policy = PasswordPolicy {
+                        min_len: 24,
+                        require_lower: true,
+                        require_symbol: true,
+                        ..PasswordPolicy::default()
+                };
+                let email = Email::parse("alice@example.com").unwrap();
+
+                for _ in 0..50 {
+                        let pwd = generate_temporary_password(&policy, &email, None).unwrap();
+                        assert_eq!(pwd.chars().count(), 24);
+                        assert!(policy.violations_with_context(&pwd, &email, None).is_empty());
+                }
+        }
+
+        #[test]
+        fn temporary_password_gives_up_on_a_policy_nothing_meets() {
+                let email = Email::parse("alice@example.com").unwrap();
+                let too_short = PasswordPolicy {
+                        min_len: 1,
+                        max_len: 3,
+                        ..PasswordPolicy::default()
+                };
+                let inverted = PasswordPolicy {
+                        min_len: 30,
+                        max_len: 20,
+                        ..PasswordPolicy::default()
+                };
+
+                assert_eq!(generate_temporary_password(&too_short, &email, None), None);
+                assert_eq!(generate_temporary_password(&inverted, &email, None), None);
+        }
+
+        #[tokio::test]
+        async fn password_containing_the_email_local_part_is_rejected() {
+                let email = Email::parse("john@x.com").unwrap();
+                let policy = PasswordPolicy::default();
+
+                assert_eq!(
+                        policy.violations_with_context("John12345", &email, None),
+                        vec![PasswordError::ContainsIdentifier]
+                );
+                assert!(HashedPassword::parse_with_context("John12345", &policy, &email, None)
+                        .await
+                        .is_err());
+                // The context-free parse has no identifier to compare against
+                assert!(HashedPassword::parse("John12345").await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn password_containing_the_username_is_rejected() {
+                let email = Email::parse("someone@x.com").unwrap();
+                let username = Username::parse("skywalker").unwrap();
+
+                assert_eq!(
+                        PasswordPolicy::default().violations_with_context(
+                                "SKYWALKER2024",
+                                &email,
+                                Some(&username)
+                        ),
+                        vec![PasswordError::ContainsIdentifier]
+                );
+        }
+
+        #[tokio::test]
+        async fn unrelated_strong_password_is_accepted_with_context() {
+                let email = Email::parse("john@x.com").unwrap();
+                let username = Username::parse("johnny").unwrap();
+                let policy = PasswordPolicy::default();
+
+                assert!(policy
+                        .violations_with_context("Vq7#mZ2!pLx9", &email, Some(&username))
+                        .is_empty());
+                assert!(HashedPassword::parse_with_context(
+                        "Vq7#mZ2!pLx9",
+                        &policy,
+                        &email,
+                        Some(&username)
+                )
+                .await
+                .is_ok());
+        }
+
+        #[test]
+        fn identifier_check_skips_short_identifiers_and_can_be_disabled() {
+                let email = Email::parse("jo@x.com").unwrap();
+                assert!(PasswordPolicy::default()
+                        .violations_with_context("Jo123456", &email, None)
+                        .is_empty());
+
+                let email = Email::parse("john@x.com").unwrap();
+                let policy = PasswordPolicy {
+                        reject_identifiers: false,
+                        ..PasswordPolicy::default()
+                };
+                assert!(policy.violations_with_context("John12345", &email, None).is_empty());
+        }
+
+        #[tokio::test]
+        async fn common_password_is_rejected_as_too_common() {
+                // Meets every other rule of the default policy
+                assert_eq!(password_rule_violations("Password1"), vec![PasswordError::TooCommon]);
+                assert_eq!(PasswordError::TooCommon.to_string(), "Password is too common");
+                assert!(HashedPassword::parse("Password1").await.is_err());
+        }
+
+        #[tokio::test]
+        async fn strong_random_password_is_not_too_common() {
+                assert!(!is_common_password("Vq7#mZ2!pLx9"));
+                assert!(HashedPassword::parse("Vq7#mZ2!pLx9").await.is_ok());
+        }
+
+        #[test]
+        fn common_password_check_ignores_case_and_compatibility_forms() {
+                assert!(is_common_password("PASSWORD1"));
+                assert!(is_common_password("QwErTy123"));
+                // Full-width characters normalize to their ASCII equivalents
+                assert!(is_common_password("Ｐａｓｓｗｏｒｄ１"));
+        }
+
+        #[test]
+        fn common_password_check_can_be_disabled() {
+                let policy = PasswordPolicy {
+                        reject_common: false,
+                        ..PasswordPolicy::default()
+                };
+
+                assert!(policy.violations("Password1").is_empty());
+        }
+
         #[test]
         fn can_parse_valid_argon2_hash() {
                 let raw_password = "TestPassword123";
@@ -198,6 +824,118 @@ mod tests {
                 assert_eq!(result.unwrap(), ());
         }
 
+        #[tokio::test]
+        async fn can_verify_legacy_bcrypt_hash() {
+                let raw_password = "TestPassword123";
+                let hash_string = bcrypt::hash(raw_password, 4).unwrap();
+
+                let hash_password = HashedPassword::parse_password_hash(hash_string).unwrap();
+
+                // Whatever hasher is configured, a bcrypt hash is replaced at the next login
+                assert!(hash_password.needs_rehash_for(PasswordHasherKind::Argon2, 0));
+                assert!(hash_password.needs_rehash_for(PasswordHasherKind::Scrypt, 0));
+                assert!(hash_password.verify_raw_password(raw_password).await.is_ok());
+                assert!(hash_password.verify_raw_password("WrongPassword123").await.is_err());
+        }
+
+        #[tokio::test]
+        async fn can_verify_scrypt_hash() {
+                let raw_password = "TestPassword123";
+                let hash_string = ScryptPasswordHasher.hash(raw_password.as_bytes()).unwrap();
+
+                let hash_password = HashedPassword::parse_password_hash(hash_string).unwrap();
+
+                assert!(hash_password.as_ref().starts_with("$scrypt$"));
+                assert!(hash_password.verify_raw_password(raw_password).await.is_ok());
+                assert!(hash_password.verify_raw_password("WrongPassword123").await.is_err());
+        }
+
+        #[tokio::test]
+        async fn switching_the_hasher_keeps_existing_argon2_hashes_verifiable() {
+                let raw_password = "TestPassword123";
+                let argon2_hash = HashedPassword::parse_password_hash(
+                        Argon2PasswordHasher.hash(raw_password.as_bytes()).unwrap(),
+                )
+                .unwrap();
+
+                // With scrypt configured the argon2 hash still verifies, and is flagged so the
+                // next login stores a scrypt hash instead
+                assert!(argon2_hash.verify_raw_password(raw_password).await.is_ok());
+                assert!(argon2_hash.needs_rehash_for(PasswordHasherKind::Scrypt, 0));
+                assert!(!argon2_hash.needs_rehash_for(PasswordHasherKind::Argon2, 0));
+        }
+
+        #[tokio::test]
+        async fn hash_made_with_an_old_pepper_verifies_and_is_rehashed_with_the_current_one() {
+                let raw_password = "TestPassword123";
+                let before = Peppers::new(1, [(1, "old-pepper".to_owned())]).unwrap();
+                let old_hash =
+                        compute_password_hash(raw_password.to_owned(), &before).await.unwrap();
+                assert_eq!(old_hash.pepper_version(), 1);
+
+                // Rotated: v2 is current, v1 is kept so existing hashes still verify
+                let rotated = Peppers::new(
+                        2,
+                        [(1, "old-pepper".to_owned()), (2, "new-pepper".to_owned())],
+                )
+                .unwrap();
+                assert!(old_hash.verify_raw_password_with(raw_password, &rotated).await.is_ok());
+                assert!(old_hash
+                        .verify_raw_password_with("WrongPassword123", &rotated)
+                        .await
+                        .is_err());
+                assert!(old_hash.needs_rehash_for(PasswordHasherKind::Argon2, rotated.current()));
+
+                let new_hash =
+                        compute_password_hash(raw_password.to_owned(), &rotated).await.unwrap();
+                assert_eq!(new_hash.pepper_version(), 2);
+                assert!(!new_hash.needs_rehash_for(PasswordHasherKind::Argon2, rotated.current()));
+                assert!(new_hash.verify_raw_password_with(raw_password, &rotated).await.is_ok());
+
+                // The pepper is part of what was hashed: without it the hash does not verify
+                let unpeppered = new_hash.clone().with_pepper_version(0);
+                assert!(unpeppered.verify_raw_password_with(raw_password, &rotated).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn only_hashes_below_the_current_argon2_costs_need_rehash() {
+                let salt = SaltString::generate(&mut OsRng);
+                let weak = Argon2::new(
+                        Algorithm::Argon2id,
+                        Version::V0x13,
+                        Params::new(4096, 1, 1, None).unwrap(),
+                )
+                .hash_password(b"TestPassword123", &salt)
+                .unwrap()
+                .to_string();
+
+                assert!(HashedPassword::parse_password_hash(weak).unwrap().needs_rehash());
+                assert!(!HashedPassword::parse("TestPassword123").await.unwrap().needs_rehash());
+        }
+
+        #[test]
+        fn truncated_bcrypt_hash_is_rejected() {
+                let hash_string = bcrypt::hash("TestPassword123", 4).unwrap();
+                assert!(HashedPassword::parse_password_hash(hash_string[..40].to_owned()).is_err());
+        }
+
+        #[tokio::test]
+        async fn recently_used_password_is_rejected() {
+                let current = HashedPassword::parse("CurrentPassword1").await.unwrap();
+                let previous = HashedPassword::parse("PreviousPassword1").await.unwrap();
+                let recent = [current, previous];
+
+                assert_eq!(
+                        ensure_not_recently_used("PreviousPassword1", &recent).await,
+                        Err(PasswordError::RecentlyUsed)
+                );
+                assert_eq!(
+                        ensure_not_recently_used("CurrentPassword1", &recent).await,
+                        Err(PasswordError::RecentlyUsed)
+                );
+                assert!(ensure_not_recently_used("BrandNewPassword1", &recent).await.is_ok());
+        }
+
         #[derive(Debug, Clone)]
         struct ValidPasswordFixture(pub String);
 
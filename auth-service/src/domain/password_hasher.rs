@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use argon2::{
+        password_hash::{self, rand_core::OsRng, PasswordHash, PasswordVerifier, SaltString},
+        Algorithm, Argon2, Params, Version,
+};
+use scrypt::Scrypt;
+
+use crate::utils::constants::{ARGON2_ITERATIONS, ARGON2_MEMORY_KIB, ARGON2_PARALLELISM};
+
+/// Computes new password hashes as PHC strings. Only hashing goes through the configured
+/// hasher: verification reads the algorithm from the stored hash (see `verify_password_hash`),
+/// so switching `PASSWORD_HASHER` never locks out users whose hash was made by another one.
+pub trait PasswordHasher: Send + Sync {
+        /// PHC identifier of the hashes this hasher produces
+        fn algorithm(&self) -> &'static str;
+        /// Hash `password` under a fresh random salt
+        fn hash(&self, password: &[u8]) -> Result<String, password_hash::Error>;
+}
+
+/// Argon2id, with the costs from `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`, and
+/// `ARGON2_PARALLELISM`
+pub struct Argon2PasswordHasher;
+
+impl PasswordHasher for Argon2PasswordHasher {
+        fn algorithm(&self) -> &'static str {
+                argon2::ARGON2ID_IDENT.as_str()
+        }
+
+        fn hash(&self, password: &[u8]) -> Result<String, password_hash::Error> {
+                use argon2::PasswordHasher as _;
+
+                let salt = SaltString::generate(&mut OsRng);
+                let hash =
+                        Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_target_params()?)
+                                .hash_password(password, &salt)?;
+                Ok(hash.to_string())
+        }
+}
+
+/// scrypt, with the crate's recommended costs
+pub struct ScryptPasswordHasher;
+
+impl PasswordHasher for ScryptPasswordHasher {
+        fn algorithm(&self) -> &'static str {
+                scrypt::ALG_ID.as_str()
+        }
+
+        fn hash(&self, password: &[u8]) -> Result<String, password_hash::Error> {
+                use scrypt::password_hash::PasswordHasher as _;
+
+                let salt = SaltString::generate(&mut OsRng);
+                Ok(Scrypt.hash_password(password, &salt)?.to_string())
+        }
+}
+
+/// Which `PasswordHasher` new hashes are made with, set by `PASSWORD_HASHER`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordHasherKind {
+        #[default]
+        Argon2,
+        Scrypt,
+}
+
+impl PasswordHasherKind {
+        pub fn hasher(self) -> &'static dyn PasswordHasher {
+                match self {
+                        Self::Argon2 => &Argon2PasswordHasher,
+                        Self::Scrypt => &ScryptPasswordHasher,
+                }
+        }
+}
+
+impl FromStr for PasswordHasherKind {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                        "argon2" => Ok(Self::Argon2),
+                        "scrypt" => Ok(Self::Scrypt),
+                        other => Err(format!("Unknown password hasher: {other}")),
+                }
+        }
+}
+
+/// Check `password` against a PHC hash made by any of the supported hashers, picked by the
+/// hash's own algorithm identifier
+pub fn verify_password_hash(password: &[u8], hash: &str) -> Result<(), password_hash::Error> {
+        let hash = PasswordHash::new(hash)?;
+
+        if hash.algorithm == scrypt::ALG_ID {
+                Scrypt.verify_password(password, &hash)
+        } else {
+                Argon2::default().verify_password(password, &hash)
+        }
+}
+
+/// Argon2 costs for new hashes, from `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`, and
+/// `ARGON2_PARALLELISM`
+pub(crate) fn argon2_target_params() -> Result<Params, argon2::Error> {
+        Params::new(*ARGON2_MEMORY_KIB, *ARGON2_ITERATIONS, *ARGON2_PARALLELISM, None)
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_each_hasher_verifies_its_own_hashes() {
+                for kind in [PasswordHasherKind::Argon2, PasswordHasherKind::Scrypt] {
+                        let hasher = kind.hasher();
+                        let hash = hasher.hash(b"ValidPassword123").unwrap();
+
+                        assert_eq!(
+                                PasswordHash::new(&hash).unwrap().algorithm.as_str(),
+                                hasher.algorithm()
+                        );
+                        assert!(verify_password_hash(b"ValidPassword123", &hash).is_ok());
+                        assert!(verify_password_hash(b"WrongPassword123", &hash).is_err());
+                }
+        }
+
+        #[test]
+        fn test_hasher_kind_parses_known_names() {
+                assert_eq!("argon2".parse(), Ok(PasswordHasherKind::Argon2));
+                assert_eq!("scrypt".parse(), Ok(PasswordHasherKind::Scrypt));
+                assert!("bcrypt".parse::<PasswordHasherKind>().is_err());
+        }
+}
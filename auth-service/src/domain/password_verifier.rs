@@ -0,0 +1,30 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::domain::HashedPassword;
+
+/// Checks a raw password against a stored hash. Injectable so tests can observe that the
+/// check runs, e.g. on the dummy path `PostgresUserStore::validate_user` takes for unknown users.
+#[async_trait]
+pub trait PasswordHashVerifier {
+        async fn verify(
+                &self,
+                hash: &HashedPassword,
+                password_candidate: &str,
+        ) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// Default verifier, backed by `HashedPassword::verify_raw_password`
+pub struct Argon2PasswordVerifier;
+
+#[async_trait]
+impl PasswordHashVerifier for Argon2PasswordVerifier {
+        async fn verify(
+                &self,
+                hash: &HashedPassword,
+                password_candidate: &str,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+                hash.verify_raw_password(password_candidate).await
+        }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::utils::constants::{
+        env::{
+                PASSWORD_PEPPER_CURRENT_ENV_VAR, PASSWORD_PEPPER_ENV_VAR_PREFIX, SECRET_FILE_SUFFIX,
+        },
+        read_secret_file,
+};
+
+/// Server-side secrets mixed into passwords before they are hashed, so a leaked users table
+/// cannot be cracked without them. Each pepper has a version, stored next to every hash made
+/// with it, which lets a pepper be rotated without locking anyone out: old hashes keep
+/// verifying with the pepper they were made with and are rehashed with the current one on the
+/// next successful login.
+///
+/// Version 0 means no pepper. It is what hashes from before peppering (and legacy bcrypt
+/// hashes) carry, and the current version when `PASSWORD_PEPPER_CURRENT` is unset.
+#[derive(Clone, Default)]
+pub struct Peppers {
+        current: u16,
+        secrets: HashMap<u16, String>,
+}
+
+impl Peppers {
+        /// Peppers by version, with new hashes made under `current`. Fails when `current`
+        /// names a version with no secret, or a secret is empty.
+        pub fn new(
+                current: u16,
+                secrets: impl IntoIterator<Item = (u16, String)>,
+        ) -> Result<Self, String> {
+                let secrets: HashMap<u16, String> = secrets.into_iter().collect();
+
+                if secrets.contains_key(&0) {
+                        return Err("Pepper version 0 is reserved for unpeppered hashes".to_owned());
+                }
+                if let Some(version) = secrets.iter().find_map(|(v, s)| s.is_empty().then_some(v)) {
+                        return Err(format!("Pepper version {version} is empty"));
+                }
+                if current != 0 && !secrets.contains_key(&current) {
+                        return Err(format!("No pepper is set for current version {current}"));
+                }
+
+                Ok(Self {
+                        current,
+                        secrets,
+                })
+        }
+
+        /// Read `PASSWORD_PEPPER_V<n>` (or the file named by `PASSWORD_PEPPER_V<n>_FILE`, which
+        /// takes precedence) for every version `n` set, and `PASSWORD_PEPPER_CURRENT`. Panics on
+        /// an invalid combination, like other settings read from the environment.
+        pub fn from_env() -> Self {
+                dotenvy::dotenv().ok();
+
+                let mut secrets = HashMap::new();
+                let mut files = Vec::new();
+                for (name, value) in std::env::vars() {
+                        let Some(version) = name.strip_prefix(PASSWORD_PEPPER_ENV_VAR_PREFIX)
+                        else {
+                                continue;
+                        };
+                        // Blank counts as unset, as compose passes through unset variables
+                        if value.is_empty() {
+                                continue;
+                        }
+                        let (version, from_file) = match version.strip_suffix(SECRET_FILE_SUFFIX) {
+                                Some(version) => (version, true),
+                                None => (version, false),
+                        };
+                        let version = version.parse::<u16>().unwrap_or_else(|_| {
+                                panic!("{} has an invalid pepper version", name)
+                        });
+                        match from_file {
+                                true => files.push((version, name, value)),
+                                false => {
+                                        secrets.insert(version, value);
+                                }
+                        }
+                }
+                for (version, name, path) in files {
+                        secrets.insert(version, read_secret_file(&name, path.trim()));
+                }
+                let current = match std::env::var(PASSWORD_PEPPER_CURRENT_ENV_VAR) {
+                        Ok(value) if !value.trim().is_empty() => {
+                                value.trim().parse().unwrap_or_else(|_| {
+                                        panic!(
+                                                "{} has an invalid value: {}",
+                                                PASSWORD_PEPPER_CURRENT_ENV_VAR, value
+                                        )
+                                })
+                        }
+                        _ => 0,
+                };
+
+                Self::new(current, secrets).unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        /// Version new hashes are made with
+        pub fn current(&self) -> u16 {
+                self.current
+        }
+
+        /// `password` as it is handed to the hasher under pepper `version`: unchanged for
+        /// version 0, otherwise its HMAC-SHA256 keyed with that version's pepper
+        pub(crate) fn apply(&self, version: u16, password: &[u8]) -> Result<Vec<u8>, String> {
+                if version == 0 {
+                        return Ok(password.to_vec());
+                }
+                let secret = self
+                        .secrets
+                        .get(&version)
+                        .ok_or_else(|| format!("No pepper is set for version {version}"))?;
+
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                        .map_err(|e| e.to_string())?;
+                mac.update(password);
+
+                Ok(mac.finalize().into_bytes().to_vec())
+        }
+}
+
+impl std::fmt::Debug for Peppers {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the secrets themselves
+                let mut versions: Vec<_> = self.secrets.keys().collect();
+                versions.sort();
+                f.debug_struct("Peppers")
+                        .field("current", &self.current)
+                        .field("versions", &versions)
+                        .finish()
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_current_version_must_have_a_pepper() {
+                assert!(Peppers::new(0, []).is_ok());
+                assert!(Peppers::new(2, [(1, "old".to_owned())]).is_err());
+                assert!(Peppers::new(1, [(1, String::new())]).is_err());
+                assert!(Peppers::new(0, [(0, "zero".to_owned())]).is_err());
+        }
+
+        #[test]
+        fn test_each_version_peppers_differently() {
+                let peppers =
+                        Peppers::new(2, [(1, "old".to_owned()), (2, "new".to_owned())]).unwrap();
+
+                assert_eq!(peppers.apply(0, b"password").unwrap(), b"password");
+                assert_ne!(
+                        peppers.apply(1, b"password").unwrap(),
+                        peppers.apply(2, b"password").unwrap()
+                );
+                assert!(peppers.apply(3, b"password").is_err());
+        }
+
+        #[test]
+        fn test_debug_output_omits_the_secrets() {
+                let peppers = Peppers::new(1, [(1, "super-secret-pepper".to_owned())]).unwrap();
+
+                assert!(!format!("{peppers:?}").contains("super-secret-pepper"));
+        }
+}
@@ -0,0 +1,170 @@
+/// Shortest and longest number of digits (country code included) allowed by E.164
+const MIN_DIGITS: usize = 7;
+const MAX_DIGITS: usize = 15;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+        /// Parse a phone number and normalize it to E.164 (`+` followed by digits only)
+        ///
+        /// Requirements:
+        /// - Not empty
+        /// - Starts with `+` and a country code, which cannot begin with 0
+        /// - Only digits after that, optionally grouped with spaces, dashes, dots or parentheses
+        /// - (E.164) 7 to 15 digits in total
+        pub fn parse(phone_str: &str) -> Result<Self, PhoneNumberError> {
+                let phone_str = phone_str.trim();
+
+                if phone_str.is_empty() {
+                        return Err(PhoneNumberError::Empty);
+                }
+
+                let Some(rest) = phone_str.strip_prefix('+') else {
+                        return Err(PhoneNumberError::MissingCountryCode);
+                };
+
+                let mut digits = String::with_capacity(MAX_DIGITS);
+                for c in rest.chars() {
+                        match c {
+                                '0'..='9' => digits.push(c),
+                                ' ' | '-' | '.' | '(' | ')' => {}
+                                _ => return Err(PhoneNumberError::InvalidCharacter),
+                        }
+                }
+
+                if digits.is_empty() || digits.starts_with('0') {
+                        return Err(PhoneNumberError::MissingCountryCode);
+                }
+                if !(MIN_DIGITS..=MAX_DIGITS).contains(&digits.len()) {
+                        return Err(PhoneNumberError::InvalidLength);
+                }
+
+                Ok(PhoneNumber(format!("+{}", digits)))
+        }
+
+        /// Get the phone number as a string slice, in E.164 form
+        pub fn as_str(&self) -> &str {
+                &self.0
+        }
+}
+
+impl AsRef<str> for PhoneNumber {
+        fn as_ref(&self) -> &str {
+                &self.0
+        }
+}
+
+impl std::fmt::Display for PhoneNumber {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+        }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PhoneNumberError {
+        Empty,
+        /// No leading `+` and country code
+        MissingCountryCode,
+        /// Anything other than digits and the allowed separators
+        InvalidCharacter,
+        /// Fewer than 7 or more than 15 digits
+        InvalidLength,
+}
+
+impl std::fmt::Display for PhoneNumberError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        PhoneNumberError::Empty => write!(f, "Phone number cannot be empty"),
+                        PhoneNumberError::MissingCountryCode => {
+                                write!(f, "Phone number must start with + and a country code")
+                        }
+                        PhoneNumberError::InvalidCharacter => {
+                                write!(f, "Phone number contains invalid characters")
+                        }
+                        PhoneNumberError::InvalidLength => {
+                                write!(f, "Phone number must have between 7 and 15 digits")
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        // Valid phone number test cases
+        #[test]
+        fn test_valid_e164_numbers() {
+                for number in ["+14155552671", "+442071838750", "+6831234", "+861012345678901"] {
+                        let phone = PhoneNumber::parse(number).unwrap();
+                        assert_eq!(phone.as_str(), number);
+                }
+        }
+
+        #[test]
+        fn test_spaces_and_dashes_are_normalized() {
+                let phone = PhoneNumber::parse("+1 415-555-2671").unwrap();
+                assert_eq!(phone.as_str(), "+14155552671");
+        }
+
+        #[test]
+        fn test_parentheses_and_dots_are_normalized() {
+                let phone = PhoneNumber::parse("  +44 (20) 7183.8750 ").unwrap();
+                assert_eq!(phone.as_str(), "+442071838750");
+        }
+
+        // Invalid phone number test cases
+        #[test]
+        fn test_empty_string() {
+                assert_eq!(PhoneNumber::parse(""), Err(PhoneNumberError::Empty));
+                assert_eq!(PhoneNumber::parse("   "), Err(PhoneNumberError::Empty));
+        }
+
+        #[test]
+        fn test_missing_plus() {
+                assert_eq!(
+                        PhoneNumber::parse("14155552671"),
+                        Err(PhoneNumberError::MissingCountryCode)
+                );
+        }
+
+        #[test]
+        fn test_country_code_starting_with_zero() {
+                assert_eq!(
+                        PhoneNumber::parse("+04155552671"),
+                        Err(PhoneNumberError::MissingCountryCode)
+                );
+                assert_eq!(PhoneNumber::parse("+"), Err(PhoneNumberError::MissingCountryCode));
+        }
+
+        #[test]
+        fn test_invalid_characters() {
+                assert_eq!(
+                        PhoneNumber::parse("+1 415 CALL NOW"),
+                        Err(PhoneNumberError::InvalidCharacter)
+                );
+                assert_eq!(
+                        PhoneNumber::parse("+1415555+2671"),
+                        Err(PhoneNumberError::InvalidCharacter)
+                );
+        }
+
+        #[test]
+        fn test_too_short_or_too_long() {
+                assert_eq!(PhoneNumber::parse("+123456"), Err(PhoneNumberError::InvalidLength));
+                assert_eq!(
+                        PhoneNumber::parse("+1234567890123456"),
+                        Err(PhoneNumberError::InvalidLength)
+                );
+        }
+
+        // Trait implementation tests
+        #[test]
+        fn test_as_ref_and_display() {
+                let phone = PhoneNumber::parse("+1 415 555 2671").unwrap();
+                let phone_ref: &str = phone.as_ref();
+                assert_eq!(phone_ref, "+14155552671");
+                assert_eq!(format!("{}", phone), "+14155552671");
+        }
+}
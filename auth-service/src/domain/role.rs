@@ -0,0 +1,61 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+        #[default]
+        User,
+        Admin,
+}
+
+impl Role {
+        /// Parse a role as stored in the database
+        pub fn parse(role: &str) -> Result<Self, String> {
+                match role {
+                        "user" => Ok(Role::User),
+                        "admin" => Ok(Role::Admin),
+                        other => Err(format!("Unknown role: {other}")),
+                }
+        }
+
+        /// Get the role as it is stored in the database
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        Role::User => "user",
+                        Role::Admin => "admin",
+                }
+        }
+}
+
+impl AsRef<str> for Role {
+        fn as_ref(&self) -> &str {
+                self.as_str()
+        }
+}
+
+impl std::fmt::Display for Role {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.as_str())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_roundtrip() {
+                for role in [Role::User, Role::Admin] {
+                        assert_eq!(Role::parse(role.as_str()), Ok(role));
+                }
+        }
+
+        #[test]
+        fn test_parse_unknown_role() {
+                let result = Role::parse("superuser");
+                assert_eq!(result, Err("Unknown role: superuser".to_string()));
+        }
+
+        #[test]
+        fn test_default_is_user() {
+                assert_eq!(Role::default(), Role::User);
+        }
+}
@@ -0,0 +1,65 @@
+/// How a user receives their second-factor code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwoFAMethod {
+        /// A one-time code is emailed on every login
+        #[default]
+        Email,
+        /// The code comes from an enrolled authenticator app. Nothing verifies such codes yet,
+        /// so Postgres refuses to store this method
+        Totp,
+}
+
+impl TwoFAMethod {
+        /// Parse a 2FA method as stored in the database
+        pub fn parse(method: &str) -> Result<Self, String> {
+                match method {
+                        "email" => Ok(TwoFAMethod::Email),
+                        "totp" => Ok(TwoFAMethod::Totp),
+                        other => Err(format!("Unknown 2FA method: {other}")),
+                }
+        }
+
+        /// Get the 2FA method as it is stored in the database
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        TwoFAMethod::Email => "email",
+                        TwoFAMethod::Totp => "totp",
+                }
+        }
+}
+
+impl AsRef<str> for TwoFAMethod {
+        fn as_ref(&self) -> &str {
+                self.as_str()
+        }
+}
+
+impl std::fmt::Display for TwoFAMethod {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.as_str())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_roundtrip() {
+                for method in [TwoFAMethod::Email, TwoFAMethod::Totp] {
+                        assert_eq!(TwoFAMethod::parse(method.as_str()), Ok(method));
+                }
+        }
+
+        #[test]
+        fn test_parse_unknown_method() {
+                let result = TwoFAMethod::parse("sms");
+                assert_eq!(result, Err("Unknown 2FA method: sms".to_string()));
+        }
+
+        #[test]
+        fn test_default_is_email() {
+                assert_eq!(TwoFAMethod::default(), TwoFAMethod::Email);
+        }
+}
@@ -1,10 +1,39 @@
-use crate::domain::{email::Email, password::HashedPassword};
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+        display_name::DisplayName, email::Email, password::HashedPassword,
+        phone_number::PhoneNumber, role::Role, two_fa_method::TwoFAMethod, username::Username,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct User {
         pub email: Email,
         pub password: HashedPassword,
         pub requires_2fa: bool,
+        pub two_fa_method: TwoFAMethod,
+        pub role: Role,
+        /// Set when the account has been soft-deleted. Soft-deleted users are hidden from
+        /// `UserStore::get_user` but their row is kept so the account can be reactivated.
+        pub deleted_at: Option<DateTime<Utc>>,
+        /// Last successful login, starting at account creation. Accounts idle for longer than
+        /// `INACTIVITY_EXPIRY_DAYS` are soft-deleted by the inactivity expiry job.
+        pub last_login_at: DateTime<Utc>,
+        /// Optional contact number, e.g. for SMS 2FA
+        pub phone: Option<PhoneNumber>,
+        /// Optional login name, usable in place of the email at `/login`
+        pub username: Option<Username>,
+        /// Optional name the UI greets the user by
+        pub display_name: Option<DisplayName>,
+        /// Whether the owner has confirmed they receive mail at `email`. Accounts created
+        /// before verification existed count as verified.
+        pub email_verified: bool,
+        /// Logins are refused until this time
+        pub locked_until: Option<DateTime<Utc>>,
+        /// Set when the account has been banned; a banned account cannot log in
+        pub banned_at: Option<DateTime<Utc>>,
+        /// Set by an admin's forced password reset: the next login must choose a new password
+        /// before it gets a session
+        pub must_change_password: bool,
 }
 impl User {
         pub fn new(email: Email, password: HashedPassword, requires_2fa: bool) -> Self {
@@ -12,8 +41,59 @@ impl User {
                         email,
                         password,
                         requires_2fa,
+                        two_fa_method: TwoFAMethod::default(),
+                        role: Role::default(),
+                        deleted_at: None,
+                        last_login_at: Utc::now(),
+                        phone: None,
+                        username: None,
+                        display_name: None,
+                        email_verified: true,
+                        locked_until: None,
+                        banned_at: None,
+                        must_change_password: false,
                 }
         }
+        pub fn with_role(mut self, role: Role) -> Self {
+                self.role = role;
+                self
+        }
+        pub fn with_two_fa_method(mut self, two_fa_method: TwoFAMethod) -> Self {
+                self.two_fa_method = two_fa_method;
+                self
+        }
+        pub fn with_last_login_at(mut self, last_login_at: DateTime<Utc>) -> Self {
+                self.last_login_at = last_login_at;
+                self
+        }
+        pub fn with_phone(mut self, phone: PhoneNumber) -> Self {
+                self.phone = Some(phone);
+                self
+        }
+        pub fn with_username(mut self, username: Username) -> Self {
+                self.username = Some(username);
+                self
+        }
+        pub fn with_display_name(mut self, display_name: DisplayName) -> Self {
+                self.display_name = Some(display_name);
+                self
+        }
+        pub fn with_email_verified(mut self, email_verified: bool) -> Self {
+                self.email_verified = email_verified;
+                self
+        }
+        pub fn with_locked_until(mut self, locked_until: DateTime<Utc>) -> Self {
+                self.locked_until = Some(locked_until);
+                self
+        }
+        pub fn with_banned_at(mut self, banned_at: DateTime<Utc>) -> Self {
+                self.banned_at = Some(banned_at);
+                self
+        }
+        pub fn with_must_change_password(mut self, must_change_password: bool) -> Self {
+                self.must_change_password = must_change_password;
+                self
+        }
         pub fn email(&self) -> &Email {
                 &self.email
         }
@@ -35,4 +115,162 @@ impl User {
         pub fn requires_2fa(&self) -> bool {
                 self.requires_2fa
         }
+        pub fn two_fa_method(&self) -> TwoFAMethod {
+                self.two_fa_method
+        }
+        pub fn role(&self) -> Role {
+                self.role
+        }
+        pub fn is_admin(&self) -> bool {
+                self.role == Role::Admin
+        }
+        pub fn deleted_at(&self) -> Option<DateTime<Utc>> {
+                self.deleted_at
+        }
+        pub fn is_deleted(&self) -> bool {
+                self.deleted_at.is_some()
+        }
+        pub fn last_login_at(&self) -> DateTime<Utc> {
+                self.last_login_at
+        }
+        pub fn phone(&self) -> Option<&PhoneNumber> {
+                self.phone.as_ref()
+        }
+        pub fn username(&self) -> Option<&Username> {
+                self.username.as_ref()
+        }
+        pub fn display_name(&self) -> Option<&DisplayName> {
+                self.display_name.as_ref()
+        }
+        pub fn email_verified(&self) -> bool {
+                self.email_verified
+        }
+        pub fn locked_until(&self) -> Option<DateTime<Utc>> {
+                self.locked_until
+        }
+        pub fn banned_at(&self) -> Option<DateTime<Utc>> {
+                self.banned_at
+        }
+        pub fn must_change_password(&self) -> bool {
+                self.must_change_password
+        }
+        /// Whether the account may log in at `now`. A ban outranks a lock, which outranks an
+        /// unverified email, so the most lasting reason is the one reported.
+        pub fn standing(&self, now: DateTime<Utc>) -> AccountStanding {
+                if self.banned_at.is_some() {
+                        return AccountStanding::Banned;
+                }
+                if let Some(until) = self.locked_until.filter(|until| *until > now) {
+                        return AccountStanding::Locked {
+                                until,
+                        };
+                }
+                if !self.email_verified {
+                        return AccountStanding::EmailNotVerified;
+                }
+                AccountStanding::Good
+        }
+}
+
+/// Whether an account may log in; see `User::standing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountStanding {
+        Good,
+        Banned,
+        Locked {
+                until: DateTime<Utc>,
+        },
+        EmailNotVerified,
+}
+
+/// The serializable view of a [`User`]. Handlers that return user data must return this rather
+/// than `User`, which carries the password hash and deliberately derives no `Serialize`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PublicUser {
+        pub email: String,
+        #[serde(rename = "requires2FA")]
+        pub requires_2fa: bool,
+        pub role: Role,
+        /// Unix timestamp in seconds
+        pub last_login_at: i64,
+        /// Unix timestamp in seconds; only set for soft-deleted accounts
+        pub deleted_at: Option<i64>,
+        #[serde(default)]
+        pub display_name: Option<String>,
+}
+
+impl From<&User> for PublicUser {
+        fn from(user: &User) -> Self {
+                Self {
+                        email: user.email_str().to_owned(),
+                        requires_2fa: user.requires_2fa(),
+                        role: user.role(),
+                        last_login_at: user.last_login_at().timestamp(),
+                        deleted_at: user.deleted_at().map(|at| at.timestamp()),
+                        display_name: user.display_name().map(|name| name.as_str().to_owned()),
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        fn create_test_user() -> User {
+                let password = HashedPassword::parse_password_hash(
+                        "$argon2id$v=19$m=15000,t=2,p=1$c2FsdHNhbHRzYWx0$ZmFrZWhhc2hmYWtlaGFzaA"
+                                .to_owned(),
+                )
+                .unwrap();
+                User::new(Email::parse("test@example.com").unwrap(), password, true)
+                        .with_role(Role::Admin)
+                        .with_display_name(DisplayName::parse("Test User").unwrap())
+        }
+
+        #[test]
+        fn test_public_user_copies_public_fields() {
+                let user = create_test_user();
+                let public = PublicUser::from(&user);
+
+                assert_eq!(public.email, "test@example.com");
+                assert!(public.requires_2fa);
+                assert_eq!(public.role, Role::Admin);
+                assert_eq!(public.last_login_at, user.last_login_at().timestamp());
+                assert_eq!(public.deleted_at, None);
+                assert_eq!(public.display_name.as_deref(), Some("Test User"));
+        }
+
+        #[test]
+        fn test_public_user_json_has_no_password() {
+                let user = create_test_user();
+                let json = serde_json::to_value(PublicUser::from(&user)).unwrap();
+
+                // Check every key, not just the names we know about
+                let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+                assert!(keys.iter().all(|key| !key.to_lowercase().contains("password")));
+                assert!(!json.to_string().contains(user.password_str()));
+        }
+
+        #[test]
+        fn test_standing_reports_ban_then_lock_then_unverified() {
+                let now = Utc::now();
+                let user = create_test_user().with_email_verified(false);
+                assert_eq!(user.standing(now), AccountStanding::EmailNotVerified);
+
+                let until = now + chrono::Duration::hours(1);
+                let user = user.with_locked_until(until);
+                assert_eq!(
+                        user.standing(now),
+                        AccountStanding::Locked {
+                                until
+                        }
+                );
+                // Once the lock runs out, the next reason applies
+                assert_eq!(user.standing(until), AccountStanding::EmailNotVerified);
+
+                let user = user.with_banned_at(now);
+                assert_eq!(user.standing(now), AccountStanding::Banned);
+
+                assert_eq!(create_test_user().standing(now), AccountStanding::Good);
+        }
 }
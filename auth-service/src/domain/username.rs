@@ -0,0 +1,143 @@
+/// Shortest and longest username allowed
+const MIN_LEN: usize = 3;
+const MAX_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Username(String);
+
+impl Username {
+        /// Parse a username and normalize it to lowercase, so usernames are unique regardless
+        /// of case
+        ///
+        /// Requirements:
+        /// - Not empty
+        /// - 3 to 32 characters
+        /// - Starts with an ASCII letter
+        /// - Only ASCII letters, digits, `_`, `.` and `-`, so a username can never be
+        ///   mistaken for an email address
+        pub fn parse(username_str: &str) -> Result<Self, UsernameError> {
+                let username_str = username_str.trim();
+
+                if username_str.is_empty() {
+                        return Err(UsernameError::Empty);
+                }
+                if !username_str.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c)) {
+                        return Err(UsernameError::InvalidCharacter);
+                }
+                if !username_str.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                        return Err(UsernameError::MustStartWithLetter);
+                }
+                if !(MIN_LEN..=MAX_LEN).contains(&username_str.len()) {
+                        return Err(UsernameError::InvalidLength);
+                }
+
+                Ok(Username(username_str.to_ascii_lowercase()))
+        }
+
+        /// Get the username as a string slice, in lowercase
+        pub fn as_str(&self) -> &str {
+                &self.0
+        }
+}
+
+impl AsRef<str> for Username {
+        fn as_ref(&self) -> &str {
+                &self.0
+        }
+}
+
+impl std::fmt::Display for Username {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+        }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UsernameError {
+        Empty,
+        /// Anything other than ASCII letters, digits, `_`, `.` and `-`
+        InvalidCharacter,
+        MustStartWithLetter,
+        /// Fewer than 3 or more than 32 characters
+        InvalidLength,
+}
+
+impl std::fmt::Display for UsernameError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        UsernameError::Empty => write!(f, "Username cannot be empty"),
+                        UsernameError::InvalidCharacter => write!(
+                                f,
+                                "Username may only contain letters, digits, '_', '.' and '-'"
+                        ),
+                        UsernameError::MustStartWithLetter => {
+                                write!(f, "Username must start with a letter")
+                        }
+                        UsernameError::InvalidLength => {
+                                write!(f, "Username must be between 3 and 32 characters")
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        // Valid username test cases
+        #[test]
+        fn test_valid_usernames() {
+                for username in ["bob", "alice_92", "jean-luc.picard", "x".repeat(32).as_str()] {
+                        let parsed = Username::parse(username).unwrap();
+                        assert_eq!(parsed.as_str(), username);
+                }
+        }
+
+        #[test]
+        fn test_username_is_trimmed_and_lowercased() {
+                let username = Username::parse("  Alice_92 ").unwrap();
+                assert_eq!(username.as_str(), "alice_92");
+        }
+
+        // Invalid username test cases
+        #[test]
+        fn test_empty_string() {
+                assert_eq!(Username::parse(""), Err(UsernameError::Empty));
+                assert_eq!(Username::parse("   "), Err(UsernameError::Empty));
+        }
+
+        #[test]
+        fn test_email_is_not_a_username() {
+                assert_eq!(
+                        Username::parse("user@example.com"),
+                        Err(UsernameError::InvalidCharacter)
+                );
+        }
+
+        #[test]
+        fn test_invalid_characters() {
+                assert_eq!(Username::parse("bob smith"), Err(UsernameError::InvalidCharacter));
+                assert_eq!(Username::parse("bøb"), Err(UsernameError::InvalidCharacter));
+        }
+
+        #[test]
+        fn test_must_start_with_letter() {
+                assert_eq!(Username::parse("9lives"), Err(UsernameError::MustStartWithLetter));
+                assert_eq!(Username::parse("_bob"), Err(UsernameError::MustStartWithLetter));
+        }
+
+        #[test]
+        fn test_too_short_or_too_long() {
+                assert_eq!(Username::parse("bo"), Err(UsernameError::InvalidLength));
+                assert_eq!(Username::parse(&"x".repeat(33)), Err(UsernameError::InvalidLength));
+        }
+
+        // Trait implementation tests
+        #[test]
+        fn test_as_ref_and_display() {
+                let username = Username::parse("Bob").unwrap();
+                let username_ref: &str = username.as_ref();
+                assert_eq!(username_ref, "bob");
+                assert_eq!(format!("{}", username), "bob");
+        }
+}
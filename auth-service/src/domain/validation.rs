@@ -0,0 +1,104 @@
+/// A single input problem, reported against the request field that caused it
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FieldError {
+        pub field: String,
+        pub message: String,
+}
+
+/// Every input problem found in a request, so the client can flag all bad fields at once
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ValidationErrors {
+        pub fields: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+                self.fields.push(FieldError {
+                        field: field.into(),
+                        message: message.into(),
+                });
+        }
+
+        pub fn is_empty(&self) -> bool {
+                self.fields.is_empty()
+        }
+
+        /// Add an error for `field` when `value` is empty or only whitespace
+        pub fn require(&mut self, field: &str, value: &str) {
+                if value.trim().is_empty() {
+                        self.add(field, format!("{field} is required"));
+                }
+        }
+
+        /// `Ok` when no error was added
+        pub fn into_result(self) -> Result<(), ValidationErrors> {
+                if self.is_empty() {
+                        Ok(())
+                } else {
+                        Err(self)
+                }
+        }
+}
+
+/// Checks a request payload can be acted on at all, run by `ValidatedJson` and
+/// `ValidatedJsonOrForm` right after it deserializes. A failure is a 422, like a missing field; whether the values are acceptable
+/// (an email's format, the password policy) is left to the handler, which answers 400.
+pub trait Validate {
+        fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_add_keeps_every_error_in_order() {
+                let mut errors = ValidationErrors::new();
+                assert!(errors.is_empty());
+
+                errors.add("email", "Email has an invalid format");
+                errors.add("password", "Password must be at least 8 characters");
+
+                assert_eq!(
+                        errors.fields,
+                        vec![
+                                FieldError {
+                                        field: "email".to_owned(),
+                                        message: "Email has an invalid format".to_owned(),
+                                },
+                                FieldError {
+                                        field: "password".to_owned(),
+                                        message: "Password must be at least 8 characters"
+                                                .to_owned(),
+                                },
+                        ]
+                );
+        }
+
+        #[test]
+        fn test_require_rejects_blank_values_only() {
+                let mut errors = ValidationErrors::new();
+                errors.require("email", "not-an-email");
+                assert_eq!(errors.clone().into_result(), Ok(()));
+
+                errors.require("email", "");
+                errors.require("password", " \t ");
+                assert_eq!(
+                        errors.into_result().unwrap_err().fields,
+                        vec![
+                                FieldError {
+                                        field: "email".to_owned(),
+                                        message: "email is required".to_owned(),
+                                },
+                                FieldError {
+                                        field: "password".to_owned(),
+                                        message: "password is required".to_owned(),
+                                },
+                        ]
+                );
+        }
+}
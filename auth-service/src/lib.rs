@@ -8,8 +8,9 @@ pub mod services;
 pub mod utils;
 
 // Imports
+use arc_swap::ArcSwap;
 use axum::{
-        extract::Json,
+        extract::{connect_info::IntoMakeServiceWithConnectInfo, ConnectInfo, Json},
         http::{HeaderValue, Method, StatusCode},
         response::IntoResponse,
         routing::{get, get_service, post, MethodRouter},
@@ -20,30 +21,62 @@ use redis::{Client as RedisClient, Connection, RedisError};
 use reqwest::Url;
 use router::app_routes;
 use routes::{
-        handle_login, handle_login_or_signup, handle_logout, handle_signup, handle_verify_2fa,
-        handle_verify_token,
+        handle_ban_tokens, handle_change_email, handle_change_password, handle_check_email,
+        handle_confirm_email_change, handle_data_export, handle_debug_token,
+        handle_force_reset_password, handle_list_sessions, handle_login, handle_login_history,
+        handle_login_or_signup, handle_logout, handle_logout_redirect, handle_me, handle_metrics,
+        handle_password_policy, handle_reactivate_account, handle_readiness,
+        handle_request_magic_link, handle_required_password_change, handle_revoke_session,
+        handle_rotate_key, handle_security_summary, handle_session_status,
+        handle_set_maintenance_mode, handle_sign_out_other_sessions, handle_signup,
+        handle_verify_2fa, handle_verify_2fa_check, handle_verify_magic_link, handle_verify_token,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPoolOptions, Executor, PgPool, Pool, Postgres};
-use std::sync::Arc;
+use std::{
+        future::Future,
+        net::SocketAddr,
+        sync::{atomic::AtomicBool, Arc},
+        time::Duration,
+};
 use tokio::sync::RwLock;
 use tower_http::{
         cors::CorsLayer,
         services::{ServeDir, ServeFile},
 };
-use utils::fetch_assets;
+use utils::{api_only_fallback, fetch_assets};
 use uuid::Uuid;
 
+#[cfg(feature = "tls")]
+use axum_server::tls_rustls::{RustlsAcceptor, RustlsConfig};
+
 use crate::{
-        domain::{two_fa_code, BannedTokenStore, EmailClient, TwoFACodeStore, UserStore},
+        domain::{
+                two_fa_code, AuditLogStore, BannedTokenStore, CaptchaVerifier, Email, EmailClient,
+                FailedLoginStore, HashedPassword, IdempotencyStore, MagicLinkStore, MxResolver,
+                PendingEmailChangeStore, RateLimitStore, Role, SessionStore, TwoFACodeStore, User,
+                UserStore, UserStoreError,
+        },
         services::data_stores::{
                 postgres_user_store::PostgresUserStore, HashmapTwoFACodeStore,
-                HashsetBannedTokenStore, MockEmailClient, RedisBannedTokenStore,
-                RedisTwoFACodeStore,
+                HashsetBannedTokenStore, MockEmailClient, RedisAuditLogStore,
+                RedisBannedTokenStore, RedisFailedLoginStore, RedisIdempotencyStore,
+                RedisMagicLinkStore, RedisPendingEmailChangeStore, RedisRateLimitStore,
+                RedisSessionStore, RedisTwoFACodeStore,
         },
-        utils::constants::{
-                env::{DROPLET_URL_ENV_VAR, LOCALHOST_URL_ENV_VAR},
-                get_env_var, DATABASE_URL, REDIS_HOST_NAME,
+        services::failover_email_client::FailoverEmailClient,
+        services::retrying_email_client::RetryingEmailClient,
+        services::webhook_notifier::{WebhookEvent, WebhookNotifier},
+        utils::{
+                config::{AppConfig, TlsConfig},
+                constants::{
+                        env::{DROPLET_URL_ENV_VAR, LOCALHOST_URL_ENV_VAR},
+                        get_env_var, DATABASE_URL, EMAIL_RETRY_BACKOFF_MILLIS, EMAIL_SEND_ATTEMPTS,
+                        REDIS_HOST_NAME, REDIS_POOL_MAX_SIZE, REDIS_PORT,
+                        RUN_MIGRATIONS_ON_STARTUP,
+                },
+                key_ring::KeyRing,
+                metrics::Metrics,
         },
 };
 
@@ -52,15 +85,49 @@ pub type AppResult<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 pub type UserStoreType = Arc<RwLock<Box<dyn UserStore + Send + Sync>>>;
 pub type BannedTokenStoreType = Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>>;
 pub type TwoFACodeStoreType = Arc<RwLock<Box<dyn TwoFACodeStore + Send + Sync>>>;
+pub type IdempotencyStoreType = Arc<RwLock<Box<dyn IdempotencyStore + Send + Sync>>>;
+pub type SessionStoreType = Arc<RwLock<Box<dyn SessionStore + Send + Sync>>>;
+pub type FailedLoginStoreType = Arc<RwLock<Box<dyn FailedLoginStore + Send + Sync>>>;
+pub type AuditLogStoreType = Arc<RwLock<Box<dyn AuditLogStore + Send + Sync>>>;
+pub type PendingEmailChangeStoreType = Arc<RwLock<Box<dyn PendingEmailChangeStore + Send + Sync>>>;
+pub type MagicLinkStoreType = Arc<RwLock<Box<dyn MagicLinkStore + Send + Sync>>>;
+pub type RateLimitStoreType = Arc<RwLock<Box<dyn RateLimitStore + Send + Sync>>>;
 pub type EmailClientType = Arc<dyn EmailClient + Send + Sync>;
+pub type WebhookNotifierType = Arc<WebhookNotifier>;
+pub type CaptchaVerifierType = Arc<dyn CaptchaVerifier + Send + Sync>;
+pub type MxResolverType = Arc<dyn MxResolver + Send + Sync>;
+pub type KeyRingType = Arc<ArcSwap<KeyRing>>;
 pub type RedisResult = core::result::Result<RedisClient, RedisError>;
+pub type RedisPool = r2d2::Pool<RedisClient>;
+pub type RedisPooledConnection = r2d2::PooledConnection<RedisClient>;
 pub type HandlerResult<T> = core::result::Result<T, AuthAPIError>;
 
 pub struct AppState {
         pub user_store: UserStoreType,
         pub banned_token_store: BannedTokenStoreType,
         pub two_fa_code_store: TwoFACodeStoreType,
+        pub idempotency_store: IdempotencyStoreType,
+        pub session_store: SessionStoreType,
+        pub failed_login_store: FailedLoginStoreType,
+        pub audit_log_store: AuditLogStoreType,
+        pub pending_email_change_store: PendingEmailChangeStoreType,
+        pub magic_link_store: MagicLinkStoreType,
+        pub rate_limit_store: RateLimitStoreType,
         pub email_client: EmailClientType,
+        /// `None` when no `WEBHOOK_URL` is configured
+        pub webhook_notifier: Option<WebhookNotifierType>,
+        /// `None` when `CAPTCHA_ENABLED` is unset; signup then skips the CAPTCHA check
+        pub captcha_verifier: Option<CaptchaVerifierType>,
+        /// `None` when `VERIFY_EMAIL_MX` is unset; signup then skips the MX check
+        pub mx_resolver: Option<MxResolverType>,
+        /// JWT signing keys; replaced as a whole by `/admin/rotate-key`
+        pub key_ring: KeyRingType,
+        /// Read-only maintenance; starts from `MAINTENANCE_MODE`, switched by
+        /// `/admin/maintenance`, and enforced by `maintenance_guard`
+        pub maintenance_mode: Arc<AtomicBool>,
+        /// Counters served at `/metrics`
+        pub metrics: Arc<Metrics>,
+        pub config: Arc<AppConfig>,
 }
 
 #[derive(Default, Clone)]
@@ -68,7 +135,19 @@ pub struct AppStateBuilder {
         pub user_store: Option<UserStoreType>,
         pub banned_token_store: Option<BannedTokenStoreType>,
         pub two_fa_code_store: Option<TwoFACodeStoreType>,
+        pub idempotency_store: Option<IdempotencyStoreType>,
+        pub session_store: Option<SessionStoreType>,
+        pub failed_login_store: Option<FailedLoginStoreType>,
+        pub audit_log_store: Option<AuditLogStoreType>,
+        pub pending_email_change_store: Option<PendingEmailChangeStoreType>,
+        pub magic_link_store: Option<MagicLinkStoreType>,
+        pub rate_limit_store: Option<RateLimitStoreType>,
         pub email_client: Option<EmailClientType>,
+        pub webhook_notifier: Option<WebhookNotifierType>,
+        pub captcha_verifier: Option<CaptchaVerifierType>,
+        pub mx_resolver: Option<MxResolverType>,
+        pub key_ring: Option<KeyRing>,
+        pub config: Option<AppConfig>,
 }
 
 impl AppStateBuilder {
@@ -91,17 +170,104 @@ impl AppStateBuilder {
                 self
         }
 
+        pub fn idempotency_store(mut self, idempotency_store: IdempotencyStoreType) -> Self {
+                self.idempotency_store = Some(idempotency_store);
+                self
+        }
+
+        pub fn session_store(mut self, session_store: SessionStoreType) -> Self {
+                self.session_store = Some(session_store);
+                self
+        }
+
+        pub fn failed_login_store(mut self, failed_login_store: FailedLoginStoreType) -> Self {
+                self.failed_login_store = Some(failed_login_store);
+                self
+        }
+
+        pub fn audit_log_store(mut self, audit_log_store: AuditLogStoreType) -> Self {
+                self.audit_log_store = Some(audit_log_store);
+                self
+        }
+
+        pub fn pending_email_change_store(
+                mut self,
+                pending_email_change_store: PendingEmailChangeStoreType,
+        ) -> Self {
+                self.pending_email_change_store = Some(pending_email_change_store);
+                self
+        }
+
+        pub fn magic_link_store(mut self, magic_link_store: MagicLinkStoreType) -> Self {
+                self.magic_link_store = Some(magic_link_store);
+                self
+        }
+
+        pub fn rate_limit_store(mut self, rate_limit_store: RateLimitStoreType) -> Self {
+                self.rate_limit_store = Some(rate_limit_store);
+                self
+        }
+
         pub fn email_client(mut self, email_client: EmailClientType) -> Self {
                 self.email_client = Some(email_client);
                 self
         }
 
+        /// Optional – account events are not reported when not set
+        pub fn webhook_notifier(mut self, webhook_notifier: WebhookNotifier) -> Self {
+                self.webhook_notifier = Some(Arc::new(webhook_notifier));
+                self
+        }
+
+        /// Optional – signup does not require a CAPTCHA when not set
+        pub fn captcha_verifier(mut self, captcha_verifier: CaptchaVerifierType) -> Self {
+                self.captcha_verifier = Some(captcha_verifier);
+                self
+        }
+
+        /// Optional – signup does not check the email domain's MX records when not set
+        pub fn mx_resolver(mut self, mx_resolver: MxResolverType) -> Self {
+                self.mx_resolver = Some(mx_resolver);
+                self
+        }
+
+        /// Optional – falls back to `KeyRing::default()` when not set
+        pub fn key_ring(mut self, key_ring: KeyRing) -> Self {
+                self.key_ring = Some(key_ring);
+                self
+        }
+
+        /// Optional – falls back to `AppConfig::default()` when not set
+        pub fn config(mut self, config: AppConfig) -> Self {
+                self.config = Some(config);
+                self
+        }
+
         pub fn build(self) -> AppState {
+                let config = self.config.unwrap_or_default();
                 AppState {
                         user_store: self.user_store.expect("User Store"),
                         banned_token_store: self.banned_token_store.expect("Banned Token Store"),
                         two_fa_code_store: self.two_fa_code_store.expect("2FA Code Store"),
+                        idempotency_store: self.idempotency_store.expect("Idempotency Store"),
+                        session_store: self.session_store.expect("Session Store"),
+                        failed_login_store: self.failed_login_store.expect("Failed Login Store"),
+                        audit_log_store: self.audit_log_store.expect("Audit Log Store"),
+                        pending_email_change_store: self
+                                .pending_email_change_store
+                                .expect("Pending Email Change Store"),
+                        magic_link_store: self.magic_link_store.expect("Magic Link Store"),
+                        rate_limit_store: self.rate_limit_store.expect("Rate Limit Store"),
                         email_client: self.email_client.expect("Email Client"),
+                        webhook_notifier: self.webhook_notifier,
+                        captcha_verifier: self.captcha_verifier,
+                        mx_resolver: self.mx_resolver,
+                        key_ring: Arc::new(ArcSwap::from_pointee(
+                                self.key_ring.unwrap_or_default(),
+                        )),
+                        maintenance_mode: Arc::new(AtomicBool::new(config.maintenance_mode)),
+                        metrics: Arc::new(Metrics::new()),
+                        config: Arc::new(config),
                 }
         }
 }
@@ -112,45 +278,179 @@ impl Clone for AppState {
                         user_store: Arc::clone(&self.user_store),
                         banned_token_store: Arc::clone(&self.banned_token_store),
                         two_fa_code_store: Arc::clone(&self.two_fa_code_store),
+                        idempotency_store: Arc::clone(&self.idempotency_store),
+                        session_store: Arc::clone(&self.session_store),
+                        failed_login_store: Arc::clone(&self.failed_login_store),
+                        audit_log_store: Arc::clone(&self.audit_log_store),
+                        pending_email_change_store: Arc::clone(&self.pending_email_change_store),
+                        magic_link_store: Arc::clone(&self.magic_link_store),
+                        rate_limit_store: Arc::clone(&self.rate_limit_store),
                         email_client: Arc::clone(&self.email_client),
+                        webhook_notifier: self.webhook_notifier.clone(),
+                        captcha_verifier: self.captcha_verifier.clone(),
+                        mx_resolver: self.mx_resolver.clone(),
+                        key_ring: Arc::clone(&self.key_ring),
+                        maintenance_mode: Arc::clone(&self.maintenance_mode),
+                        metrics: Arc::clone(&self.metrics),
+                        config: Arc::clone(&self.config),
                 }
         }
 }
 
+impl AppState {
+        /// Report an account event to the webhook, if one is configured
+        pub fn notify_webhook(&self, event: WebhookEvent, email: &Email) {
+                if let Some(notifier) = &self.webhook_notifier {
+                        notifier.notify(event, email);
+                }
+        }
+}
+
+/// Result of `seed_admin`
+#[derive(Debug, PartialEq, Eq)]
+pub enum SeedAdminOutcome {
+        Created,
+        /// The email is already registered; the account was left as it is
+        AlreadyExists,
+}
+
+/// Create an admin account for `email` unless the email is already registered (soft-deleted
+/// accounts included), so it is safe to run on every boot. An existing account is never
+/// promoted or given a new password.
+#[tracing::instrument(name = "Seed admin", skip_all)]
+pub async fn seed_admin(
+        user_store: &UserStoreType,
+        email: &Email,
+        password: &str,
+        config: &AppConfig,
+) -> AppResult<SeedAdminOutcome> {
+        match user_store.read().await.get_user_including_deleted(email).await {
+                Ok(_) => return Ok(SeedAdminOutcome::AlreadyExists),
+                Err(UserStoreError::UserNotFound) => {}
+                Err(e) => return Err(format!("Failed to look up admin account: {:?}", e).into()),
+        }
+
+        let password = HashedPassword::parse_with_policy(password, &config.password_policy).await?;
+        let admin = User::new(email.clone(), password, false).with_role(Role::Admin);
+
+        match user_store.write().await.add_user(admin).await {
+                Ok(()) => Ok(SeedAdminOutcome::Created),
+                // Another instance seeded it between the lookup and the insert
+                Err(UserStoreError::UserAlreadyExists) => Ok(SeedAdminOutcome::AlreadyExists),
+                Err(e) => Err(format!("Failed to create admin account: {:?}", e).into()),
+        }
+}
+
 /// Application
 #[derive(Debug)]
 pub struct Application {
-        server: axum::serve::Serve<tokio::net::TcpListener, Router, Router>,
+        server: Server,
         pub address: String,
 }
 
+/// Plain HTTP is the default; HTTPS is only available with the `tls` feature
+#[derive(Debug)]
+enum Server {
+        Http(
+                axum::serve::Serve<
+                        tokio::net::TcpListener,
+                        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+                        axum::middleware::AddExtension<Router, ConnectInfo<SocketAddr>>,
+                >,
+        ),
+        #[cfg(feature = "tls")]
+        Https(Box<axum_server::Server<SocketAddr, RustlsAcceptor>>, Router),
+}
+
 impl Application {
         pub async fn build(app_state: AppState, address: impl Into<String>) -> AppResult<Self> {
-                let asset_dir = fetch_assets();
+                let router = build_router(app_state)?;
+
+                let addr: String = address.into();
+                let listener = tokio::net::TcpListener::bind(&addr).await?;
+                let address = listener.local_addr()?.to_string();
+
+                // Peer addresses are recorded with each session
+                let server = axum::serve(
+                        listener,
+                        router.into_make_service_with_connect_info::<SocketAddr>(),
+                );
 
-                let allowed_origins = get_allowed_origins()?;
-                let cors = get_cors(allowed_origins);
+                Ok(Application {
+                        server: Server::Http(server),
+                        address,
+                })
+        }
+
+        /// Like `build`, but terminates TLS with the certificate and key in `tls`
+        #[cfg(feature = "tls")]
+        pub async fn build_tls(
+                app_state: AppState,
+                address: impl Into<String>,
+                tls: &TlsConfig,
+        ) -> AppResult<Self> {
+                // rustls needs a process-wide crypto provider; `Err` means one is already installed
+                let _ = rustls::crypto::ring::default_provider().install_default();
+                let rustls_config =
+                        RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
 
-                let router = app_routes(app_state, cors, asset_dir);
+                let router = build_router(app_state)?;
 
                 let addr: String = address.into();
-                let listener = tokio::net::TcpListener::bind(&addr).await?;
+                let listener = std::net::TcpListener::bind(&addr)?;
+                listener.set_nonblocking(true)?;
                 let address = listener.local_addr()?.to_string();
 
-                let server = axum::serve(listener, router);
+                let server = axum_server::from_tcp_rustls(listener, rustls_config)?;
 
                 Ok(Application {
-                        server,
+                        server: Server::Https(Box::new(server), router),
                         address,
                 })
         }
 
         pub async fn run(self) -> Result<(), std::io::Error> {
+                self.run_until(std::future::pending()).await
+        }
+
+        /// Like `run`, but once `shutdown` completes stops accepting connections and returns
+        /// when the requests in flight have been answered
+        pub async fn run_until<F>(self, shutdown: F) -> Result<(), std::io::Error>
+        where
+                F: Future<Output = ()> + Send + 'static,
+        {
                 tracing::info!("Listening on {}", &self.address);
-                self.server.await
+                match self.server {
+                        Server::Http(server) => server.with_graceful_shutdown(shutdown).await,
+                        #[cfg(feature = "tls")]
+                        Server::Https(server, router) => {
+                                let handle = axum_server::Handle::new();
+                                let shutdown_handle = handle.clone();
+                                tokio::spawn(async move {
+                                        shutdown.await;
+                                        shutdown_handle.graceful_shutdown(None);
+                                });
+                                let service =
+                                        router.into_make_service_with_connect_info::<SocketAddr>();
+                                server.handle(handle).serve(service).await
+                        }
+                }
         }
 }
 
+fn build_router(app_state: AppState) -> AppResult<Router> {
+        let asset_dir = if app_state.config.serve_ui {
+                fetch_assets(&app_state.config.assets_dir)
+        } else {
+                api_only_fallback()
+        };
+
+        let allowed_origins = get_allowed_origins()?;
+        let cors = get_cors(allowed_origins);
+
+        Ok(app_routes(app_state, cors, asset_dir))
+}
+
 fn get_allowed_origins() -> Result<[HeaderValue; 2], Box<dyn std::error::Error>> {
         let localhost_url_header = get_env_var(LOCALHOST_URL_ENV_VAR).parse::<HeaderValue>()?;
         let droplet_url_header = get_env_var(DROPLET_URL_ENV_VAR).parse::<HeaderValue>()?;
@@ -160,7 +460,7 @@ fn get_allowed_origins() -> Result<[HeaderValue; 2], Box<dyn std::error::Error>>
 
 fn get_cors(origins: [HeaderValue; 2]) -> CorsLayer {
         CorsLayer::new()
-                .allow_methods([Method::GET, Method::POST])
+                .allow_methods([Method::GET, Method::POST, Method::DELETE])
                 .allow_credentials(true)
                 .allow_origin(origins)
 }
@@ -175,14 +475,30 @@ async fn get_postgres_pool(url: &str) -> Result<PgPool, sqlx::Error> {
         PgPoolOptions::new().max_connections(5).connect(url).await
 }
 
-/// Production: connect to the existing database and run migrations.
+/// Production: connect to the existing database and, unless `RUN_MIGRATIONS_ON_STARTUP` is
+/// false, run migrations.
 pub async fn init_postgres_pool() -> PgPool {
-        let url = DATABASE_URL.to_owned();
-        let pool = get_postgres_pool(&url).await.expect("Failed to connect to Postgres");
-        sqlx::migrate!().run(&pool).await.expect("Failed to run database migrations");
+        let pool = connect_postgres_pool().await;
+        if *RUN_MIGRATIONS_ON_STARTUP {
+                run_migrations(&pool).await.expect("Failed to run database migrations");
+        } else {
+                tracing::info!("Skipped database migrations on startup");
+        }
         pool
 }
 
+/// Connect to the `DATABASE_URL` database without migrating it
+pub async fn connect_postgres_pool() -> PgPool {
+        let url = DATABASE_URL.to_owned();
+        get_postgres_pool(&url).await.expect("Failed to connect to Postgres")
+}
+
+/// Apply the migrations the database does not have yet. Those already applied are skipped,
+/// so running it again is a no-op.
+pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateError> {
+        sqlx::migrate!().run(pool).await
+}
+
 /// Test-only: create a fresh UUID-named database, run migrations, and return a pool.
 /// This gives each test run an isolated, clean database.
 pub async fn configure_postgresql() -> PgPool {
@@ -218,27 +534,74 @@ pub async fn configure_database(db_conn_string: &str, db_name: &str) {
         sqlx::migrate!().run(&connection).await.expect("Failed to migrate the database.");
 }
 
-fn configure_redis() -> redis::Connection {
-        get_redis_client(REDIS_HOST_NAME.to_owned())
-                .expect("Failed to get Redis client")
-                .get_connection()
-                .expect("Failed to get Redis connection")
+/// Connection pool for `REDIS_HOST_NAME`:`REDIS_PORT`, built once at startup and shared by
+/// every Redis-backed store, the way `PgPool` is shared by the Postgres stores
+pub fn get_redis_pool() -> Arc<RedisPool> {
+        let client = get_redis_client(format!("{}:{}", *REDIS_HOST_NAME, *REDIS_PORT))
+                .expect("Failed to get Redis client");
+        let pool = r2d2::Pool::builder()
+                .max_size(REDIS_POOL_MAX_SIZE)
+                .build(client)
+                .expect("Failed to create Redis connection pool");
+
+        Arc::new(pool)
 }
 
 pub fn get_user_store(pool: Pool<Postgres>) -> Arc<RwLock<Box<dyn UserStore + Send + Sync>>> {
         Arc::new(RwLock::new(Box::new(PostgresUserStore::new(pool))))
 }
 
-pub fn get_banned_token_store() -> BannedTokenStoreType {
-        let client = configure_redis();
-        Arc::new(RwLock::new(Box::new(RedisBannedTokenStore::new(client))))
+pub fn get_banned_token_store(pool: Arc<RedisPool>) -> BannedTokenStoreType {
+        Arc::new(RwLock::new(Box::new(RedisBannedTokenStore::new(pool))))
+}
+
+pub fn get_two_fa_code_store(
+        pool: Arc<RedisPool>,
+) -> Arc<RwLock<Box<dyn TwoFACodeStore + Send + Sync>>> {
+        Arc::new(RwLock::new(Box::new(RedisTwoFACodeStore::new(pool))))
+}
+
+pub fn get_idempotency_store(pool: Arc<RedisPool>) -> IdempotencyStoreType {
+        Arc::new(RwLock::new(Box::new(RedisIdempotencyStore::new(pool))))
 }
 
-pub fn get_two_fa_code_store() -> Arc<RwLock<Box<dyn TwoFACodeStore + Send + Sync>>> {
-        let conn = configure_redis();
-        Arc::new(RwLock::new(Box::new(RedisTwoFACodeStore::new(conn))))
+pub fn get_session_store(pool: Arc<RedisPool>) -> SessionStoreType {
+        Arc::new(RwLock::new(Box::new(RedisSessionStore::new(pool))))
 }
 
+pub fn get_failed_login_store(pool: Arc<RedisPool>) -> FailedLoginStoreType {
+        Arc::new(RwLock::new(Box::new(RedisFailedLoginStore::new(pool))))
+}
+
+pub fn get_audit_log_store(pool: Arc<RedisPool>) -> AuditLogStoreType {
+        Arc::new(RwLock::new(Box::new(RedisAuditLogStore::new(pool))))
+}
+
+pub fn get_pending_email_change_store(pool: Arc<RedisPool>) -> PendingEmailChangeStoreType {
+        Arc::new(RwLock::new(Box::new(RedisPendingEmailChangeStore::new(pool))))
+}
+
+pub fn get_magic_link_store(pool: Arc<RedisPool>) -> MagicLinkStoreType {
+        Arc::new(RwLock::new(Box::new(RedisMagicLinkStore::new(pool))))
+}
+
+pub fn get_rate_limit_store(pool: Arc<RedisPool>) -> RateLimitStoreType {
+        Arc::new(RwLock::new(Box::new(RedisRateLimitStore::new(pool))))
+}
+
+/// The email client: each provider retries transient send failures, then the next provider
+/// in the list is tried
 pub fn get_email_client() -> Arc<dyn EmailClient + Send + Sync> {
-        Arc::new(MockEmailClient)
+        let providers: Vec<EmailClientType> = vec![Arc::new(MockEmailClient)];
+        let retried = providers
+                .into_iter()
+                .map(|provider| -> EmailClientType {
+                        Arc::new(RetryingEmailClient::new(
+                                provider,
+                                EMAIL_SEND_ATTEMPTS,
+                                Duration::from_millis(EMAIL_RETRY_BACKOFF_MILLIS),
+                        ))
+                })
+                .collect();
+        Arc::new(FailoverEmailClient::new(retried))
 }
@@ -1,45 +1,171 @@
 // src/main.rs
 use auth_service::{
-        domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore},
-        get_banned_token_store, get_email_client, get_redis_client, get_two_fa_code_store,
-        get_user_store, init_postgres_pool,
-        services::data_stores::{
-                postgres_user_store::PostgresUserStore, HashmapTwoFACodeStore, HashmapUserStore,
-                HashsetBannedTokenStore, MockEmailClient,
+        connect_postgres_pool,
+        domain::{BannedTokenStore, Email, EmailClient, TwoFACodeStore, UserStore},
+        get_audit_log_store, get_banned_token_store, get_email_client, get_failed_login_store,
+        get_idempotency_store, get_magic_link_store, get_pending_email_change_store,
+        get_rate_limit_store, get_redis_client, get_redis_pool, get_session_store,
+        get_two_fa_code_store, get_user_store, init_postgres_pool, run_migrations, seed_admin,
+        services::{
+                captcha_verifier::ReqwestCaptchaVerifier,
+                data_stores::{
+                        postgres_user_store::PostgresUserStore, HashmapTwoFACodeStore,
+                        HashmapUserStore, HashsetBannedTokenStore, MockEmailClient,
+                },
+                inactivity_expiry::spawn_inactivity_expiry_job,
+                mx_resolver::HickoryMxResolver,
+                webhook_notifier::WebhookNotifier,
         },
         utils::{
-                constants::{prod, REDIS_HOST_NAME},
+                config::{AppConfig, TlsConfig},
+                constants::{
+                        env::{ADMIN_EMAIL_ENV_VAR, ADMIN_PASSWORD_ENV_VAR, JWT_SECRET_ENV_VAR},
+                        get_env_var, prod, JWT_SECRET, MIN_JWT_SECRET_BYTES, PASSWORD_PEPPERS,
+                        REDIS_HOST_NAME,
+                },
                 tracing::init_tracing,
         },
         AppState, AppStateBuilder, Application,
 };
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
         color_eyre::install()?;
-        init_tracing();
+        let tracer_provider = init_tracing();
+
+        // `--migrate` applies pending migrations and exits without serving, for deployments
+        // that set RUN_MIGRATIONS_ON_STARTUP=false and migrate as a separate step
+        if std::env::args().any(|arg| arg == "--migrate") {
+                let pg_pool = connect_postgres_pool().await;
+                run_migrations(&pg_pool).await.expect("failed to run database migrations");
+                tracing::info!("Applied database migrations");
+                return Ok(());
+        }
 
         let pg_pool = init_postgres_pool().await;
 
         let user_store = get_user_store(pg_pool);
-        let banned_token_store = get_banned_token_store();
-        let two_fa_code_store = get_two_fa_code_store();
+        let redis_pool = get_redis_pool();
+        let banned_token_store = get_banned_token_store(Arc::clone(&redis_pool));
+        let two_fa_code_store = get_two_fa_code_store(Arc::clone(&redis_pool));
+        let idempotency_store = get_idempotency_store(Arc::clone(&redis_pool));
+        let session_store = get_session_store(Arc::clone(&redis_pool));
+        let failed_login_store = get_failed_login_store(Arc::clone(&redis_pool));
+        let audit_log_store = get_audit_log_store(Arc::clone(&redis_pool));
+        let pending_email_change_store = get_pending_email_change_store(Arc::clone(&redis_pool));
+        let magic_link_store = get_magic_link_store(Arc::clone(&redis_pool));
+        let rate_limit_store = get_rate_limit_store(redis_pool);
         let email_client = get_email_client();
+        let config = AppConfig::from_env();
+        // Read now so a pepper misconfiguration stops startup rather than the first signup
+        tracing::info!(version = PASSWORD_PEPPERS.current(), "Using password pepper");
+        // Likewise for the signing secret, which is held to the same length as a rotated one
+        if JWT_SECRET.len() < MIN_JWT_SECRET_BYTES {
+                return Err(format!(
+                        "{} must be at least {} bytes",
+                        JWT_SECRET_ENV_VAR, MIN_JWT_SECRET_BYTES
+                )
+                .into());
+        }
+
+        // `--seed-admin` creates the ADMIN_EMAIL account before serving, unless it already
+        // exists, so it can be passed on every boot
+        if std::env::args().any(|arg| arg == "--seed-admin") {
+                let email = Email::parse(&get_env_var(ADMIN_EMAIL_ENV_VAR))
+                        .unwrap_or_else(|e| panic!("{} is invalid: {}", ADMIN_EMAIL_ENV_VAR, e));
+                let password = get_env_var(ADMIN_PASSWORD_ENV_VAR);
+                let outcome = seed_admin(&user_store, &email, &password, &config)
+                        .await
+                        .expect("failed to seed admin");
+                tracing::info!(account = %email, ?outcome, "Seeded admin");
+        }
 
-        let app_state = AppStateBuilder::new()
+        // Dropping `shutdown_tx` stops the background jobs
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let inactivity_expiry_job = config.inactivity_expiry_days.map(|days| {
+                spawn_inactivity_expiry_job(Arc::clone(&user_store), days, shutdown_rx.clone())
+        });
+
+        let mut builder = AppStateBuilder::new()
                 .user_store(user_store)
                 .banned_token_store(banned_token_store)
                 .two_fa_code_store(two_fa_code_store)
+                .idempotency_store(idempotency_store)
+                .session_store(session_store)
+                .failed_login_store(failed_login_store)
+                .audit_log_store(audit_log_store)
+                .pending_email_change_store(pending_email_change_store)
+                .magic_link_store(magic_link_store)
+                .rate_limit_store(rate_limit_store)
                 .email_client(email_client)
-                .build();
+                .config(config);
+        if let Some(webhook_notifier) = WebhookNotifier::from_env()? {
+                builder = builder.webhook_notifier(webhook_notifier);
+        }
+        if let Some(captcha_verifier) = ReqwestCaptchaVerifier::from_env()? {
+                builder = builder.captcha_verifier(Arc::new(captcha_verifier));
+        }
+        if let Some(mx_resolver) = HickoryMxResolver::from_env() {
+                builder = builder.mx_resolver(Arc::new(mx_resolver));
+        }
+        let app_state = builder.build();
+
+        let app = match TlsConfig::from_env() {
+                #[cfg(feature = "tls")]
+                Some(tls) => Application::build_tls(app_state, prod::APP_ADDRESS, &tls).await,
+                #[cfg(not(feature = "tls"))]
+                Some(_) => panic!(
+                        "TLS_ENABLED is set but auth-service was built without the `tls` feature"
+                ),
+                None => Application::build(app_state, prod::APP_ADDRESS).await,
+        }
+        .expect("failed to build Application");
 
-        let app = Application::build(app_state, prod::APP_ADDRESS)
-                .await
-                .expect("failed to build Application");
+        app.run_until(shutdown_signal()).await?;
 
-        app.run().await.expect("failed to run application");
+        // Stop the background jobs, then flush the spans still buffered for the OTLP exporter
+        drop(shutdown_tx);
+        if let Some(job) = inactivity_expiry_job {
+                job.await?;
+        }
+        if let Some(provider) = tracer_provider {
+                provider.shutdown()?;
+        }
+        tracing::info!("Shut down");
         Ok(())
 }
+
+/// Completes on Ctrl+C or, on Unix, SIGTERM (what `docker stop` sends). A handler that cannot
+/// be installed is logged and never fires, rather than shutting the server down at once.
+async fn shutdown_signal() {
+        let ctrl_c = async {
+                if let Err(e) = tokio::signal::ctrl_c().await {
+                        tracing::error!(error = %e, "Failed to listen for Ctrl+C");
+                        std::future::pending::<()>().await;
+                }
+        };
+
+        #[cfg(unix)]
+        let terminate = async {
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                        Ok(mut signal) => {
+                                signal.recv().await;
+                        }
+                        Err(e) => {
+                                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                                std::future::pending::<()>().await;
+                        }
+                }
+        };
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        tokio::select! {
+                _ = ctrl_c => {}
+                _ = terminate => {}
+        }
+        tracing::info!("Shutting down after the requests in flight");
+}
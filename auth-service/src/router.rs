@@ -1,30 +1,121 @@
 use crate::{
-        domain::UserStore,
-        handle_login, handle_login_or_signup, handle_logout, handle_signup, handle_verify_2fa,
-        handle_verify_token,
-        utils::tracing::{make_span_with_request_id, on_request, on_response},
+        domain::{AuthAPIError, UserStore},
+        handle_ban_tokens, handle_change_email, handle_change_password, handle_check_email,
+        handle_confirm_email_change, handle_data_export, handle_debug_token,
+        handle_force_reset_password, handle_list_sessions, handle_login, handle_login_history,
+        handle_login_or_signup, handle_logout, handle_logout_redirect, handle_me, handle_metrics,
+        handle_password_policy, handle_reactivate_account, handle_readiness,
+        handle_request_magic_link, handle_required_password_change, handle_revoke_session,
+        handle_rotate_key, handle_security_summary, handle_session_status,
+        handle_set_maintenance_mode, handle_sign_out_other_sessions, handle_signup,
+        handle_verify_2fa, handle_verify_2fa_check, handle_verify_magic_link, handle_verify_token,
+        utils::{
+                content_negotiation::negotiate_error_format,
+                maintenance::maintenance_guard,
+                tracing::{make_span_with_request_id, on_request, on_response},
+        },
         AppState,
 };
 use axum::{
+        error_handling::HandleErrorLayer,
+        extract::DefaultBodyLimit,
+        http::StatusCode,
+        middleware,
         routing::MethodRouter,
-        routing::{get, post},
-        Router,
+        routing::{delete, get, post},
+        BoxError, Router,
 };
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use std::time::Duration;
+use tower::{limit::ConcurrencyLimitLayer, load_shed::LoadShedLayer, ServiceBuilder};
+use tower_http::{cors::CorsLayer, timeout::TimeoutLayer, trace::TraceLayer};
 
 pub fn app_routes(app_state: AppState, cors: CorsLayer, asset_dir: MethodRouter) -> Router {
-        Router::new()
+        // Applies to the API routes only; the asset fallback never reads a request body
+        let body_limit = DefaultBodyLimit::max(app_state.config.max_request_body_bytes);
+        // Abandons a handler stuck on a slow database or email provider
+        let timeout = TimeoutLayer::with_status_code(
+                StatusCode::GATEWAY_TIMEOUT,
+                Duration::from_secs(app_state.config.request_timeout_seconds),
+        );
+
+        let mut router = Router::new()
                 .fallback_service(asset_dir)
-                .route("/", get(handle_login_or_signup))
                 .route("/signup", post(handle_signup))
+                .route("/signup/check-email", get(handle_check_email))
                 .route("/login", post(handle_login))
-                .route("/logout", post(handle_logout))
+                .route("/login/change-password", post(handle_required_password_change))
+                .route("/logout", post(handle_logout).get(handle_logout_redirect))
                 .route("/verify-2fa", post(handle_verify_2fa))
+                .route("/verify-2fa/check", post(handle_verify_2fa_check))
                 .route("/verify-token", post(handle_verify_token))
-                .with_state(app_state)
-                .layer(cors)
-                .layer(TraceLayer::new_for_http()
+                .route("/reactivate-account", post(handle_reactivate_account))
+                .route("/admin/ban-tokens", post(handle_ban_tokens))
+                .route("/admin/rotate-key", post(handle_rotate_key))
+                .route("/admin/maintenance", post(handle_set_maintenance_mode))
+                .route("/admin/force-reset-password", post(handle_force_reset_password))
+                .route("/change-password", post(handle_change_password))
+                .route("/change-email", post(handle_change_email))
+                .route("/change-email/confirm", get(handle_confirm_email_change))
+                .route("/password/policy", get(handle_password_policy))
+                .route("/me", get(handle_me))
+                .route("/me/export", get(handle_data_export))
+                .route("/sessions", get(handle_list_sessions))
+                .route("/sessions/{jti}", delete(handle_revoke_session))
+                .route("/session/status", get(handle_session_status))
+                .route("/security/sessions", get(handle_list_sessions))
+                .route("/security/sign-out-others", post(handle_sign_out_other_sessions))
+                .route("/security/login-history", get(handle_login_history))
+                .route("/security/summary", get(handle_security_summary))
+                .route("/health/ready", get(handle_readiness));
+
+        // With SERVE_UI=false `/` is left to the fallback's JSON 404, like any unknown path
+        if app_state.config.serve_ui {
+                router = router.route("/", get(handle_login_or_signup));
+        }
+
+        // Passwordless login; left unmounted (so it 404s) unless MAGIC_LINK_LOGIN is true
+        if app_state.config.magic_link_login {
+                router = router
+                        .route("/magic-link/request", post(handle_request_magic_link))
+                        .route("/magic-link/verify", get(handle_verify_magic_link));
+        }
+
+        // Left unmounted (so it 404s) unless METRICS_ENDPOINT is true; scrape it from inside the
+        // network rather than exposing it publicly
+        if app_state.config.metrics_endpoint {
+                router = router.route("/metrics", get(handle_metrics));
+        }
+
+        // Dev-only routes; left unmounted (so they 404) unless DEBUG_ENDPOINTS is explicitly true
+        if app_state.config.debug_endpoints {
+                tracing::warn!("DEBUG_ENDPOINTS is enabled; /debug/token is exposed");
+                router = router.route("/debug/token", get(handle_debug_token));
+        }
+
+        let max_concurrent_requests = app_state.config.max_concurrent_requests;
+
+        // Refuses writes during maintenance before the body is read or a handler runs
+        let mut router = router
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), maintenance_guard))
+                .route_layer(body_limit)
+                .route_layer(timeout)
+                .with_state(app_state);
+
+        // Sheds load with a 503 once the limit is reached, instead of letting a burst queue up
+        // behind the Postgres pool
+        if let Some(max) = max_concurrent_requests {
+                router = router.layer(ServiceBuilder::new()
+                        .layer(HandleErrorLayer::new(|_: BoxError| async {
+                                AuthAPIError::Overloaded
+                        }))
+                        .layer(LoadShedLayer::new())
+                        .layer(ConcurrencyLimitLayer::new(max)));
+        }
+
+        router.layer(middleware::from_fn(negotiate_error_format)).layer(cors).layer(
+                TraceLayer::new_for_http()
                         .make_span_with(make_span_with_request_id)
                         .on_request(on_request)
-                        .on_response(on_response))
+                        .on_response(on_response),
+        )
 }
@@ -0,0 +1,132 @@
+// src/routes/ban_tokens.rs
+use axum::{
+        extract::{Json, State},
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{BannedTokenStoreError, Email},
+        routes::{active_sessions, end_session, RequireAdmin},
+        utils::auth::Token,
+        AppState, HandlerResult,
+};
+
+/// POST – /admin/ban-tokens (admin only)
+///
+/// Bans every listed token and ends every session of every listed account. Each item gets
+/// its own result, in request order, so one bad item never aborts the rest of the batch.
+#[tracing::instrument(name = "Ban tokens", skip_all, err(Debug))]
+pub async fn handle_ban_tokens(
+        State(state): State<AppState>,
+        RequireAdmin(admin): RequireAdmin,
+        Json(payload): Json<BanTokensPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_ban_tokens");
+
+        let mut tokens = Vec::with_capacity(payload.tokens.len());
+        for token in payload.tokens {
+                tokens.push(ban_token(&state, token).await);
+        }
+
+        let mut emails = Vec::with_capacity(payload.emails.len());
+        for email in payload.emails {
+                emails.push(end_sessions_of(&state, email).await);
+        }
+
+        tracing::info!(
+                admin = %admin.email(),
+                tokens = tokens.len(),
+                accounts = emails.len(),
+                "Batch token ban"
+        );
+
+        Ok(Json(BanTokensResponse {
+                tokens,
+                emails,
+        }))
+}
+
+async fn ban_token(state: &AppState, token: String) -> BanStatus {
+        let Ok(token) = Token::parse(token) else {
+                return BanStatus::Invalid;
+        };
+
+        let mut banned_token_store = state.banned_token_store.write().await;
+        match banned_token_store.is_banned(&token).await {
+                Ok(true) => return BanStatus::AlreadyBanned,
+                Ok(false) => {}
+                Err(_) => return BanStatus::Failed,
+        }
+        match banned_token_store.ban_token(token).await {
+                Ok(()) => BanStatus::Banned,
+                Err(BannedTokenStoreError::TokenAlreadyBanned) => BanStatus::AlreadyBanned,
+                Err(BannedTokenStoreError::UnexpectedError) => BanStatus::Failed,
+        }
+}
+
+async fn end_sessions_of(state: &AppState, email: String) -> AccountBanResult {
+        let result = |status, sessions_ended| AccountBanResult {
+                email: email.clone(),
+                status,
+                sessions_ended,
+        };
+
+        let Ok(parsed) = Email::parse(&email) else {
+                return result(BanStatus::Invalid, 0);
+        };
+
+        let mut session_store = state.session_store.write().await;
+        let sessions = match active_sessions(state, &mut **session_store, &parsed).await {
+                Ok(sessions) => sessions,
+                Err(_) => return result(BanStatus::Failed, 0),
+        };
+
+        let mut sessions_ended = 0;
+        for session in sessions {
+                if end_session(state, &mut **session_store, &parsed, session).await.is_err() {
+                        return result(BanStatus::Failed, sessions_ended);
+                }
+                sessions_ended += 1;
+        }
+
+        result(BanStatus::Banned, sessions_ended)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BanTokensPayload {
+        /// Raw JWTs to ban
+        #[serde(default)]
+        pub tokens: Vec<String>,
+        /// Accounts whose active sessions should all be ended
+        #[serde(default)]
+        pub emails: Vec<String>,
+}
+
+/// Results in the same order as the request's `tokens` and `emails`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BanTokensResponse {
+        pub tokens: Vec<BanStatus>,
+        pub emails: Vec<AccountBanResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountBanResult {
+        pub email: String,
+        pub status: BanStatus,
+        /// Sessions whose tokens were banned by this request
+        pub sessions_ended: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanStatus {
+        /// Banned (for an account: every active session ended) by this request
+        Banned,
+        /// The token was banned before this request
+        AlreadyBanned,
+        /// Malformed token or email
+        Invalid,
+        /// The store failed; the item may be retried
+        Failed,
+}
@@ -0,0 +1,133 @@
+// src/routes/change_email.rs
+use axum::{
+        extract::{Json, Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+        domain::{AuthAPIError, Email, PendingEmailChange, UserStoreError},
+        routes::{end_all_sessions, CurrentUser},
+        utils::constants::{EMAIL_CHANGE_CONFIRM_SUBJECT, EMAIL_CHANGE_NOTICE_SUBJECT},
+        AppState, HandlerResult,
+};
+
+/// POST – /change-email (requires a valid JWT cookie)
+///
+/// Starts an email change. The current address is told about it straight away, and the
+/// new address is sent a link to `/change-email/confirm`; nothing changes until that link
+/// is followed.
+#[tracing::instrument(name = "Change email", skip_all, err(Debug))]
+pub async fn handle_change_email(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+        Json(payload): Json<ChangeEmailPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_change_email");
+
+        // Returns 400 – malformed email
+        let new_email = Email::parse(&payload.new_email)?;
+
+        // Returns 409 – soft-deleted accounts keep their email, so they count as taken
+        match state.user_store.read().await.get_user_including_deleted(&new_email).await {
+                Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+                Err(UserStoreError::UserNotFound) => {}
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        }
+
+        // The current owner hears about the change before it can be confirmed, so a hijacked
+        // session cannot move the account away silently
+        let notice = format!(
+                "A change of your account's email address to {} was requested. If this wasn't \
+                 you, change your password now; the change only happens once the new address \
+                 confirms it.",
+                new_email.as_str()
+        );
+        state.email_client
+                .send_email(&current_user.email, EMAIL_CHANGE_NOTICE_SUBJECT, &notice)
+                .await
+                .map_err(|e| {
+                        tracing::error!(error = %e, "Failed to send email change notice");
+                        AuthAPIError::UnexpectedError
+                })?;
+
+        let token = Uuid::new_v4().to_string();
+        state.pending_email_change_store
+                .write()
+                .await
+                .add_change(
+                        token.clone(),
+                        PendingEmailChange {
+                                old_email: current_user.email,
+                                new_email: new_email.clone(),
+                        },
+                )
+                .await?;
+
+        let confirmation = format!(
+                "Confirm your new email address by opening {}/change-email/confirm?token={}",
+                state.config.public_url.trim_end_matches('/'),
+                token
+        );
+        state.email_client
+                .send_email(&new_email, EMAIL_CHANGE_CONFIRM_SUBJECT, &confirmation)
+                .await
+                .map_err(|e| {
+                        tracing::error!(error = %e, "Failed to send email change confirmation");
+                        AuthAPIError::UnexpectedError
+                })?;
+
+        Ok((
+                StatusCode::ACCEPTED,
+                Json(ChangeEmailResponse {
+                        message: "Check your new email address to confirm the change".to_owned(),
+                }),
+        ))
+}
+
+/// GET – /change-email/confirm?token=
+///
+/// Applies the email change the token was issued for and ends every session of the old
+/// address, so the user logs in again with the new one.
+// A missing `token` parameter is rejected with 400 by Axum's Query extractor
+#[tracing::instrument(name = "Confirm email change", skip_all, err(Debug))]
+pub async fn handle_confirm_email_change(
+        State(state): State<AppState>,
+        Query(query): Query<ConfirmEmailChangeQuery>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_confirm_email_change");
+
+        // Returns 400 – unknown, expired, or already used token
+        let change =
+                state.pending_email_change_store.write().await.take_change(&query.token).await?;
+
+        // Returns 404 if the account was deleted meanwhile, 409 if the new email was taken
+        state.user_store.write().await.update_email(&change.old_email, &change.new_email).await?;
+
+        end_all_sessions(&state, &change.old_email).await?;
+
+        Ok((
+                StatusCode::OK,
+                Json(ChangeEmailResponse {
+                        message: "Email changed successfully".to_owned(),
+                }),
+        ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangeEmailPayload {
+        #[serde(rename = "newEmail")]
+        pub new_email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmEmailChangeQuery {
+        pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChangeEmailResponse {
+        pub message: String,
+}
@@ -0,0 +1,106 @@
+// src/routes/change_password.rs
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{ensure_not_recently_used, AuthAPIError, HashedPassword},
+        routes::CurrentUser,
+        services::webhook_notifier::WebhookEvent,
+        AppState, HandlerResult,
+};
+
+/// POST – /change-password (requires a valid JWT cookie)
+///
+/// The new password is rejected if it matches the current password or any of the last
+/// `PASSWORD_HISTORY_DEPTH` passwords.
+#[tracing::instrument(name = "Change password", skip_all, err(Debug))]
+pub async fn handle_change_password(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+        Json(payload): Json<ChangePasswordPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_change_password");
+
+        let email = current_user.email;
+
+        // Returns 401 – the current password must be re-entered to change it
+        state.user_store
+                .read()
+                .await
+                .validate_user(&email, &payload.current_password)
+                .await
+                .map_err(|_| AuthAPIError::Unauthorized)?;
+
+        // Returns 400 – new password does not meet the password requirements
+        let new_password = HashedPassword::parse_with_context(
+                &payload.new_password,
+                &state.config.password_policy,
+                &email,
+                None,
+        )
+        .await
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+        /// Gather the current hash and the history in one read-lock scope, then release the
+        /// lock before hashing so other requests are not blocked on argon2
+        let recent_passwords = {
+                let store = state.user_store.read().await;
+                let mut recent = vec![store.get_user(&email).await?.password_to_owned()];
+                recent.extend(store.get_password_history(&email).await?);
+                recent
+        };
+
+        // Returns 400 – new password was used recently
+        ensure_not_recently_used(&payload.new_password, &recent_passwords).await?;
+
+        state.user_store
+                .write()
+                .await
+                .update_password(&email, new_password, state.config.password_history_depth)
+                .await?;
+
+        state.notify_webhook(WebhookEvent::PasswordChanged, &email);
+
+        Ok((
+                StatusCode::OK,
+                Json(ChangePasswordResponse {
+                        message: "Password changed successfully".to_owned(),
+                }),
+        ))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangePasswordPayload {
+        #[serde(rename = "currentPassword")]
+        current_password: String,
+        #[serde(rename = "newPassword")]
+        new_password: String,
+}
+
+impl ChangePasswordPayload {
+        pub fn new(current_password: String, new_password: String) -> Self {
+                Self {
+                        current_password,
+                        new_password,
+                }
+        }
+}
+
+impl std::fmt::Debug for ChangePasswordPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display either password
+                f.debug_struct("ChangePasswordPayload")
+                        .field("current_password", &"[REDACTED]")
+                        .field("new_password", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChangePasswordResponse {
+        pub message: String,
+}
@@ -0,0 +1,77 @@
+// src/routes/check_email.rs
+use std::time::Duration;
+
+use axum::{
+        extract::{Json, Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::{
+        domain::{AuthAPIError, Email, RateLimitScope, UserStoreError},
+        routes::{check_rate_limit, ClientInfo},
+        utils::constants::CHECK_EMAIL_MIN_RESPONSE_MILLIS,
+        AppState, HandlerResult,
+};
+
+/// GET – /signup/check-email?email=
+///
+/// Tells the signup form whether an email can still be registered. Every lookup takes at
+/// least `CHECK_EMAIL_MIN_RESPONSE_MILLIS`, so its timing does not give a taken email away,
+/// and each client IP gets `max_email_checks_per_ip` lookups per `CHECK_EMAIL_WINDOW_SECONDS`
+/// (429 past it), which is what bounds enumerating accounts through it. With
+/// HIDE_SIGNUP_CONFLICTS every well-formed email is reported available, as signup itself no
+/// longer reveals which are taken.
+// A missing `email` parameter is rejected with 400 by Axum's Query extractor
+#[tracing::instrument(name = "Check email availability", skip_all, err(Debug))]
+pub async fn handle_check_email(
+        State(state): State<AppState>,
+        client: ClientInfo,
+        Query(query): Query<CheckEmailQuery>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_check_email");
+
+        let started = Instant::now();
+
+        // Returns 429 – too many lookups from this IP. A client without a known IP is not
+        // limited
+        if let (Some(limit), Some(ip)) = (state.config.max_email_checks_per_ip, &client.ip) {
+                check_rate_limit(&state, RateLimitScope::CheckEmailIp, ip, limit).await?;
+        }
+
+        // Returns 400 – malformed email
+        let email = Email::parse(&query.email)?;
+
+        let available = state.config.hide_signup_conflicts || is_available(&state, &email).await?;
+
+        tokio::time::sleep_until(started + Duration::from_millis(CHECK_EMAIL_MIN_RESPONSE_MILLIS))
+                .await;
+
+        Ok((
+                StatusCode::OK,
+                Json(CheckEmailResponse {
+                        available,
+                }),
+        ))
+}
+
+async fn is_available(state: &AppState, email: &Email) -> Result<bool, AuthAPIError> {
+        // Soft-deleted accounts keep their email, so they count as taken
+        match state.user_store.read().await.get_user_including_deleted(email).await {
+                Ok(_) => Ok(false),
+                Err(UserStoreError::UserNotFound) => Ok(true),
+                Err(_) => Err(AuthAPIError::UnexpectedError),
+        }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckEmailQuery {
+        pub email: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckEmailResponse {
+        pub available: bool,
+}
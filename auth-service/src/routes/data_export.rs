@@ -0,0 +1,110 @@
+// src/routes/data_export.rs
+use axum::{
+        extract::{Json, State},
+        http::header::CONTENT_DISPOSITION,
+        response::IntoResponse,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{AuditEvent, AuthAPIError, Role, TwoFAMethod, User, UserStoreError},
+        routes::{active_sessions, CurrentUser, SessionInfo},
+        utils::constants::AUDIT_LOG_MAX_EVENTS,
+        AppState, HandlerResult,
+};
+
+/// Suggested file name for the downloaded export
+const DATA_EXPORT_FILE_NAME: &str = "account-data.json";
+
+/// GET – /me/export
+///
+/// Everything stored about the caller, as a JSON file to download: the account itself, its
+/// sign-in history and its active sessions. Password hashes and session tokens are left out.
+#[tracing::instrument(name = "Export account data", skip_all, err(Debug))]
+pub async fn handle_data_export(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_data_export");
+
+        let user = match state.user_store.read().await.get_user(&current_user.email).await {
+                Ok(user) => user,
+                Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        };
+
+        let login_history = state
+                .audit_log_store
+                .read()
+                .await
+                .get_events(&current_user.email, 0, AUDIT_LOG_MAX_EVENTS)
+                .await?;
+
+        let sessions = {
+                let mut session_store = state.session_store.write().await;
+                active_sessions(&state, &mut **session_store, &current_user.email).await?
+        };
+
+        let export = DataExport {
+                exported_at: Utc::now().timestamp(),
+                account: ExportedAccount::from(&user),
+                login_history,
+                sessions: sessions.into_iter().map(SessionInfo::from).collect(),
+        };
+
+        Ok((
+                [(
+                        CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}\"", DATA_EXPORT_FILE_NAME),
+                )],
+                Json(export),
+        ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataExport {
+        /// Unix timestamp in seconds
+        pub exported_at: i64,
+        pub account: ExportedAccount,
+        /// Newest first, up to `AUDIT_LOG_MAX_EVENTS`
+        pub login_history: Vec<AuditEvent>,
+        pub sessions: Vec<SessionInfo>,
+}
+
+/// Every stored field of a `User` except the password hash. Timestamps are Unix seconds.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedAccount {
+        pub email: String,
+        pub username: Option<String>,
+        pub display_name: Option<String>,
+        pub phone: Option<String>,
+        pub role: Role,
+        pub email_verified: bool,
+        #[serde(rename = "requires2FA")]
+        pub requires_2fa: bool,
+        pub two_fa_method: TwoFAMethod,
+        pub last_login_at: i64,
+        pub locked_until: Option<i64>,
+        pub banned_at: Option<i64>,
+        pub deleted_at: Option<i64>,
+}
+
+impl From<&User> for ExportedAccount {
+        fn from(user: &User) -> Self {
+                Self {
+                        email: user.email_str().to_owned(),
+                        username: user.username().map(|username| username.as_str().to_owned()),
+                        display_name: user.display_name().map(|name| name.as_str().to_owned()),
+                        phone: user.phone().map(|phone| phone.as_str().to_owned()),
+                        role: user.role(),
+                        email_verified: user.email_verified(),
+                        requires_2fa: user.requires_2fa(),
+                        two_fa_method: user.two_fa_method(),
+                        last_login_at: user.last_login_at().timestamp(),
+                        locked_until: user.locked_until().map(|at| at.timestamp()),
+                        banned_at: user.banned_at().map(|at| at.timestamp()),
+                        deleted_at: user.deleted_at().map(|at| at.timestamp()),
+                }
+        }
+}
@@ -0,0 +1,77 @@
+// src/routes/debug_token.rs
+use axum::{
+        extract::{Json, State},
+        http::{HeaderMap, StatusCode},
+        response::IntoResponse,
+};
+use jsonwebtoken::{dangerous::insecure_decode, get_current_timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::AuthAPIError,
+        routes::presented_token,
+        utils::auth::{has_valid_signature, Token},
+        AppState, HandlerResult,
+};
+
+/// GET – /debug/token (dev-only, mounted when `DEBUG_ENDPOINTS=true`)
+///
+/// Decodes the token the request presents (see `presented_token`) and reports what is
+/// inside it. Unlike every other route, an invalid, expired, or banned token is still a 200;
+/// only a missing or undecodable token is rejected.
+pub async fn handle_debug_token(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_debug_token");
+
+        let token = presented_token(&headers)
+                .filter(|token| !token.is_empty())
+                .ok_or(AuthAPIError::MissingToken)?;
+        let token = Token::parse(token).map_err(|_| AuthAPIError::UnprocessableContent)?;
+
+        let decoded = insecure_decode::<serde_json::Value>(token.as_str())
+                .map_err(|_| AuthAPIError::UnprocessableContent)?;
+
+        let expired = decoded
+                .claims
+                .get("exp")
+                .and_then(serde_json::Value::as_u64)
+                .map(|exp| exp < get_current_timestamp());
+        let banned = state
+                .banned_token_store
+                .read()
+                .await
+                .is_banned(&token)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        Ok((
+                StatusCode::OK,
+                Json(TokenIntrospection {
+                        algorithm: format!("{:?}", decoded.header.alg),
+                        claims: decoded.claims,
+                        signature_valid: has_valid_signature(
+                                token.as_str(),
+                                &state.key_ring.load(),
+                        ),
+                        expired,
+                        banned,
+                }),
+        ))
+}
+
+/// What `/debug/token` found inside a token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenIntrospection {
+        /// `alg` from the token header
+        pub algorithm: String,
+        /// Every claim in the payload, including ones this service does not set
+        pub claims: serde_json::Value,
+        /// Whether the signature matches the JWT secret
+        pub signature_valid: bool,
+        /// `None` when the token has no numeric `exp` claim
+        pub expired: Option<bool>,
+        /// Whether the token is in the banned token store
+        pub banned: bool,
+}
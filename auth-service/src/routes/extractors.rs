@@ -0,0 +1,259 @@
+// src/routes/extractors.rs
+use std::{
+        convert::Infallible,
+        net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+        extract::{ConnectInfo, Form, FromRequest, FromRequestParts, Json, Request},
+        http::{
+                header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+                request::Parts,
+                HeaderMap,
+        },
+        response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use serde::de::DeserializeOwned;
+
+use crate::{
+        domain::{AuthAPIError, Email, User, Validate},
+        utils::{
+                auth::{validate_token, Claims, Token},
+                config::TrustedProxyHeader,
+                constants::JWT_COOKIE_NAME,
+        },
+        AppState,
+};
+
+/// The authenticated caller, resolved from a valid, non-banned JWT sent as an
+/// `Authorization: Bearer` header or the auth cookie (see `presented_token`).
+///
+/// Rejects with 400 when no token is sent and 401 when the token is invalid or banned.
+#[derive(Debug)]
+pub struct CurrentUser {
+        pub email: Email,
+        pub token: Token,
+        pub claims: Claims,
+}
+
+impl FromRequestParts<AppState> for CurrentUser {
+        type Rejection = AuthAPIError;
+
+        async fn from_request_parts(
+                parts: &mut Parts,
+                state: &AppState,
+        ) -> Result<Self, Self::Rejection> {
+                let token = match presented_token(&parts.headers) {
+                        Some(token) if !token.is_empty() => token,
+                        _ => return Err(AuthAPIError::MissingToken),
+                };
+                let token = Token::parse(token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+                let claims = validate_token(
+                        &state.banned_token_store,
+                        &state.key_ring.load_full(),
+                        &token,
+                )
+                .await
+                .map_err(|_| AuthAPIError::InvalidToken)?;
+                let email = Email::parse(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+
+                Ok(Self {
+                        email,
+                        token,
+                        claims,
+                })
+        }
+}
+
+/// The JWT a request carries: the `Authorization: Bearer` token when there is one, for
+/// clients that cannot keep cookies, otherwise the auth cookie's value (possibly empty).
+pub(crate) fn presented_token(headers: &HeaderMap) -> Option<String> {
+        bearer_token(headers).or_else(|| cookie_token(headers))
+}
+
+/// Token of an `Authorization: Bearer <token>` header; other schemes are ignored
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<String> {
+        let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+        let (scheme, token) = value.trim().split_once(' ')?;
+        let token = token.trim();
+
+        (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then(|| token.to_owned())
+}
+
+/// Value of the auth cookie, possibly empty
+pub(crate) fn cookie_token(headers: &HeaderMap) -> Option<String> {
+        CookieJar::from_headers(headers)
+                .get(&JWT_COOKIE_NAME)
+                .map(|cookie| cookie.value().to_owned())
+}
+
+/// An authenticated caller whose account currently holds the admin role.
+///
+/// The role is read from the user store rather than trusted from the token, so demoting
+/// an admin takes effect immediately. Rejects non-admins with 403.
+#[derive(Debug)]
+pub struct RequireAdmin(pub User);
+
+impl FromRequestParts<AppState> for RequireAdmin {
+        type Rejection = AuthAPIError;
+
+        async fn from_request_parts(
+                parts: &mut Parts,
+                state: &AppState,
+        ) -> Result<Self, Self::Rejection> {
+                let current_user = CurrentUser::from_request_parts(parts, state).await?;
+
+                let user = state
+                        .user_store
+                        .read()
+                        .await
+                        .get_user(&current_user.email)
+                        .await
+                        .map_err(|_| AuthAPIError::Unauthorized)?;
+
+                if !user.is_admin() {
+                        return Err(AuthAPIError::Forbidden);
+                }
+
+                Ok(RequireAdmin(user))
+        }
+}
+
+/// Where a request came from, recorded with each session and sign-in event.
+///
+/// The IP is the peer address, unless `AppConfig::trusted_proxy_header` names the header the
+/// proxy reports it in: then it is read from that header alone (for `X-Forwarded-For`, the
+/// last hop, since earlier ones are sent by the client and can be forged). The other header
+/// is never read, as the proxy passes it on as the client sent it, and a value that is
+/// missing or not an IP falls back to the peer address.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+        pub ip: Option<String>,
+        pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for ClientInfo {
+        type Rejection = Infallible;
+
+        async fn from_request_parts(
+                parts: &mut Parts,
+                state: &AppState,
+        ) -> Result<Self, Self::Rejection> {
+                let forwarded = state
+                        .config
+                        .trusted_proxy_header
+                        .and_then(|header| forwarded_ip(&parts.headers, header));
+                let peer = parts
+                        .extensions
+                        .get::<ConnectInfo<SocketAddr>>()
+                        .map(|ConnectInfo(addr)| addr.ip().to_string());
+                let user_agent = parts
+                        .headers
+                        .get(USER_AGENT)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_owned);
+
+                Ok(Self {
+                        ip: forwarded.or(peer),
+                        user_agent,
+                })
+        }
+}
+
+/// The client IP a trusted proxy reported in `header`, if it holds one
+fn forwarded_ip(headers: &HeaderMap, header: TrustedProxyHeader) -> Option<String> {
+        let value = headers.get(header.as_str())?.to_str().ok()?;
+        let ip = match header {
+                TrustedProxyHeader::XForwardedFor => value.rsplit(',').next()?,
+                TrustedProxyHeader::XRealIp => value,
+        };
+
+        ip.trim().parse::<IpAddr>().ok().map(|ip| ip.to_string())
+}
+
+/// A JSON body that has also passed its `Validate` checks.
+///
+/// Rejects like `Json` when the body is not JSON or does not deserialize (422 for a missing
+/// or mistyped field), and with a 422 listing every failing field when validation fails.
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+        T: DeserializeOwned + Validate,
+        S: Send + Sync,
+{
+        type Rejection = Response;
+
+        async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let Json(payload) = Json::<T>::from_request(req, state)
+                        .await
+                        .map_err(IntoResponse::into_response)?;
+
+                validated(payload).map(ValidatedJson).map_err(IntoResponse::into_response)
+        }
+}
+
+/// A body sent either as JSON or, as a plain HTML form submits it, as
+/// `application/x-www-form-urlencoded`. Any other `Content-Type` is read as JSON, so
+/// rejections match `Json`'s.
+#[derive(Debug)]
+pub struct JsonOrForm<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonOrForm<T>
+where
+        T: DeserializeOwned,
+        S: Send + Sync,
+{
+        type Rejection = Response;
+
+        async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                if has_form_content_type(req.headers()) {
+                        let Form(payload) = Form::<T>::from_request(req, state)
+                                .await
+                                .map_err(IntoResponse::into_response)?;
+                        Ok(JsonOrForm(payload))
+                } else {
+                        let Json(payload) = Json::<T>::from_request(req, state)
+                                .await
+                                .map_err(IntoResponse::into_response)?;
+                        Ok(JsonOrForm(payload))
+                }
+        }
+}
+
+fn has_form_content_type(headers: &HeaderMap) -> bool {
+        headers.get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(';').next())
+                .is_some_and(|mime| {
+                        mime.trim().eq_ignore_ascii_case("application/x-www-form-urlencoded")
+                })
+}
+
+/// `ValidatedJson` for the routes the bundled UI's forms post to: a `JsonOrForm` body that
+/// has also passed its `Validate` checks
+#[derive(Debug)]
+pub struct ValidatedJsonOrForm<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJsonOrForm<T>
+where
+        T: DeserializeOwned + Validate,
+        S: Send + Sync,
+{
+        type Rejection = Response;
+
+        async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let JsonOrForm(payload) = JsonOrForm::<T>::from_request(req, state).await?;
+
+                validated(payload).map(ValidatedJsonOrForm).map_err(IntoResponse::into_response)
+        }
+}
+
+fn validated<T: Validate>(payload: T) -> Result<T, AuthAPIError> {
+        payload.validate().map_err(AuthAPIError::UnprocessablePayload)?;
+
+        Ok(payload)
+}
@@ -0,0 +1,193 @@
+// src/routes/force_reset_password.rs
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{
+                ensure_not_recently_used, generate_temporary_password, AuthAPIError, Email,
+                HashedPassword, UserStoreError,
+        },
+        routes::{end_all_sessions, RequireAdmin},
+        services::webhook_notifier::WebhookEvent,
+        utils::{auth::validate_password_change_token, constants::PASSWORD_RESET_BY_ADMIN_SUBJECT},
+        AppState, HandlerResult,
+};
+
+/// POST – /admin/force-reset-password (admin only)
+///
+/// Replaces the password of an account with a random temporary one, ends its sessions, and
+/// emails the password to the owner. Logging in with it only gets a password change token
+/// (see `complete_login`), so the owner has to choose a new password at
+/// `/login/change-password` before getting a session.
+#[tracing::instrument(name = "Force password reset", skip_all, err(Debug))]
+pub async fn handle_force_reset_password(
+        State(state): State<AppState>,
+        RequireAdmin(admin): RequireAdmin,
+        Json(payload): Json<ForceResetPasswordPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_force_reset_password");
+
+        // Returns 400 – invalid email
+        let email = Email::parse(&payload.email)?;
+
+        // Returns 404 – no active account for this email
+        let user = state.user_store.read().await.get_user(&email).await?;
+        if user.is_deleted() {
+                return Err(AuthAPIError::UserNotFound);
+        }
+
+        let policy = &state.config.password_policy;
+        let temporary_password = generate_temporary_password(policy, &email, user.username())
+                .ok_or_else(|| {
+                        tracing::error!("No temporary password meets the password policy");
+                        AuthAPIError::UnexpectedError
+                })?;
+        let hashed = HashedPassword::parse_with_context(
+                &temporary_password,
+                policy,
+                &email,
+                user.username(),
+        )
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        state.user_store.write().await.force_password_reset(&email, hashed).await?;
+
+        // Whoever prompted the reset may hold a session; the owner gets a new one once they
+        // have chosen a password
+        end_all_sessions(&state, &email).await?;
+
+        tracing::info!(admin = %admin.email(), account = %email, "Password reset by admin");
+
+        let content = format!(
+                "An administrator has reset the password of your account. Log in with this \
+                 temporary password, after which you will be asked to choose a new one:\n\n{}",
+                temporary_password
+        );
+        if let Err(e) = state
+                .email_client
+                .send_email(&email, PASSWORD_RESET_BY_ADMIN_SUBJECT, &content)
+                .await
+        {
+                // The reset stands: an admin can run it again once email works
+                tracing::error!(error = %e, "Failed to send temporary password");
+                return Err(AuthAPIError::UnexpectedError);
+        }
+
+        Ok((
+                StatusCode::OK,
+                Json(ForceResetPasswordResponse {
+                        message: "Temporary password sent".to_owned(),
+                }),
+        ))
+}
+
+/// POST – /login/change-password
+///
+/// Sets a new password for an account whose login returned 403 `password_change_required`,
+/// authenticated by the `changeToken` from that response. Clears the requirement; the client
+/// then logs in with the new password as usual, 2FA included.
+#[tracing::instrument(name = "Required password change", skip_all, err(Debug))]
+pub async fn handle_required_password_change(
+        State(state): State<AppState>,
+        Json(payload): Json<RequiredPasswordChangePayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_required_password_change");
+
+        // Returns 401 – the token is forged, expired, or not a password change token
+        let claims = validate_password_change_token(&payload.change_token, &state.key_ring.load())
+                .map_err(|_| AuthAPIError::Unauthorized)?;
+        let email = Email::parse(&claims.sub).map_err(|_| AuthAPIError::Unauthorized)?;
+
+        // Returns 401 – the password was already changed, or reset again since the token was
+        // issued, so the token is spent
+        let user = match state.user_store.read().await.get_user(&email).await {
+                Ok(user) => user,
+                Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::Unauthorized),
+                Err(e) => return Err(e.into()),
+        };
+        if !user.must_change_password()
+                || user.password().fingerprint() != claims.password_fingerprint
+        {
+                return Err(AuthAPIError::Unauthorized);
+        }
+
+        // Returns 400 – new password does not meet the password requirements
+        let new_password = HashedPassword::parse_with_context(
+                &payload.new_password,
+                &state.config.password_policy,
+                &email,
+                user.username(),
+        )
+        .await
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+        // Returns 400 – new password is the temporary one or was used recently
+        let mut recent_passwords = vec![user.password_to_owned()];
+        recent_passwords.extend(state.user_store.read().await.get_password_history(&email).await?);
+        ensure_not_recently_used(&payload.new_password, &recent_passwords).await?;
+
+        state.user_store
+                .write()
+                .await
+                .update_password(&email, new_password, state.config.password_history_depth)
+                .await?;
+
+        state.notify_webhook(WebhookEvent::PasswordChanged, &email);
+
+        Ok((
+                StatusCode::OK,
+                Json(ForceResetPasswordResponse {
+                        message: "Password changed successfully".to_owned(),
+                }),
+        ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForceResetPasswordPayload {
+        email: String,
+}
+
+impl ForceResetPasswordPayload {
+        pub fn new(email: String) -> Self {
+                Self {
+                        email,
+                }
+        }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RequiredPasswordChangePayload {
+        #[serde(rename = "changeToken")]
+        change_token: String,
+        #[serde(rename = "newPassword")]
+        new_password: String,
+}
+
+impl RequiredPasswordChangePayload {
+        pub fn new(change_token: String, new_password: String) -> Self {
+                Self {
+                        change_token,
+                        new_password,
+                }
+        }
+}
+
+impl std::fmt::Debug for RequiredPasswordChangePayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the token or the password
+                f.debug_struct("RequiredPasswordChangePayload")
+                        .field("change_token", &"[REDACTED]")
+                        .field("new_password", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ForceResetPasswordResponse {
+        pub message: String,
+}
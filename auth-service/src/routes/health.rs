@@ -0,0 +1,40 @@
+// src/routes/health.rs
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+
+/// GET – /health/ready
+///
+/// 200 while the user store answers, 503 otherwise, so a load balancer stops routing to an
+/// instance that cannot reach its database.
+#[tracing::instrument(name = "Readiness check", skip_all)]
+pub async fn handle_readiness(State(state): State<AppState>) -> impl IntoResponse {
+        match state.user_store.read().await.ping().await {
+                Ok(()) => (
+                        StatusCode::OK,
+                        Json(ReadinessResponse {
+                                status: "ready".to_owned(),
+                        }),
+                ),
+                Err(e) => {
+                        tracing::warn!(error = ?e, "User store is unreachable");
+                        (
+                                StatusCode::SERVICE_UNAVAILABLE,
+                                Json(ReadinessResponse {
+                                        status: "unavailable".to_owned(),
+                                }),
+                        )
+                }
+        }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+        /// `"ready"` or `"unavailable"`
+        pub status: String,
+}
@@ -4,167 +4,790 @@ use axum::{
         http::StatusCode,
         response::IntoResponse,
 };
-use axum_extra::extract::CookieJar;
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use crate::{
         domain::{
-                AuthAPIError, Email, HashedPassword, LoginAttemptId, TwoFACode,
-                TwoFACodeStoreError, UserStore,
+                AccountStanding, ActiveSession, AuditEvent, AuditEventKind, AuthAPIError,
+                BannedTokenStoreError, Email, HashedPassword, LoginAttemptId, TwoFACode,
+                TwoFAMethod, User, UserStore, UserStoreError, Username, Validate, ValidationErrors,
+        },
+        routes::{
+                active_sessions, end_session, locked_by_2fa_failures_until, ClientInfo,
+                ValidatedJsonOrForm,
+        },
+        services::{
+                data_stores::postgres_user_store::dummy_password_hash,
+                webhook_notifier::WebhookEvent,
+        },
+        utils::{
+                auth::{
+                        generate_auth_cookie_with_claims, generate_challenge_token,
+                        generate_password_change_token, validate_token, Token,
+                },
+                config::SessionEvictionPolicy,
+                constants::{FAILED_LOGIN_ALERT_SUBJECT, JWT_COOKIE_NAME},
         },
-        utils::auth::generate_auth_cookie,
         AppState, HandlerResult,
 };
 
 // If the JSON object is missing or malformed, a 422 HTTP status code will  be sent back (handled by Axum's JSON extractor)
 pub async fn handle_login(
         State(state): State<AppState>,
+        client: ClientInfo,
         jar: CookieJar,
-        Json(payload): Json<LoginPayload>,
+        ValidatedJsonOrForm(payload): ValidatedJsonOrForm<LoginPayload>,
 ) -> (CookieJar, HandlerResult<impl IntoResponse>) {
-        println!("->> {:<12} – handle_login", "HANDLER");
+        tracing::debug!("handle_login");
+
+        // An IP that has failed too many logins is refused before it can try another account
+        match ip_throttled_until(&state, &client).await {
+                Ok(None) => {}
+                Ok(Some(until)) => {
+                        tracing::info!(ip = ?client.ip, reason = "login_throttled", %until, "Refused login after too many failures from this IP");
+                        return (jar, Err(AuthAPIError::login_throttled_until(until)));
+                }
+                Err(e) => return (jar, Err(e)),
+        }
 
         // If the JSON object contains invalid credentials (format), a 400 HTTP status code should be sent back.
-        let email = match Email::parse(&payload.email) {
-                Ok(email) => email,
-                Err(e) => return (jar, Err(e.into())),
+        let email = match resolve_identifier(&state, &payload.identifier).await {
+                Ok(Some(email)) => email,
+                // An unknown username fails like an unknown email: a hash verified for nothing,
+                // counted against the IP and delayed, so neither timing nor throttling tells
+                // which usernames exist
+                Ok(None) => {
+                        if let Ok(dummy) = dummy_password_hash().await {
+                                let _ = dummy.verify_raw_password(&payload.password).await;
+                        }
+                        record_failed_login_from_ip(&state, &client).await;
+                        delay_failed_login(&state).await;
+                        return (jar, Err(AuthAPIError::Unauthorized));
+                }
+                Err(e) => return (jar, Err(e)),
         };
+        // Only the stored hash decides a login: the password policy applies to new passwords,
+        // so tightening it does not lock out existing accounts
         let raw_password = payload.password;
-        let password = match HashedPassword::parse(&raw_password).await {
-                Ok(password) => password,
-                Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
-        };
 
-        let store = state.user_store.read().await;
+        match locked_by_2fa_failures_until(&state, &email).await {
+                Ok(None) => {}
+                Ok(Some(until)) => {
+                        tracing::info!(account = %email, reason = "account_locked", %until, "Refused login after too many wrong 2FA codes");
+                        return (jar, Err(AuthAPIError::account_locked_until(until)));
+                }
+                Err(e) => return (jar, Err(e)),
+        }
 
         // Validate user credentials - return 401 for any validation failure
-        if (store.validate_user(&email, &raw_password).await).is_err() {
+        let validation = state.user_store.read().await.validate_user(&email, &raw_password).await;
+        if let Err(e) = validation {
+                // The response is the same 401 whatever the reason; only the log tells them apart
+                let reason = match e {
+                        UserStoreError::InvalidCredentials => "wrong_password",
+                        UserStoreError::UserNotFound => "unknown_user",
+                        _ => "store_error",
+                };
+                tracing::info!(email = email.as_str(), reason, "Login failed");
+                record_failed_login_from_ip(&state, &client).await;
+                // Only a wrong password for an existing account can be reported to its owner
+                if e == UserStoreError::InvalidCredentials {
+                        record_failed_login(&state, &email).await;
+                        record_audit_event(&state, &email, AuditEventKind::LoginFailed, &client)
+                                .await;
+                }
+                delay_failed_login(&state).await;
                 return (jar, Err(AuthAPIError::Unauthorized));
         }
 
+        // Like the account standing below, only reported once the password is right: refusing
+        // a wrong one with 403 would tell apart accounts that exist, since only they are
+        // counted
+        match account_locked_by_failed_logins_until(&state, &email).await {
+                Ok(None) => {}
+                Ok(Some(until)) => {
+                        tracing::info!(account = %email, reason = "account_locked", %until, "Refused login after too many wrong passwords");
+                        return (jar, Err(AuthAPIError::account_locked_until(until)));
+                }
+                Err(e) => return (jar, Err(e)),
+        }
+
         // Get User
-        let user = match store.get_user(&email).await {
+        let user = match state.user_store.read().await.get_user(&email).await {
                 Ok(user) => user,
                 Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
         };
 
+        // Banned, locked and unverified accounts are only told so once their password checks
+        // out; until then they get the same 401 as an unknown email
+        if let Err(e) = ensure_good_standing(&user) {
+                return (jar, Err(e));
+        }
+
+        complete_login(&user, &state, client, jar).await
+}
+
+/// Finish a login for `user`, whose password or magic link checked out: a 2FA challenge for
+/// accounts with 2FA, otherwise a session. Every login path ends here, so none can skip a
+/// required password change.
+pub(crate) async fn complete_login(
+        user: &User,
+        state: &AppState,
+        client: ClientInfo,
+        jar: CookieJar,
+) -> (CookieJar, Result<(StatusCode, Json<LoginResponse>), AuthAPIError>) {
+        // An admin reset the password: logging in only buys a token for choosing a new one,
+        // not a session
+        if user.must_change_password() {
+                tracing::info!(email = user.email().as_str(), "Login requires a password change");
+                return (jar, password_change_required(user, state));
+        }
+
         match user.requires_2fa() {
-                true => handle_2fa(user.email(), &state, jar).await,
-                false => handle_no_2fa(user.email(), jar).await,
+                true => handle_2fa(user.email(), user.two_fa_method(), state, jar).await,
+                false => handle_no_2fa(user.email(), state, client, jar).await,
         }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Find the email of the account named by a login `identifier`: an email address, or
+/// failing that a username.
+///
+/// Returns 400 when the identifier is neither, and `None` for an unknown username, which the
+/// caller fails the way credential validation fails an unknown email.
+async fn resolve_identifier(
+        state: &AppState,
+        identifier: &str,
+) -> Result<Option<Email>, AuthAPIError> {
+        if let Ok(email) = Email::parse(identifier) {
+                return Ok(Some(email));
+        }
+
+        // Neither the identifier nor the reason it failed as an email is logged: a login form
+        // filled in by mistake may carry the password here
+        let username = Username::parse(identifier).map_err(|e| {
+                tracing::info!(field = "identifier", reason = ?e, "Rejected login input");
+                AuthAPIError::InvalidCredentials
+        })?;
+        match state.user_store.read().await.get_user_by_username(&username).await {
+                Ok(user) => Ok(Some(user.email_to_owned())),
+                Err(UserStoreError::UserNotFound) => {
+                        tracing::info!(
+                                username = username.as_str(),
+                                reason = "unknown_user",
+                                "Login failed"
+                        );
+                        Ok(None)
+                }
+                Err(_) => Err(AuthAPIError::UnexpectedError),
+        }
+}
+
+/// Refuse a login for an account that is not in good standing, with a 403 naming why. Only
+/// call it once the password or login link checked out, so the 403 cannot be used to find
+/// accounts.
+///
+/// States are checked in the order banned, locked (until `locked_until` passes), then email not
+/// verified, so an account in several reports the most lasting.
+pub(crate) fn ensure_good_standing(user: &User) -> Result<(), AuthAPIError> {
+        let email = user.email();
+        match user.standing(Utc::now()) {
+                AccountStanding::Good => Ok(()),
+                AccountStanding::Banned => {
+                        tracing::info!(account = %email, reason = "account_banned", "Refused login for banned account");
+                        Err(AuthAPIError::AccountBanned)
+                }
+                AccountStanding::Locked {
+                        until,
+                } => {
+                        tracing::info!(account = %email, reason = "account_locked", %until, "Refused login for locked account");
+                        Err(AuthAPIError::account_locked_until(until))
+                }
+                AccountStanding::EmailNotVerified => {
+                        tracing::info!(account = %email, reason = "email_not_verified", "Refused login for unverified email");
+                        Err(AuthAPIError::EmailNotVerified)
+                }
+        }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct LoginPayload {
-        email: String,
+        /// Email or username. Still accepted as `email` for existing clients.
+        #[serde(alias = "email")]
+        identifier: String,
         password: String,
 }
 
 impl LoginPayload {
-        pub fn new(email: String, password: String) -> Self {
+        pub fn new(identifier: String, password: String) -> Self {
                 Self {
-                        email,
+                        identifier,
                         password,
                 }
         }
 }
 
-async fn handle_2fa(
+impl Validate for LoginPayload {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+                let mut errors = ValidationErrors::new();
+                errors.require("identifier", &self.identifier);
+                errors.require("password", &self.password);
+                errors.into_result()
+        }
+}
+
+impl std::fmt::Debug for LoginPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the password
+                f.debug_struct("LoginPayload")
+                        .field("identifier", &self.identifier)
+                        .field("password", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+pub(crate) async fn handle_2fa(
         email: &Email,
+        method: TwoFAMethod,
         state: &AppState,
         jar: CookieJar,
 ) -> (CookieJar, Result<(StatusCode, Json<LoginResponse>), AuthAPIError>) {
+        /// Enforce the daily cap on 2FA emails before generating a code we may not send
+        if method == TwoFAMethod::Email {
+                if let Some(cap) = state.config.max_2fa_emails_per_day {
+                        let sent = match state
+                                .two_fa_code_store
+                                .write()
+                                .await
+                                .record_email_sent(email)
+                                .await
+                        {
+                                Ok(sent) => sent,
+                                Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+                        };
+                        if sent > cap {
+                                tracing::warn!(cap, "Daily 2FA email cap reached");
+                                return (jar, Err(AuthAPIError::TwoFAEmailLimitReached));
+                        }
+                }
+        }
+
         /// Generate a new random login attempt ID and 2FA code
         let login_attempt_id = LoginAttemptId::default();
         let two_fa_code = TwoFACode::default();
 
-        /// Store the ID and code in our 2FA code store
+        /// Sign the attempt ID into a challenge token, which the client hands back to
+        /// `/verify-2fa` in its place; it lives as long as the attempt
+        let challenge_token = match generate_challenge_token(
+                email,
+                &login_attempt_id,
+                state.config.login_attempt_ttl_seconds,
+                &state.key_ring.load(),
+        ) {
+                Ok(token) => token,
+                Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+        };
+
+        /// Store the ID and code in our 2FA code store, alongside any other pending attempts
+        /// for the account, e.g. from another device
+        if let Err(e) = state
+                .two_fa_code_store
+                .write()
+                .await
+                .add_code(email.to_owned(), login_attempt_id.clone(), two_fa_code.clone())
+                .await
         {
-                let mut two_fa_store = state.two_fa_code_store.write().await;
-                let add_code_result = two_fa_store
-                        .add_code(email.to_owned(), login_attempt_id.clone(), two_fa_code.clone())
-                        .await;
-                match add_code_result {
-                        Ok(_) => {}
-                        Err(TwoFACodeStoreError::CodeAlreadyExists) => {
-                                // Replace stale pending 2FA code for a new login attempt.
-                                if two_fa_store.remove_code(email).await.is_err() {
-                                        return (jar, Err(AuthAPIError::UnexpectedError));
-                                }
-                                if two_fa_store
-                                        .add_code(
-                                                email.to_owned(),
-                                                login_attempt_id.clone(),
-                                                two_fa_code.clone(),
-                                        )
-                                        .await
-                                        .is_err()
-                                {
-                                        return (jar, Err(AuthAPIError::UnexpectedError));
-                                }
-                        }
-                        _ => {
-                                return (jar, Err(AuthAPIError::UnexpectedError));
+                tracing::error!(error = ?e, "Failed to store 2FA code");
+                return (jar, Err(AuthAPIError::UnexpectedError));
+        }
+
+        /// Send 2FA Code via Email Client – TOTP users read their code from an authenticator app
+        if method == TwoFAMethod::Email {
+                let message =
+                        state.config.two_fa_email_template.render(two_fa_code.as_ref(), email);
+                let send_email_result =
+                        state.email_client.send_email(email, &message.subject, &message.body).await;
+                if let Err(e) = send_email_result {
+                        tracing::error!(error = %e, "Failed to send 2FA email");
+                        // The user never received this code, so don't leave it pending for the
+                        // next login attempt to trip over
+                        if let Err(e) = state
+                                .two_fa_code_store
+                                .write()
+                                .await
+                                .remove_code(email, &login_attempt_id)
+                                .await
+                        {
+                                tracing::error!(error = ?e, "Failed to remove unsent 2FA code");
                         }
+                        return (jar, Err(AuthAPIError::UnexpectedError));
                 }
         }
 
-        /// Send 2FA Code via Email Client
-        let send_email_result = state
-                .email_client
-                .send_email(email, "2FA: Verify Email", two_fa_code.as_ref())
-                .await;
-        if (send_email_result).is_err() {
-                return (jar, Err(AuthAPIError::UnexpectedError));
-        }
+        state.metrics.record_2fa_challenge_started();
 
-        /// Return the login attempt ID to the client
+        /// Return the challenge token and 2FA method to the client
         let response = Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
                 message: "2FA required".to_owned(),
-                login_attempt_id: login_attempt_id.as_ref().to_string(),
+                challenge_token,
+                method: method.as_str().to_owned(),
         }));
 
         (jar, Ok((StatusCode::PARTIAL_CONTENT, response)))
 }
 
-async fn handle_no_2fa(
+/// 403 carrying a password change token for `/login/change-password`; it lives as long as a
+/// login attempt
+fn password_change_required(
+        user: &User,
+        state: &AppState,
+) -> Result<(StatusCode, Json<LoginResponse>), AuthAPIError> {
+        let change_token = generate_password_change_token(
+                user.email(),
+                user.password(),
+                state.config.login_attempt_ttl_seconds,
+                &state.key_ring.load(),
+        )
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        let response =
+                Json(LoginResponse::PasswordChangeRequired(PasswordChangeRequiredResponse {
+                        error: "Password change required".to_owned(),
+                        code: PASSWORD_CHANGE_REQUIRED_CODE.to_owned(),
+                        change_token,
+                }));
+        Ok((StatusCode::FORBIDDEN, response))
+}
+
+pub(crate) async fn handle_no_2fa(
         email: &Email,
+        state: &AppState,
+        client: ClientInfo,
         jar: CookieJar,
 ) -> (CookieJar, Result<(StatusCode, Json<LoginResponse>), AuthAPIError>) {
+        if let Err(e) = supersede_existing_session(state, &jar).await {
+                return (jar, Err(e));
+        }
+
         // Generate auth cookie only when 2FA is not required.
-        let auth_cookie = match generate_auth_cookie(email) {
-                Ok(cookie) => cookie,
-                Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+        let (auth_cookie, expires_at) = match start_session(state, email, client.clone()).await {
+                Ok(cookie_and_expiry) => cookie_and_expiry,
+                Err(e) => return (jar, Err(e)),
         };
 
         let jar = jar.add(auth_cookie);
 
-        (jar, Ok((StatusCode::OK, Json(LoginResponse::RegularAuth))))
+        record_login(state, email, &client).await;
+
+        let response = Json(LoginResponse::RegularAuth(RegularAuthResponse {
+                expires_at,
+                email: email.as_str().to_owned(),
+        }));
+
+        (jar, Ok((StatusCode::OK, response)))
 }
 
-// The login route can return 2 possible success responses.
+/// End the session of a still-valid JWT cookie sent along with a login, so logging in again
+/// replaces the previous session instead of leaving its token usable. A missing, invalid,
+/// expired or already banned cookie is ignored.
+pub(crate) async fn supersede_existing_session(
+        state: &AppState,
+        jar: &CookieJar,
+) -> Result<(), AuthAPIError> {
+        let Some(Ok(token)) = jar.get(&JWT_COOKIE_NAME).map(|cookie| Token::parse(cookie.value()))
+        else {
+                return Ok(());
+        };
+        let Ok(claims) =
+                validate_token(&state.banned_token_store, &state.key_ring.load_full(), &token)
+                        .await
+        else {
+                return Ok(());
+        };
+
+        match state.banned_token_store.write().await.ban_token(token).await {
+                Ok(()) | Err(BannedTokenStoreError::TokenAlreadyBanned) => {}
+                Err(BannedTokenStoreError::UnexpectedError) => {
+                        return Err(AuthAPIError::UnexpectedError)
+                }
+        }
+
+        if let Ok(email) = Email::parse(&claims.sub) {
+                state.session_store.write().await.remove_session(&email, &claims.jti).await?;
+        }
+        tracing::info!(jti = %claims.jti, "Superseded previous session on login");
+
+        Ok(())
+}
+
+/// Issue a new auth cookie for `email` and record the session, enforcing
+/// `MAX_SESSIONS_PER_USER` when it is set. Returns the cookie and the token's expiry.
+///
+/// Sessions that expired or were logged out no longer count. If the limit is still reached,
+/// the login is refused or the oldest sessions are evicted by banning their tokens,
+/// depending on `SESSION_EVICTION_POLICY`.
+pub(crate) async fn start_session(
+        state: &AppState,
+        email: &Email,
+        client: ClientInfo,
+) -> Result<(Cookie<'static>, usize), AuthAPIError> {
+        let (cookie, claims) = generate_auth_cookie_with_claims(email, &state.key_ring.load())?;
+
+        let mut session_store = state.session_store.write().await;
+
+        if let Some(max_sessions) = state.config.max_sessions_per_user {
+                let mut active = active_sessions(state, &mut **session_store, email).await?;
+
+                if active.len() >= max_sessions {
+                        match state.config.session_eviction_policy {
+                                SessionEvictionPolicy::Reject => {
+                                        return Err(AuthAPIError::SessionLimitReached)
+                                }
+                                SessionEvictionPolicy::EvictOldest => {
+                                        let excess = active.len() + 1 - max_sessions;
+                                        for session in active.drain(..excess) {
+                                                let jti = session.jti.clone();
+                                                end_session(
+                                                        state,
+                                                        &mut **session_store,
+                                                        email,
+                                                        session,
+                                                )
+                                                .await?;
+                                                tracing::info!(jti = %jti, "Evicted oldest session");
+                                        }
+                                }
+                        }
+                }
+        }
+
+        session_store
+                .add_session(
+                        email,
+                        ActiveSession {
+                                jti: claims.jti,
+                                token: Token::parse(cookie.value())
+                                        .map_err(|_| AuthAPIError::UnexpectedError)?,
+                                expires_at: claims.exp,
+                                created_at: Utc::now().timestamp(),
+                                ip: client.ip,
+                                user_agent: client.user_agent,
+                        },
+                )
+                .await?;
+
+        Ok((cookie, claims.exp))
+}
+
+/// Stamp the user's last login time, reset the failed-login count, add the login to the
+/// user's login history, and report it to the webhook. The login has already succeeded, so a
+/// failure here is logged rather than turned into an error response.
+pub(crate) async fn record_login(state: &AppState, email: &Email, client: &ClientInfo) {
+        if let Err(e) = state.user_store.write().await.record_login(email).await {
+                tracing::warn!(error = ?e, "Failed to record last login time");
+        }
+        if let Err(e) = state.failed_login_store.write().await.clear_failures(email).await {
+                tracing::warn!(error = ?e, "Failed to reset failed login count");
+        }
+        record_audit_event(state, email, AuditEventKind::LoginSuccess, client).await;
+        state.notify_webhook(WebhookEvent::UserLoggedIn, email);
+}
+
+/// Add a sign-in event to the login history of `email`, logging rather than returning a
+/// failure: the history is informational and never decides a login
+async fn record_audit_event(
+        state: &AppState,
+        email: &Email,
+        kind: AuditEventKind,
+        client: &ClientInfo,
+) {
+        let event = AuditEvent {
+                kind,
+                occurred_at: Utc::now().timestamp(),
+                ip: client.ip.clone(),
+                user_agent: client.user_agent.clone(),
+        };
+        if let Err(e) = state.audit_log_store.write().await.record_event(email, event).await {
+                tracing::warn!(error = ?e, "Failed to record login history event");
+        }
+}
+
+/// Wait a random time between `login_min_delay_ms` and `login_max_delay_ms` before refusing a
+/// login for a wrong password or unknown account. Both cases wait the same way, so the delay
+/// does not tell them apart.
+async fn delay_failed_login(state: &AppState) {
+        let min = state.config.login_min_delay_ms;
+        let max = state.config.login_max_delay_ms.max(min);
+        if max == 0 {
+                return;
+        }
+
+        let delay_ms = rand::rng().random_range(min..=max);
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+}
+
+/// When logins from the client's IP may be allowed again, if it has had
+/// `max_failed_logins_per_ip` failed logins within the window: the oldest of them leaving the
+/// window brings the count back under the limit
+async fn ip_throttled_until(
+        state: &AppState,
+        client: &ClientInfo,
+) -> Result<Option<DateTime<Utc>>, AuthAPIError> {
+        let (Some(limit), Some(ip)) = (state.config.max_failed_logins_per_ip, &client.ip) else {
+                return Ok(None);
+        };
+
+        let store = state.failed_login_store.read().await;
+        let failures =
+                store.get_ip_failures(ip).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+        if failures < limit {
+                return Ok(None);
+        }
+        let reset_at = store
+                .get_ip_failures_reset_at(ip)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        Ok(reset_at.and_then(|reset_at| DateTime::from_timestamp(reset_at, 0)))
+}
+
+/// When the account's lock may end, if it has had `max_failed_logins_per_account` wrong
+/// passwords within the window: the oldest of them leaving the window brings the count back
+/// under the limit
+async fn account_locked_by_failed_logins_until(
+        state: &AppState,
+        email: &Email,
+) -> Result<Option<DateTime<Utc>>, AuthAPIError> {
+        let Some(limit) = state.config.max_failed_logins_per_account else {
+                return Ok(None);
+        };
+
+        let store = state.failed_login_store.read().await;
+        let failures =
+                store.get_failures(email).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+        if failures < limit {
+                return Ok(None);
+        }
+        let reset_at = store
+                .get_failures_reset_at(email)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        Ok(reset_at.and_then(|reset_at| DateTime::from_timestamp(reset_at, 0)))
+}
+
+/// Count a failed login, for whatever account, against the client's IP towards
+/// `max_failed_logins_per_ip`. A client without a known IP is not counted.
+async fn record_failed_login_from_ip(state: &AppState, client: &ClientInfo) {
+        let (Some(_), Some(ip)) = (state.config.max_failed_logins_per_ip, &client.ip) else {
+                return;
+        };
+
+        if let Err(e) = state.failed_login_store.write().await.record_ip_failure(ip).await {
+                tracing::warn!(error = ?e, "Failed to record failed login from IP");
+        }
+}
+
+/// Count a wrong password for `email` towards `max_failed_logins_per_account` and, once
+/// `failed_login_alert_threshold` is reached, email the account owner. At most one alert goes
+/// out per `FAILED_LOGIN_ALERT_INTERVAL_SECONDS`. The caller answers 401 either way, so
+/// failures here are only logged.
+async fn record_failed_login(state: &AppState, email: &Email) {
+        let threshold = state.config.failed_login_alert_threshold;
+        if threshold.is_none() && state.config.max_failed_logins_per_account.is_none() {
+                return;
+        }
+
+        let mut store = state.failed_login_store.write().await;
+        let failures = match store.record_failure(email).await {
+                Ok(failures) => failures,
+                Err(e) => {
+                        tracing::warn!(error = ?e, "Failed to record failed login");
+                        return;
+                }
+        };
+        if threshold.is_none_or(|threshold| failures < threshold) {
+                return;
+        }
+
+        match store.claim_alert(email).await {
+                Ok(true) => {}
+                // Already alerted within the interval
+                Ok(false) => return,
+                Err(e) => {
+                        tracing::warn!(error = ?e, "Failed to check failed login alert throttle");
+                        return;
+                }
+        }
+        drop(store);
+
+        let content = format!(
+                "There have been {} failed attempts to sign in to your account. If this wasn't you, \
+                 consider changing your password.",
+                failures
+        );
+        match state.email_client.send_email(email, FAILED_LOGIN_ALERT_SUBJECT, &content).await {
+                Ok(()) => tracing::info!(account = %email, failures, "Sent failed login alert"),
+                Err(e) => {
+                        tracing::warn!(account = %email, error = %e, "Failed to send failed login alert")
+                }
+        }
+}
+
+// The login route can return 2 possible success responses, plus the password change prompt.
 // This enum models each response!
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum LoginResponse {
-        RegularAuth,
+        RegularAuth(RegularAuthResponse),
         TwoFactorAuth(TwoFactorAuthResponse),
+        PasswordChangeRequired(PasswordChangeRequiredResponse),
 }
 
 impl IntoResponse for LoginResponse {
         fn into_response(self) -> axum::response::Response {
                 match self {
-                        LoginResponse::RegularAuth => StatusCode::OK.into_response(),
+                        LoginResponse::RegularAuth(res) => {
+                                (StatusCode::OK, Json(res)).into_response()
+                        }
                         LoginResponse::TwoFactorAuth(res) => {
                                 (StatusCode::PARTIAL_CONTENT, Json(res)).into_response()
                         }
+                        LoginResponse::PasswordChangeRequired(res) => {
+                                (StatusCode::FORBIDDEN, Json(res)).into_response()
+                        }
                 }
         }
 }
 
+/// Body of a successful login, for both the non-2FA path and `/verify-2fa`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegularAuthResponse {
+        /// `exp` claim of the issued JWT (Unix timestamp in seconds), so the client knows
+        /// when to refresh without decoding the token
+        pub expires_at: usize,
+        /// Account the session belongs to, for clients that cannot read the cookie
+        pub email: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TwoFactorAuthResponse {
         pub message: String,
-        #[serde(rename = "loginAttemptId")]
-        pub login_attempt_id: String,
+        /// Signed token naming the pending login attempt; see `generate_challenge_token`
+        #[serde(rename = "challengeToken")]
+        pub challenge_token: String,
+        /// `"email"` or `"totp"` – tells the client which kind of code to prompt for
+        pub method: String,
+}
+
+/// `code` of the 403 a login gets when an admin has reset the account's password
+pub const PASSWORD_CHANGE_REQUIRED_CODE: &str = "password_change_required";
+
+/// Body of a login refused until the account chooses a new password
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordChangeRequiredResponse {
+        pub error: String,
+        /// Always `PASSWORD_CHANGE_REQUIRED_CODE`
+        pub code: String,
+        /// Signed token for `/login/change-password`; see `generate_password_change_token`
+        #[serde(rename = "changeToken")]
+        pub change_token: String,
+}
+
+#[cfg(test)]
+mod tests {
+        use std::sync::Arc;
+
+        use tokio::sync::RwLock;
+
+        use super::*;
+        use crate::{
+                domain::{ErrorResponse, User},
+                services::data_stores::{
+                        HashmapAuditLogStore, HashmapFailedLoginStore, HashmapIdempotencyStore,
+                        HashmapMagicLinkStore, HashmapPendingEmailChangeStore,
+                        HashmapRateLimitStore, HashmapSessionStore, HashmapTwoFACodeStore,
+                        HashmapUserStore, HashsetBannedTokenStore, MockEmailClient,
+                },
+                utils::tracing::{build_subscriber, CapturedLogs, LogFormat},
+                AppStateBuilder,
+        };
+
+        #[tokio::test]
+        async fn test_wrong_password_logs_its_reason_but_answers_an_opaque_401() {
+                let email = Email::parse("user@example.com").unwrap();
+                let mut user_store = HashmapUserStore::new();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+                user_store.add_user(User::new(email, password, false)).await.unwrap();
+                let state = AppStateBuilder::new()
+                        .user_store(Arc::new(RwLock::new(Box::new(user_store))))
+                        .banned_token_store(Arc::new(RwLock::new(Box::new(
+                                HashsetBannedTokenStore::new(),
+                        ))))
+                        .two_fa_code_store(Arc::new(RwLock::new(Box::new(
+                                HashmapTwoFACodeStore::new(),
+                        ))))
+                        .idempotency_store(Arc::new(RwLock::new(Box::new(
+                                HashmapIdempotencyStore::new(),
+                        ))))
+                        .session_store(Arc::new(RwLock::new(Box::new(HashmapSessionStore::new()))))
+                        .failed_login_store(Arc::new(RwLock::new(Box::new(
+                                HashmapFailedLoginStore::new(),
+                        ))))
+                        .audit_log_store(Arc::new(RwLock::new(Box::new(
+                                HashmapAuditLogStore::new(),
+                        ))))
+                        .pending_email_change_store(Arc::new(RwLock::new(Box::new(
+                                HashmapPendingEmailChangeStore::new(),
+                        ))))
+                        .magic_link_store(Arc::new(RwLock::new(Box::new(
+                                HashmapMagicLinkStore::new(),
+                        ))))
+                        .rate_limit_store(Arc::new(RwLock::new(Box::new(
+                                HashmapRateLimitStore::new(),
+                        ))))
+                        .email_client(Arc::new(MockEmailClient))
+                        .build();
+
+                let logs = CapturedLogs::default();
+                let writer = logs.clone();
+                let _guard = tracing::subscriber::set_default(build_subscriber(
+                        LogFormat::Json,
+                        move || writer.clone(),
+                ));
+
+                let payload = LoginPayload::new(
+                        "user@example.com".to_owned(),
+                        "WrongPassword123".to_owned(),
+                );
+                let client = ClientInfo {
+                        ip: None,
+                        user_agent: None,
+                };
+                let response = handle_login(
+                        State(state),
+                        client,
+                        CookieJar::new(),
+                        ValidatedJsonOrForm(payload),
+                )
+                .await
+                .into_response();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body.error, "Unauthorized");
+                assert_eq!(body.code, None);
+
+                let logs = logs.contents();
+                assert!(!logs.contains("WrongPassword123"), "password leaked: {logs}");
+
+                let event: serde_json::Value = logs
+                        .lines()
+                        .map(|line| serde_json::from_str(line).unwrap())
+                        .find(|event: &serde_json::Value| event["message"] == "Login failed")
+                        .expect("no failed login logged");
+                assert_eq!(event["reason"], "wrong_password");
+                assert_eq!(event["email"], "user@example.com");
+        }
 }
@@ -0,0 +1,121 @@
+// src/routes/login_history.rs
+use std::net::IpAddr;
+
+use axum::{
+        extract::{Json, Query, State},
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{AuditEvent, AuditEventKind},
+        routes::CurrentUser,
+        utils::constants::{DEFAULT_LOGIN_HISTORY_PAGE_SIZE, MAX_LOGIN_HISTORY_PAGE_SIZE},
+        AppState, HandlerResult,
+};
+
+/// GET – /security/login-history?limit=&offset=
+///
+/// Lists the caller's recent sign-ins and wrong-password attempts, newest first. `limit`
+/// defaults to `DEFAULT_LOGIN_HISTORY_PAGE_SIZE` and is capped at
+/// `MAX_LOGIN_HISTORY_PAGE_SIZE`.
+#[tracing::instrument(name = "Login history", skip_all, err(Debug))]
+pub async fn handle_login_history(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+        Query(query): Query<LoginHistoryQuery>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_login_history");
+
+        let limit = query
+                .limit
+                .unwrap_or(DEFAULT_LOGIN_HISTORY_PAGE_SIZE)
+                .min(MAX_LOGIN_HISTORY_PAGE_SIZE);
+        let offset = query.offset.unwrap_or(0);
+
+        let events = state
+                .audit_log_store
+                .read()
+                .await
+                .get_events(&current_user.email, offset, limit)
+                .await?;
+
+        let truncate_ips = state.config.login_history_truncate_ips;
+        Ok(Json(LoginHistoryResponse {
+                events: events
+                        .into_iter()
+                        .map(|event| LoginHistoryEntry::new(event, truncate_ips))
+                        .collect(),
+                offset,
+                limit,
+        }))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LoginHistoryQuery {
+        pub limit: Option<usize>,
+        pub offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginHistoryResponse {
+        pub events: Vec<LoginHistoryEntry>,
+        /// Paging the events were listed with, after defaults and the cap were applied
+        pub offset: usize,
+        pub limit: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginHistoryEntry {
+        pub kind: AuditEventKind,
+        /// Unix timestamp in seconds
+        pub occurred_at: i64,
+        pub ip: Option<String>,
+        pub user_agent: Option<String>,
+}
+
+impl LoginHistoryEntry {
+        fn new(event: AuditEvent, truncate_ip: bool) -> Self {
+                let ip = match truncate_ip {
+                        true => event.ip.as_deref().and_then(truncate),
+                        false => event.ip,
+                };
+
+                Self {
+                        kind: event.kind,
+                        occurred_at: event.occurred_at,
+                        ip,
+                        user_agent: event.user_agent,
+                }
+        }
+}
+
+/// The network an IP belongs to: the last IPv4 octet zeroed, or an IPv6 address cut to its
+/// /48. Anything that does not parse as an IP is dropped rather than shown in full.
+fn truncate(ip: &str) -> Option<String> {
+        match ip.parse::<IpAddr>().ok()? {
+                IpAddr::V4(ip) => {
+                        let [a, b, c, _] = ip.octets();
+                        Some(IpAddr::from([a, b, c, 0]).to_string())
+                }
+                IpAddr::V6(ip) => {
+                        let [a, b, c, ..] = ip.segments();
+                        Some(IpAddr::from([a, b, c, 0, 0, 0, 0, 0]).to_string())
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_truncate_keeps_only_the_network() {
+                assert_eq!(truncate("203.0.113.42").as_deref(), Some("203.0.113.0"));
+                assert_eq!(
+                        truncate("2001:db8:85a3:8d3:1319:8a2e:370:7348").as_deref(),
+                        Some("2001:db8:85a3::")
+                );
+                assert_eq!(truncate("not-an-ip"), None);
+        }
+}
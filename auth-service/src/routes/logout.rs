@@ -1,33 +1,60 @@
 // src/routes/logout.rs
-use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        response::{IntoResponse, Redirect},
+};
 use axum_extra::extract::{
         cookie::{Cookie, SameSite},
         CookieJar,
 };
 
 use crate::{
-        domain::BannedTokenStoreError,
-        utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+        domain::{AuthAPIError, BannedTokenStoreError, Email},
+        routes::{end_all_sessions, presented_token},
+        utils::{
+                auth::{validate_token, Claims, Token},
+                constants::JWT_COOKIE_NAME,
+        },
         AppState, HandlerResult,
 };
 
+/// POST – /logout
+///
+/// Bans the presented token, the `Authorization: Bearer` one if sent, otherwise the
+/// cookie's, and clears the cookie. With `LOGOUT_REVOKES_ALL` set, every other session of the
+/// user is ended as well.
 pub async fn handle_logout(
         state: State<AppState>,
+        headers: HeaderMap,
         jar: CookieJar,
 ) -> (CookieJar, HandlerResult<impl IntoResponse>) {
-        println!("->> {:<12} – handle_logout", "HANDLER");
-        let token = match jar.get(JWT_COOKIE_NAME) {
-                Some(cookie) => cookie.value().to_owned(),
+        tracing::debug!("handle_logout");
+        // An empty cookie, as left behind by clients that blank it rather than delete it, is
+        // not really a login, so it counts as no token unless `strict_empty_token` is set
+        let token = match presented_token(&headers) {
+                Some(token) if token.is_empty() && !state.config.strict_empty_token => {
+                        return (jar, Err(LogoutError::MissingToken.into()))
+                }
+                Some(token) => token,
                 None => return (jar, Err(LogoutError::MissingToken.into())),
         };
 
-        if token.is_empty() {
-                return (jar, Err(LogoutError::InvalidToken.into()));
-        }
+        let token = match Token::parse(token) {
+                Ok(token) => token,
+                Err(_) => return (jar, Err(LogoutError::InvalidToken.into())),
+        };
 
-        if validate_token(&state.banned_token_store, &token).await.is_err() {
-                return (jar, Err(LogoutError::InvalidToken.into()));
-        }
+        let claims = match validate_token(
+                &state.banned_token_store,
+                &state.key_ring.load_full(),
+                &token,
+        )
+        .await
+        {
+                Ok(claims) => claims,
+                Err(_) => return (jar, Err(LogoutError::InvalidToken.into())),
+        };
 
         if let Err(error) = state.banned_token_store.write().await.ban_token(token).await {
                 match error {
@@ -40,14 +67,67 @@ pub async fn handle_logout(
                 }
         }
 
-        let removal_cookie = Cookie::build((JWT_COOKIE_NAME, ""))
+        if let Err(e) = end_sibling_sessions(&state, &claims).await {
+                return (jar, Err(e));
+        }
+
+        let jar = jar.remove(removal_cookie());
+
+        (jar, Ok(StatusCode::OK))
+}
+
+/// GET – /logout
+///
+/// Logout for plain browser navigation: the cookie is always cleared and the browser is
+/// redirected to `/`. A valid token is banned on a best-effort basis; a missing or invalid
+/// token is not an error here, unlike `POST /logout`.
+pub async fn handle_logout_redirect(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        jar: CookieJar,
+) -> (CookieJar, Redirect) {
+        tracing::debug!("handle_logout_redirect");
+
+        if let Some(token) = presented_token(&headers).and_then(|token| Token::parse(token).ok()) {
+                if let Ok(claims) = validate_token(
+                        &state.banned_token_store,
+                        &state.key_ring.load_full(),
+                        &token,
+                )
+                .await
+                {
+                        if let Err(e) =
+                                state.banned_token_store.write().await.ban_token(token).await
+                        {
+                                tracing::warn!(error = ?e, "Failed to ban token on GET /logout");
+                        }
+                        if let Err(e) = end_sibling_sessions(&state, &claims).await {
+                                tracing::warn!(error = ?e, "Failed to end sessions on GET /logout");
+                        }
+                }
+        }
+
+        (jar.remove(removal_cookie()), Redirect::to("/"))
+}
+
+/// With `LOGOUT_REVOKES_ALL` set, end every other session of the user who logged out
+async fn end_sibling_sessions(state: &AppState, claims: &Claims) -> Result<(), AuthAPIError> {
+        if !state.config.logout_revokes_all {
+                return Ok(());
+        }
+        match Email::parse(&claims.sub) {
+                Ok(email) => end_all_sessions(state, &email).await,
+                Err(_) => Ok(()),
+        }
+}
+
+/// Cookie that tells the browser to drop the JWT cookie
+fn removal_cookie() -> Cookie<'static> {
+        Cookie::build((JWT_COOKIE_NAME.as_str(), ""))
                 .path("/")
                 .http_only(true)
                 .same_site(SameSite::Lax)
-                .build();
-        let jar = jar.remove(removal_cookie);
-
-        (jar, Ok(StatusCode::OK))
+                .build()
 }
 
 pub enum LogoutError {
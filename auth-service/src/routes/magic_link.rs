@@ -0,0 +1,153 @@
+// src/routes/magic_link.rs
+use axum::{
+        extract::{Json, Query, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+        domain::{AuthAPIError, Email, RateLimitScope, UserStoreError},
+        routes::{
+                check_rate_limit, complete_login, ensure_good_standing,
+                locked_by_2fa_failures_until, ClientInfo,
+        },
+        utils::constants::MAGIC_LINK_SUBJECT,
+        AppState, HandlerResult,
+};
+
+/// POST – /magic-link/request (only mounted when `MAGIC_LINK_LOGIN` is set)
+///
+/// Emails a single-use login link to the account. The answer is the same 200 whether or not
+/// the account exists, and the link is stored and sent in the background, where a failure is
+/// only logged, so neither the answer nor its timing gives registered emails away.
+///
+/// Requests are capped per client IP (`max_magic_links_per_ip`) and per email
+/// (`max_magic_links_per_email`) within `MAGIC_LINK_REQUEST_WINDOW_SECONDS`, counted whether
+/// or not the account exists, so the 429 past either cap gives nothing away either.
+#[tracing::instrument(name = "Request magic link", skip_all, err(Debug))]
+pub async fn handle_request_magic_link(
+        State(state): State<AppState>,
+        client: ClientInfo,
+        Json(payload): Json<MagicLinkRequestPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_request_magic_link");
+
+        // Returns 400 – malformed email
+        let email = Email::parse(&payload.email)?;
+
+        // Returns 429 – too many requests from this IP, or for this email
+        if let (Some(limit), Some(ip)) = (state.config.max_magic_links_per_ip, &client.ip) {
+                check_rate_limit(&state, RateLimitScope::MagicLinkIp, ip, limit).await?;
+        }
+        if let Some(limit) = state.config.max_magic_links_per_email {
+                check_rate_limit(&state, RateLimitScope::MagicLinkEmail, email.as_ref(), limit)
+                        .await?;
+        }
+
+        match state.user_store.read().await.get_user(&email).await {
+                Ok(_) => {
+                        let state = state.clone();
+                        tokio::spawn(async move { send_magic_link(&state, &email).await });
+                }
+                Err(UserStoreError::UserNotFound) => {
+                        tracing::info!(reason = "unknown_user", "Magic link not sent");
+                }
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        }
+
+        Ok((
+                StatusCode::OK,
+                Json(MagicLinkResponse {
+                        message: "If an account exists for this email, a login link has been sent"
+                                .to_owned(),
+                }),
+        ))
+}
+
+/// GET – /magic-link/verify?token= (only mounted when `MAGIC_LINK_LOGIN` is set)
+///
+/// Redeems a login link. The link stands in for the password only: the account must be in
+/// good standing, an account with 2FA still gets its 2FA challenge, and one whose password
+/// an admin reset still has to change it, exactly as after a password login.
+// A missing `token` parameter is rejected with 400 by Axum's Query extractor
+#[tracing::instrument(name = "Verify magic link", skip_all)]
+pub async fn handle_verify_magic_link(
+        State(state): State<AppState>,
+        client: ClientInfo,
+        jar: CookieJar,
+        Query(query): Query<VerifyMagicLinkQuery>,
+) -> (CookieJar, HandlerResult<impl IntoResponse>) {
+        tracing::debug!("handle_verify_magic_link");
+
+        // Returns 401 – unknown, expired, or already used link
+        let email = match state.magic_link_store.write().await.take_token(&query.token).await {
+                Ok(email) => email,
+                Err(e) => return (jar, Err(e.into())),
+        };
+
+        // Returns 401 – the account was deleted after the link was sent
+        let user = match state.user_store.read().await.get_user(&email).await {
+                Ok(user) => user,
+                Err(UserStoreError::UserNotFound) => {
+                        return (jar, Err(AuthAPIError::InvalidMagicLink))
+                }
+                Err(_) => return (jar, Err(AuthAPIError::UnexpectedError)),
+        };
+
+        // Returns 403 – banned, locked or unverified account
+        if let Err(e) = ensure_good_standing(&user) {
+                return (jar, Err(e));
+        }
+        match locked_by_2fa_failures_until(&state, &email).await {
+                Ok(None) => {}
+                Ok(Some(until)) => return (jar, Err(AuthAPIError::account_locked_until(until))),
+                Err(e) => return (jar, Err(e)),
+        }
+
+        complete_login(&user, &state, client, jar).await
+}
+
+/// Store a new login link for `email` and mail it. Runs after the response has been sent,
+/// so failures can only be logged.
+async fn send_magic_link(state: &AppState, email: &Email) {
+        let token = Uuid::new_v4().to_string();
+        if let Err(e) = state
+                .magic_link_store
+                .write()
+                .await
+                .add_token(token.clone(), email.clone(), state.config.magic_link_ttl_seconds)
+                .await
+        {
+                tracing::error!(error = ?e, "Failed to store magic link");
+                return;
+        }
+
+        let content = format!(
+                "Log in by opening the link below. It works once and expires in {} minutes; if \
+                 you didn't ask for it, you can ignore this email.\n\n{}/magic-link/verify?token={}",
+                state.config.magic_link_ttl_seconds.div_ceil(60),
+                state.config.public_url.trim_end_matches('/'),
+                token
+        );
+        if let Err(e) = state.email_client.send_email(email, MAGIC_LINK_SUBJECT, &content).await {
+                tracing::error!(error = %e, "Failed to send magic link");
+        }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkRequestPayload {
+        pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyMagicLinkQuery {
+        pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MagicLinkResponse {
+        pub message: String,
+}
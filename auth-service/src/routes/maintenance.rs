@@ -0,0 +1,44 @@
+// src/routes/maintenance.rs
+use std::sync::atomic::Ordering;
+
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{routes::RequireAdmin, AppState, HandlerResult};
+
+/// POST – /admin/maintenance (admin only)
+///
+/// Turns read-only maintenance on or off for this instance until the next restart, which
+/// goes back to `MAINTENANCE_MODE`.
+#[tracing::instrument(name = "Set maintenance mode", skip_all, err(Debug))]
+pub async fn handle_set_maintenance_mode(
+        State(state): State<AppState>,
+        RequireAdmin(admin): RequireAdmin,
+        Json(payload): Json<MaintenanceModePayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_set_maintenance_mode");
+
+        state.maintenance_mode.store(payload.enabled, Ordering::Relaxed);
+        tracing::info!(admin = %admin.email(), enabled = payload.enabled, "Maintenance mode set");
+
+        Ok((
+                StatusCode::OK,
+                Json(MaintenanceModeResponse {
+                        enabled: payload.enabled,
+                }),
+        ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceModePayload {
+        pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MaintenanceModeResponse {
+        pub enabled: bool,
+}
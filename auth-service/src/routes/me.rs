@@ -0,0 +1,31 @@
+// src/routes/me.rs
+use axum::{
+        extract::{Json, State},
+        response::IntoResponse,
+};
+
+use crate::{
+        domain::{AuthAPIError, PublicUser, UserStoreError},
+        routes::CurrentUser,
+        AppState, HandlerResult,
+};
+
+/// GET – /me
+///
+/// Returns the caller's own account. A token for an account that no longer exists is
+/// treated as invalid.
+#[tracing::instrument(name = "Get current user", skip_all, err(Debug))]
+pub async fn handle_me(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_me");
+
+        let user = match state.user_store.read().await.get_user(&current_user.email).await {
+                Ok(user) => user,
+                Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        };
+
+        Ok(Json(PublicUser::from(&user)))
+}
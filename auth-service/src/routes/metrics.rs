@@ -0,0 +1,24 @@
+// src/routes/metrics.rs
+use axum::{extract::State, http::header::CONTENT_TYPE, response::IntoResponse};
+
+use crate::AppState;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// GET – /metrics (only mounted when `METRICS_ENDPOINT` is set)
+///
+/// Counters for this instance in the Prometheus text format. The pending 2FA gauge is read
+/// from the 2FA code store on each scrape, and left out when the store cannot be read.
+#[tracing::instrument(name = "Metrics", skip_all)]
+pub async fn handle_metrics(State(state): State<AppState>) -> impl IntoResponse {
+        let pending = match state.two_fa_code_store.read().await.count_pending_codes().await {
+                Ok(pending) => Some(pending),
+                Err(e) => {
+                        tracing::warn!(error = ?e, "Failed to count pending 2FA codes");
+                        None
+                }
+        };
+
+        ([(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], state.metrics.render(pending))
+}
@@ -1,15 +1,57 @@
 // src/routes/mod.rs
+mod ban_tokens;
+mod change_email;
+mod change_password;
+mod check_email;
+mod data_export;
+mod debug_token;
+mod extractors;
+mod force_reset_password;
+mod health;
 mod login;
+mod login_history;
 mod logout;
+mod magic_link;
+mod maintenance;
+mod me;
+mod metrics;
+mod password_policy;
+mod rate_limit;
+mod reactivate_account;
 mod root;
+mod rotate_key;
+mod security_summary;
+mod session_status;
+mod sessions;
 mod signup;
 mod verify_2fa;
 mod verify_token;
 
 // re-export items from sub-modules
+pub use ban_tokens::*;
+pub use change_email::*;
+pub use change_password::*;
+pub use check_email::*;
+pub use data_export::*;
+pub use debug_token::*;
+pub use extractors::*;
+pub use force_reset_password::*;
+pub use health::*;
 pub use login::*;
+pub use login_history::*;
 pub use logout::*;
+pub use magic_link::*;
+pub use maintenance::*;
+pub use me::*;
+pub use metrics::*;
+pub use password_policy::*;
+pub(crate) use rate_limit::*;
+pub use reactivate_account::*;
 pub use root::*;
+pub use rotate_key::*;
+pub use security_summary::*;
+pub use session_status::*;
+pub use sessions::*;
 pub use signup::*;
 pub use verify_2fa::*;
 pub use verify_token::*;
@@ -0,0 +1,17 @@
+// src/routes/password_policy.rs
+use axum::{
+        extract::{Json, State},
+        response::IntoResponse,
+};
+
+use crate::AppState;
+
+/// GET – /password/policy
+///
+/// The rules signup and password change enforce, read from the same `AppConfig`, so a client
+/// can validate a new password exactly as the server will.
+pub async fn handle_password_policy(State(state): State<AppState>) -> impl IntoResponse {
+        tracing::debug!("handle_password_policy");
+
+        Json(state.config.password_policy.clone())
+}
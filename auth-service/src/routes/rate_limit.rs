@@ -0,0 +1,29 @@
+// src/routes/rate_limit.rs
+use crate::{
+        domain::{AuthAPIError, RateLimitScope},
+        AppState,
+};
+
+/// Count a request by `subject` in `scope`, refusing it with 429 once `subject` has made more
+/// than `limit` within the scope's window. Refused requests still count, so a client that
+/// keeps retrying stays limited.
+pub(crate) async fn check_rate_limit(
+        state: &AppState,
+        scope: RateLimitScope,
+        subject: &str,
+        limit: u32,
+) -> Result<(), AuthAPIError> {
+        let count = state
+                .rate_limit_store
+                .write()
+                .await
+                .record_request(scope, subject)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        if count > limit {
+                tracing::warn!(scope = scope.as_str(), limit, "Rate limit reached");
+                return Err(AuthAPIError::TooManyRequests);
+        }
+
+        Ok(())
+}
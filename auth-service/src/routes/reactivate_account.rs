@@ -0,0 +1,43 @@
+// src/routes/reactivate_account.rs
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+
+use crate::{domain::Email, routes::RequireAdmin, AppState, HandlerResult};
+
+/// POST – /reactivate-account (admin only)
+///
+/// Clears the soft-delete marker of an account so its owner can log in again.
+#[tracing::instrument(name = "Reactivate account", skip_all, err(Debug))]
+pub async fn handle_reactivate_account(
+        State(state): State<AppState>,
+        RequireAdmin(admin): RequireAdmin,
+        Json(payload): Json<ReactivateAccountPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_reactivate_account");
+
+        // Returns 400 – invalid email
+        let email = Email::parse(&payload.email)?;
+
+        // Returns 404 – no account (deleted or not) for this email
+        state.user_store.write().await.reactivate(&email).await?;
+
+        tracing::info!(admin = %admin.email(), account = %email, "Account reactivated");
+
+        Ok(StatusCode::OK)
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReactivateAccountPayload {
+        email: String,
+}
+
+impl ReactivateAccountPayload {
+        pub fn new(email: String) -> Self {
+                Self {
+                        email,
+                }
+        }
+}
@@ -1,13 +1,17 @@
 // src/routes/root.rs
 use axum::{
+        extract::State,
         http::StatusCode,
         response::{Html, IntoResponse},
 };
 
-pub async fn handle_login_or_signup() -> impl IntoResponse {
-        println!("->> {:<12} – handle_login_or_signup", "HANDLER");
+use crate::AppState;
 
-        let html = match tokio::fs::read_to_string("assets/index.html").await {
+pub async fn handle_login_or_signup(State(state): State<AppState>) -> impl IntoResponse {
+        tracing::debug!("handle_login_or_signup");
+
+        let index = state.config.assets_dir.join("index.html");
+        let html = match tokio::fs::read_to_string(index).await {
                 Ok(content) => Html(content),
                 Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
         };
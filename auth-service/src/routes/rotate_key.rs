@@ -0,0 +1,86 @@
+// src/routes/rotate_key.rs
+use std::sync::Arc;
+
+use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+
+use crate::{
+        domain::ValidationErrors, routes::RequireAdmin, utils::constants::MIN_JWT_SECRET_BYTES,
+        AppState, HandlerResult,
+};
+
+/// POST – /admin/rotate-key (admin only)
+///
+/// Makes `secret` the JWT signing key and demotes the current key to previous. Tokens signed
+/// with the demoted key stay valid until they expire; tokens signed with the key it replaced
+/// in turn stop validating.
+///
+/// The rotation only changes the key ring of the instance that handles the request, and only
+/// until it restarts, when it signs with `JWT_SECRET` again. With several instances, rotate
+/// each one to the same secret (its `kid` is derived from the secret, so they agree on it),
+/// and set `JWT_SECRET` to it before the next deploy.
+#[tracing::instrument(name = "Rotate signing key", skip_all, err(Debug))]
+pub async fn handle_rotate_key(
+        State(state): State<AppState>,
+        RequireAdmin(admin): RequireAdmin,
+        Json(payload): Json<RotateKeyPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_rotate_key");
+
+        // Returns 400 – secret too short to be a safe HMAC key
+        if payload.secret.len() < MIN_JWT_SECRET_BYTES {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                        "secret",
+                        format!("Secret must be at least {} bytes", MIN_JWT_SECRET_BYTES),
+                );
+                return Err(errors.into());
+        }
+
+        let previous =
+                state.key_ring.rcu(|key_ring| Arc::new(key_ring.rotate(payload.secret.as_bytes())));
+        let kid = state.key_ring.load().current().kid().to_owned();
+
+        tracing::info!(
+                admin = %admin.email(),
+                kid,
+                previous_kid = previous.current().kid(),
+                "Signing key rotated"
+        );
+
+        Ok((
+                StatusCode::OK,
+                Json(RotateKeyResponse {
+                        kid,
+                }),
+        ))
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RotateKeyPayload {
+        secret: String,
+}
+
+impl std::fmt::Debug for RotateKeyPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the secret
+                f.debug_struct("RotateKeyPayload").field("secret", &"[REDACTED]").finish()
+        }
+}
+
+impl RotateKeyPayload {
+        pub fn new(secret: impl Into<String>) -> Self {
+                Self {
+                        secret: secret.into(),
+                }
+        }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RotateKeyResponse {
+        /// `kid` header of tokens signed from now on
+        pub kid: String,
+}
@@ -0,0 +1,84 @@
+// src/routes/security_summary.rs
+use axum::{
+        extract::{Json, State},
+        response::IntoResponse,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{AuditEventKind, AuthAPIError, Email, TwoFAMethod, UserStoreError},
+        routes::{active_sessions, CurrentUser},
+        utils::constants::{AUDIT_LOG_MAX_EVENTS, SECURITY_SUMMARY_FAILED_LOGINS_WINDOW_SECONDS},
+        AppState, HandlerResult,
+};
+
+/// GET – /security/summary
+///
+/// The caller's account security at a glance. The session and sign-in counts are left out
+/// when their store cannot be read, rather than failing the whole summary.
+#[tracing::instrument(name = "Security summary", skip_all, err(Debug))]
+pub async fn handle_security_summary(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_security_summary");
+
+        let user = match state.user_store.read().await.get_user(&current_user.email).await {
+                Ok(user) => user,
+                Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        };
+
+        Ok(Json(SecuritySummaryResponse {
+                last_login_at: user.last_login_at().timestamp(),
+                active_session_count: active_session_count(&state, &current_user.email).await,
+                two_factor_enabled: user.requires_2fa(),
+                two_factor_method: user.requires_2fa().then(|| user.two_fa_method()),
+                recent_failed_logins: recent_failed_logins(&state, &current_user.email).await,
+        }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecuritySummaryResponse {
+        /// Unix timestamp in seconds
+        pub last_login_at: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub active_session_count: Option<usize>,
+        pub two_factor_enabled: bool,
+        /// Only set when 2FA is enabled
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub two_factor_method: Option<TwoFAMethod>,
+        /// Wrong passwords given for the account within
+        /// `SECURITY_SUMMARY_FAILED_LOGINS_WINDOW_SECONDS`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub recent_failed_logins: Option<usize>,
+}
+
+async fn active_session_count(state: &AppState, email: &Email) -> Option<usize> {
+        let mut session_store = state.session_store.write().await;
+        match active_sessions(state, &mut **session_store, email).await {
+                Ok(sessions) => Some(sessions.len()),
+                Err(e) => {
+                        tracing::warn!(error = ?e, "Failed to count sessions for security summary");
+                        None
+                }
+        }
+}
+
+async fn recent_failed_logins(state: &AppState, email: &Email) -> Option<usize> {
+        let since = Utc::now().timestamp() - SECURITY_SUMMARY_FAILED_LOGINS_WINDOW_SECONDS;
+        let events =
+                state.audit_log_store.read().await.get_events(email, 0, AUDIT_LOG_MAX_EVENTS).await;
+        match events {
+                Ok(events) => Some(events
+                        .iter()
+                        .filter(|event| event.kind == AuditEventKind::LoginFailed)
+                        .filter(|event| event.occurred_at >= since)
+                        .count()),
+                Err(e) => {
+                        tracing::warn!(error = ?e, "Failed to read sign-ins for security summary");
+                        None
+                }
+        }
+}
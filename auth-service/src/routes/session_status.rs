@@ -0,0 +1,72 @@
+// src/routes/session_status.rs
+use axum::{
+        extract::{Json, State},
+        http::{HeaderMap, StatusCode},
+        response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{AuthAPIError, Email, UserStoreError},
+        routes::presented_token,
+        utils::auth::{validate_token, Token},
+        AppState, HandlerResult,
+};
+
+/// GET – /session/status
+///
+/// Whether the token the request carries (see `presented_token`) still grants a session, for
+/// frontends polling to notice a forced logout. Answers 200 either way, so the client
+/// branches on `active` rather than handling a 401. Only reads: nothing is banned, refreshed
+/// or recorded.
+pub async fn handle_session_status(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_session_status");
+
+        let expires_at = active_session_expiry(&state, &headers).await?;
+
+        Ok((
+                StatusCode::OK,
+                Json(SessionStatusResponse {
+                        active: expires_at.is_some(),
+                        expires_at,
+                }),
+        ))
+}
+
+/// `exp` of the presented token if it is signed, unexpired, not banned and names an account
+/// that still exists; `None` otherwise
+async fn active_session_expiry(
+        state: &AppState,
+        headers: &HeaderMap,
+) -> Result<Option<usize>, AuthAPIError> {
+        let Some(token) = presented_token(headers).and_then(|token| Token::parse(token).ok())
+        else {
+                return Ok(None);
+        };
+        let Ok(claims) =
+                validate_token(&state.banned_token_store, &state.key_ring.load_full(), &token)
+                        .await
+        else {
+                return Ok(None);
+        };
+        let Ok(email) = Email::parse(&claims.sub) else {
+                return Ok(None);
+        };
+
+        // Like `/verify-token`, a token for an account that no longer exists is not a session
+        match state.user_store.read().await.get_user(&email).await {
+                Ok(_) => Ok(Some(claims.exp)),
+                Err(UserStoreError::UserNotFound) => Ok(None),
+                Err(_) => Err(AuthAPIError::UnexpectedError),
+        }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionStatusResponse {
+        pub active: bool,
+        /// `exp` claim of the token (Unix timestamp in seconds); `null` when not active
+        pub expires_at: Option<usize>,
+}
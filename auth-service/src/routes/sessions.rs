@@ -0,0 +1,176 @@
+// src/routes/sessions.rs
+use axum::{
+        extract::{Json, Path, State},
+        http::StatusCode,
+        response::IntoResponse,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+        domain::{ActiveSession, AuthAPIError, BannedTokenStoreError, Email, SessionStore},
+        routes::CurrentUser,
+        AppState, HandlerResult,
+};
+
+/// GET – /sessions, /security/sessions
+///
+/// Lists the caller's active sessions, oldest first.
+#[tracing::instrument(name = "List sessions", skip_all, err(Debug))]
+pub async fn handle_list_sessions(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_list_sessions");
+
+        let mut session_store = state.session_store.write().await;
+        let sessions = active_sessions(&state, &mut **session_store, &current_user.email).await?;
+
+        Ok(Json(SessionsResponse {
+                sessions: sessions.into_iter().map(SessionInfo::from).collect(),
+        }))
+}
+
+/// DELETE – /sessions/{jti}
+///
+/// Ends one of the caller's sessions by banning its token.
+#[tracing::instrument(name = "Revoke session", skip_all, err(Debug))]
+pub async fn handle_revoke_session(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+        Path(jti): Path<String>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_revoke_session");
+
+        let mut session_store = state.session_store.write().await;
+
+        // Returns 404 – only the caller's own sessions are searched, so another user's `jti`
+        // is indistinguishable from one that never existed
+        let session = active_sessions(&state, &mut **session_store, &current_user.email)
+                .await?
+                .into_iter()
+                .find(|session| session.jti == jti)
+                .ok_or(AuthAPIError::SessionNotFound)?;
+
+        end_session(&state, &mut **session_store, &current_user.email, session).await?;
+
+        Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST – /security/sign-out-others
+///
+/// Ends every session of the caller except the one making the request, banning their tokens.
+#[tracing::instrument(name = "Sign out other sessions", skip_all, err(Debug))]
+pub async fn handle_sign_out_other_sessions(
+        State(state): State<AppState>,
+        current_user: CurrentUser,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!("handle_sign_out_other_sessions");
+
+        let mut session_store = state.session_store.write().await;
+        let others: Vec<_> = active_sessions(&state, &mut **session_store, &current_user.email)
+                .await?
+                .into_iter()
+                .filter(|session| session.jti != current_user.claims.jti)
+                .collect();
+
+        let sessions_ended = others.len();
+        for session in others {
+                end_session(&state, &mut **session_store, &current_user.email, session).await?;
+        }
+
+        Ok(Json(SignOutOthersResponse {
+                sessions_ended,
+        }))
+}
+
+/// Sessions of `email` that can still be used, oldest first. Sessions that expired or whose
+/// token was banned (logout, eviction, revocation) are dropped from the store on the way.
+pub(crate) async fn active_sessions(
+        state: &AppState,
+        session_store: &mut (dyn SessionStore + Send + Sync),
+        email: &Email,
+) -> Result<Vec<ActiveSession>, AuthAPIError> {
+        let now = Utc::now().timestamp().max(0) as usize;
+
+        let mut active = Vec::new();
+        for session in session_store.get_sessions(email).await? {
+                let is_banned = state
+                        .banned_token_store
+                        .read()
+                        .await
+                        .is_banned(&session.token)
+                        .await
+                        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+                if is_banned || session.expires_at <= now {
+                        session_store.remove_session(email, &session.jti).await?;
+                } else {
+                        active.push(session);
+                }
+        }
+
+        Ok(active)
+}
+
+/// Ban the session's token and stop tracking it
+pub(crate) async fn end_session(
+        state: &AppState,
+        session_store: &mut (dyn SessionStore + Send + Sync),
+        email: &Email,
+        session: ActiveSession,
+) -> Result<(), AuthAPIError> {
+        match state.banned_token_store.write().await.ban_token(session.token).await {
+                // Already banned still means the session is over
+                Ok(()) | Err(BannedTokenStoreError::TokenAlreadyBanned) => {}
+                Err(BannedTokenStoreError::UnexpectedError) => {
+                        return Err(AuthAPIError::UnexpectedError)
+                }
+        }
+
+        session_store.remove_session(email, &session.jti).await?;
+
+        Ok(())
+}
+
+/// End every active session of `email`
+pub(crate) async fn end_all_sessions(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+        let mut session_store = state.session_store.write().await;
+        for session in active_sessions(state, &mut **session_store, email).await? {
+                end_session(state, &mut **session_store, email, session).await?;
+        }
+
+        Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+        pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignOutOthersResponse {
+        /// Sessions whose tokens were banned by this request
+        pub sessions_ended: usize,
+}
+
+/// Public view of a session; the token itself is never returned
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+        pub jti: String,
+        /// Unix timestamp in seconds
+        pub created_at: i64,
+        pub ip: Option<String>,
+        pub user_agent: Option<String>,
+}
+
+impl From<ActiveSession> for SessionInfo {
+        fn from(session: ActiveSession) -> Self {
+                Self {
+                        jti: session.jti,
+                        created_at: session.created_at,
+                        ip: session.ip,
+                        user_agent: session.user_agent,
+                }
+        }
+}
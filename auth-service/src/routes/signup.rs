@@ -1,26 +1,71 @@
 // src/routes/signup.rs
 use crate::{
-        domain::{AuthAPIError, Email, ErrorResponse, HashedPassword, User, UserStore},
+        domain::{
+                AuthAPIError, DisplayName, Email, EmailError, ErrorResponse, HashedPassword,
+                PasswordPolicy, User, UserStore, UserStoreError, Username, Validate,
+                ValidationErrors,
+        },
+        routes::{record_login, start_session, ClientInfo, ValidatedJsonOrForm},
+        services::webhook_notifier::WebhookEvent,
+        utils::constants::{
+                IDEMPOTENCY_KEY_HEADER, MAX_IDEMPOTENCY_KEY_LENGTH, SIGNUP_CONFLICT_SUBJECT,
+        },
         AppState, HandlerResult,
 };
 use axum::{
         extract::{Json, State},
-        http::StatusCode,
+        http::{HeaderMap, StatusCode},
         response::IntoResponse,
         Json as JsonData,
 };
+use axum_extra::extract::CookieJar;
 use regex::Regex;
 
+const SIGNUP_SUCCESS_MESSAGE: &str = "User created successfully!";
+
 /// POST – /signup
 #[tracing::instrument(name = "Singnup", skip_all, err(Debug))]
 pub async fn handle_signup(
         State(state): State<AppState>,
-        Json(payload): Json<SignupPayload>,
+        client: ClientInfo,
+        jar: CookieJar,
+        headers: HeaderMap,
+        ValidatedJsonOrForm(payload): ValidatedJsonOrForm<SignupPayload>,
 ) -> HandlerResult<impl IntoResponse> {
-        println!("->> {:<12} — handle_signup – {payload:?}", "HANDLER");
+        tracing::debug!(?payload, "handle_signup");
+
+        // Returns 400 – header present but empty, too long, or not visible ASCII
+        let idempotency_key = get_idempotency_key(&headers)?;
+
+        // Returns 400 – CAPTCHA required but missing or rejected. Checked before validation so
+        // bots never reach password hashing.
+        verify_captcha(&state, payload.captcha_token.as_deref()).await?;
 
         // If the signup route is called with invalid input (ex: an incorrectly formatted email address or password), a 400 HTTP status code should be returned.
-        let (req_email, req_pwd) = validate_credentials(&payload.email, &payload.password).await?;
+        // Every failing field is listed in the response body.
+        let user = validate_signup(&payload, &state.config.password_policy).await?;
+        let req_email = user.email_to_owned();
+
+        /// A retry of a signup that already succeeded gets the original 201 instead of a 409.
+        /// It is not logged in again, as the password was never checked against the account.
+        if let Some(key) = &idempotency_key {
+                let recorded = state
+                        .idempotency_store
+                        .read()
+                        .await
+                        .get_response(key, &req_email)
+                        .await
+                        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+                if let Some(recorded) = recorded {
+                        let response: SignupResponse = serde_json::from_str(&recorded)
+                                .map_err(|_| AuthAPIError::UnexpectedError)?;
+                        return Ok((jar, response));
+                }
+        }
+
+        // Returns 400 – MX verification is on and the email's domain has no MX records
+        verify_email_domain(&state, &req_email).await?;
 
         // If one attempts to create a new user with an existing email address, a 409 HTTP status code should be returned.
         // NOTE: Scope created to prevent deadlock. Read lock is dropped before write
@@ -29,30 +74,241 @@ pub async fn handle_signup(
                 store_guard.get_user(&req_email).await.is_ok()
         }; // NOTE:  Read lock dropped here
 
-        /// If user already exists, return 409
+        /// If user already exists, return 409, or with HIDE_SIGNUP_CONFLICTS the usual 201 while
+        /// the owner is told by email instead
         if user_exists {
+                return signup_conflict(&state, jar, req_email);
+        }
+
+        // NOTE: Now safe to acquire write lock. A taken username is also reported as a 409.
+        let added = state.user_store.write().await.add_user(user).await;
+        match added {
+                Ok(()) => {}
+                // The email was taken after the check above, by a concurrent signup or a
+                // soft-deleted account, so it gets the same answer as an existing one
+                Err(UserStoreError::UserAlreadyExists) if email_taken(&state, &req_email).await => {
+                        return signup_conflict(&state, jar, req_email);
+                }
+                Err(UserStoreError::UserAlreadyExists) => {
+                        return Err(AuthAPIError::UserAlreadyExists)
+                }
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        }
+
+        state.notify_webhook(WebhookEvent::UserCreated, &req_email);
+
+        /// 2FA users still complete the second factor at `/login`. The user has been created at
+        /// this point, so a session that cannot be started is logged and left to `/login`. With
+        /// HIDE_SIGNUP_CONFLICTS nobody is logged in, as a cookie only a new account gets would
+        /// tell it apart from an existing one.
+        let auto_login = state.config.auto_login_after_signup
+                && !state.config.hide_signup_conflicts
+                && !payload.requires_2fa;
+        let jar = if auto_login {
+                match start_session(&state, &req_email, client.clone()).await {
+                        Ok((cookie, _)) => {
+                                record_login(&state, &req_email, &client).await;
+                                jar.add(cookie)
+                        }
+                        Err(e) => {
+                                tracing::warn!(error = ?e, "Failed to log in after signup");
+                                jar
+                        }
+                }
+        } else {
+                jar
+        };
+
+        let response = SignupResponse::new(SIGNUP_SUCCESS_MESSAGE);
+
+        /// Record the outcome for retries. The user has been created at this point, so a
+        /// failure to record is logged rather than turned into an error response.
+        if let Some(key) = &idempotency_key {
+                let recorded = match serde_json::to_string(&response) {
+                        Ok(body) => state
+                                .idempotency_store
+                                .write()
+                                .await
+                                .save_response(key, &req_email, body)
+                                .await
+                                .is_ok(),
+                        Err(_) => false,
+                };
+                if !recorded {
+                        tracing::warn!("Failed to record signup response for idempotency key");
+                }
+        }
+
+        Ok((jar, response))
+}
+
+/// 409, or with HIDE_SIGNUP_CONFLICTS the usual 201 while the owner of `email` is told instead
+fn signup_conflict(
+        state: &AppState,
+        jar: CookieJar,
+        email: Email,
+) -> Result<(CookieJar, SignupResponse), AuthAPIError> {
+        if !state.config.hide_signup_conflicts {
                 return Err(AuthAPIError::UserAlreadyExists);
         }
+        notify_signup_conflict(state, email);
+        Ok((jar, SignupResponse::new(SIGNUP_SUCCESS_MESSAGE)))
+}
+
+/// Whether a row holds `email`, soft-deleted ones included
+async fn email_taken(state: &AppState, email: &Email) -> bool {
+        state.user_store.read().await.get_user_including_deleted(email).await.is_ok()
+}
+
+/// Tell the owner of `email` that someone tried to sign up with it. The email is sent in the
+/// background and a failure is only logged, so the response is the same, and as quick, as
+/// for a new account.
+fn notify_signup_conflict(state: &AppState, email: Email) {
+        tracing::info!(reason = "existing_email", "Signup conflict hidden");
+        let email_client = state.email_client.clone();
+        tokio::spawn(async move {
+                let content = "Someone just tried to create an account with this email address, \
+                               which already has one. Nothing about your account was changed. If \
+                               it was you, log in with your existing password; if not, you can \
+                               ignore this email.";
+                if let Err(e) =
+                        email_client.send_email(&email, SIGNUP_CONFLICT_SUBJECT, content).await
+                {
+                        tracing::error!(error = %e, "Failed to send signup conflict notice");
+                }
+        });
+}
+
+/// Read the optional `Idempotency-Key` header
+fn get_idempotency_key(headers: &HeaderMap) -> Result<Option<String>, AuthAPIError> {
+        let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+                return Ok(None);
+        };
+
+        let key = value.to_str().map_err(|_| AuthAPIError::InvalidIdempotencyKey)?;
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+                return Err(AuthAPIError::InvalidIdempotencyKey);
+        }
+
+        Ok(Some(key.to_owned()))
+}
 
-        let user = User::new(req_email, req_pwd, payload.requires_2fa);
+/// Check the signup CAPTCHA token when a verifier is configured; a no-op otherwise
+async fn verify_captcha(state: &AppState, token: Option<&str>) -> Result<(), AuthAPIError> {
+        let Some(verifier) = &state.captcha_verifier else {
+                return Ok(());
+        };
 
-        // NOTE: Now safe to acquire write lock
-        match state.user_store.write().await.add_user(user).await {
-                Ok(_) => Ok(SignupResponse::new("User created successfully!")),
-                Err(_) => Err(AuthAPIError::UserAlreadyExists),
+        let token = token.filter(|t| !t.is_empty()).ok_or(AuthAPIError::CaptchaFailed)?;
+        verifier.verify(token).await.map_err(|e| {
+                tracing::info!(error = %e, "CAPTCHA verification failed");
+                AuthAPIError::CaptchaFailed
+        })
+}
+
+/// Check the email's domain has MX records when a resolver is configured; a no-op otherwise.
+/// A lookup that fails outright lets the signup through, so a DNS outage does not block
+/// signups.
+async fn verify_email_domain(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+        let Some(resolver) = &state.mx_resolver else {
+                return Ok(());
+        };
+
+        let domain = email.domain();
+        match resolver.has_mx_records(&domain).await {
+                Ok(true) => Ok(()),
+                Ok(false) => {
+                        tracing::info!(field = "email", %domain, "Rejected signup input");
+                        let mut errors = ValidationErrors::new();
+                        errors.add("email", EmailError::UndeliverableDomain.to_string());
+                        Err(errors.into())
+                }
+                Err(e) => {
+                        tracing::warn!(%domain, error = %e, "Skipped MX check");
+                        Ok(())
+                }
         }
 }
 
-async fn validate_credentials(
-        email: &str,
-        password: &str,
-) -> Result<(Email, HashedPassword), AuthAPIError> {
-        let email = Email::parse(email).map_err(|_| AuthAPIError::InvalidCredentials)?;
-        let pwd = HashedPassword::parse(password)
+/// Validate email, password, and the optional username and display name independently,
+/// collecting every failure instead of stopping at the first one, and build the new user
+async fn validate_signup(
+        payload: &SignupPayload,
+        policy: &PasswordPolicy,
+) -> Result<User, AuthAPIError> {
+        let SignupPayload {
+                email,
+                password,
+                username,
+                display_name,
+                ..
+        } = payload;
+        let mut errors = ValidationErrors::new();
+
+        // The rejected value itself is never logged: a field filled in by mistake may hold the
+        // password. The email is logged only once it has parsed as an email.
+        let email = Email::parse(email)
+                .map_err(|e| {
+                        tracing::info!(field = "email", reason = ?e, "Rejected signup input");
+                        errors.add("email", e.to_string())
+                })
+                .ok();
+        let username = username
+                .as_deref()
+                .map(Username::parse)
+                .transpose()
+                .map_err(|e| {
+                        tracing::info!(field = "username", reason = ?e, "Rejected signup input");
+                        errors.add("username", e.to_string())
+                })
+                .ok();
+        // The password is checked against the email and username only once both have parsed
+        let violations = match (&email, &username) {
+                (Some(email), Some(username)) => {
+                        policy.violations_with_context(password, email, username.as_ref())
+                }
+                _ => policy.violations(password),
+        };
+        for violation in violations {
+                tracing::info!(
+                        field = "password",
+                        reason = ?violation,
+                        email = email.as_ref().map(Email::as_str),
+                        "Rejected signup input"
+                );
+                errors.add("password", violation.to_string());
+        }
+        let display_name = display_name
+                .as_deref()
+                .map(DisplayName::parse)
+                .transpose()
+                .map_err(|e| {
+                        tracing::info!(field = "display_name", reason = ?e, "Rejected signup input");
+                        errors.add("display_name", e.to_string())
+                })
+                .ok();
+
+        let (email, username, display_name) = match (email, username, display_name) {
+                (Some(email), Some(username), Some(display_name)) if errors.is_empty() => {
+                        (email, username, display_name)
+                }
+                _ => return Err(errors.into()),
+        };
+
+        // The rules already passed, so a failure here is in hashing itself
+        let pwd = HashedPassword::parse_with_context(password, policy, &email, username.as_ref())
                 .await
-                .map_err(|_| AuthAPIError::InvalidCredentials)?;
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+        let mut user = User::new(email, pwd, payload.requires_2fa);
+        if let Some(username) = username {
+                user = user.with_username(username);
+        }
+        if let Some(display_name) = display_name {
+                user = user.with_display_name(display_name);
+        }
 
-        Ok((email, pwd))
+        Ok(user)
 }
 
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -74,12 +330,44 @@ impl IntoResponse for SignupResponse {
         }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct SignupPayload {
         email: String,
         password: String,
         #[serde(rename = "requires2FA")]
         requires_2fa: bool,
+        /// Only required when CAPTCHA verification is enabled
+        #[serde(rename = "captchaToken", default, skip_serializing_if = "Option::is_none")]
+        captcha_token: Option<String>,
+        /// Optional login name, usable in place of the email at `/login`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        /// Optional name the UI greets the user by
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        display_name: Option<String>,
+}
+
+impl Validate for SignupPayload {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+                let mut errors = ValidationErrors::new();
+                errors.require("email", &self.email);
+                errors.require("password", &self.password);
+                errors.into_result()
+        }
+}
+
+impl std::fmt::Debug for SignupPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the password or CAPTCHA token
+                f.debug_struct("SignupPayload")
+                        .field("email", &self.email)
+                        .field("password", &"[REDACTED]")
+                        .field("requires_2fa", &self.requires_2fa)
+                        .field("captcha_token", &self.captcha_token.as_ref().map(|_| "[REDACTED]"))
+                        .field("username", &self.username)
+                        .field("display_name", &self.display_name)
+                        .finish()
+        }
 }
 
 impl SignupPayload {
@@ -88,8 +376,23 @@ impl SignupPayload {
                         email,
                         password,
                         requires_2fa,
+                        captcha_token: None,
+                        username: None,
+                        display_name: None,
                 }
         }
+        pub fn with_captcha_token(mut self, captcha_token: impl Into<String>) -> Self {
+                self.captcha_token = Some(captcha_token.into());
+                self
+        }
+        pub fn with_username(mut self, username: impl Into<String>) -> Self {
+                self.username = Some(username.into());
+                self
+        }
+        pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+                self.display_name = Some(display_name.into());
+                self
+        }
         pub fn email(&self) -> &String {
                 &self.email
         }
@@ -106,3 +409,61 @@ impl SignupPayload {
                 self.password.clone()
         }
 }
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::{
+                domain::{FieldError, PasswordError},
+                utils::tracing::{build_subscriber, CapturedLogs, LogFormat},
+        };
+
+        #[tokio::test]
+        async fn test_rejected_password_logs_the_reason_but_not_the_password() {
+                let logs = CapturedLogs::default();
+                let writer = logs.clone();
+                let _guard = tracing::subscriber::set_default(build_subscriber(
+                        LogFormat::Json,
+                        move || writer.clone(),
+                ));
+
+                let payload = SignupPayload::new(
+                        "user@example.com".to_owned(),
+                        "Short1".to_owned(),
+                        false,
+                );
+                let result = validate_signup(&payload, &PasswordPolicy::default()).await;
+                assert!(result.is_err());
+
+                let logs = logs.contents();
+                assert!(!logs.contains("Short1"), "password leaked: {logs}");
+
+                let event: serde_json::Value = logs
+                        .lines()
+                        .map(|line| serde_json::from_str(line).unwrap())
+                        .find(|event: &serde_json::Value| event["field"] == "password")
+                        .expect("no password rejection logged");
+                assert_eq!(event["message"], "Rejected signup input");
+                assert_eq!(event["reason"], "TooShort(8)");
+                assert_eq!(event["email"], "user@example.com");
+        }
+
+        #[tokio::test]
+        async fn test_password_containing_the_email_local_part_is_rejected() {
+                let payload =
+                        SignupPayload::new("john@x.com".to_owned(), "John12345".to_owned(), false);
+
+                let result = validate_signup(&payload, &PasswordPolicy::default()).await;
+
+                let Err(AuthAPIError::InvalidInput(errors)) = result else {
+                        panic!("expected a validation error, got {:?}", result.map(|_| ()));
+                };
+                assert_eq!(
+                        errors.fields,
+                        vec![FieldError {
+                                field: "password".to_owned(),
+                                message: PasswordError::ContainsIdentifier.to_string(),
+                        }]
+                );
+        }
+}
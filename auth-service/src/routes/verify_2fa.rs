@@ -1,71 +1,313 @@
 // src/routes/verify_2fa.rs
 use axum::{
-        extract::{Json, State},
+        extract::{FromRequest, Json, Request, State},
         http::StatusCode,
-        response::IntoResponse,
+        response::{IntoResponse, Response},
 };
 use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Duration, Utc};
 
 use crate::{
         domain::{
                 AuthAPIError, Email, EmailError, HashedPassword, LoginAttemptId, TwoFACode,
-                TwoFACodeStoreError,
+                TwoFACodeStoreError, Validate, ValidationErrors,
+        },
+        routes::{
+                record_login, start_session, supersede_existing_session, ClientInfo,
+                RegularAuthResponse, ValidatedJson,
+        },
+        utils::{
+                auth::validate_challenge_token, constants::TWO_FA_FAILURE_WINDOW_SECONDS,
+                metrics::TwoFAVerificationResult,
         },
-        utils::auth::{generate_auth_cookie, GenerateTokenError},
         AppState, HandlerResult,
 };
 
 // If the request is processed successfully, a 200 HTTP status code should be returned and the JWT auth cookie should be set.
 pub async fn handle_verify_2fa(
         State(state): State<AppState>,
+        client: ClientInfo,
         jar: CookieJar,
-        Json(payload): Json<Verify2FAPayload>,
+        Verified2FAChallenge(email): Verified2FAChallenge,
 ) -> (CookieJar, HandlerResult<impl IntoResponse>) {
-        println!("->> {:<12} — handle_verify_2fa – {}", "HANDLER", payload.email);
+        /// Returns 500 – failed to end the session of an existing cookie
+        if let Err(e) = supersede_existing_session(&state, &jar).await {
+                return (jar, Err(e));
+        }
 
-        /// Returns 400 – invalid input
-        let (email, login_attempt_id, code) = match verify_payload(payload) {
-                Ok(valid_payload) => valid_payload,
-                Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+        /// Returns 409 – session limit reached under the reject policy
+        /// Returns 500 – Internal error creating auth token
+        let (cookie, expires_at) = match start_session(&state, &email, client.clone()).await {
+                Ok(cookie_and_expiry) => cookie_and_expiry,
+                Err(e) => return (jar, Err(e)),
         };
 
-        /// Returns 401 – Email not found
-        let (store_login_attempt_id, store_code) =
-                match state.two_fa_code_store.read().await.get_code(&email).await {
-                        Ok(login_attempt_and_id) => login_attempt_and_id,
-                        Err(_) => return (jar, Err(TwoFACodeStoreError::CodeNotFound.into())),
-                };
+        let jar = jar.add(cookie);
 
-        /// Returns 401 – Incorrect login attempt id or 2FA code
-        if login_attempt_id.as_ref() != store_login_attempt_id.as_ref()
-                || code.as_ref() != store_code.as_ref()
-        {
-                return (jar, Err(AuthAPIError::Unauthorized));
+        record_login(&state, &email, &client).await;
+
+        (
+                jar,
+                Ok((
+                        StatusCode::OK,
+                        Json(RegularAuthResponse {
+                                expires_at,
+                                email: email.as_str().to_owned(),
+                        }),
+                )),
+        )
+}
+
+/// The account of a `/verify-2fa` payload whose challenge checks out: the challenge token
+/// was issued for the email, a code is pending for its login attempt, the code matches and
+/// the attempt is within `LOGIN_ATTEMPT_TTL_SECONDS`. The code is consumed, so a challenge
+/// verifies once.
+///
+/// Rejects like `ValidatedJson` for a malformed body, otherwise with:
+/// - 400 – invalid email or code
+/// - 401 – challenge token not valid for the email, or wrong code
+/// - 401 `no_active_2fa_challenge` – no code pending for the login attempt
+/// - 401 `2fa_challenge_expired` – the right code, but the login attempt expired
+/// - 403 – too many wrong codes for the account; it is locked
+/// - 429 – too many checks of this code; the user has to log in again
+#[derive(Debug)]
+pub struct Verified2FAChallenge(pub Email);
+
+impl FromRequest<AppState> for Verified2FAChallenge {
+        type Rejection = Response;
+
+        async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+                let ValidatedJson(payload) =
+                        ValidatedJson::<Verify2FAPayload>::from_request(req, state).await?;
+                tracing::debug!(email = %payload.email, "handle_verify_2fa");
+
+                verify_challenge(state, payload)
+                        .await
+                        .map(Self)
+                        .map_err(IntoResponse::into_response)
+        }
+}
+
+async fn verify_challenge(
+        state: &AppState,
+        payload: Verify2FAPayload,
+) -> Result<Email, AuthAPIError> {
+        let (email, login_attempt_id, code) = verify_payload(state, payload)?;
+
+        let check = check_code(state, &email, &login_attempt_id, &code).await;
+        if let Some(result) = verification_result(&check) {
+                state.metrics.record_2fa_verification(result);
+        }
+        match check? {
+                CodeCheck::Valid => {}
+                CodeCheck::NoActiveChallenge => {
+                        return Err(TwoFACodeStoreError::NoActiveChallenge.into())
+                }
+                CodeCheck::Mismatch => return Err(AuthAPIError::Unauthorized),
+                CodeCheck::Expired => {
+                        let _ = state
+                                .two_fa_code_store
+                                .write()
+                                .await
+                                .remove_code(&email, &login_attempt_id)
+                                .await;
+                        return Err(AuthAPIError::TwoFAChallengeExpired);
+                }
+        }
+
+        // A concurrent request that consumed the code first wins; this one has no challenge
+        // left to verify
+        match state.two_fa_code_store.write().await.remove_code(&email, &login_attempt_id).await {
+                Ok(()) => Ok(email),
+                Err(TwoFACodeStoreError::CodeNotFound) => Err(AuthAPIError::NoActive2FAChallenge),
+                Err(_) => Err(AuthAPIError::UnexpectedError),
+        }
+}
+
+// Reports whether a 2FA code would be accepted by `/verify-2fa`, without consuming it or
+// setting a cookie. The check still counts toward MAX_2FA_ATTEMPTS, so it can't be used to
+// guess codes any faster than `/verify-2fa` itself.
+pub async fn handle_verify_2fa_check(
+        State(state): State<AppState>,
+        ValidatedJson(payload): ValidatedJson<Verify2FAPayload>,
+) -> HandlerResult<impl IntoResponse> {
+        tracing::debug!(email = %payload.email, "handle_verify_2fa_check");
+
+        /// Returns 400 – invalid input
+        /// Returns 401 – challenge token not valid for the email
+        let (email, login_attempt_id, code) = verify_payload(&state, payload)?;
+
+        /// Returns 403 – too many wrong codes for the account; it is locked
+        /// Returns 429 – too many checks of this code; the user has to log in again
+        let valid = check_code(&state, &email, &login_attempt_id, &code).await? == CodeCheck::Valid;
+
+        Ok((
+                StatusCode::OK,
+                Json(Verify2FACheckResponse {
+                        valid,
+                }),
+        ))
+}
+
+/// Outcome of checking a submitted code against the pending one
+#[derive(Debug, PartialEq)]
+enum CodeCheck {
+        Valid,
+        /// No code is pending for the login attempt
+        NoActiveChallenge,
+        /// Wrong code
+        Mismatch,
+        /// The code matches but the login attempt outlived LOGIN_ATTEMPT_TTL_SECONDS
+        Expired,
+}
+
+/// How a code check counts toward `auth_2fa_verifications_total`; `None` for a store failure,
+/// which says nothing about the challenge
+fn verification_result(check: &Result<CodeCheck, AuthAPIError>) -> Option<TwoFAVerificationResult> {
+        match check {
+                Ok(CodeCheck::Valid) => Some(TwoFAVerificationResult::Success),
+                Ok(CodeCheck::NoActiveChallenge) => {
+                        Some(TwoFAVerificationResult::NoActiveChallenge)
+                }
+                Ok(CodeCheck::Mismatch) => Some(TwoFAVerificationResult::Mismatch),
+                Ok(CodeCheck::Expired) => Some(TwoFAVerificationResult::Expired),
+                Err(AuthAPIError::UnexpectedError) => None,
+                Err(_) => Some(TwoFAVerificationResult::Rejected),
         }
+}
 
-        /// If credentials match, remove 2FA code from store & set JWT auth-token cookie
+async fn check_code(
+        state: &AppState,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+) -> Result<CodeCheck, AuthAPIError> {
+        let store_code = match state
+                .two_fa_code_store
+                .read()
+                .await
+                .get_code(email, login_attempt_id)
+                .await
         {
-                state.two_fa_code_store
+                Ok(code) => code,
+                Err(TwoFACodeStoreError::NoActiveChallenge) => {
+                        return Ok(CodeCheck::NoActiveChallenge)
+                }
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        };
+
+        // A locked account's challenges are void, whichever device they were issued to
+        if let Some(until) = locked_by_2fa_failures_until(state, email).await? {
+                let _ = state
+                        .two_fa_code_store
                         .write()
                         .await
-                        .remove_code(&email)
-                        .await
-                        .expect("Infalliable");
+                        .remove_code(email, login_attempt_id)
+                        .await;
+                return Err(AuthAPIError::account_locked_until(until));
         }
 
-        /// Returns 500 – Internal error creating auth token
-        let cookie = match generate_auth_cookie(&email) {
-                Ok(cookie) => cookie,
-                Err(_) => return (jar, Err(GenerateTokenError::UnexpectedError.into())),
+        // Every check counts, right or wrong, so the limit caps guesses across both routes
+        if let Some(limit) = state.config.max_2fa_attempts {
+                let mut two_fa_store = state.two_fa_code_store.write().await;
+                let attempts = match two_fa_store.record_attempt(email, login_attempt_id).await {
+                        Ok(attempts) => attempts,
+                        Err(TwoFACodeStoreError::CodeNotFound) => {
+                                return Ok(CodeCheck::NoActiveChallenge)
+                        }
+                        Err(_) => return Err(AuthAPIError::UnexpectedError),
+                };
+                if attempts > limit {
+                        tracing::warn!(limit, "2FA attempt limit reached; discarding code");
+                        let _ = two_fa_store.remove_code(email, login_attempt_id).await;
+                        return Err(AuthAPIError::TwoFAAttemptLimitReached);
+                }
+        }
+
+        if code.as_ref() != store_code.as_ref() {
+                return record_failure(state, email, login_attempt_id).await;
+        }
+
+        let issued_at = match state
+                .two_fa_code_store
+                .read()
+                .await
+                .get_issued_at(email, login_attempt_id)
+                .await
+        {
+                Ok(issued_at) => issued_at,
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
         };
+        let age = Utc::now().timestamp().saturating_sub(issued_at);
+        if age > state.config.login_attempt_ttl_seconds as i64 {
+                return Ok(CodeCheck::Expired);
+        }
 
-        let jar = jar.add(cookie);
+        Ok(CodeCheck::Valid)
+}
 
-        (jar, Ok(StatusCode::OK))
+/// Count a wrong code toward MAX_2FA_FAILURES, locking the account and discarding the code
+/// once it is reached
+async fn record_failure(
+        state: &AppState,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+) -> Result<CodeCheck, AuthAPIError> {
+        let Some(limit) = state.config.max_2fa_failures else {
+                return Ok(CodeCheck::Mismatch);
+        };
+
+        let mut two_fa_store = state.two_fa_code_store.write().await;
+        let failures = two_fa_store
+                .record_failure(email)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        if failures >= limit {
+                tracing::warn!(account = %email, limit, "2FA failure limit reached; locking account");
+                let _ = two_fa_store.remove_code(email, login_attempt_id).await;
+                let reset_at = two_fa_store
+                        .get_failures_reset_at(email)
+                        .await
+                        .map_err(|_| AuthAPIError::UnexpectedError)?
+                        .and_then(|reset_at| DateTime::from_timestamp(reset_at, 0))
+                        .unwrap_or_else(|| {
+                                Utc::now() + Duration::seconds(TWO_FA_FAILURE_WINDOW_SECONDS)
+                        });
+                return Err(AuthAPIError::account_locked_until(reset_at));
+        }
+
+        Ok(CodeCheck::Mismatch)
+}
+
+/// When the account's lock ends, if it has had MAX_2FA_FAILURES wrong 2FA codes within the
+/// current window, which locks it until the window ends
+pub(crate) async fn locked_by_2fa_failures_until(
+        state: &AppState,
+        email: &Email,
+) -> Result<Option<DateTime<Utc>>, AuthAPIError> {
+        let Some(limit) = state.config.max_2fa_failures else {
+                return Ok(None);
+        };
+
+        let two_fa_store = state.two_fa_code_store.read().await;
+        let failures = two_fa_store
+                .get_failures(email)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        if failures < limit {
+                return Ok(None);
+        }
+        let reset_at = two_fa_store
+                .get_failures_reset_at(email)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        Ok(reset_at.and_then(|reset_at| DateTime::from_timestamp(reset_at, 0)))
 }
 
-// Returns 400 if any invalid input
+// Returns 400 if any invalid input, and 401 if the challenge token is not one we issued for
+// the email
 fn verify_payload(
+        state: &AppState,
         payload: Verify2FAPayload,
 ) -> Result<(Email, LoginAttemptId, TwoFACode), AuthAPIError> {
         /// Returns 400 – invalid email
@@ -74,29 +316,234 @@ fn verify_payload(
                 Err(e) => return Err(AuthAPIError::InvalidCredentials),
         };
 
-        let req_login_attempt_id = match LoginAttemptId::parse(payload.login_attempt_id.clone()) {
-                Ok(id) => id,
+        let req_code = match TwoFACode::parse(payload.code.clone()) {
+                Ok(code) => code,
                 Err(e) => {
-                        eprintln!("{}", e);
+                        tracing::debug!(error = %e, "Invalid 2FA code");
                         return Err(AuthAPIError::InvalidCredentials);
                 }
         };
 
-        let req_code = match TwoFACode::parse(payload.code.clone()) {
-                Ok(code) => code,
+        /// Returns 401 – tampered, expired, or foreign challenge token
+        let claims =
+                match validate_challenge_token(&payload.challenge_token, &state.key_ring.load()) {
+                        Ok(claims) => claims,
+                        Err(e) => {
+                                tracing::debug!(error = %e, "Invalid challenge token");
+                                return Err(AuthAPIError::Unauthorized);
+                        }
+                };
+        if claims.sub != req_email.as_ref() {
+                tracing::debug!("Challenge token issued for another account");
+                return Err(AuthAPIError::Unauthorized);
+        }
+        let req_login_attempt_id = match LoginAttemptId::parse(claims.login_attempt_id) {
+                Ok(id) => id,
                 Err(e) => {
-                        eprintln!("{}", e);
-                        return Err(AuthAPIError::InvalidCredentials);
+                        tracing::debug!(error = %e, "Invalid login attempt ID");
+                        return Err(AuthAPIError::Unauthorized);
                 }
         };
 
         Ok((req_email, req_login_attempt_id, req_code))
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct Verify2FAPayload {
         email: String,
-        #[serde(rename = "loginAttemptId")]
-        login_attempt_id: String,
+        /// The token from the 206 login response
+        #[serde(rename = "challengeToken")]
+        challenge_token: String,
         code: String,
 }
+
+impl Validate for Verify2FAPayload {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+                let mut errors = ValidationErrors::new();
+                errors.require("email", &self.email);
+                errors.require("challengeToken", &self.challenge_token);
+                errors.require("code", &self.code);
+                errors.into_result()
+        }
+}
+
+impl std::fmt::Debug for Verify2FAPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the one-time code or the challenge token it pairs with
+                f.debug_struct("Verify2FAPayload")
+                        .field("email", &self.email)
+                        .field("challenge_token", &"[REDACTED]")
+                        .field("code", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Verify2FACheckResponse {
+        pub valid: bool,
+}
+
+#[cfg(test)]
+mod tests {
+        use std::sync::Arc;
+
+        use axum::{body::Body, http::header::CONTENT_TYPE};
+        use tokio::sync::RwLock;
+
+        use super::*;
+        use crate::{
+                domain::ErrorResponse,
+                services::data_stores::{
+                        HashmapAuditLogStore, HashmapFailedLoginStore, HashmapIdempotencyStore,
+                        HashmapMagicLinkStore, HashmapPendingEmailChangeStore,
+                        HashmapRateLimitStore, HashmapSessionStore, HashmapTwoFACodeStore,
+                        HashmapUserStore, HashsetBannedTokenStore, MockEmailClient,
+                },
+                utils::{auth::generate_challenge_token, config::AppConfig},
+                AppStateBuilder,
+        };
+
+        const CODE: &str = "123456";
+
+        fn test_state(config: AppConfig) -> AppState {
+                AppStateBuilder::new()
+                        .config(config)
+                        .user_store(Arc::new(RwLock::new(Box::new(HashmapUserStore::new()))))
+                        .banned_token_store(Arc::new(RwLock::new(Box::new(
+                                HashsetBannedTokenStore::new(),
+                        ))))
+                        .two_fa_code_store(Arc::new(RwLock::new(Box::new(
+                                HashmapTwoFACodeStore::new(),
+                        ))))
+                        .idempotency_store(Arc::new(RwLock::new(Box::new(
+                                HashmapIdempotencyStore::new(),
+                        ))))
+                        .session_store(Arc::new(RwLock::new(Box::new(HashmapSessionStore::new()))))
+                        .failed_login_store(Arc::new(RwLock::new(Box::new(
+                                HashmapFailedLoginStore::new(),
+                        ))))
+                        .audit_log_store(Arc::new(RwLock::new(Box::new(
+                                HashmapAuditLogStore::new(),
+                        ))))
+                        .pending_email_change_store(Arc::new(RwLock::new(Box::new(
+                                HashmapPendingEmailChangeStore::new(),
+                        ))))
+                        .magic_link_store(Arc::new(RwLock::new(Box::new(
+                                HashmapMagicLinkStore::new(),
+                        ))))
+                        .rate_limit_store(Arc::new(RwLock::new(Box::new(
+                                HashmapRateLimitStore::new(),
+                        ))))
+                        .email_client(Arc::new(MockEmailClient))
+                        .build()
+        }
+
+        /// Issue a challenge for `email` the way a 206 login does, returning its token. The code
+        /// is only stored when `pending` is set.
+        async fn start_challenge(state: &AppState, email: &Email, pending: bool) -> String {
+                let login_attempt_id = LoginAttemptId::default();
+                if pending {
+                        state.two_fa_code_store
+                                .write()
+                                .await
+                                .add_code(
+                                        email.clone(),
+                                        login_attempt_id.clone(),
+                                        TwoFACode::parse(CODE.to_owned()).unwrap(),
+                                )
+                                .await
+                                .unwrap();
+                }
+                generate_challenge_token(email, &login_attempt_id, 600, &state.key_ring.load())
+                        .unwrap()
+        }
+
+        /// Run the extractor on a `/verify-2fa` body
+        async fn extract(
+                state: &AppState,
+                email: &Email,
+                challenge_token: &str,
+                code: &str,
+        ) -> Result<Email, Response> {
+                let body = serde_json::json!({
+                        "email": email.as_str(),
+                        "challengeToken": challenge_token,
+                        "code": code,
+                });
+                let req = Request::builder()
+                        .method("POST")
+                        .uri("/verify-2fa")
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap();
+
+                Verified2FAChallenge::from_request(req, state)
+                        .await
+                        .map(|Verified2FAChallenge(email)| email)
+        }
+
+        async fn error_code(rejection: Response) -> (StatusCode, Option<String>) {
+                let status = rejection.status();
+                let body = axum::body::to_bytes(rejection.into_body(), usize::MAX).await.unwrap();
+                let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+                (status, body.code)
+        }
+
+        #[tokio::test]
+        async fn test_accepts_the_pending_code_once() {
+                let state = test_state(AppConfig::default());
+                let email = Email::parse("user@example.com").unwrap();
+                let token = start_challenge(&state, &email, true).await;
+
+                assert_eq!(extract(&state, &email, &token, CODE).await.unwrap(), email);
+
+                let rejection = extract(&state, &email, &token, CODE).await.unwrap_err();
+                assert_eq!(
+                        error_code(rejection).await,
+                        (StatusCode::UNAUTHORIZED, Some("no_active_2fa_challenge".to_owned()))
+                );
+        }
+
+        #[tokio::test]
+        async fn test_rejects_an_email_with_no_pending_challenge() {
+                let state = test_state(AppConfig::default());
+                let email = Email::parse("unknown@example.com").unwrap();
+                let token = start_challenge(&state, &email, false).await;
+
+                let rejection = extract(&state, &email, &token, CODE).await.unwrap_err();
+                assert_eq!(
+                        error_code(rejection).await,
+                        (StatusCode::UNAUTHORIZED, Some("no_active_2fa_challenge".to_owned()))
+                );
+        }
+
+        #[tokio::test]
+        async fn test_rejects_a_wrong_code() {
+                let state = test_state(AppConfig::default());
+                let email = Email::parse("user@example.com").unwrap();
+                let token = start_challenge(&state, &email, true).await;
+
+                let rejection = extract(&state, &email, &token, "654321").await.unwrap_err();
+                assert_eq!(error_code(rejection).await, (StatusCode::UNAUTHORIZED, None));
+
+                // The code is still pending for another try
+                assert!(extract(&state, &email, &token, CODE).await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_rejects_an_expired_challenge() {
+                let state = test_state(AppConfig {
+                        login_attempt_ttl_seconds: 0,
+                        ..AppConfig::default()
+                });
+                let email = Email::parse("user@example.com").unwrap();
+                let token = start_challenge(&state, &email, true).await;
+                tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+                let rejection = extract(&state, &email, &token, CODE).await.unwrap_err();
+                assert_eq!(
+                        error_code(rejection).await,
+                        (StatusCode::UNAUTHORIZED, Some("2fa_challenge_expired".to_owned()))
+                );
+        }
+}
@@ -1,36 +1,83 @@
 // src/routes/verify_token.rs
 use axum::{
         extract::{Json, State},
-        http::StatusCode,
+        http::{HeaderMap, StatusCode},
         response::IntoResponse,
 };
 
-use crate::{domain::AuthAPIError, utils::auth::validate_token, AppState, HandlerResult};
+use crate::{
+        domain::{AuthAPIError, Email, Role, UserStoreError},
+        routes::{bearer_token, cookie_token},
+        utils::auth::{validate_token, Token},
+        AppState, HandlerResult,
+};
 
-// If the JSON object is missing or malformed, a 422 HTTP status code will be sent back (handled by Axum's JSON extractor)
+/// POST – /verify-token
+///
+/// The token is taken from an `Authorization: Bearer` header if present, then from the JSON
+/// body, then from the auth cookie.
+// A malformed JSON body is rejected with 422 by Axum's JSON extractor
 pub async fn handle_verify_token(
         State(state): State<AppState>,
-        Json(payload): Json<VerifyTokenPayload>,
+        headers: HeaderMap,
+        payload: Option<Json<VerifyTokenPayload>>,
 ) -> HandlerResult<impl IntoResponse> {
-        println!("->> {:<12} — handle_verify_token – REDACTED", "HANDLER");
+        tracing::debug!("handle_verify_token");
 
-        if payload.token.is_empty() {
+        let token = bearer_token(&headers)
+                .or(payload.map(|Json(payload)| payload.token))
+                .or_else(|| cookie_token(&headers))
+                .unwrap_or_default();
+        if token.is_empty() {
                 return Err(TokenError::MalformedInput.into());
         }
+        let token = Token::parse(token).map_err(|_| TokenError::InvalidToken)?;
 
         // Validate the token
-        validate_token(&state.banned_token_store, &payload.token)
+        let claims = validate_token(&state.banned_token_store, &state.key_ring.load_full(), &token)
                 .await
                 .map_err(|_| TokenError::InvalidToken)?;
+        let email = Email::parse(&claims.sub).map_err(|_| TokenError::InvalidToken)?;
+
+        // The role comes from the user store, like `RequireAdmin`, so a role change is reflected
+        // immediately. A token for an account that no longer exists is treated as invalid.
+        let role = match state.user_store.read().await.get_user(&email).await {
+                Ok(user) => user.role(),
+                Err(UserStoreError::UserNotFound) => return Err(TokenError::InvalidToken.into()),
+                Err(_) => return Err(AuthAPIError::UnexpectedError),
+        };
 
-        Ok(StatusCode::OK.into_response())
+        Ok((
+                StatusCode::OK,
+                Json(VerifyTokenResponse {
+                        email: claims.sub,
+                        expires_at: claims.exp,
+                        role,
+                }),
+        ))
 }
 
+/// Claims of a valid token, for internal callers that need more than a 200/401
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct VerifyTokenResponse {
+        pub email: String,
+        /// `exp` claim of the token (Unix timestamp in seconds)
+        pub expires_at: usize,
+        pub role: Role,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct VerifyTokenPayload {
         token: String,
 }
 
+impl std::fmt::Debug for VerifyTokenPayload {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the raw token
+                f.debug_struct("VerifyTokenPayload").field("token", &"[REDACTED]").finish()
+        }
+}
+
 impl VerifyTokenPayload {
         pub fn new(token: String) -> Self {
                 Self {
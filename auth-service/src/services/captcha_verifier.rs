@@ -0,0 +1,118 @@
+// src/services/captcha_verifier.rs
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+
+use crate::{
+        domain::CaptchaVerifier,
+        utils::constants::{
+                env::{
+                        CAPTCHA_ENABLED_ENV_VAR, CAPTCHA_SECRET_ENV_VAR, CAPTCHA_VERIFY_URL_ENV_VAR,
+                },
+                CAPTCHA_TIMEOUT_SECONDS, DEFAULT_CAPTCHA_VERIFY_URL,
+        },
+};
+
+/// Verifies tokens against a reCAPTCHA or hCaptcha `siteverify` endpoint.
+///
+/// Both providers take the same form body (`secret`, `response`) and answer with a JSON
+/// `success` flag, so only the URL differs between them.
+pub struct ReqwestCaptchaVerifier {
+        client: Client,
+        verify_url: Url,
+        secret: String,
+}
+
+/// The part of the provider's `siteverify` response we rely on
+#[derive(Debug, Deserialize)]
+struct SiteVerifyResponse {
+        success: bool,
+        #[serde(rename = "error-codes", default)]
+        error_codes: Vec<String>,
+}
+
+impl ReqwestCaptchaVerifier {
+        /// Fails when the HTTP client cannot be built, e.g. without a usable TLS backend
+        pub fn new(verify_url: Url, secret: impl Into<String>) -> Result<Self, reqwest::Error> {
+                let client = Client::builder()
+                        .timeout(Duration::from_secs(CAPTCHA_TIMEOUT_SECONDS))
+                        .build()?;
+
+                Ok(Self {
+                        client,
+                        verify_url,
+                        secret: secret.into(),
+                })
+        }
+
+        /// Build a verifier when `CAPTCHA_ENABLED` is true, or `None` to leave signup
+        /// unprotected. Panics when enabled without `CAPTCHA_SECRET` or with an invalid
+        /// `CAPTCHA_VERIFY_URL`, so a half-configured deployment fails at startup, and returns
+        /// the error of an HTTP client that cannot be built.
+        pub fn from_env() -> Result<Option<Self>, reqwest::Error> {
+                let enabled = std::env::var(CAPTCHA_ENABLED_ENV_VAR)
+                        .map(|v| v.trim().parse::<bool>())
+                        .unwrap_or(Ok(false))
+                        .unwrap_or_else(|_| {
+                                panic!("{} has an invalid value", CAPTCHA_ENABLED_ENV_VAR)
+                        });
+                if !enabled {
+                        return Ok(None);
+                }
+
+                let secret = std::env::var(CAPTCHA_SECRET_ENV_VAR)
+                        .ok()
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or_else(|| {
+                                panic!(
+                                        "{} must be set when {} is true",
+                                        CAPTCHA_SECRET_ENV_VAR, CAPTCHA_ENABLED_ENV_VAR
+                                )
+                        });
+
+                let verify_url = std::env::var(CAPTCHA_VERIFY_URL_ENV_VAR)
+                        .ok()
+                        .filter(|v| !v.trim().is_empty())
+                        .unwrap_or_else(|| DEFAULT_CAPTCHA_VERIFY_URL.to_owned());
+                let verify_url = Url::parse(verify_url.trim()).unwrap_or_else(|_| {
+                        panic!("{} has an invalid value", CAPTCHA_VERIFY_URL_ENV_VAR)
+                });
+
+                Self::new(verify_url, secret).map(Some)
+        }
+}
+
+#[async_trait]
+impl CaptchaVerifier for ReqwestCaptchaVerifier {
+        async fn verify(&self, token: &str) -> Result<(), String> {
+                let response = self
+                        .client
+                        .post(self.verify_url.clone())
+                        .form(&[("secret", self.secret.as_str()), ("response", token)])
+                        .send()
+                        .await
+                        .and_then(|res| res.error_for_status())
+                        .map_err(|e| format!("CAPTCHA provider request failed: {e}"))?
+                        .json::<SiteVerifyResponse>()
+                        .await
+                        .map_err(|e| format!("Invalid CAPTCHA provider response: {e}"))?;
+
+                if response.success {
+                        Ok(())
+                } else {
+                        Err(format!("CAPTCHA rejected: {:?}", response.error_codes))
+                }
+        }
+}
+
+impl std::fmt::Debug for ReqwestCaptchaVerifier {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the provider secret
+                f.debug_struct("ReqwestCaptchaVerifier")
+                        .field("verify_url", &self.verify_url.as_str())
+                        .field("secret", &"[REDACTED]")
+                        .finish()
+        }
+}
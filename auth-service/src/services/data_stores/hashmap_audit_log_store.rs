@@ -0,0 +1,79 @@
+use std::collections::{HashMap, VecDeque};
+
+use async_trait::async_trait;
+
+use crate::{
+        domain::{AuditEvent, AuditLogStore, AuditLogStoreError, Email},
+        utils::constants::AUDIT_LOG_MAX_EVENTS,
+};
+
+#[derive(Default, Debug)]
+pub struct HashmapAuditLogStore {
+        /// Newest first, like the Redis list
+        events: HashMap<Email, VecDeque<AuditEvent>>,
+}
+
+impl HashmapAuditLogStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+#[async_trait]
+impl AuditLogStore for HashmapAuditLogStore {
+        async fn record_event(
+                &mut self,
+                email: &Email,
+                event: AuditEvent,
+        ) -> Result<(), AuditLogStoreError> {
+                let events = self.events.entry(email.clone()).or_default();
+                events.push_front(event);
+                events.truncate(AUDIT_LOG_MAX_EVENTS);
+                Ok(())
+        }
+
+        async fn get_events(
+                &self,
+                email: &Email,
+                offset: usize,
+                limit: usize,
+        ) -> Result<Vec<AuditEvent>, AuditLogStoreError> {
+                Ok(self.events
+                        .get(email)
+                        .map(|events| events.iter().skip(offset).take(limit).cloned().collect())
+                        .unwrap_or_default())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::domain::AuditEventKind;
+
+        fn event(occurred_at: i64) -> AuditEvent {
+                AuditEvent {
+                        kind: AuditEventKind::LoginSuccess,
+                        occurred_at,
+                        ip: None,
+                        user_agent: None,
+                }
+        }
+
+        #[tokio::test]
+        async fn test_events_are_paged_newest_first_and_capped() {
+                let mut store = HashmapAuditLogStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+
+                for occurred_at in 0..AUDIT_LOG_MAX_EVENTS as i64 + 5 {
+                        store.record_event(&email, event(occurred_at)).await.unwrap();
+                }
+
+                let newest = AUDIT_LOG_MAX_EVENTS as i64 + 4;
+                let page = store.get_events(&email, 1, 2).await.unwrap();
+                assert_eq!(page, vec![event(newest - 1), event(newest - 2)]);
+
+                let all = store.get_events(&email, 0, usize::MAX).await.unwrap();
+                assert_eq!(all.len(), AUDIT_LOG_MAX_EVENTS);
+                assert_eq!(all.last(), Some(&event(5)));
+        }
+}
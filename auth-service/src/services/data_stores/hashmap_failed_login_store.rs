@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+        domain::{Email, FailedLoginStore, FailedLoginStoreError},
+        services::data_stores::sliding_window::SlidingWindows,
+        utils::constants::{FAILED_LOGIN_ALERT_INTERVAL_SECONDS, FAILED_LOGIN_WINDOW_SECONDS},
+};
+
+#[derive(Debug)]
+pub struct HashmapFailedLoginStore {
+        /// Failure times per account over the last `FAILED_LOGIN_WINDOW_SECONDS`
+        failures: SlidingWindows<Email>,
+        /// The same per client IP
+        ip_failures: SlidingWindows<String>,
+        /// Time (Unix seconds) of the last alert per account
+        last_alerts: HashMap<Email, i64>,
+}
+
+impl HashmapFailedLoginStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+impl Default for HashmapFailedLoginStore {
+        fn default() -> Self {
+                Self {
+                        failures: SlidingWindows::new(FAILED_LOGIN_WINDOW_SECONDS),
+                        ip_failures: SlidingWindows::new(FAILED_LOGIN_WINDOW_SECONDS),
+                        last_alerts: HashMap::new(),
+                }
+        }
+}
+
+#[async_trait]
+impl FailedLoginStore for HashmapFailedLoginStore {
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, FailedLoginStoreError> {
+                Ok(self.failures.record(email.clone()))
+        }
+
+        async fn get_failures(&self, email: &Email) -> Result<u32, FailedLoginStoreError> {
+                Ok(self.failures.count(email))
+        }
+
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, FailedLoginStoreError> {
+                Ok(self.failures.oldest_expires_at(email))
+        }
+
+        async fn clear_failures(&mut self, email: &Email) -> Result<(), FailedLoginStoreError> {
+                self.failures.clear(email);
+                Ok(())
+        }
+
+        async fn record_ip_failure(&mut self, ip: &str) -> Result<u32, FailedLoginStoreError> {
+                Ok(self.ip_failures.record(ip.to_owned()))
+        }
+
+        async fn get_ip_failures(&self, ip: &str) -> Result<u32, FailedLoginStoreError> {
+                Ok(self.ip_failures.count(ip))
+        }
+
+        async fn get_ip_failures_reset_at(
+                &self,
+                ip: &str,
+        ) -> Result<Option<i64>, FailedLoginStoreError> {
+                Ok(self.ip_failures.oldest_expires_at(ip))
+        }
+
+        async fn claim_alert(&mut self, email: &Email) -> Result<bool, FailedLoginStoreError> {
+                let now = Utc::now().timestamp();
+                match self.last_alerts.get(email) {
+                        Some(last) if now - last < FAILED_LOGIN_ALERT_INTERVAL_SECONDS => Ok(false),
+                        _ => {
+                                self.last_alerts.insert(email.clone(), now);
+                                Ok(true)
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_failures_are_counted_until_cleared() {
+                let mut store = HashmapFailedLoginStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+
+                assert_eq!(store.record_failure(&email).await, Ok(1));
+                assert_eq!(store.record_failure(&email).await, Ok(2));
+
+                store.clear_failures(&email).await.unwrap();
+                assert_eq!(store.record_failure(&email).await, Ok(1));
+        }
+
+        #[tokio::test]
+        async fn test_ip_failures_are_counted_apart_from_accounts() {
+                let mut store = HashmapFailedLoginStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+
+                assert_eq!(store.record_ip_failure("203.0.113.7").await, Ok(1));
+                assert_eq!(store.record_ip_failure("203.0.113.7").await, Ok(2));
+                assert_eq!(store.record_failure(&email).await, Ok(1));
+
+                assert_eq!(store.get_ip_failures("203.0.113.7").await, Ok(2));
+                assert_eq!(store.get_ip_failures("198.51.100.1").await, Ok(0));
+                assert_eq!(store.get_failures(&email).await, Ok(1));
+
+                // Clearing an account's failures leaves the IP's count alone
+                store.clear_failures(&email).await.unwrap();
+                assert_eq!(store.get_failures(&email).await, Ok(0));
+                assert_eq!(store.get_ip_failures("203.0.113.7").await, Ok(2));
+                assert!(store.get_ip_failures_reset_at("203.0.113.7").await.unwrap().is_some());
+                assert_eq!(store.get_failures_reset_at(&email).await, Ok(None));
+        }
+
+        #[tokio::test]
+        async fn test_failures_expire_with_the_window() {
+                let mut store = HashmapFailedLoginStore::new();
+                let stale = Utc::now().timestamp_millis() - FAILED_LOGIN_WINDOW_SECONDS * 1_000;
+                for _ in 0..5 {
+                        store.ip_failures.record_at("203.0.113.7".to_owned(), stale);
+                }
+
+                assert_eq!(store.get_ip_failures("203.0.113.7").await, Ok(0));
+                assert_eq!(store.get_ip_failures_reset_at("203.0.113.7").await, Ok(None));
+                assert_eq!(store.record_ip_failure("203.0.113.7").await, Ok(1));
+        }
+
+        #[tokio::test]
+        async fn test_failures_leave_the_window_one_at_a_time() {
+                let mut store = HashmapFailedLoginStore::new();
+                let window_millis = FAILED_LOGIN_WINDOW_SECONDS * 1_000;
+                let now = Utc::now().timestamp_millis();
+                let older = now - window_millis + 60_000;
+                let newer = now - window_millis / 2;
+                for at in [older, older, newer] {
+                        store.ip_failures.record_at("203.0.113.7".to_owned(), at);
+                }
+
+                // Unlike a fixed window, the older failures expire without taking the newer
+                // one with them
+                assert_eq!(store.get_ip_failures("203.0.113.7").await, Ok(3));
+                assert_eq!(
+                        store.get_ip_failures_reset_at("203.0.113.7").await,
+                        Ok(Some((older + window_millis + 999) / 1_000))
+                );
+                assert_eq!(store.ip_failures.record_at("203.0.113.7".to_owned(), now + 60_000), 2);
+        }
+
+        #[tokio::test]
+        async fn test_only_one_alert_is_claimed_per_interval() {
+                let mut store = HashmapFailedLoginStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let other = Email::parse("other@example.com").unwrap();
+
+                assert_eq!(store.claim_alert(&email).await, Ok(true));
+                assert_eq!(store.claim_alert(&email).await, Ok(false));
+                assert_eq!(store.claim_alert(&other).await, Ok(true));
+        }
+}
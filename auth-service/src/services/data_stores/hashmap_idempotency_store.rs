@@ -0,0 +1,102 @@
+use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use crate::{
+        domain::{Email, IdempotencyStore, IdempotencyStoreError},
+        utils::constants::IDEMPOTENCY_KEY_TTL_SECONDS,
+};
+
+#[derive(Debug)]
+pub struct HashmapIdempotencyStore {
+        responses: HashMap<(String, Email), (String, Instant)>,
+        ttl: Duration,
+}
+
+impl Default for HashmapIdempotencyStore {
+        fn default() -> Self {
+                Self {
+                        responses: HashMap::new(),
+                        ttl: Duration::from_secs(IDEMPOTENCY_KEY_TTL_SECONDS),
+                }
+        }
+}
+
+impl HashmapIdempotencyStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        #[cfg(test)]
+        fn with_ttl(ttl: Duration) -> Self {
+                Self {
+                        ttl,
+                        ..Self::default()
+                }
+        }
+}
+
+#[async_trait]
+impl IdempotencyStore for HashmapIdempotencyStore {
+        async fn get_response(
+                &self,
+                key: &str,
+                email: &Email,
+        ) -> Result<Option<String>, IdempotencyStoreError> {
+                let response = self
+                        .responses
+                        .get(&(key.to_owned(), email.clone()))
+                        .filter(|(_, saved_at)| saved_at.elapsed() < self.ttl)
+                        .map(|(response, _)| response.clone());
+
+                Ok(response)
+        }
+
+        async fn save_response(
+                &mut self,
+                key: &str,
+                email: &Email,
+                response: String,
+        ) -> Result<(), IdempotencyStoreError> {
+                // Drop expired entries so the map does not grow without bound
+                let ttl = self.ttl;
+                self.responses.retain(|_, (_, saved_at)| saved_at.elapsed() < ttl);
+
+                self.responses.insert((key.to_owned(), email.clone()), (response, Instant::now()));
+                Ok(())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_saved_response_is_scoped_to_key_and_email() {
+                let mut store = HashmapIdempotencyStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let other = Email::parse("other@example.com").unwrap();
+
+                store.save_response("key-1", &email, "body".to_owned()).await.unwrap();
+
+                assert_eq!(
+                        store.get_response("key-1", &email).await.unwrap(),
+                        Some("body".to_owned())
+                );
+                assert_eq!(store.get_response("key-2", &email).await.unwrap(), None);
+                assert_eq!(store.get_response("key-1", &other).await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn test_expired_response_is_not_returned() {
+                let mut store = HashmapIdempotencyStore::with_ttl(Duration::ZERO);
+                let email = Email::parse("test@example.com").unwrap();
+
+                store.save_response("key-1", &email, "body".to_owned()).await.unwrap();
+
+                assert_eq!(store.get_response("key-1", &email).await.unwrap(), None);
+        }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::domain::{Email, MagicLinkStore, MagicLinkStoreError};
+
+#[derive(Default, Debug)]
+pub struct HashmapMagicLinkStore {
+        /// Account and the time (Unix seconds) the link expires at, per token
+        tokens: HashMap<String, (Email, i64)>,
+}
+
+impl HashmapMagicLinkStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+#[async_trait]
+impl MagicLinkStore for HashmapMagicLinkStore {
+        async fn add_token(
+                &mut self,
+                token: String,
+                email: Email,
+                ttl_seconds: u64,
+        ) -> Result<(), MagicLinkStoreError> {
+                let expires_at = Utc::now().timestamp() + ttl_seconds as i64;
+                self.tokens.insert(token, (email, expires_at));
+                Ok(())
+        }
+
+        async fn take_token(&mut self, token: &str) -> Result<Email, MagicLinkStoreError> {
+                match self.tokens.remove(token) {
+                        Some((email, expires_at)) if Utc::now().timestamp() < expires_at => {
+                                Ok(email)
+                        }
+                        _ => Err(MagicLinkStoreError::TokenNotFound),
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        fn email() -> Email {
+                Email::parse("user@example.com").unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_token_can_be_taken_once() {
+                let mut store = HashmapMagicLinkStore::new();
+                store.add_token("token".to_owned(), email(), 60).await.unwrap();
+
+                assert_eq!(store.take_token("token").await, Ok(email()));
+                assert_eq!(
+                        store.take_token("token").await,
+                        Err(MagicLinkStoreError::TokenNotFound)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_expired_token_is_not_found() {
+                let mut store = HashmapMagicLinkStore::new();
+                store.add_token("token".to_owned(), email(), 0).await.unwrap();
+
+                assert_eq!(
+                        store.take_token("token").await,
+                        Err(MagicLinkStoreError::TokenNotFound)
+                );
+        }
+}
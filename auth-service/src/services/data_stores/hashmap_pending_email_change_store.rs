@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+        domain::{PendingEmailChange, PendingEmailChangeStore, PendingEmailChangeStoreError},
+        utils::constants::EMAIL_CHANGE_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default, Debug)]
+pub struct HashmapPendingEmailChangeStore {
+        /// Pending change and the time (Unix seconds) it expires at, per token
+        changes: HashMap<String, (PendingEmailChange, i64)>,
+}
+
+impl HashmapPendingEmailChangeStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+#[async_trait]
+impl PendingEmailChangeStore for HashmapPendingEmailChangeStore {
+        async fn add_change(
+                &mut self,
+                token: String,
+                change: PendingEmailChange,
+        ) -> Result<(), PendingEmailChangeStoreError> {
+                let expires_at = Utc::now().timestamp() + EMAIL_CHANGE_TOKEN_TTL_SECONDS as i64;
+                self.changes.insert(token, (change, expires_at));
+                Ok(())
+        }
+
+        async fn take_change(
+                &mut self,
+                token: &str,
+        ) -> Result<PendingEmailChange, PendingEmailChangeStoreError> {
+                match self.changes.remove(token) {
+                        Some((change, expires_at)) if Utc::now().timestamp() < expires_at => {
+                                Ok(change)
+                        }
+                        _ => Err(PendingEmailChangeStoreError::TokenNotFound),
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::domain::Email;
+
+        fn create_test_change() -> PendingEmailChange {
+                PendingEmailChange {
+                        old_email: Email::parse("old@example.com").unwrap(),
+                        new_email: Email::parse("new@example.com").unwrap(),
+                }
+        }
+
+        #[tokio::test]
+        async fn test_change_can_be_taken_once() {
+                let mut store = HashmapPendingEmailChangeStore::new();
+                store.add_change("token".to_owned(), create_test_change()).await.unwrap();
+
+                assert_eq!(store.take_change("token").await, Ok(create_test_change()));
+                assert_eq!(
+                        store.take_change("token").await,
+                        Err(PendingEmailChangeStoreError::TokenNotFound)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_expired_change_is_not_found() {
+                let mut store = HashmapPendingEmailChangeStore::new();
+                store.add_change("token".to_owned(), create_test_change()).await.unwrap();
+
+                // Backdate the expiry
+                store.changes.get_mut("token").unwrap().1 = Utc::now().timestamp();
+
+                assert_eq!(
+                        store.take_change("token").await,
+                        Err(PendingEmailChangeStoreError::TokenNotFound)
+                );
+        }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::{
+        domain::{RateLimitScope, RateLimitStore, RateLimitStoreError},
+        services::data_stores::sliding_window::SlidingWindows,
+};
+
+#[derive(Default, Debug)]
+pub struct HashmapRateLimitStore {
+        /// Request times per subject, over each scope's own window
+        requests: HashMap<RateLimitScope, SlidingWindows<String>>,
+}
+
+impl HashmapRateLimitStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+#[async_trait]
+impl RateLimitStore for HashmapRateLimitStore {
+        async fn record_request(
+                &mut self,
+                scope: RateLimitScope,
+                subject: &str,
+        ) -> Result<u32, RateLimitStoreError> {
+                Ok(self.requests
+                        .entry(scope)
+                        .or_insert_with(|| SlidingWindows::new(scope.window_seconds()))
+                        .record(subject.to_owned()))
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_requests_are_counted_per_subject() {
+                let mut store = HashmapRateLimitStore::new();
+                let scope = RateLimitScope::CheckEmailIp;
+
+                assert_eq!(store.record_request(scope, "203.0.113.7").await, Ok(1));
+                assert_eq!(store.record_request(scope, "203.0.113.7").await, Ok(2));
+                assert_eq!(store.record_request(scope, "198.51.100.1").await, Ok(1));
+        }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::domain::{ActiveSession, Email, SessionStore, SessionStoreError};
+
+#[derive(Default, Debug)]
+pub struct HashmapSessionStore {
+        sessions: HashMap<Email, Vec<ActiveSession>>,
+}
+
+impl HashmapSessionStore {
+        pub fn new() -> Self {
+                Self::default()
+        }
+}
+
+#[async_trait]
+impl SessionStore for HashmapSessionStore {
+        async fn add_session(
+                &mut self,
+                email: &Email,
+                session: ActiveSession,
+        ) -> Result<(), SessionStoreError> {
+                self.sessions.entry(email.clone()).or_default().push(session);
+                Ok(())
+        }
+
+        async fn get_sessions(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<ActiveSession>, SessionStoreError> {
+                Ok(self.sessions.get(email).cloned().unwrap_or_default())
+        }
+
+        async fn remove_session(
+                &mut self,
+                email: &Email,
+                jti: &str,
+        ) -> Result<(), SessionStoreError> {
+                if let Some(sessions) = self.sessions.get_mut(email) {
+                        sessions.retain(|session| session.jti != jti);
+                        if sessions.is_empty() {
+                                self.sessions.remove(email);
+                        }
+                }
+                Ok(())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::utils::auth::Token;
+
+        fn session(jti: &str) -> ActiveSession {
+                ActiveSession {
+                        jti: jti.to_owned(),
+                        token: Token::parse(format!("header.payload.{jti}")).unwrap(),
+                        expires_at: 0,
+                        created_at: 0,
+                        ip: None,
+                        user_agent: None,
+                }
+        }
+
+        #[tokio::test]
+        async fn test_sessions_are_returned_oldest_first_and_removed_by_jti() {
+                let mut store = HashmapSessionStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+
+                store.add_session(&email, session("a")).await.unwrap();
+                store.add_session(&email, session("b")).await.unwrap();
+                store.add_session(&email, session("c")).await.unwrap();
+                store.remove_session(&email, "b").await.unwrap();
+
+                let sessions = store.get_sessions(&email).await.unwrap();
+                assert_eq!(sessions, vec![session("a"), session("c")]);
+        }
+}
@@ -1,12 +1,26 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::Utc;
 
-use crate::domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError};
+use crate::{
+        domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+        utils::constants::{TWO_FA_EMAIL_WINDOW_SECONDS, TWO_FA_FAILURE_WINDOW_SECONDS},
+};
+
+/// A pending code is keyed by the account and the login attempt it was issued for
+type AttemptKey = (Email, LoginAttemptId);
 
 #[derive(Default, Debug)]
 pub struct HashmapTwoFACodeStore {
-        codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+        /// Code and the Unix timestamp it was added at
+        codes: HashMap<AttemptKey, (TwoFACode, i64)>,
+        /// 2FA emails sent and the time (Unix seconds) the current window started
+        emails_sent: HashMap<Email, (u32, i64)>,
+        /// Checks made against each pending code
+        attempts: HashMap<AttemptKey, u32>,
+        /// Wrong codes and the time (Unix seconds) the current window started
+        failures: HashMap<Email, (u32, i64)>,
 }
 
 impl HashmapTwoFACodeStore {
@@ -15,6 +29,10 @@ impl HashmapTwoFACodeStore {
         }
 }
 
+fn attempt_key(email: &Email, login_attempt_id: &LoginAttemptId) -> AttemptKey {
+        (email.clone(), login_attempt_id.clone())
+}
+
 #[async_trait]
 impl TwoFACodeStore for HashmapTwoFACodeStore {
         async fn add_code(
@@ -23,15 +41,23 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
                 login_attempt_id: LoginAttemptId,
                 code: TwoFACode,
         ) -> Result<(), TwoFACodeStoreError> {
-                if self.codes.contains_key(&email) {
+                let key = (email, login_attempt_id);
+                if self.codes.contains_key(&key) {
                         return Err(TwoFACodeStoreError::CodeAlreadyExists);
                 }
-                self.codes.insert(email, (login_attempt_id, code));
+                self.attempts.remove(&key);
+                self.codes.insert(key, (code, Utc::now().timestamp()));
                 Ok(())
         }
 
-        async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-                if self.codes.remove(email).is_none() {
+        async fn remove_code(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<(), TwoFACodeStoreError> {
+                let key = attempt_key(email, login_attempt_id);
+                self.attempts.remove(&key);
+                if self.codes.remove(&key).is_none() {
                         return Err(TwoFACodeStoreError::CodeNotFound);
                 }
 
@@ -41,12 +67,95 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         async fn get_code(
                 &self,
                 email: &Email,
-        ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-                match self.codes.get(email) {
-                        Some(login_id_and_code) => Ok(login_id_and_code.clone()),
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<TwoFACode, TwoFACodeStoreError> {
+                match self.codes.get(&attempt_key(email, login_attempt_id)) {
+                        Some((code, _)) => Ok(code.clone()),
+                        None => Err(TwoFACodeStoreError::NoActiveChallenge),
+                }
+        }
+
+        async fn get_issued_at(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<i64, TwoFACodeStoreError> {
+                match self.codes.get(&attempt_key(email, login_attempt_id)) {
+                        Some((_, issued_at)) => Ok(*issued_at),
                         None => Err(TwoFACodeStoreError::CodeNotFound),
                 }
         }
+
+        async fn record_email_sent(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                let now = Utc::now().timestamp();
+                let (count, window_start) =
+                        self.emails_sent.entry(email.clone()).or_insert((0, now));
+
+                // Like the Redis TTL: the window runs from the first email
+                if now - *window_start >= TWO_FA_EMAIL_WINDOW_SECONDS {
+                        *count = 0;
+                        *window_start = now;
+                }
+                *count += 1;
+
+                Ok(*count)
+        }
+
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                let now = Utc::now().timestamp();
+                let (count, window_start) = self.failures.entry(email.clone()).or_insert((0, now));
+
+                // Like the Redis TTL: the window runs from the first failure
+                if now - *window_start >= TWO_FA_FAILURE_WINDOW_SECONDS {
+                        *count = 0;
+                        *window_start = now;
+                }
+                *count += 1;
+
+                Ok(*count)
+        }
+
+        async fn get_failures(&self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                let now = Utc::now().timestamp();
+                match self.failures.get(email) {
+                        Some((count, window_start))
+                                if now - window_start < TWO_FA_FAILURE_WINDOW_SECONDS =>
+                        {
+                                Ok(*count)
+                        }
+                        _ => Ok(0),
+                }
+        }
+
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, TwoFACodeStoreError> {
+                let now = Utc::now().timestamp();
+                Ok(self.failures
+                        .get(email)
+                        .map(|(_, window_start)| window_start + TWO_FA_FAILURE_WINDOW_SECONDS)
+                        .filter(|reset_at| *reset_at > now))
+        }
+
+        async fn record_attempt(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<u32, TwoFACodeStoreError> {
+                let key = attempt_key(email, login_attempt_id);
+                if !self.codes.contains_key(&key) {
+                        return Err(TwoFACodeStoreError::CodeNotFound);
+                }
+                let attempts = self.attempts.entry(key).or_insert(0);
+                *attempts += 1;
+
+                Ok(*attempts)
+        }
+
+        async fn count_pending_codes(&self) -> Result<u64, TwoFACodeStoreError> {
+                Ok(self.codes.len() as u64)
+        }
 }
 
 #[cfg(test)]
@@ -82,32 +191,52 @@ mod tests {
                 assert!(result.is_ok());
 
                 // Verify the code was actually stored
-                let stored = store.get_code(&email).await.unwrap();
-                assert_eq!(stored.0, login_id);
-                assert_eq!(stored.1, code);
+                let stored = store.get_code(&email, &login_id).await.unwrap();
+                assert_eq!(stored, code);
         }
 
         #[tokio::test]
-        async fn test_add_code_rejects_duplicate() {
+        async fn test_add_code_rejects_duplicate_login_attempt() {
                 let mut store = HashmapTwoFACodeStore::default();
                 let email = create_test_email();
-                let login_id1 = create_test_login_attempt_id();
+                let login_id = create_test_login_attempt_id();
                 let code1 = create_test_2fa_code();
-                let login_id2 = create_test_login_attempt_id();
                 let code2 = TwoFACode::parse("654321".to_string()).unwrap();
 
                 // Add first code - should succeed
-                store.add_code(email.clone(), login_id1.clone(), code1.clone()).await.unwrap();
+                store.add_code(email.clone(), login_id.clone(), code1.clone()).await.unwrap();
 
-                // Try to add second code - should fail with error
-                let result = store.add_code(email.clone(), login_id2, code2).await;
-                assert!(result.is_err());
-                assert_eq!(result.unwrap_err(), TwoFACodeStoreError::CodeAlreadyExists);
+                // Try to add a second code for the same attempt - should fail with error
+                let result = store.add_code(email.clone(), login_id.clone(), code2).await;
+                assert_eq!(result, Err(TwoFACodeStoreError::CodeAlreadyExists));
 
                 // Verify the first code is still intact (not overwritten)
-                let stored = store.get_code(&email).await.unwrap();
-                assert_eq!(stored.0, login_id1);
-                assert_eq!(stored.1, code1);
+                assert_eq!(store.get_code(&email, &login_id).await, Ok(code1));
+        }
+
+        #[tokio::test]
+        async fn test_concurrent_login_attempts_keep_their_own_codes() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                let login_id1 = create_test_login_attempt_id();
+                let code1 = create_test_2fa_code();
+                let login_id2 = create_test_login_attempt_id();
+                let code2 = TwoFACode::parse("654321".to_string()).unwrap();
+
+                store.add_code(email.clone(), login_id1.clone(), code1.clone()).await.unwrap();
+                store.add_code(email.clone(), login_id2.clone(), code2.clone()).await.unwrap();
+                assert_eq!(store.get_code(&email, &login_id1).await, Ok(code1));
+                assert_eq!(store.get_code(&email, &login_id2).await, Ok(code2.clone()));
+
+                // Checks and removal of one attempt leave the other alone
+                assert_eq!(store.record_attempt(&email, &login_id1).await, Ok(1));
+                assert_eq!(store.record_attempt(&email, &login_id2).await, Ok(1));
+                store.remove_code(&email, &login_id1).await.unwrap();
+                assert_eq!(
+                        store.get_code(&email, &login_id1).await,
+                        Err(TwoFACodeStoreError::NoActiveChallenge)
+                );
+                assert_eq!(store.get_code(&email, &login_id2).await, Ok(code2));
         }
 
         #[tokio::test]
@@ -118,17 +247,14 @@ mod tests {
                 let code1 = create_test_2fa_code();
 
                 // Add and then remove code
-                store.add_code(email.clone(), login_id1, code1).await.unwrap();
-                store.remove_code(&email).await.unwrap();
+                store.add_code(email.clone(), login_id1.clone(), code1).await.unwrap();
+                store.remove_code(&email, &login_id1).await.unwrap();
 
-                // Now adding a new code should succeed
-                let login_id2 = create_test_login_attempt_id();
+                // Now adding a new code for the same attempt should succeed
                 let code2 = TwoFACode::parse("654321".to_string()).unwrap();
-                store.add_code(email.clone(), login_id2.clone(), code2.clone()).await.unwrap();
+                store.add_code(email.clone(), login_id1.clone(), code2.clone()).await.unwrap();
 
-                let stored = store.get_code(&email).await.unwrap();
-                assert_eq!(stored.0, login_id2);
-                assert_eq!(stored.1, code2);
+                assert_eq!(store.get_code(&email, &login_id1).await, Ok(code2));
         }
 
         #[tokio::test]
@@ -140,12 +266,9 @@ mod tests {
 
                 store.add_code(email.clone(), login_id.clone(), code.clone()).await.unwrap();
 
-                let result = store.get_code(&email).await;
+                let result = store.get_code(&email, &login_id).await;
 
-                assert!(result.is_ok());
-                let (retrieved_login_id, retrieved_code) = result.unwrap();
-                assert_eq!(retrieved_login_id, login_id);
-                assert_eq!(retrieved_code, code);
+                assert_eq!(result, Ok(code));
         }
 
         #[tokio::test]
@@ -153,10 +276,118 @@ mod tests {
                 let store = HashmapTwoFACodeStore::default();
                 let email = create_test_email();
 
-                let result = store.get_code(&email).await;
+                let result = store.get_code(&email, &create_test_login_attempt_id()).await;
 
                 assert!(result.is_err());
-                assert!(matches!(result.unwrap_err(), TwoFACodeStoreError::CodeNotFound));
+                assert!(matches!(result.unwrap_err(), TwoFACodeStoreError::NoActiveChallenge));
+        }
+
+        #[tokio::test]
+        async fn test_get_issued_at() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+
+                let login_id = create_test_login_attempt_id();
+
+                let before = Utc::now().timestamp();
+                store.add_code(email.clone(), login_id.clone(), create_test_2fa_code())
+                        .await
+                        .unwrap();
+                let issued_at = store.get_issued_at(&email, &login_id).await.unwrap();
+
+                assert!(issued_at >= before && issued_at <= Utc::now().timestamp());
+                store.remove_code(&email, &login_id).await.unwrap();
+                assert_eq!(
+                        store.get_issued_at(&email, &login_id).await,
+                        Err(TwoFACodeStoreError::CodeNotFound)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_emails_sent_are_counted_per_address() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                let other = Email::parse("other@example.com").unwrap();
+
+                assert_eq!(store.record_email_sent(&email).await, Ok(1));
+                assert_eq!(store.record_email_sent(&email).await, Ok(2));
+                assert_eq!(store.record_email_sent(&other).await, Ok(1));
+        }
+
+        #[tokio::test]
+        async fn test_attempts_reset_when_code_is_replaced() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                let login_id = create_test_login_attempt_id();
+
+                assert_eq!(
+                        store.record_attempt(&email, &login_id).await,
+                        Err(TwoFACodeStoreError::CodeNotFound)
+                );
+
+                store.add_code(email.clone(), login_id.clone(), create_test_2fa_code())
+                        .await
+                        .unwrap();
+                assert_eq!(store.record_attempt(&email, &login_id).await, Ok(1));
+                assert_eq!(store.record_attempt(&email, &login_id).await, Ok(2));
+
+                store.remove_code(&email, &login_id).await.unwrap();
+                store.add_code(email.clone(), login_id.clone(), create_test_2fa_code())
+                        .await
+                        .unwrap();
+                assert_eq!(store.record_attempt(&email, &login_id).await, Ok(1));
+        }
+
+        #[tokio::test]
+        async fn test_failures_are_counted_across_login_attempts_until_the_window_ends() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                let other = Email::parse("other@example.com").unwrap();
+
+                assert_eq!(store.get_failures(&email).await, Ok(0));
+                assert_eq!(store.record_failure(&email).await, Ok(1));
+                assert_eq!(store.record_failure(&email).await, Ok(2));
+                assert_eq!(store.get_failures(&email).await, Ok(2));
+                assert_eq!(store.get_failures(&other).await, Ok(0));
+
+                // Backdate the window start past the end of the window
+                store.failures.get_mut(&email).unwrap().1 -= TWO_FA_FAILURE_WINDOW_SECONDS;
+
+                assert_eq!(store.get_failures(&email).await, Ok(0));
+                assert_eq!(store.record_failure(&email).await, Ok(1));
+        }
+
+        #[tokio::test]
+        async fn test_failures_reset_one_window_after_the_first() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                assert_eq!(store.get_failures_reset_at(&email).await, Ok(None));
+
+                store.record_failure(&email).await.unwrap();
+                let window_start = store.failures[&email].1;
+                let reset_at = Some(window_start + TWO_FA_FAILURE_WINDOW_SECONDS);
+                assert_eq!(store.get_failures_reset_at(&email).await, Ok(reset_at));
+
+                // A later failure does not push the reset back
+                store.record_failure(&email).await.unwrap();
+                assert_eq!(store.get_failures_reset_at(&email).await, Ok(reset_at));
+
+                store.failures.get_mut(&email).unwrap().1 -= TWO_FA_FAILURE_WINDOW_SECONDS;
+                assert_eq!(store.get_failures_reset_at(&email).await, Ok(None));
+        }
+
+        #[tokio::test]
+        async fn test_emails_sent_reset_after_window() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+
+                store.record_email_sent(&email).await.unwrap();
+                store.record_email_sent(&email).await.unwrap();
+
+                // Backdate the window start past the end of the window
+                store.emails_sent.get_mut(&email).unwrap().1 -= TWO_FA_EMAIL_WINDOW_SECONDS;
+
+                assert_eq!(store.record_email_sent(&email).await, Ok(1));
         }
 
         #[tokio::test]
@@ -167,20 +398,20 @@ mod tests {
                 let code = create_test_2fa_code();
 
                 // Add code first
-                store.add_code(email.clone(), login_id, code).await.unwrap();
+                store.add_code(email.clone(), login_id.clone(), code).await.unwrap();
 
                 // Verify it exists
-                assert!(store.get_code(&email).await.is_ok());
+                assert!(store.get_code(&email, &login_id).await.is_ok());
 
                 // Remove it
-                let result = store.remove_code(&email).await;
+                let result = store.remove_code(&email, &login_id).await;
 
                 assert!(result.is_ok());
 
                 // Verify it's gone
-                let get_result = store.get_code(&email).await;
+                let get_result = store.get_code(&email, &login_id).await;
                 assert!(get_result.is_err());
-                assert!(matches!(get_result.unwrap_err(), TwoFACodeStoreError::CodeNotFound));
+                assert!(matches!(get_result.unwrap_err(), TwoFACodeStoreError::NoActiveChallenge));
         }
 
         #[tokio::test]
@@ -188,7 +419,7 @@ mod tests {
                 let mut store = HashmapTwoFACodeStore::default();
                 let email = create_test_email();
 
-                let result = store.remove_code(&email).await;
+                let result = store.remove_code(&email, &create_test_login_attempt_id()).await;
 
                 assert!(result.is_err());
                 assert!(matches!(result.unwrap_err(), TwoFACodeStoreError::CodeNotFound));
@@ -209,19 +440,17 @@ mod tests {
                 store.add_code(email2.clone(), login_id2.clone(), code2.clone()).await.unwrap();
 
                 // Verify both exist and are correct
-                let result1 = store.get_code(&email1).await.unwrap();
-                let result2 = store.get_code(&email2).await.unwrap();
+                assert_eq!(store.get_code(&email1, &login_id1).await, Ok(code1));
+                assert_eq!(store.get_code(&email2, &login_id2).await, Ok(code2));
 
-                assert_eq!(result1.0, login_id1);
-                assert_eq!(result1.1, code1);
-                assert_eq!(result2.0, login_id2);
-                assert_eq!(result2.1, code2);
+                // An attempt ID only matches the email it was issued for
+                assert!(store.get_code(&email1, &login_id2).await.is_err());
 
                 // Remove one and verify the other still exists
-                store.remove_code(&email1).await.unwrap();
+                store.remove_code(&email1, &login_id1).await.unwrap();
 
-                assert!(store.get_code(&email1).await.is_err());
-                assert!(store.get_code(&email2).await.is_ok());
+                assert!(store.get_code(&email1, &login_id1).await.is_err());
+                assert!(store.get_code(&email2, &login_id2).await.is_ok());
         }
 
         #[tokio::test]
@@ -230,9 +459,9 @@ mod tests {
                 let email = create_test_email();
 
                 // Default store should be empty
-                let result = store.get_code(&email).await;
+                let result = store.get_code(&email, &create_test_login_attempt_id()).await;
                 assert!(result.is_err());
-                assert!(matches!(result.unwrap_err(), TwoFACodeStoreError::CodeNotFound));
+                assert!(matches!(result.unwrap_err(), TwoFACodeStoreError::NoActiveChallenge));
         }
 
         #[tokio::test]
@@ -254,61 +483,75 @@ mod tests {
                 let code = create_test_2fa_code();
 
                 // Add to store1
-                store1.add_code(email.clone(), login_id, code).await.unwrap();
+                store1.add_code(email.clone(), login_id.clone(), code).await.unwrap();
 
                 // Verify store1 has it, store2 doesn't
-                assert!(store1.get_code(&email).await.is_ok());
-                assert!(store2.get_code(&email).await.is_err());
+                assert!(store1.get_code(&email, &login_id).await.is_ok());
+                assert!(store2.get_code(&email, &login_id).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_count_pending_codes() {
+                let mut store = HashmapTwoFACodeStore::default();
+                let email = create_test_email();
+                let first = create_test_login_attempt_id();
+                let second = create_test_login_attempt_id();
+                assert_eq!(store.count_pending_codes().await, Ok(0));
+
+                store.add_code(email.clone(), first.clone(), create_test_2fa_code()).await.unwrap();
+                store.add_code(email.clone(), second, create_test_2fa_code()).await.unwrap();
+                assert_eq!(store.count_pending_codes().await, Ok(2));
+
+                store.remove_code(&email, &first).await.unwrap();
+                assert_eq!(store.count_pending_codes().await, Ok(1));
         }
 
         #[tokio::test]
         async fn test_large_number_of_entries() {
                 let mut store = HashmapTwoFACodeStore::default();
                 let num_entries = 1000;
+                let entries: Vec<_> = (0..num_entries)
+                        .map(|i| {
+                                let email = Email::parse(format!("user{}@example.com", i).as_str())
+                                        .unwrap();
+                                (email, create_test_login_attempt_id())
+                        })
+                        .collect();
 
                 // Add many entries
-                for i in 0..num_entries {
-                        let email =
-                                Email::parse(format!("user{}@example.com", i).as_str()).unwrap();
-                        let login_id = create_test_login_attempt_id();
+                for (i, (email, login_id)) in entries.iter().enumerate() {
                         let code = TwoFACode::parse(format!("{:06}", i % 1000000)).unwrap();
 
-                        store.add_code(email, login_id, code).await.unwrap();
+                        store.add_code(email.clone(), login_id.clone(), code).await.unwrap();
                 }
 
                 // Verify a few random entries exist
                 for i in [0, 100, 500, 999] {
-                        let email =
-                                Email::parse(format!("user{}@example.com", i).as_str()).unwrap();
-                        let result = store.get_code(&email).await;
+                        let (email, login_id) = &entries[i];
+                        let result = store.get_code(email, login_id).await;
                         assert!(result.is_ok(), "Entry {} should exist", i);
                 }
 
                 // Remove half the entries
-                for i in 0..num_entries / 2 {
-                        let email =
-                                Email::parse(format!("user{}@example.com", i).as_str()).unwrap();
-                        store.remove_code(&email).await.unwrap();
+                let (removed, remaining) = entries.split_at(num_entries / 2);
+                for (email, login_id) in removed {
+                        store.remove_code(email, login_id).await.unwrap();
                 }
 
                 // Verify removed entries are gone and remaining entries still exist
-                for i in 0..num_entries / 2 {
-                        let email =
-                                Email::parse(format!("user{}@example.com", i).as_str()).unwrap();
+                for (email, login_id) in removed {
                         assert!(
-                                store.get_code(&email).await.is_err(),
+                                store.get_code(email, login_id).await.is_err(),
                                 "Entry {} should be removed",
-                                i
+                                email.as_ref()
                         );
                 }
 
-                for i in num_entries / 2..num_entries {
-                        let email =
-                                Email::parse(format!("user{}@example.com", i).as_str()).unwrap();
+                for (email, login_id) in remaining {
                         assert!(
-                                store.get_code(&email).await.is_ok(),
+                                store.get_code(email, login_id).await.is_ok(),
                                 "Entry {} should still exist",
-                                i
+                                email.as_ref()
                         );
                 }
         }
@@ -325,17 +568,18 @@ mod tests {
                 // Add initial code
                 {
                         let mut store_guard = store.lock().await;
-                        store_guard.add_code(email.clone(), login_id, code).await.unwrap();
+                        store_guard.add_code(email.clone(), login_id.clone(), code).await.unwrap();
                 }
 
                 // Test that multiple concurrent reads work
                 let handles: Vec<_> = (0..10)
                         .map(|_| {
                                 let email_clone = email.clone();
+                                let login_id_clone = login_id.clone();
                                 let store_clone = Arc::clone(&store);
                                 tokio::task::spawn(async move {
                                         let store_guard = store_clone.lock().await;
-                                        store_guard.get_code(&email_clone).await
+                                        store_guard.get_code(&email_clone, &login_id_clone).await
                                 })
                         })
                         .collect();
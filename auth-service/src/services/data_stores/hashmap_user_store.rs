@@ -1,10 +1,13 @@
-use crate::domain::{Email, HashedPassword, User, UserStore, UserStoreError};
+use crate::domain::{Email, HashedPassword, User, UserStore, UserStoreError, Username};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
 #[derive(Default)]
 pub struct HashmapUserStore {
         #[cfg_attr(test, allow(dead_code))]
         pub(crate) users: HashMap<Email, User>,
+        /// Previous password hashes per user, most recent first
+        password_history: HashMap<Email, Vec<HashedPassword>>,
 }
 
 impl HashmapUserStore {
@@ -30,6 +33,11 @@ impl UserStore for HashmapUserStore {
                 if self.users.contains_key(user.email()) {
                         return Err(UserStoreError::UserAlreadyExists);
                 };
+                if let Some(username) = user.username() {
+                        if self.users.values().any(|u| u.username() == Some(username)) {
+                                return Err(UserStoreError::UserAlreadyExists);
+                        }
+                }
                 self.users.insert(user.email_to_owned(), user);
 
                 Ok(())
@@ -37,18 +45,36 @@ impl UserStore for HashmapUserStore {
 
         /// Returns User or 404 NOT FOUND
         async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+                match self.users.get(email) {
+                        Some(user) if !user.is_deleted() => Ok(user.clone()),
+                        _ => Err(UserStoreError::UserNotFound),
+                }
+        }
+
+        /// Returns User or 404 NOT FOUND
+        async fn get_user_by_username(&self, username: &Username) -> Result<User, UserStoreError> {
+                self.users
+                        .values()
+                        .find(|user| user.username() == Some(username) && !user.is_deleted())
+                        .cloned()
+                        .ok_or(UserStoreError::UserNotFound)
+        }
+
+        /// Returns User (even if soft-deleted) or 404 NOT FOUND
+        async fn get_user_including_deleted(&self, email: &Email) -> Result<User, UserStoreError> {
                 match self.users.get(email) {
                         Some(user) => Ok(user.clone()),
                         None => Err(UserStoreError::UserNotFound),
                 }
         }
+
         /// Returns () or 400 BAD REQUEST
         async fn validate_user(
                 &self,
                 email: &Email,
                 raw_password: &str,
         ) -> Result<(), UserStoreError> {
-                let user: &User = self.users.get(email).ok_or(UserStoreError::UserNotFound)?;
+                let user: User = self.get_user(email).await?;
 
                 user.password()
                         .verify_raw_password(raw_password)
@@ -57,6 +83,130 @@ impl UserStore for HashmapUserStore {
 
                 Ok(())
         }
+
+        /// Returns () or 404 NOT FOUND if the user is missing or already deleted
+        async fn soft_delete(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                match self.users.get_mut(email) {
+                        Some(user) if !user.is_deleted() => {
+                                user.deleted_at = Some(Utc::now());
+                                Ok(())
+                        }
+                        _ => Err(UserStoreError::UserNotFound),
+                }
+        }
+
+        /// Returns () or 404 NOT FOUND
+        async fn reactivate(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                let user = self.users.get_mut(email).ok_or(UserStoreError::UserNotFound)?;
+                user.deleted_at = None;
+                user.last_login_at = Utc::now();
+
+                Ok(())
+        }
+
+        /// Returns () or 404 NOT FOUND if the user is missing or deleted
+        async fn record_login(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                match self.users.get_mut(email) {
+                        Some(user) if !user.is_deleted() => {
+                                user.last_login_at = Utc::now();
+                                Ok(())
+                        }
+                        _ => Err(UserStoreError::UserNotFound),
+                }
+        }
+
+        /// Returns the number of users soft-deleted
+        async fn expire_inactive(
+                &mut self,
+                cutoff: DateTime<Utc>,
+                limit: u64,
+        ) -> Result<u64, UserStoreError> {
+                let now = Utc::now();
+                let mut expired = 0;
+                for user in self.users.values_mut() {
+                        if expired == limit {
+                                break;
+                        }
+                        if !user.is_deleted() && user.last_login_at < cutoff {
+                                user.deleted_at = Some(now);
+                                expired += 1;
+                        }
+                }
+
+                Ok(expired)
+        }
+
+        /// Returns () or 404 NOT FOUND
+        async fn update_password(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+                history_depth: usize,
+        ) -> Result<(), UserStoreError> {
+                let user = self.users.get_mut(email).ok_or(UserStoreError::UserNotFound)?;
+                let previous = std::mem::replace(&mut user.password, password);
+                user.must_change_password = false;
+
+                let history = self.password_history.entry(email.clone()).or_default();
+                history.insert(0, previous);
+                history.truncate(history_depth);
+
+                Ok(())
+        }
+
+        async fn force_password_reset(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+        ) -> Result<(), UserStoreError> {
+                let user = match self.users.get_mut(email) {
+                        Some(user) if !user.is_deleted() => user,
+                        _ => return Err(UserStoreError::UserNotFound),
+                };
+                user.password = password;
+                user.must_change_password = true;
+
+                Ok(())
+        }
+
+        /// Returns (), 404 NOT FOUND, or 409 CONFLICT if the new email is taken
+        async fn update_email(
+                &mut self,
+                old_email: &Email,
+                new_email: &Email,
+        ) -> Result<(), UserStoreError> {
+                if self.users.contains_key(new_email) {
+                        return Err(UserStoreError::UserAlreadyExists);
+                }
+                let mut user = match self.users.remove(old_email) {
+                        Some(user) if !user.is_deleted() => user,
+                        Some(user) => {
+                                self.users.insert(old_email.clone(), user);
+                                return Err(UserStoreError::UserNotFound);
+                        }
+                        None => return Err(UserStoreError::UserNotFound),
+                };
+                user.email = new_email.clone();
+                self.users.insert(new_email.clone(), user);
+
+                if let Some(history) = self.password_history.remove(old_email) {
+                        self.password_history.insert(new_email.clone(), history);
+                }
+
+                Ok(())
+        }
+
+        /// Returns the password history (possibly empty) or 404 NOT FOUND
+        async fn get_password_history(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<HashedPassword>, UserStoreError> {
+                if !self.users.contains_key(email) {
+                        return Err(UserStoreError::UserNotFound);
+                }
+
+                Ok(self.password_history.get(email).cloned().unwrap_or_default())
+        }
 }
 
 #[cfg(test)]
@@ -105,4 +255,221 @@ mod tests {
 
                 assert!(store.validate_user(&email, raw_password).await.is_ok());
         }
+
+        #[tokio::test]
+        async fn test_validate_user_rejects_wrong_password() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+
+                store.add_user(User::new(email.clone(), password, false)).await.unwrap();
+
+                // The stored hash is never compared directly; only the raw password verifies
+                let stored_hash = store.get_user(&email).await.unwrap().password_str().to_owned();
+                assert_eq!(
+                        store.validate_user(&email, &stored_hash).await,
+                        Err(UserStoreError::InvalidCredentials)
+                );
+                assert_eq!(
+                        store.validate_user(&email, "WrongPassword123").await,
+                        Err(UserStoreError::InvalidCredentials)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_get_user_by_username() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let username = Username::parse("tester").unwrap();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+
+                let user =
+                        User::new(email.clone(), password, false).with_username(username.clone());
+                store.add_user(user.clone()).await.unwrap();
+
+                assert_eq!(store.get_user_by_username(&username).await.unwrap(), user);
+                assert_eq!(
+                        store.get_user_by_username(&Username::parse("nobody").unwrap()).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_add_user_rejects_taken_username() {
+                let mut store = HashmapUserStore::new();
+                let username = Username::parse("tester").unwrap();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+
+                let first = User::new(
+                        Email::parse("first@example.com").unwrap(),
+                        password.clone(),
+                        false,
+                )
+                .with_username(username.clone());
+                let second =
+                        User::new(Email::parse("second@example.com").unwrap(), password, false)
+                                .with_username(username);
+
+                store.add_user(first).await.unwrap();
+                assert_eq!(store.add_user(second).await, Err(UserStoreError::UserAlreadyExists));
+        }
+
+        #[tokio::test]
+        async fn test_ping_is_always_ok() {
+                assert_eq!(HashmapUserStore::new().ping().await, Ok(()));
+        }
+
+        #[tokio::test]
+        async fn test_soft_deleted_user_is_hidden_but_kept() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let raw_password = "ValidPassword123";
+                let password = HashedPassword::parse(raw_password).await.unwrap();
+
+                store.add_user(User::new(email.clone(), password, false)).await.unwrap();
+                store.soft_delete(&email).await.unwrap();
+
+                assert_eq!(store.get_user(&email).await, Err(UserStoreError::UserNotFound));
+                assert_eq!(
+                        store.validate_user(&email, raw_password).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+                assert!(store.get_user_including_deleted(&email).await.unwrap().is_deleted());
+
+                // Deleting twice is reported as not found
+                assert_eq!(store.soft_delete(&email).await, Err(UserStoreError::UserNotFound));
+        }
+
+        #[tokio::test]
+        async fn test_reactivate_user() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let raw_password = "ValidPassword123";
+                let password = HashedPassword::parse(raw_password).await.unwrap();
+
+                store.add_user(User::new(email.clone(), password, false)).await.unwrap();
+                store.soft_delete(&email).await.unwrap();
+                store.reactivate(&email).await.unwrap();
+
+                assert!(!store.get_user(&email).await.unwrap().is_deleted());
+                assert!(store.validate_user(&email, raw_password).await.is_ok());
+
+                let unknown = Email::parse("unknown@example.com").unwrap();
+                assert_eq!(store.reactivate(&unknown).await, Err(UserStoreError::UserNotFound));
+        }
+
+        #[tokio::test]
+        async fn test_update_password_rotates_history_to_depth() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let first = HashedPassword::parse("FirstPassword1").await.unwrap();
+                let second = HashedPassword::parse("SecondPassword1").await.unwrap();
+                let third = HashedPassword::parse("ThirdPassword1").await.unwrap();
+
+                store.add_user(User::new(email.clone(), first.clone(), false)).await.unwrap();
+                assert!(store.get_password_history(&email).await.unwrap().is_empty());
+
+                store.update_password(&email, second.clone(), 1).await.unwrap();
+                assert_eq!(store.get_password_history(&email).await.unwrap(), vec![first]);
+
+                store.update_password(&email, third.clone(), 1).await.unwrap();
+                assert_eq!(store.get_password_history(&email).await.unwrap(), vec![second]);
+                assert_eq!(store.get_user(&email).await.unwrap().password, third);
+        }
+
+        #[tokio::test]
+        async fn test_force_password_reset_sets_flag_until_next_update() {
+                let mut store = HashmapUserStore::new();
+                let email = Email::parse("test@example.com").unwrap();
+                let first = HashedPassword::parse("FirstPassword1").await.unwrap();
+                let temporary = HashedPassword::parse("TemporaryPassword1").await.unwrap();
+                let second = HashedPassword::parse("SecondPassword1").await.unwrap();
+
+                store.add_user(User::new(email.clone(), first, false)).await.unwrap();
+                store.force_password_reset(&email, temporary.clone()).await.unwrap();
+                let user = store.get_user(&email).await.unwrap();
+                assert!(user.must_change_password());
+                assert_eq!(user.password, temporary);
+                assert!(store.get_password_history(&email).await.unwrap().is_empty());
+
+                store.update_password(&email, second, 5).await.unwrap();
+                assert!(!store.get_user(&email).await.unwrap().must_change_password());
+
+                let unknown = Email::parse("unknown@example.com").unwrap();
+                assert_eq!(
+                        store.force_password_reset(&unknown, temporary).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_update_email_moves_user_and_history() {
+                let mut store = HashmapUserStore::new();
+                let old = Email::parse("old@example.com").unwrap();
+                let new = Email::parse("new@example.com").unwrap();
+                let taken = Email::parse("taken@example.com").unwrap();
+                let first = HashedPassword::parse("FirstPassword1").await.unwrap();
+                let second = HashedPassword::parse("SecondPassword1").await.unwrap();
+
+                store.add_user(User::new(old.clone(), first.clone(), false)).await.unwrap();
+                store.add_user(User::new(taken.clone(), first.clone(), false)).await.unwrap();
+                store.update_password(&old, second, 5).await.unwrap();
+
+                assert_eq!(
+                        store.update_email(&old, &taken).await,
+                        Err(UserStoreError::UserAlreadyExists)
+                );
+
+                store.update_email(&old, &new).await.unwrap();
+                assert_eq!(store.get_user(&old).await, Err(UserStoreError::UserNotFound));
+                assert_eq!(store.get_user(&new).await.unwrap().email(), &new);
+                assert_eq!(store.get_password_history(&new).await.unwrap(), vec![first]);
+        }
+
+        #[tokio::test]
+        async fn test_expire_inactive_only_touches_idle_users() {
+                let mut store = HashmapUserStore::new();
+                let idle = Email::parse("idle@example.com").unwrap();
+                let active = Email::parse("active@example.com").unwrap();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+                let long_ago = Utc::now() - chrono::Duration::days(100);
+
+                store.add_user(
+                        User::new(idle.clone(), password.clone(), false)
+                                .with_last_login_at(long_ago),
+                )
+                .await
+                .unwrap();
+                store.add_user(User::new(active.clone(), password, false)).await.unwrap();
+
+                let cutoff = Utc::now() - chrono::Duration::days(30);
+                assert_eq!(store.expire_inactive(cutoff, 10).await.unwrap(), 1);
+
+                assert!(store.get_user_including_deleted(&idle).await.unwrap().is_deleted());
+                assert!(store.get_user(&active).await.is_ok());
+
+                // Already-expired users are not counted again
+                assert_eq!(store.expire_inactive(cutoff, 10).await.unwrap(), 0);
+        }
+
+        #[tokio::test]
+        async fn test_expire_inactive_stops_at_the_limit() {
+                let mut store = HashmapUserStore::new();
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+                let long_ago = Utc::now() - chrono::Duration::days(100);
+                for address in ["first@example.com", "second@example.com"] {
+                        let email = Email::parse(address).unwrap();
+                        store.add_user(
+                                User::new(email, password.clone(), false)
+                                        .with_last_login_at(long_ago),
+                        )
+                        .await
+                        .unwrap();
+                }
+
+                let cutoff = Utc::now() - chrono::Duration::days(30);
+                assert_eq!(store.expire_inactive(cutoff, 1).await.unwrap(), 1);
+                assert_eq!(store.expire_inactive(cutoff, 1).await.unwrap(), 1);
+                assert_eq!(store.expire_inactive(cutoff, 1).await.unwrap(), 0);
+        }
 }
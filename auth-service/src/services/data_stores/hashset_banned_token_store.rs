@@ -1,12 +1,15 @@
 // src/services/hashset_banned_token_store.rs
 use async_trait::async_trait;
 
-use crate::domain::{BannedTokenStore, BannedTokenStoreError};
+use crate::{
+        domain::{BannedTokenStore, BannedTokenStoreError},
+        utils::auth::Token,
+};
 use std::collections::HashSet;
 
 #[derive(Default, Debug, Clone)]
 pub struct HashsetBannedTokenStore {
-        banned_tokens: HashSet<String>,
+        banned_tokens: HashSet<Token>,
 }
 
 impl HashsetBannedTokenStore {
@@ -17,7 +20,7 @@ impl HashsetBannedTokenStore {
 
 #[async_trait]
 impl BannedTokenStore for HashsetBannedTokenStore {
-        async fn ban_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
+        async fn ban_token(&mut self, token: Token) -> Result<(), BannedTokenStoreError> {
                 if self.banned_tokens.contains(&token) {
                         Err(BannedTokenStoreError::TokenAlreadyBanned)
                 } else {
@@ -26,7 +29,7 @@ impl BannedTokenStore for HashsetBannedTokenStore {
                 }
         }
 
-        async fn is_banned(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+        async fn is_banned(&self, token: &Token) -> Result<bool, BannedTokenStoreError> {
                 Ok(self.banned_tokens.contains(token))
         }
 }
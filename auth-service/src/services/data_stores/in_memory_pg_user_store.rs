@@ -0,0 +1,446 @@
+// src/services/data_stores/in_memory_pg_user_store.rs
+use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::postgres_user_store::dummy_password_hash;
+use crate::domain::{
+        data_stores::{UserStore, UserStoreError},
+        Argon2PasswordVerifier, Email, HashedPassword, PasswordHashVerifier, TwoFAMethod, User,
+        Username,
+};
+
+/// In-memory stand-in for `PostgresUserStore` that answers every call the way the database
+/// would, so its edge cases can be tested without a connection.
+///
+/// Unlike `HashmapUserStore`, it enforces the `users` table's unique constraints (email,
+/// username, and password hash) and its email-only 2FA method check, reports a statement that matches no row as `UserNotFound`
+/// in the same order Postgres does, and verifies passwords like `PostgresUserStore`: through
+/// the injected verifier, against a dummy hash for unknown users, and rehashing a password
+/// stored under weaker argon2 costs.
+pub struct InMemoryPgUserStore {
+        tables: Mutex<Tables>,
+        verifier: Arc<dyn PasswordHashVerifier + Send + Sync>,
+}
+
+#[derive(Default)]
+struct Tables {
+        users: HashMap<Email, User>,
+        /// Rows of `password_history` as `(email, hash)`, oldest first like its BIGSERIAL id
+        password_history: Vec<(Email, HashedPassword)>,
+}
+
+impl Default for InMemoryPgUserStore {
+        fn default() -> Self {
+                Self::new()
+        }
+}
+
+impl InMemoryPgUserStore {
+        pub fn new() -> Self {
+                Self::with_verifier(Arc::new(Argon2PasswordVerifier))
+        }
+
+        pub fn with_verifier(verifier: Arc<dyn PasswordHashVerifier + Send + Sync>) -> Self {
+                Self {
+                        tables: Mutex::new(Tables::default()),
+                        verifier,
+                }
+        }
+
+        fn tables(&self) -> MutexGuard<'_, Tables> {
+                // Every change is made in full before the guard drops, so the data is sound
+                // even if a panicking test poisoned the lock
+                self.tables.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        /// Overwrite the user's hash without touching the password history
+        fn set_password_hash(
+                &self,
+                email: &Email,
+                password: &HashedPassword,
+        ) -> Result<(), UserStoreError> {
+                let mut tables = self.tables();
+                let Some(user) = tables.users.get(email) else {
+                        return Err(UserStoreError::UserNotFound);
+                };
+                let mut updated = user.clone();
+                updated.password = password.clone();
+                // `PostgresUserStore` reports any failed `UPDATE` of the hash as unexpected
+                if tables.violates_unique(&updated, Some(email)) {
+                        return Err(UserStoreError::UnexpectedError);
+                }
+                tables.users.insert(email.clone(), updated);
+
+                Ok(())
+        }
+}
+
+impl Tables {
+        /// Whether `row` would break a unique constraint of `users`, ignoring the row stored
+        /// under `replacing` since an `UPDATE` may keep its own values
+        fn violates_unique(&self, row: &User, replacing: Option<&Email>) -> bool {
+                let same_username = |other: &User| {
+                        row.username().is_some() && other.username() == row.username()
+                };
+                // `users_email_lower_key` compares emails lowercased
+                let same_email = |other: &User| {
+                        other.email_str().to_lowercase() == row.email_str().to_lowercase()
+                };
+                self.users.values().filter(|other| Some(other.email()) != replacing).any(|other| {
+                        same_email(other)
+                                || other.password_str() == row.password_str()
+                                || same_username(other)
+                })
+        }
+
+        fn active_user_mut(&mut self, email: &Email) -> Result<&mut User, UserStoreError> {
+                match self.users.get_mut(email) {
+                        Some(user) if !user.is_deleted() => Ok(user),
+                        _ => Err(UserStoreError::UserNotFound),
+                }
+        }
+}
+
+#[async_trait]
+impl UserStore for InMemoryPgUserStore {
+        async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+                // `users_two_fa_method_check`
+                if user.two_fa_method() != TwoFAMethod::Email {
+                        return Err(UserStoreError::UnexpectedError);
+                }
+                let mut tables = self.tables();
+                if tables.violates_unique(&user, None) {
+                        return Err(UserStoreError::UserAlreadyExists);
+                }
+                tables.users.insert(user.email_to_owned(), user);
+
+                Ok(())
+        }
+
+        async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+                match self.tables().users.get(email) {
+                        Some(user) if !user.is_deleted() => Ok(user.clone()),
+                        _ => Err(UserStoreError::UserNotFound),
+                }
+        }
+
+        async fn get_user_by_username(&self, username: &Username) -> Result<User, UserStoreError> {
+                self.tables()
+                        .users
+                        .values()
+                        .find(|user| user.username() == Some(username) && !user.is_deleted())
+                        .cloned()
+                        .ok_or(UserStoreError::UserNotFound)
+        }
+
+        async fn get_user_including_deleted(&self, email: &Email) -> Result<User, UserStoreError> {
+                self.tables().users.get(email).cloned().ok_or(UserStoreError::UserNotFound)
+        }
+
+        async fn validate_user(
+                &self,
+                email: &Email,
+                raw_password: &str,
+        ) -> Result<(), UserStoreError> {
+                let user = match self.get_user(email).await {
+                        Ok(user) => user,
+                        Err(UserStoreError::UserNotFound) => {
+                                let dummy = dummy_password_hash().await?;
+                                let _ = self.verifier.verify(dummy, raw_password).await;
+                                return Err(UserStoreError::UserNotFound);
+                        }
+                        Err(e) => return Err(e),
+                };
+
+                self.verifier
+                        .verify(user.password(), raw_password)
+                        .await
+                        .map_err(|_| UserStoreError::InvalidCredentials)?;
+
+                if user.password().needs_rehash() {
+                        let rehashed = match HashedPassword::rehash(raw_password).await {
+                                Ok(password) => self.set_password_hash(email, &password),
+                                Err(_) => Err(UserStoreError::UnexpectedError),
+                        };
+                        if let Err(e) = rehashed {
+                                tracing::warn!(error = ?e, "Failed to rehash password");
+                        }
+                }
+
+                Ok(())
+        }
+
+        async fn soft_delete(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                self.tables().active_user_mut(email)?.deleted_at = Some(Utc::now());
+                Ok(())
+        }
+
+        async fn reactivate(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                let mut tables = self.tables();
+                let user = tables.users.get_mut(email).ok_or(UserStoreError::UserNotFound)?;
+                user.deleted_at = None;
+                user.last_login_at = Utc::now();
+
+                Ok(())
+        }
+
+        async fn record_login(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                self.tables().active_user_mut(email)?.last_login_at = Utc::now();
+                Ok(())
+        }
+
+        async fn expire_inactive(
+                &mut self,
+                cutoff: DateTime<Utc>,
+                limit: u64,
+        ) -> Result<u64, UserStoreError> {
+                let now = Utc::now();
+                let mut expired = 0;
+                for user in self.tables().users.values_mut() {
+                        if expired == limit {
+                                break;
+                        }
+                        if !user.is_deleted() && user.last_login_at < cutoff {
+                                user.deleted_at = Some(now);
+                                expired += 1;
+                        }
+                }
+
+                Ok(expired)
+        }
+
+        async fn update_password(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+                history_depth: usize,
+        ) -> Result<(), UserStoreError> {
+                let mut tables = self.tables();
+                let Some(user) = tables.users.get(email) else {
+                        return Err(UserStoreError::UserNotFound);
+                };
+                let previous = user.password_to_owned();
+                let mut updated = user.clone();
+                updated.password = password;
+                updated.must_change_password = false;
+                // The whole transaction rolls back, history row included
+                if tables.violates_unique(&updated, Some(email)) {
+                        return Err(UserStoreError::UnexpectedError);
+                }
+                tables.users.insert(email.clone(), updated);
+
+                tables.password_history.push((email.clone(), previous));
+
+                // Keep only the `history_depth` most recent entries
+                let entries = tables.password_history.iter().filter(|(owner, _)| owner == email);
+                let mut excess = entries.count().saturating_sub(history_depth);
+                tables.password_history.retain(|(owner, _)| {
+                        let oldest_excess = owner == email && excess > 0;
+                        if oldest_excess {
+                                excess -= 1;
+                        }
+                        !oldest_excess
+                });
+
+                Ok(())
+        }
+
+        async fn force_password_reset(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+        ) -> Result<(), UserStoreError> {
+                let mut tables = self.tables();
+                let mut updated = tables.active_user_mut(email)?.clone();
+                updated.password = password;
+                updated.must_change_password = true;
+                if tables.violates_unique(&updated, Some(email)) {
+                        return Err(UserStoreError::UnexpectedError);
+                }
+                tables.users.insert(email.clone(), updated);
+
+                Ok(())
+        }
+
+        async fn update_email(
+                &mut self,
+                old_email: &Email,
+                new_email: &Email,
+        ) -> Result<(), UserStoreError> {
+                let mut tables = self.tables();
+                // No row matches the `WHERE`, so no constraint is ever checked
+                let mut updated = tables.active_user_mut(old_email)?.clone();
+                updated.email = new_email.clone();
+                if tables.violates_unique(&updated, Some(old_email)) {
+                        return Err(UserStoreError::UserAlreadyExists);
+                }
+                tables.users.remove(old_email);
+                tables.users.insert(new_email.clone(), updated);
+
+                // ON UPDATE CASCADE
+                for (owner, _) in tables.password_history.iter_mut() {
+                        if owner == old_email {
+                                *owner = new_email.clone();
+                        }
+                }
+
+                Ok(())
+        }
+
+        async fn get_password_history(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<HashedPassword>, UserStoreError> {
+                self.get_user_including_deleted(email).await?;
+
+                Ok(self.tables()
+                        .password_history
+                        .iter()
+                        .rev()
+                        .filter(|(owner, _)| owner == email)
+                        .map(|(_, hash)| hash.clone())
+                        .collect())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use argon2::{
+                password_hash::{rand_core::OsRng, SaltString},
+                Algorithm, Argon2, Params, PasswordHasher, Version,
+        };
+
+        use super::*;
+
+        async fn user(email: &str) -> User {
+                let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+                User::new(Email::parse(email).unwrap(), password, false)
+        }
+
+        #[tokio::test]
+        async fn test_unique_constraints_are_user_already_exists() {
+                let mut store = InMemoryPgUserStore::new();
+                let username = Username::parse("tester").unwrap();
+                let first = user("first@example.com").await.with_username(username.clone());
+                store.add_user(first.clone()).await.unwrap();
+
+                // Same email, same username, and even the same (salted) hash are all rejected
+                let same_email = user("first@example.com").await;
+                let same_username = user("second@example.com").await.with_username(username);
+                let same_hash = User::new(
+                        Email::parse("third@example.com").unwrap(),
+                        first.password_to_owned(),
+                        false,
+                );
+                for duplicate in [same_email, same_username, same_hash] {
+                        assert_eq!(
+                                store.add_user(duplicate).await,
+                                Err(UserStoreError::UserAlreadyExists)
+                        );
+                }
+
+                // A soft-deleted row still holds its email
+                store.soft_delete(first.email()).await.unwrap();
+                assert_eq!(
+                        store.add_user(user("first@example.com").await).await,
+                        Err(UserStoreError::UserAlreadyExists)
+                );
+        }
+
+        #[tokio::test]
+        async fn test_missing_and_deleted_users_are_user_not_found() {
+                let mut store = InMemoryPgUserStore::new();
+                let missing = Email::parse("missing@example.com").unwrap();
+                let deleted = user("deleted@example.com").await;
+                let deleted_email = deleted.email_to_owned();
+                store.add_user(deleted).await.unwrap();
+                store.soft_delete(&deleted_email).await.unwrap();
+                let password = HashedPassword::parse("OtherPassword123").await.unwrap();
+
+                for email in [&missing, &deleted_email] {
+                        assert_eq!(store.get_user(email).await, Err(UserStoreError::UserNotFound));
+                        assert_eq!(
+                                store.soft_delete(email).await,
+                                Err(UserStoreError::UserNotFound)
+                        );
+                        assert_eq!(
+                                store.record_login(email).await,
+                                Err(UserStoreError::UserNotFound)
+                        );
+                        assert_eq!(
+                                store.validate_user(email, "ValidPassword123").await,
+                                Err(UserStoreError::UserNotFound)
+                        );
+                }
+                assert_eq!(
+                        store.update_password(&missing, password.clone(), 5).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+                assert_eq!(
+                        store.get_password_history(&missing).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+                // A soft-deleted user keeps their history
+                assert_eq!(store.get_password_history(&deleted_email).await, Ok(vec![]));
+        }
+
+        #[tokio::test]
+        async fn test_update_email_checks_the_row_before_the_constraint() {
+                let mut store = InMemoryPgUserStore::new();
+                let old = user("old@example.com").await;
+                let taken = user("taken@example.com").await;
+                let (old_email, taken_email) = (old.email_to_owned(), taken.email_to_owned());
+                store.add_user(old).await.unwrap();
+                store.add_user(taken).await.unwrap();
+                store.soft_delete(&taken_email).await.unwrap();
+
+                let missing = Email::parse("missing@example.com").unwrap();
+                assert_eq!(
+                        store.update_email(&missing, &taken_email).await,
+                        Err(UserStoreError::UserNotFound)
+                );
+                assert_eq!(
+                        store.update_email(&old_email, &taken_email).await,
+                        Err(UserStoreError::UserAlreadyExists)
+                );
+                // Setting a row's email to its current value violates nothing
+                assert_eq!(store.update_email(&old_email, &old_email).await, Ok(()));
+        }
+
+        #[tokio::test]
+        async fn test_validate_user_rehashes_a_weaker_hash() {
+                let mut store = InMemoryPgUserStore::new();
+                let raw_password = "ValidPassword123";
+                let weak_hash = Argon2::new(
+                        Algorithm::Argon2id,
+                        Version::V0x13,
+                        Params::new(4096, 1, 1, None).unwrap(),
+                )
+                .hash_password(raw_password.as_bytes(), &SaltString::generate(&mut OsRng))
+                .unwrap()
+                .to_string();
+                let weak = HashedPassword::parse_password_hash(weak_hash).unwrap();
+                assert!(weak.needs_rehash());
+
+                let email = Email::parse("test@example.com").unwrap();
+                store.add_user(User::new(email.clone(), weak, false)).await.unwrap();
+
+                assert_eq!(store.validate_user(&email, raw_password).await, Ok(()));
+                assert!(!store.get_user(&email).await.unwrap().password().needs_rehash());
+                assert_eq!(store.validate_user(&email, raw_password).await, Ok(()));
+        }
+
+        #[tokio::test]
+        async fn test_dummy_hash_uses_the_configured_hasher_and_costs() {
+                let dummy = dummy_password_hash().await.unwrap();
+
+                // The same hash every time, made the way a new password would be
+                assert!(std::ptr::eq(dummy, dummy_password_hash().await.unwrap()));
+                assert!(!dummy.needs_rehash());
+        }
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+
+use crate::domain::CaptchaVerifier;
+
+/// Accepts exactly one token, so tests can exercise both outcomes without a provider
+pub struct MockCaptchaVerifier {
+        valid_token: String,
+}
+
+impl MockCaptchaVerifier {
+        pub fn new(valid_token: impl Into<String>) -> Self {
+                Self {
+                        valid_token: valid_token.into(),
+                }
+        }
+}
+
+#[async_trait]
+impl CaptchaVerifier for MockCaptchaVerifier {
+        async fn verify(&self, token: &str) -> Result<(), String> {
+                if token == self.valid_token {
+                        Ok(())
+                } else {
+                        Err("CAPTCHA token rejected".to_owned())
+                }
+        }
+}
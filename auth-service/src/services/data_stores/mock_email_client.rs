@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::domain::{Email, EmailClient};
+use crate::domain::{Email, EmailClient, EmailClientError};
 
 pub struct MockEmailClient;
 
@@ -11,12 +11,14 @@ impl EmailClient for MockEmailClient {
                 recipient: &Email,
                 subject: &str,
                 content: &str,
-        ) -> Result<(), String> {
+        ) -> Result<(), EmailClientError> {
+                // The body may carry a temporary password, login link, or code, which must never
+                // reach the logs; only its size is shown
                 println!(
-                        "Sending email to {} with subject {} and content: {}",
+                        "Sending email to {} with subject {} and {} bytes of content",
                         recipient.as_ref(),
                         subject,
-                        content,
+                        content.len(),
                 );
 
                 Ok(())
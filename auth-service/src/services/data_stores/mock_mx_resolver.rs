@@ -0,0 +1,29 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+
+use crate::domain::{EmailDomain, MxResolver};
+
+/// Reports MX records for exactly the given domains, so tests need no DNS
+pub struct MockMxResolver {
+        domains_with_mx: HashSet<String>,
+}
+
+impl MockMxResolver {
+        pub fn new<I, S>(domains_with_mx: I) -> Self
+        where
+                I: IntoIterator<Item = S>,
+                S: Into<String>,
+        {
+                Self {
+                        domains_with_mx: domains_with_mx.into_iter().map(Into::into).collect(),
+                }
+        }
+}
+
+#[async_trait]
+impl MxResolver for MockMxResolver {
+        async fn has_mx_records(&self, domain: &EmailDomain) -> Result<bool, String> {
+                Ok(self.domains_with_mx.contains(domain.as_str()))
+        }
+}
@@ -1,14 +1,50 @@
+pub mod hashmap_audit_log_store;
+pub mod hashmap_failed_login_store;
+pub mod hashmap_idempotency_store;
+pub mod hashmap_magic_link_store;
+pub mod hashmap_pending_email_change_store;
+pub mod hashmap_rate_limit_store;
+pub mod hashmap_session_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
+pub mod in_memory_pg_user_store;
+pub mod mock_captcha_verifier;
 pub mod mock_email_client;
+pub mod mock_mx_resolver;
 pub mod postgres_user_store;
+pub mod redis_audit_log_store;
 pub mod redis_banned_token_store;
+mod redis_counter;
+pub mod redis_failed_login_store;
+pub mod redis_idempotency_store;
+pub mod redis_magic_link_store;
+pub mod redis_pending_email_change_store;
+pub mod redis_rate_limit_store;
+pub mod redis_session_store;
 pub mod redis_two_fa_code_store;
+mod sliding_window;
 
+pub use hashmap_audit_log_store::*;
+pub use hashmap_failed_login_store::*;
+pub use hashmap_idempotency_store::*;
+pub use hashmap_magic_link_store::*;
+pub use hashmap_pending_email_change_store::*;
+pub use hashmap_rate_limit_store::*;
+pub use hashmap_session_store::*;
 pub use hashmap_two_fa_code_store::*;
 pub use hashmap_user_store::*;
 pub use hashset_banned_token_store::*;
+pub use in_memory_pg_user_store::*;
+pub use mock_captcha_verifier::*;
 pub use mock_email_client::*;
+pub use mock_mx_resolver::*;
+pub use redis_audit_log_store::*;
 pub use redis_banned_token_store::*;
+pub use redis_failed_login_store::*;
+pub use redis_idempotency_store::*;
+pub use redis_magic_link_store::*;
+pub use redis_pending_email_change_store::*;
+pub use redis_rate_limit_store::*;
+pub use redis_session_store::*;
 pub use redis_two_fa_code_store::*;
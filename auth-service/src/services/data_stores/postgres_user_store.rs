@@ -1,20 +1,76 @@
 // src/services//data_stores/postgres_user_store.rs
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
 
 use crate::domain::{
         data_stores::{UserStore, UserStoreError},
-        Email, HashedPassword, User,
+        Argon2PasswordVerifier, DisplayName, Email, HashedPassword, PasswordHashVerifier,
+        PhoneNumber, Role, TwoFAMethod, User, Username,
 };
 
+static DUMMY_PASSWORD_HASH: OnceCell<HashedPassword> = OnceCell::const_new();
+
+/// Hash verified against when the user does not exist, so a login for an unknown email
+/// takes as long as one for a known email. Made on first use from a random password with the
+/// configured hasher, costs, and pepper, so it costs what a real hash does however those are
+/// set; no password is expected to match it.
+pub(crate) async fn dummy_password_hash() -> Result<&'static HashedPassword, UserStoreError> {
+        DUMMY_PASSWORD_HASH
+                .get_or_try_init(|| async {
+                        HashedPassword::rehash(&Uuid::new_v4().to_string()).await
+                })
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)
+}
+
 pub struct PostgresUserStore {
         pool: PgPool,
+        verifier: Arc<dyn PasswordHashVerifier + Send + Sync>,
 }
 
 impl PostgresUserStore {
         pub fn new(pool: PgPool) -> Self {
+                Self::with_verifier(pool, Arc::new(Argon2PasswordVerifier))
+        }
+
+        pub fn with_verifier(
+                pool: PgPool,
+                verifier: Arc<dyn PasswordHashVerifier + Send + Sync>,
+        ) -> Self {
                 Self {
                         pool,
+                        verifier,
+                }
+        }
+}
+
+impl PostgresUserStore {
+        /// Overwrite the user's hash without touching the password history
+        async fn set_password_hash(
+                &self,
+                email: &Email,
+                password: &HashedPassword,
+        ) -> Result<(), UserStoreError> {
+                let updated = sqlx::query!(
+                        r#"
+                        UPDATE users SET password_hash = $2, pepper_version = $3 WHERE email = $1
+                        "#,
+                        email.as_str(),
+                        password.as_ref(),
+                        password.pepper_version() as i16
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                match updated.rows_affected() {
+                        0 => Err(UserStoreError::UserNotFound),
+                        _ => Ok(()),
                 }
         }
 }
@@ -25,16 +81,40 @@ impl UserStore for PostgresUserStore {
         async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
                 sqlx::query!(
                         r#"
-                        INSERT INTO users (email, password_hash, requires_2fa)
-                        VALUES ($1, $2, $3)
+                        INSERT INTO users (
+                                email, password_hash, pepper_version, requires_2fa, two_fa_method,
+                                role, last_login_at, phone, username, email_verified,
+                                locked_until, banned_at, display_name, must_change_password
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
                         "#,
                         user.email_str(),
                         user.password_str(),
+                        user.password().pepper_version() as i16,
                         user.requires_2fa(),
+                        user.two_fa_method().as_str(),
+                        user.role().as_str(),
+                        user.last_login_at(),
+                        user.phone().map(PhoneNumber::as_str),
+                        user.username().map(Username::as_str),
+                        user.email_verified(),
+                        user.locked_until(),
+                        user.banned_at(),
+                        user.display_name().map(DisplayName::as_str),
+                        user.must_change_password(),
                 )
                 .execute(&self.pool)
                 .await
                 .map_err(|e| match e {
+                        // TOTP codes cannot be verified yet, so no account may be set up for them
+                        sqlx::Error::Database(db_err)
+                                if db_err.constraint() == Some("users_two_fa_method_check") =>
+                        {
+                                tracing::error!("Refused to store an unsupported 2FA method");
+                                UserStoreError::UnexpectedError
+                        }
+                        // The email primary key, `users_email_lower_key` (the same address in
+                        // another case), or the username or password hash unique constraints
                         sqlx::Error::Database(db_err) if db_err.constraint().is_some() => {
                                 UserStoreError::UserAlreadyExists
                         }
@@ -45,29 +125,62 @@ impl UserStore for PostgresUserStore {
 
         #[tracing::instrument(name = "Retrieving user from PostgreSQL", skip_all)]
         async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
-                let row = sqlx::query!(
+                sqlx::query_as!(
+                        UserRow,
                         r#"
-                        SELECT email, password_hash, requires_2fa
+                        SELECT email, password_hash, pepper_version, requires_2fa, two_fa_method,
+                               role, deleted_at, last_login_at, phone, username, email_verified,
+                               locked_until, banned_at, display_name, must_change_password
                         FROM users
-                        WHERE email = $1
+                        WHERE email = $1 AND deleted_at IS NULL
                         "#,
                         email.as_str()
                 )
                 .fetch_one(&self.pool)
                 .await
-                .map_err(|e| match e {
-                        sqlx::Error::RowNotFound => UserStoreError::UserNotFound,
-                        _ => UserStoreError::UnexpectedError,
-                })?;
+                .map_err(map_fetch_error)?
+                .try_into()
+        }
 
-                let email: Email =
-                        Email::parse(&row.email).map_err(|_| UserStoreError::UnexpectedError)?;
-                let password: HashedPassword =
-                        HashedPassword::parse_password_hash(row.password_hash)
-                                .map_err(|_| UserStoreError::UnexpectedError)?;
-                let user = User::new(email, password, row.requires_2fa);
+        #[tracing::instrument(name = "Retrieving user by username from PostgreSQL", skip_all)]
+        async fn get_user_by_username(&self, username: &Username) -> Result<User, UserStoreError> {
+                sqlx::query_as!(
+                        UserRow,
+                        r#"
+                        SELECT email, password_hash, pepper_version, requires_2fa, two_fa_method,
+                               role, deleted_at, last_login_at, phone, username, email_verified,
+                               locked_until, banned_at, display_name, must_change_password
+                        FROM users
+                        WHERE username = $1 AND deleted_at IS NULL
+                        "#,
+                        username.as_str()
+                )
+                .fetch_one(&self.pool)
+                .await
+                .map_err(map_fetch_error)?
+                .try_into()
+        }
 
-                Ok(user)
+        #[tracing::instrument(
+                name = "Retrieving user (including deleted) from PostgreSQL",
+                skip_all
+        )]
+        async fn get_user_including_deleted(&self, email: &Email) -> Result<User, UserStoreError> {
+                sqlx::query_as!(
+                        UserRow,
+                        r#"
+                        SELECT email, password_hash, pepper_version, requires_2fa, two_fa_method,
+                               role, deleted_at, last_login_at, phone, username, email_verified,
+                               locked_until, banned_at, display_name, must_change_password
+                        FROM users
+                        WHERE email = $1
+                        "#,
+                        email.as_str()
+                )
+                .fetch_one(&self.pool)
+                .await
+                .map_err(map_fetch_error)?
+                .try_into()
         }
 
         #[tracing::instrument(name = "Validating user credentials in PostgreSQL", skip_all)]
@@ -76,13 +189,351 @@ impl UserStore for PostgresUserStore {
                 email: &Email,
                 raw_password: &str,
         ) -> Result<(), UserStoreError> {
-                let user = self.get_user(email).await?;
+                let user = match self.get_user(email).await {
+                        Ok(user) => user,
+                        Err(UserStoreError::UserNotFound) => {
+                                // Still pay for a verification so the response time does not
+                                // reveal whether the email is registered
+                                let dummy = dummy_password_hash().await?;
+                                let _ = self.verifier.verify(dummy, raw_password).await;
+                                return Err(UserStoreError::UserNotFound);
+                        }
+                        Err(e) => return Err(e),
+                };
 
-                user.password()
-                        .verify_raw_password(raw_password)
+                self.verifier
+                        .verify(user.password(), raw_password)
                         .await
                         .map_err(|_| UserStoreError::InvalidCredentials)?;
 
+                // A legacy bcrypt hash, or the hasher, argon2 costs, or pepper changed since this
+                // hash was stored: the password just verified, so store it again under the
+                // current ones. A failure here only delays the rehash to the next login and never
+                // fails the validation.
+                if user.password().needs_rehash() {
+                        let rehashed = match HashedPassword::rehash(raw_password).await {
+                                Ok(password) => self.set_password_hash(email, &password).await,
+                                Err(_) => Err(UserStoreError::UnexpectedError),
+                        };
+                        if let Err(e) = rehashed {
+                                tracing::warn!(error = ?e, "Failed to rehash password");
+                        }
+                }
+
+                Ok(())
+        }
+
+        #[tracing::instrument(name = "Soft-deleting user in PostgreSQL", skip_all)]
+        async fn soft_delete(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                let result = sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET deleted_at = NOW()
+                        WHERE email = $1 AND deleted_at IS NULL
+                        "#,
+                        email.as_str()
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                if result.rows_affected() == 0 {
+                        return Err(UserStoreError::UserNotFound);
+                }
+                Ok(())
+        }
+
+        #[tracing::instrument(name = "Reactivating user in PostgreSQL", skip_all)]
+        async fn reactivate(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                let result = sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET deleted_at = NULL, last_login_at = NOW()
+                        WHERE email = $1
+                        "#,
+                        email.as_str()
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                if result.rows_affected() == 0 {
+                        return Err(UserStoreError::UserNotFound);
+                }
+                Ok(())
+        }
+
+        #[tracing::instrument(name = "Recording user login in PostgreSQL", skip_all)]
+        async fn record_login(&mut self, email: &Email) -> Result<(), UserStoreError> {
+                let result = sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET last_login_at = NOW()
+                        WHERE email = $1 AND deleted_at IS NULL
+                        "#,
+                        email.as_str()
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                if result.rows_affected() == 0 {
+                        return Err(UserStoreError::UserNotFound);
+                }
+                Ok(())
+        }
+
+        #[tracing::instrument(name = "Expiring inactive users in PostgreSQL", skip_all)]
+        async fn expire_inactive(
+                &mut self,
+                cutoff: DateTime<Utc>,
+                limit: u64,
+        ) -> Result<u64, UserStoreError> {
+                let limit = i64::try_from(limit).map_err(|_| UserStoreError::UnexpectedError)?;
+                let result = sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET deleted_at = NOW()
+                        WHERE email IN (
+                                SELECT email FROM users
+                                WHERE deleted_at IS NULL AND last_login_at < $1
+                                LIMIT $2
+                        )
+                        "#,
+                        cutoff,
+                        limit
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                Ok(result.rows_affected())
+        }
+
+        #[tracing::instrument(name = "Updating user password in PostgreSQL", skip_all)]
+        async fn update_password(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+                history_depth: usize,
+        ) -> Result<(), UserStoreError> {
+                let mut tx =
+                        self.pool.begin().await.map_err(|_| UserStoreError::UnexpectedError)?;
+
+                // Move the current hash into the history before overwriting it
+                let archived = sqlx::query!(
+                        r#"
+                        INSERT INTO password_history (email, password_hash, pepper_version)
+                        SELECT email, password_hash, pepper_version FROM users WHERE email = $1
+                        "#,
+                        email.as_str()
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                if archived.rows_affected() == 0 {
+                        return Err(UserStoreError::UserNotFound);
+                }
+
+                sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET password_hash = $2, pepper_version = $3, must_change_password = FALSE
+                        WHERE email = $1
+                        "#,
+                        email.as_str(),
+                        password.as_ref(),
+                        password.pepper_version() as i16
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                // Keep only the `history_depth` most recent entries
+                sqlx::query!(
+                        r#"
+                        DELETE FROM password_history
+                        WHERE email = $1 AND id NOT IN (
+                                SELECT id FROM password_history
+                                WHERE email = $1
+                                ORDER BY id DESC
+                                LIMIT $2
+                        )
+                        "#,
+                        email.as_str(),
+                        history_depth as i64
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                tx.commit().await.map_err(|_| UserStoreError::UnexpectedError)?;
                 Ok(())
         }
+
+        #[tracing::instrument(name = "Forcing user password reset in PostgreSQL", skip_all)]
+        async fn force_password_reset(
+                &mut self,
+                email: &Email,
+                password: HashedPassword,
+        ) -> Result<(), UserStoreError> {
+                let updated = sqlx::query!(
+                        r#"
+                        UPDATE users
+                        SET password_hash = $2, pepper_version = $3, must_change_password = TRUE
+                        WHERE email = $1 AND deleted_at IS NULL
+                        "#,
+                        email.as_str(),
+                        password.as_ref(),
+                        password.pepper_version() as i16
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                match updated.rows_affected() {
+                        0 => Err(UserStoreError::UserNotFound),
+                        _ => Ok(()),
+                }
+        }
+
+        #[tracing::instrument(name = "Updating user email in PostgreSQL", skip_all)]
+        async fn update_email(
+                &mut self,
+                old_email: &Email,
+                new_email: &Email,
+        ) -> Result<(), UserStoreError> {
+                // The password history follows through its ON UPDATE CASCADE foreign key
+                let updated = sqlx::query!(
+                        r#"
+                        UPDATE users SET email = $2 WHERE email = $1 AND deleted_at IS NULL
+                        "#,
+                        old_email.as_str(),
+                        new_email.as_str()
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| match e {
+                        sqlx::Error::Database(db_err) if db_err.constraint().is_some() => {
+                                UserStoreError::UserAlreadyExists
+                        }
+                        _ => UserStoreError::UnexpectedError,
+                })?;
+
+                match updated.rows_affected() {
+                        0 => Err(UserStoreError::UserNotFound),
+                        _ => Ok(()),
+                }
+        }
+
+        #[tracing::instrument(name = "Retrieving password history from PostgreSQL", skip_all)]
+        async fn get_password_history(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<HashedPassword>, UserStoreError> {
+                // Make sure the user exists so an unknown email is not reported as an empty history
+                self.get_user_including_deleted(email).await?;
+
+                let rows = sqlx::query!(
+                        r#"
+                        SELECT password_hash, pepper_version FROM password_history
+                        WHERE email = $1
+                        ORDER BY id DESC
+                        "#,
+                        email.as_str()
+                )
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                rows.into_iter()
+                        .map(|row| parse_stored_hash(row.password_hash, row.pepper_version))
+                        .collect()
+        }
+
+        #[tracing::instrument(name = "Pinging PostgreSQL", skip_all)]
+        async fn ping(&self) -> Result<(), UserStoreError> {
+                sqlx::query!("SELECT 1 AS one")
+                        .fetch_one(&self.pool)
+                        .await
+                        .map_err(|_| UserStoreError::UnexpectedError)?;
+                Ok(())
+        }
+}
+
+/// Row shape shared by every query that loads a full `User`
+struct UserRow {
+        email: String,
+        password_hash: String,
+        pepper_version: i16,
+        requires_2fa: bool,
+        two_fa_method: String,
+        role: String,
+        deleted_at: Option<DateTime<Utc>>,
+        last_login_at: DateTime<Utc>,
+        phone: Option<String>,
+        username: Option<String>,
+        email_verified: bool,
+        locked_until: Option<DateTime<Utc>>,
+        banned_at: Option<DateTime<Utc>>,
+        display_name: Option<String>,
+        must_change_password: bool,
+}
+
+impl TryFrom<UserRow> for User {
+        type Error = UserStoreError;
+
+        fn try_from(row: UserRow) -> Result<Self, Self::Error> {
+                let email: Email =
+                        Email::parse(&row.email).map_err(|_| UserStoreError::UnexpectedError)?;
+                let password = parse_stored_hash(row.password_hash, row.pepper_version)?;
+                let two_fa_method = TwoFAMethod::parse(&row.two_fa_method)
+                        .map_err(|_| UserStoreError::UnexpectedError)?;
+                let role = Role::parse(&row.role).map_err(|_| UserStoreError::UnexpectedError)?;
+
+                let mut user = User::new(email, password, row.requires_2fa)
+                        .with_two_fa_method(two_fa_method)
+                        .with_role(role)
+                        .with_last_login_at(row.last_login_at);
+                user.deleted_at = row.deleted_at;
+                user.email_verified = row.email_verified;
+                user.locked_until = row.locked_until;
+                user.banned_at = row.banned_at;
+                user.must_change_password = row.must_change_password;
+                user.phone = row
+                        .phone
+                        .map(|phone| PhoneNumber::parse(&phone))
+                        .transpose()
+                        .map_err(|_| UserStoreError::UnexpectedError)?;
+                user.username = row
+                        .username
+                        .map(|username| Username::parse(&username))
+                        .transpose()
+                        .map_err(|_| UserStoreError::UnexpectedError)?;
+                user.display_name = row
+                        .display_name
+                        .map(|display_name| DisplayName::parse(&display_name))
+                        .transpose()
+                        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+                Ok(user)
+        }
+}
+
+fn map_fetch_error(e: sqlx::Error) -> UserStoreError {
+        match e {
+                sqlx::Error::RowNotFound => UserStoreError::UserNotFound,
+                _ => UserStoreError::UnexpectedError,
+        }
+}
+
+/// A `password_hash` column with the `pepper_version` stored next to it
+fn parse_stored_hash(hash: String, pepper_version: i16) -> Result<HashedPassword, UserStoreError> {
+        let pepper_version =
+                u16::try_from(pepper_version).map_err(|_| UserStoreError::UnexpectedError)?;
+
+        HashedPassword::parse_password_hash(hash)
+                .map(|password| password.with_pepper_version(pepper_version))
+                .map_err(|_| UserStoreError::UnexpectedError)
 }
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use redis::TypedCommands;
+use std::sync::Arc;
+
+use crate::{
+        domain::{AuditEvent, AuditLogStore, AuditLogStoreError, Email},
+        utils::constants::{AUDIT_LOG_MAX_EVENTS, AUDIT_LOG_TTL_SECONDS},
+        RedisPool, RedisPooledConnection,
+};
+
+/// Events are kept in one list per user, newest first, as JSON-serialized `AuditEvent`s,
+/// trimmed to `AUDIT_LOG_MAX_EVENTS` on every write
+pub struct RedisAuditLogStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisAuditLogStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, AuditLogStoreError> {
+                self.pool.get().map_err(|_| AuditLogStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl AuditLogStore for RedisAuditLogStore {
+        async fn record_event(
+                &mut self,
+                email: &Email,
+                event: AuditEvent,
+        ) -> Result<(), AuditLogStoreError> {
+                let key = get_key(email);
+                let value = serde_json::to_string(&event)
+                        .map_err(|_| AuditLogStoreError::UnexpectedError)?;
+
+                let mut conn = self.connection()?;
+                conn.lpush(&key, value).map_err(|_| AuditLogStoreError::UnexpectedError)?;
+                conn.ltrim(&key, 0, AUDIT_LOG_MAX_EVENTS as isize - 1)
+                        .map_err(|_| AuditLogStoreError::UnexpectedError)?;
+                conn.expire(&key, AUDIT_LOG_TTL_SECONDS)
+                        .map_err(|_| AuditLogStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn get_events(
+                &self,
+                email: &Email,
+                offset: usize,
+                limit: usize,
+        ) -> Result<Vec<AuditEvent>, AuditLogStoreError> {
+                if limit == 0 || offset >= AUDIT_LOG_MAX_EVENTS {
+                        return Ok(Vec::new());
+                }
+                let stop = offset.saturating_add(limit).min(AUDIT_LOG_MAX_EVENTS) - 1;
+
+                let values = self
+                        .connection()?
+                        .lrange(get_key(email), offset as isize, stop as isize)
+                        .map_err(|_| AuditLogStoreError::UnexpectedError)?;
+
+                values.iter()
+                        .map(|value| serde_json::from_str(value))
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| AuditLogStoreError::UnexpectedError)
+        }
+}
+
+const AUDIT_LOG_PREFIX: &str = "audit_log:";
+
+fn get_key(email: &Email) -> String {
+        format!("{}{}", AUDIT_LOG_PREFIX, email.as_ref())
+}
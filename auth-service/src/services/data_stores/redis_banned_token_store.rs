@@ -1,54 +1,52 @@
-
 use async_trait::async_trait;
-use redis::{Commands, Connection};
-use tokio::sync::Mutex;
+use redis::Commands;
+use std::sync::Arc;
 
 use crate::{
         domain::{BannedTokenStore, BannedTokenStoreError},
-        utils::constants::TOKEN_TTL_SECONDS,
+        utils::{auth::Token, constants::TOKEN_TTL_SECONDS},
+        RedisPool, RedisPooledConnection,
 };
 
-type RedisConnection = Mutex<Connection>;
-
 pub struct RedisBannedTokenStore {
-        conn: RedisConnection,
+        pool: Arc<RedisPool>,
 }
 
 impl RedisBannedTokenStore {
-        pub fn new(conn: Connection) -> Self {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
                 Self {
-                        conn: Mutex::new(conn),
+                        pool,
                 }
         }
+
+        fn connection(&self) -> Result<RedisPooledConnection, BannedTokenStoreError> {
+                self.pool.get().map_err(|_| BannedTokenStoreError::UnexpectedError)
+        }
 }
 
 #[async_trait]
 impl BannedTokenStore for RedisBannedTokenStore {
-        async fn ban_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
+        async fn ban_token(&mut self, token: Token) -> Result<(), BannedTokenStoreError> {
                 let key = get_key(&token);
                 let ttl = TOKEN_TTL_SECONDS as u64;
 
-                self.conn
-                        .lock()
-                        .await
+                self.connection()?
                         .set_ex::<_, _, ()>(key, true, ttl)
                         .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
 
                 Ok(())
         }
 
-        async fn is_banned(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
-                // Check if the token exists by calling the exists method on the Redis connection
-                self.conn
-                        .lock()
-                        .await
-                        .exists::<_, bool>(token)
-                        .map_err(|_| BannedTokenStoreError::TokenAlreadyBanned)
+        async fn is_banned(&self, token: &Token) -> Result<bool, BannedTokenStoreError> {
+                // Check if the token's key exists by calling the exists method on the Redis connection
+                self.connection()?
+                        .exists::<_, bool>(get_key(token))
+                        .map_err(|_| BannedTokenStoreError::UnexpectedError)
         }
 }
 
 const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
 
-fn get_key(token: &str) -> String {
-        format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token)
+fn get_key(token: &Token) -> String {
+        format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token.as_str())
 }
@@ -0,0 +1,91 @@
+use chrono::Utc;
+use lazy_static::lazy_static;
+use redis::{RedisResult, Script, TypedCommands};
+use uuid::Uuid;
+
+use crate::RedisPooledConnection;
+
+lazy_static! {
+        /// INCR, then start the window if the counter has no TTL yet. As one script the two
+        /// run atomically, so a failure in between cannot leave a counter that never expires.
+        static ref INCREMENT_IN_WINDOW: Script = Script::new(
+                r"
+                local count = redis.call('INCR', KEYS[1])
+                if redis.call('TTL', KEYS[1]) < 0 then
+                        redis.call('EXPIRE', KEYS[1], ARGV[1])
+                end
+                return count
+                ",
+        );
+
+        /// Drop the events that have left the window, add this one scored by its time, and
+        /// count what is left. The set expires with its newest event.
+        static ref RECORD_IN_SLIDING_WINDOW: Script = Script::new(
+                r"
+                local now, window = tonumber(ARGV[1]), tonumber(ARGV[2])
+                redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+                redis.call('ZADD', KEYS[1], now, ARGV[3])
+                redis.call('PEXPIRE', KEYS[1], window)
+                return redis.call('ZCARD', KEYS[1])
+                ",
+        );
+}
+
+/// Add one to the counter at `key` and return the new count. The counter is a fixed window:
+/// it expires `window_seconds` after its first increment, whatever came after.
+pub(super) fn increment_in_window(
+        conn: &mut RedisPooledConnection,
+        key: &str,
+        window_seconds: i64,
+) -> RedisResult<i64> {
+        INCREMENT_IN_WINDOW.key(key).arg(window_seconds).invoke(&mut **conn)
+}
+
+/// Record an event in the sorted set at `key` and return how many events it holds from the
+/// last `window_seconds`. Each event stops counting exactly one window after it happened.
+pub(super) fn record_in_sliding_window(
+        conn: &mut RedisPooledConnection,
+        key: &str,
+        window_seconds: i64,
+) -> RedisResult<i64> {
+        RECORD_IN_SLIDING_WINDOW
+                .key(key)
+                .arg(Utc::now().timestamp_millis())
+                .arg(window_seconds * 1_000)
+                .arg(Uuid::new_v4().to_string())
+                .invoke(&mut **conn)
+}
+
+/// Events in the sorted set at `key` from the last `window_seconds`
+pub(super) fn count_in_sliding_window(
+        conn: &mut RedisPooledConnection,
+        key: &str,
+        window_seconds: i64,
+) -> RedisResult<usize> {
+        conn.zcount(key, sliding_window_start(window_seconds), "+inf")
+}
+
+/// When (Unix seconds) the oldest event in the sorted set at `key` from the last
+/// `window_seconds` leaves the window, or `None` if there is none
+pub(super) fn sliding_window_reset_at(
+        conn: &mut RedisPooledConnection,
+        key: &str,
+        window_seconds: i64,
+) -> RedisResult<Option<i64>> {
+        let oldest = conn.zrangebyscore_limit_withscores(
+                key,
+                sliding_window_start(window_seconds),
+                "+inf",
+                0,
+                1,
+        )?;
+
+        Ok(oldest
+                .first()
+                .map(|(_, at_millis)| (*at_millis as i64 + window_seconds * 1_000 + 999) / 1_000))
+}
+
+/// Exclusive lower score bound of the window, matching the script's `ZREMRANGEBYSCORE`
+fn sliding_window_start(window_seconds: i64) -> String {
+        format!("({}", Utc::now().timestamp_millis() - window_seconds * 1_000)
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use redis::{ExistenceCheck, SetExpiry, SetOptions, TypedCommands};
+use std::sync::Arc;
+
+use crate::{
+        domain::{Email, FailedLoginStore, FailedLoginStoreError},
+        services::data_stores::redis_counter::{
+                count_in_sliding_window, record_in_sliding_window, sliding_window_reset_at,
+        },
+        utils::constants::{FAILED_LOGIN_ALERT_INTERVAL_SECONDS, FAILED_LOGIN_WINDOW_SECONDS},
+        RedisPool, RedisPooledConnection,
+};
+
+/// Each failure count (per account and per client IP) is a sorted set of failure times, each
+/// counting for one window after it happened; the alert throttle is a marker key that expires
+/// one alert interval after it is set
+pub struct RedisFailedLoginStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisFailedLoginStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, FailedLoginStoreError> {
+                self.pool.get().map_err(|_| FailedLoginStoreError::UnexpectedError)
+        }
+
+        fn increment(&self, key: &str) -> Result<u32, FailedLoginStoreError> {
+                let count = record_in_sliding_window(
+                        &mut self.connection()?,
+                        key,
+                        FAILED_LOGIN_WINDOW_SECONDS,
+                )
+                .map_err(|_| FailedLoginStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| FailedLoginStoreError::UnexpectedError)
+        }
+
+        fn count(&self, key: &str) -> Result<u32, FailedLoginStoreError> {
+                let count = count_in_sliding_window(
+                        &mut self.connection()?,
+                        key,
+                        FAILED_LOGIN_WINDOW_SECONDS,
+                )
+                .map_err(|_| FailedLoginStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| FailedLoginStoreError::UnexpectedError)
+        }
+
+        fn reset_at(&self, key: &str) -> Result<Option<i64>, FailedLoginStoreError> {
+                sliding_window_reset_at(&mut self.connection()?, key, FAILED_LOGIN_WINDOW_SECONDS)
+                        .map_err(|_| FailedLoginStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl FailedLoginStore for RedisFailedLoginStore {
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, FailedLoginStoreError> {
+                self.increment(&get_failures_key(email))
+        }
+
+        async fn get_failures(&self, email: &Email) -> Result<u32, FailedLoginStoreError> {
+                self.count(&get_failures_key(email))
+        }
+
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, FailedLoginStoreError> {
+                self.reset_at(&get_failures_key(email))
+        }
+
+        async fn clear_failures(&mut self, email: &Email) -> Result<(), FailedLoginStoreError> {
+                self.connection()?
+                        .del(get_failures_key(email))
+                        .map_err(|_| FailedLoginStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn record_ip_failure(&mut self, ip: &str) -> Result<u32, FailedLoginStoreError> {
+                self.increment(&get_ip_failures_key(ip))
+        }
+
+        async fn get_ip_failures(&self, ip: &str) -> Result<u32, FailedLoginStoreError> {
+                self.count(&get_ip_failures_key(ip))
+        }
+
+        async fn get_ip_failures_reset_at(
+                &self,
+                ip: &str,
+        ) -> Result<Option<i64>, FailedLoginStoreError> {
+                self.reset_at(&get_ip_failures_key(ip))
+        }
+
+        async fn claim_alert(&mut self, email: &Email) -> Result<bool, FailedLoginStoreError> {
+                // `SET NX` only succeeds for the first claim within the interval
+                let options = SetOptions::default()
+                        .conditional_set(ExistenceCheck::NX)
+                        .with_expiration(SetExpiry::EX(FAILED_LOGIN_ALERT_INTERVAL_SECONDS as u64));
+
+                let reply = self
+                        .connection()?
+                        .set_options(get_alert_key(email), 1, options)
+                        .map_err(|_| FailedLoginStoreError::UnexpectedError)?;
+
+                Ok(reply.is_some())
+        }
+}
+
+const FAILED_LOGINS_PREFIX: &str = "failed_logins:";
+const FAILED_LOGINS_IP_PREFIX: &str = "failed_logins_ip:";
+const FAILED_LOGIN_ALERT_PREFIX: &str = "failed_login_alert:";
+
+fn get_failures_key(email: &Email) -> String {
+        format!("{}{}", FAILED_LOGINS_PREFIX, email.as_ref())
+}
+
+fn get_ip_failures_key(ip: &str) -> String {
+        format!("{}{}", FAILED_LOGINS_IP_PREFIX, ip)
+}
+
+fn get_alert_key(email: &Email) -> String {
+        format!("{}{}", FAILED_LOGIN_ALERT_PREFIX, email.as_ref())
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use redis::TypedCommands;
+use std::sync::Arc;
+
+use crate::{
+        domain::{Email, IdempotencyStore, IdempotencyStoreError},
+        utils::constants::IDEMPOTENCY_KEY_TTL_SECONDS,
+        RedisPool, RedisPooledConnection,
+};
+
+pub struct RedisIdempotencyStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisIdempotencyStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, IdempotencyStoreError> {
+                self.pool.get().map_err(|_| IdempotencyStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl IdempotencyStore for RedisIdempotencyStore {
+        async fn get_response(
+                &self,
+                key: &str,
+                email: &Email,
+        ) -> Result<Option<String>, IdempotencyStoreError> {
+                self.connection()?
+                        .get(get_key(key, email))
+                        .map_err(|_| IdempotencyStoreError::UnexpectedError)
+        }
+
+        async fn save_response(
+                &mut self,
+                key: &str,
+                email: &Email,
+                response: String,
+        ) -> Result<(), IdempotencyStoreError> {
+                self.connection()?
+                        .set_ex(get_key(key, email), response, IDEMPOTENCY_KEY_TTL_SECONDS)
+                        .map_err(|_| IdempotencyStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+}
+
+const IDEMPOTENCY_KEY_PREFIX: &str = "idempotency:";
+
+fn get_key(key: &str, email: &Email) -> String {
+        format!("{}{}:{}", IDEMPOTENCY_KEY_PREFIX, email.as_ref(), key)
+}
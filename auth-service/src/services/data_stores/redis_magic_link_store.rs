@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use redis::TypedCommands;
+use std::sync::Arc;
+
+use crate::{
+        domain::{Email, MagicLinkStore, MagicLinkStoreError},
+        RedisPool, RedisPooledConnection,
+};
+
+/// Each link is the account's email under its token, expiring with the link
+pub struct RedisMagicLinkStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisMagicLinkStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, MagicLinkStoreError> {
+                self.pool.get().map_err(|_| MagicLinkStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl MagicLinkStore for RedisMagicLinkStore {
+        async fn add_token(
+                &mut self,
+                token: String,
+                email: Email,
+                ttl_seconds: u64,
+        ) -> Result<(), MagicLinkStoreError> {
+                self.connection()?
+                        .set_ex(get_key(&token), email.as_str(), ttl_seconds)
+                        .map_err(|_| MagicLinkStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn take_token(&mut self, token: &str) -> Result<Email, MagicLinkStoreError> {
+                // GETDEL, so two concurrent verifications can't both redeem the link
+                let email = self
+                        .connection()?
+                        .get_del(get_key(token))
+                        .map_err(|_| MagicLinkStoreError::UnexpectedError)?
+                        .ok_or(MagicLinkStoreError::TokenNotFound)?;
+
+                Email::parse(&email).map_err(|_| MagicLinkStoreError::UnexpectedError)
+        }
+}
+
+const MAGIC_LINK_PREFIX: &str = "magic_link:";
+
+fn get_key(token: &str) -> String {
+        format!("{}{}", MAGIC_LINK_PREFIX, token)
+}
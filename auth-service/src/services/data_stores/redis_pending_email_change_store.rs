@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use redis::TypedCommands;
+use std::sync::Arc;
+
+use crate::{
+        domain::{
+                Email, PendingEmailChange, PendingEmailChangeStore, PendingEmailChangeStoreError,
+        },
+        utils::constants::EMAIL_CHANGE_TOKEN_TTL_SECONDS,
+        RedisPool, RedisPooledConnection,
+};
+
+/// Each change is a JSON value under its token that expires with the confirmation link
+pub struct RedisPendingEmailChangeStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisPendingEmailChangeStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, PendingEmailChangeStoreError> {
+                self.pool.get().map_err(|_| PendingEmailChangeStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl PendingEmailChangeStore for RedisPendingEmailChangeStore {
+        async fn add_change(
+                &mut self,
+                token: String,
+                change: PendingEmailChange,
+        ) -> Result<(), PendingEmailChangeStoreError> {
+                let value = serde_json::to_string(&PendingEmailChangeTuple(
+                        change.old_email.as_ref().to_owned(),
+                        change.new_email.as_ref().to_owned(),
+                ))
+                .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?;
+
+                self.connection()?
+                        .set_ex(get_key(&token), value, EMAIL_CHANGE_TOKEN_TTL_SECONDS)
+                        .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn take_change(
+                &mut self,
+                token: &str,
+        ) -> Result<PendingEmailChange, PendingEmailChangeStoreError> {
+                // GETDEL, so two concurrent confirmations can't both redeem the token
+                let value = self
+                        .connection()?
+                        .get_del(get_key(token))
+                        .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?
+                        .ok_or(PendingEmailChangeStoreError::TokenNotFound)?;
+
+                let PendingEmailChangeTuple(old_email, new_email) = serde_json::from_str(&value)
+                        .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?;
+
+                Ok(PendingEmailChange {
+                        old_email: Email::parse(&old_email)
+                                .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?,
+                        new_email: Email::parse(&new_email)
+                                .map_err(|_| PendingEmailChangeStoreError::UnexpectedError)?,
+                })
+        }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PendingEmailChangeTuple(String, String);
+
+const PENDING_EMAIL_CHANGE_PREFIX: &str = "pending_email_change:";
+
+fn get_key(token: &str) -> String {
+        format!("{}{}", PENDING_EMAIL_CHANGE_PREFIX, token)
+}
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{
+        domain::{RateLimitScope, RateLimitStore, RateLimitStoreError},
+        services::data_stores::redis_counter::record_in_sliding_window,
+        RedisPool, RedisPooledConnection,
+};
+
+/// Each subject's requests in a scope are a sorted set of request times, each counting for
+/// the scope's window after it was made
+pub struct RedisRateLimitStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisRateLimitStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, RateLimitStoreError> {
+                self.pool.get().map_err(|_| RateLimitStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+        async fn record_request(
+                &mut self,
+                scope: RateLimitScope,
+                subject: &str,
+        ) -> Result<u32, RateLimitStoreError> {
+                let count = record_in_sliding_window(
+                        &mut self.connection()?,
+                        &get_key(scope, subject),
+                        scope.window_seconds(),
+                )
+                .map_err(|_| RateLimitStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| RateLimitStoreError::UnexpectedError)
+        }
+}
+
+const RATE_LIMIT_PREFIX: &str = "rate_limit:";
+
+fn get_key(scope: RateLimitScope, subject: &str) -> String {
+        format!("{}{}:{}", RATE_LIMIT_PREFIX, scope.as_str(), subject)
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use redis::TypedCommands;
+use std::sync::Arc;
+
+use crate::{
+        domain::{ActiveSession, Email, SessionStore, SessionStoreError},
+        utils::constants::TOKEN_TTL_SECONDS,
+        RedisPool, RedisPooledConnection,
+};
+
+/// Sessions are kept in one list per user, oldest first, as JSON-serialized `ActiveSession`s
+pub struct RedisSessionStore {
+        pool: Arc<RedisPool>,
+}
+
+impl RedisSessionStore {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
+                Self {
+                        pool,
+                }
+        }
+
+        fn connection(&self) -> Result<RedisPooledConnection, SessionStoreError> {
+                self.pool.get().map_err(|_| SessionStoreError::UnexpectedError)
+        }
+}
+
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+        async fn add_session(
+                &mut self,
+                email: &Email,
+                session: ActiveSession,
+        ) -> Result<(), SessionStoreError> {
+                let key = get_key(email);
+                let value = serde_json::to_string(&session)
+                        .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+                let mut conn = self.connection()?;
+                conn.rpush(&key, value).map_err(|_| SessionStoreError::UnexpectedError)?;
+
+                // Every session in the list expires within one token TTL of the newest, so the
+                // list can go once that has passed
+                conn.expire(&key, TOKEN_TTL_SECONDS)
+                        .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn get_sessions(
+                &self,
+                email: &Email,
+        ) -> Result<Vec<ActiveSession>, SessionStoreError> {
+                let values = self
+                        .connection()?
+                        .lrange(get_key(email), 0, -1)
+                        .map_err(|_| SessionStoreError::UnexpectedError)?;
+
+                values.iter()
+                        .map(|value| serde_json::from_str(value))
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| SessionStoreError::UnexpectedError)
+        }
+
+        async fn remove_session(
+                &mut self,
+                email: &Email,
+                jti: &str,
+        ) -> Result<(), SessionStoreError> {
+                let key = get_key(email);
+                let mut conn = self.connection()?;
+
+                let values =
+                        conn.lrange(&key, 0, -1).map_err(|_| SessionStoreError::UnexpectedError)?;
+                for value in values {
+                        let matches = serde_json::from_str::<ActiveSession>(&value)
+                                .map(|session| session.jti == jti)
+                                .unwrap_or(false);
+                        if matches {
+                                conn.lrem(&key, 0, value)
+                                        .map_err(|_| SessionStoreError::UnexpectedError)?;
+                        }
+                }
+
+                Ok(())
+        }
+}
+
+const SESSIONS_PREFIX: &str = "sessions:";
+
+fn get_key(email: &Email) -> String {
+        format!("{}{}", SESSIONS_PREFIX, email.as_ref())
+}
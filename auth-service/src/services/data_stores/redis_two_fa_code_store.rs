@@ -1,19 +1,50 @@
 use async_trait::async_trait;
-use redis::{Connection, TypedCommands};
-use tokio::sync::Mutex;
+use chrono::Utc;
+use redis::{IntegerReplyOrNoOp, TypedCommands};
+use std::sync::Arc;
 
-use crate::domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError};
+use crate::{
+        domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+        services::data_stores::redis_counter::increment_in_window,
+        utils::constants::{TWO_FA_EMAIL_WINDOW_SECONDS, TWO_FA_FAILURE_WINDOW_SECONDS},
+        RedisPool, RedisPooledConnection,
+};
 
 pub struct RedisTwoFACodeStore {
-        conn: Mutex<Connection>,
+        pool: Arc<RedisPool>,
 }
 
 impl RedisTwoFACodeStore {
-        pub fn new(conn: Connection) -> Self {
+        pub fn new(pool: Arc<RedisPool>) -> Self {
                 Self {
-                        conn: Mutex::new(conn),
+                        pool,
                 }
         }
+
+        fn connection(&self) -> Result<RedisPooledConnection, TwoFACodeStoreError> {
+                self.pool.get().map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
+
+        fn get_tuple(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<TwoFATuple, TwoFACodeStoreError> {
+                // 1. Create a new key using the get_key helper function.
+                let key = get_key(email, login_attempt_id);
+
+                // 2. Call the get command on the Redis connection to get the value stored for the key.
+                let value: Option<String> = self
+                        .connection()?
+                        .get(key)
+                        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                // Handle the case where the key doesn't exist
+                let json_string = value.ok_or(TwoFACodeStoreError::NoActiveChallenge)?;
+
+                // Parse the JSON string into a TwoFATuple
+                serde_json::from_str(&json_string).map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
 }
 
 #[async_trait]
@@ -25,21 +56,21 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
                 code: TwoFACode,
         ) -> Result<(), TwoFACodeStoreError> {
                 // 1. Create a new key using the get_key helper function.
-                let key = get_key(&email);
+                let key = get_key(&email, &login_attempt_id);
 
-                // 2. Create a TwoFATuple instance.
-                let tuple =
-                        TwoFATuple(login_attempt_id.as_ref().to_owned(), code.as_ref().to_owned());
+                // 2. Create a TwoFATuple instance, stamped with the time the code was issued.
+                let tuple = TwoFATuple(code.as_ref().to_owned(), Utc::now().timestamp());
 
                 // 3. Use serde_json::to_string to serialize the TwoFATuple instance into a JSON string.
                 let value = serde_json::to_string(&tuple)
                         .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
 
-                // 4. Call the set_ex command on the Redis connection
-                self.conn
-                        .lock()
-                        .await
-                        .set_ex(key, value, TEN_MINUTES_IN_SECONDS)
+                // 4. Call the set_ex command on the Redis connection, starting the new code's
+                //    attempt count from zero
+                let mut conn = self.connection()?;
+                conn.set_ex(key, value, TEN_MINUTES_IN_SECONDS)
+                        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+                conn.del(get_attempts_key(&email, &login_attempt_id))
                         .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
 
                 Ok(())
@@ -48,52 +79,151 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         async fn get_code(
                 &self,
                 email: &Email,
-        ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-                // 1. Create a new key using the get_key helper function.
-                let key = get_key(email);
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<TwoFACode, TwoFACodeStoreError> {
+                let tuple = self.get_tuple(email, login_attempt_id)?;
 
-                // 2. Call the get command on the Redis connection to get the value stored for the key.
-                let value: Option<String> = self
-                        .conn
-                        .lock()
-                        .await
-                        .get(key)
-                        .map_err(|_| TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+                // Parse the 2FA code string into its proper type
+                TwoFACode::parse(tuple.0).map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
 
-                // Handle the case where the key doesn't exist
-                let json_string = value.ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+        async fn get_issued_at(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<i64, TwoFACodeStoreError> {
+                Ok(self.get_tuple(email, login_attempt_id)?.1)
+        }
 
-                // Parse the JSON string into a TwoFATuple
-                let tuple: TwoFATuple = serde_json::from_str(&json_string)
-                        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        async fn remove_code(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<(), TwoFACodeStoreError> {
+                let keys = [
+                        get_key(email, login_attempt_id),
+                        get_attempts_key(email, login_attempt_id),
+                ];
+                self.connection()?.del(&keys).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                Ok(())
+        }
+
+        async fn record_email_sent(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                // A counter that expires one window after the first email
+                let key = get_emails_sent_key(email);
+                let count = increment_in_window(
+                        &mut self.connection()?,
+                        &key,
+                        TWO_FA_EMAIL_WINDOW_SECONDS,
+                )
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
 
-                // Parse the login attempt ID string and 2FA code string into proper types
-                let login_attempt_id = LoginAttemptId::parse(tuple.0)
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                // A counter that expires one window after the first failure
+                let key = get_failures_key(email);
+                let count = increment_in_window(
+                        &mut self.connection()?,
+                        &key,
+                        TWO_FA_FAILURE_WINDOW_SECONDS,
+                )
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
+
+        async fn get_failures(&self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                let count: Option<String> = self
+                        .connection()?
+                        .get(get_failures_key(email))
                         .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
-                let two_fa_code = TwoFACode::parse(tuple.1)
+
+                match count {
+                        Some(count) => {
+                                count.parse().map_err(|_| TwoFACodeStoreError::UnexpectedError)
+                        }
+                        None => Ok(0),
+                }
+        }
+
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, TwoFACodeStoreError> {
+                // The counter's TTL is what is left of the window
+                let ttl = self
+                        .connection()?
+                        .ttl(get_failures_key(email))
                         .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
 
-                Ok((login_attempt_id, two_fa_code))
+                match ttl {
+                        IntegerReplyOrNoOp::IntegerReply(seconds) if seconds > 0 => {
+                                Ok(Some(Utc::now().timestamp() + seconds as i64))
+                        }
+                        _ => Ok(None),
+                }
         }
 
-        async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-                let key = get_key(email);
-                self.conn
-                        .lock()
-                        .await
-                        .del(key)
+        async fn record_attempt(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<u32, TwoFACodeStoreError> {
+                // Expires with the code it counts against
+                let key = get_attempts_key(email, login_attempt_id);
+                let count = increment_in_window(
+                        &mut self.connection()?,
+                        &key,
+                        TEN_MINUTES_IN_SECONDS as i64,
+                )
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                u32::try_from(count).map_err(|_| TwoFACodeStoreError::UnexpectedError)
+        }
+
+        async fn count_pending_codes(&self) -> Result<u64, TwoFACodeStoreError> {
+                // SCAN rather than KEYS, so a large keyspace doesn't block Redis
+                let mut conn = self.connection()?;
+                let keys = conn
+                        .scan_match::<_, String>(format!("{}*", TWO_FA_CODE_PREFIX))
                         .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
 
-                Ok(())
+                let mut count = 0;
+                for key in keys {
+                        key.map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+                        count += 1;
+                }
+                Ok(count)
         }
 }
 
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
+const TWO_FA_EMAILS_SENT_PREFIX: &str = "two_fa_emails_sent:";
+const TWO_FA_ATTEMPTS_PREFIX: &str = "two_fa_attempts:";
+const TWO_FA_FAILURES_PREFIX: &str = "two_fa_failures:";
 
 #[derive(serde::Serialize, serde::Deserialize)]
-struct TwoFATuple(pub String, pub String);
+/// 2FA code and the Unix timestamp it was issued at
+struct TwoFATuple(pub String, pub i64);
+
+/// One key per login attempt, so concurrent logins don't overwrite each other's codes. The
+/// attempt ID is a UUID, so the `:` before it can't be confused with one in the email.
+fn get_key(email: &Email, login_attempt_id: &LoginAttemptId) -> String {
+        format!("{}{}:{}", TWO_FA_CODE_PREFIX, email.as_ref(), login_attempt_id.as_ref())
+}
+
+fn get_emails_sent_key(email: &Email) -> String {
+        format!("{}{}", TWO_FA_EMAILS_SENT_PREFIX, email.as_ref())
+}
+
+fn get_failures_key(email: &Email) -> String {
+        format!("{}{}", TWO_FA_FAILURES_PREFIX, email.as_ref())
+}
 
-fn get_key(email: &Email) -> String {
-        format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref())
+fn get_attempts_key(email: &Email, login_attempt_id: &LoginAttemptId) -> String {
+        format!("{}{}:{}", TWO_FA_ATTEMPTS_PREFIX, email.as_ref(), login_attempt_id.as_ref())
 }
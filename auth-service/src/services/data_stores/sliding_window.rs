@@ -0,0 +1,83 @@
+use std::{
+        borrow::Borrow,
+        collections::{HashMap, VecDeque},
+        hash::Hash,
+};
+
+use chrono::Utc;
+
+/// Times (Unix milliseconds) of recent events per key, counted over a rolling window: an
+/// event stops counting exactly one window after it happened. The in-memory counterpart of
+/// `redis_counter::record_in_sliding_window`.
+#[derive(Debug)]
+pub(super) struct SlidingWindows<K> {
+        window_millis: i64,
+        events: HashMap<K, VecDeque<i64>>,
+}
+
+impl<K: Eq + Hash> SlidingWindows<K> {
+        pub(super) fn new(window_seconds: i64) -> Self {
+                Self {
+                        window_millis: window_seconds * 1_000,
+                        events: HashMap::new(),
+                }
+        }
+
+        /// Record an event for `key` now, returning how many fall within the window
+        pub(super) fn record(&mut self, key: K) -> u32 {
+                self.record_at(key, Utc::now().timestamp_millis())
+        }
+
+        pub(super) fn record_at(&mut self, key: K, at_millis: i64) -> u32 {
+                let window_start = at_millis - self.window_millis;
+                let events = self.events.entry(key).or_default();
+
+                while events.front().is_some_and(|event| *event <= window_start) {
+                        events.pop_front();
+                }
+                events.push_back(at_millis);
+
+                events.len() as u32
+        }
+
+        /// Events for `key` within the window
+        pub(super) fn count<Q>(&self, key: &Q) -> u32
+        where
+                K: Borrow<Q>,
+                Q: Eq + Hash + ?Sized,
+        {
+                self.in_window(key).count() as u32
+        }
+
+        /// When (Unix seconds) the oldest event for `key` within the window leaves it, or
+        /// `None` if there is none
+        pub(super) fn oldest_expires_at<Q>(&self, key: &Q) -> Option<i64>
+        where
+                K: Borrow<Q>,
+                Q: Eq + Hash + ?Sized,
+        {
+                self.in_window(key).next().map(|oldest| (oldest + self.window_millis + 999) / 1_000)
+        }
+
+        pub(super) fn clear<Q>(&mut self, key: &Q)
+        where
+                K: Borrow<Q>,
+                Q: Eq + Hash + ?Sized,
+        {
+                self.events.remove(key);
+        }
+
+        fn in_window<Q>(&self, key: &Q) -> impl Iterator<Item = i64> + '_
+        where
+                K: Borrow<Q>,
+                Q: Eq + Hash + ?Sized,
+        {
+                let window_start = Utc::now().timestamp_millis() - self.window_millis;
+                self.events
+                        .get(key)
+                        .into_iter()
+                        .flatten()
+                        .copied()
+                        .filter(move |event| *event > window_start)
+        }
+}
@@ -0,0 +1,151 @@
+// src/services/failover_email_client.rs
+use async_trait::async_trait;
+
+use crate::{
+        domain::{Email, EmailClient, EmailClientError},
+        EmailClientType,
+};
+
+/// Email client that sends through the first of an ordered list of providers that accepts
+/// the email.
+///
+/// A transient failure or rate limiting moves on to the next provider; a permanent rejection
+/// is returned straight away, since another provider would refuse the same email. When every
+/// provider fails, the last error is returned. Wrap each provider in a `RetryingEmailClient`
+/// to retry it before failing over.
+pub struct FailoverEmailClient {
+        clients: Vec<EmailClientType>,
+}
+
+impl FailoverEmailClient {
+        /// `clients` in the order they are tried, primary first
+        pub fn new(clients: Vec<EmailClientType>) -> Self {
+                assert!(!clients.is_empty(), "FailoverEmailClient needs at least one client");
+                Self {
+                        clients,
+                }
+        }
+}
+
+#[async_trait]
+impl EmailClient for FailoverEmailClient {
+        async fn send_email(
+                &self,
+                recipient: &Email,
+                subject: &str,
+                content: &str,
+        ) -> Result<(), EmailClientError> {
+                let mut result = Ok(());
+                for (provider, client) in self.clients.iter().enumerate() {
+                        result = client.send_email(recipient, subject, content).await;
+                        match &result {
+                                Err(EmailClientError::Permanent(_)) | Ok(()) => return result,
+                                Err(e) => {
+                                        tracing::warn!(error = %e, provider, "Email provider failed")
+                                }
+                        }
+                }
+                result
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use std::{
+                sync::{Arc, Mutex},
+                time::Duration,
+        };
+
+        use super::*;
+        use crate::services::retrying_email_client::RetryingEmailClient;
+
+        /// Fails every send with `error`, or delivers it when there is none, counting sends
+        #[derive(Default)]
+        struct StubEmailClient {
+                error: Option<EmailClientError>,
+                sends: Mutex<u32>,
+                delivered: Mutex<Vec<String>>,
+        }
+
+        impl StubEmailClient {
+                fn failing(error: EmailClientError) -> Arc<Self> {
+                        Arc::new(Self {
+                                error: Some(error),
+                                ..Default::default()
+                        })
+                }
+
+                fn sends(&self) -> u32 {
+                        *self.sends.lock().unwrap()
+                }
+        }
+
+        #[async_trait]
+        impl EmailClient for StubEmailClient {
+                async fn send_email(
+                        &self,
+                        _: &Email,
+                        subject: &str,
+                        _: &str,
+                ) -> Result<(), EmailClientError> {
+                        *self.sends.lock().unwrap() += 1;
+                        match &self.error {
+                                Some(e) => Err(e.clone()),
+                                None => {
+                                        self.delivered.lock().unwrap().push(subject.to_owned());
+                                        Ok(())
+                                }
+                        }
+                }
+        }
+
+        async fn send(client: &FailoverEmailClient) -> Result<(), EmailClientError> {
+                let recipient = Email::parse("test@example.com").unwrap();
+                client.send_email(&recipient, "subject", "content").await
+        }
+
+        #[tokio::test]
+        async fn test_transient_failure_fails_over_to_the_backup() {
+                let primary =
+                        StubEmailClient::failing(EmailClientError::Transient("timeout".to_owned()));
+                let backup = Arc::new(StubEmailClient::default());
+                let client = FailoverEmailClient::new(vec![
+                        Arc::new(RetryingEmailClient::new(primary.clone(), 2, Duration::ZERO)),
+                        Arc::new(RetryingEmailClient::new(backup.clone(), 2, Duration::ZERO)),
+                ]);
+
+                assert_eq!(send(&client).await, Ok(()));
+                // The primary is retried before failing over
+                assert_eq!(primary.sends(), 2);
+                assert_eq!(*backup.delivered.lock().unwrap(), vec!["subject".to_owned()]);
+        }
+
+        #[tokio::test]
+        async fn test_rate_limit_fails_over_but_permanent_rejection_does_not() {
+                let primary = StubEmailClient::failing(EmailClientError::RateLimited);
+                let backup = Arc::new(StubEmailClient::default());
+                let client = FailoverEmailClient::new(vec![primary, backup.clone()]);
+                assert_eq!(send(&client).await, Ok(()));
+                assert_eq!(backup.sends(), 1);
+
+                let rejection = EmailClientError::Permanent("no such mailbox".to_owned());
+                let primary = StubEmailClient::failing(rejection.clone());
+                let backup = Arc::new(StubEmailClient::default());
+                let client = FailoverEmailClient::new(vec![primary, backup.clone()]);
+                assert_eq!(send(&client).await, Err(rejection));
+                assert_eq!(backup.sends(), 0);
+        }
+
+        #[tokio::test]
+        async fn test_last_error_is_returned_when_every_provider_fails() {
+                let client = FailoverEmailClient::new(vec![
+                        StubEmailClient::failing(EmailClientError::RateLimited),
+                        StubEmailClient::failing(EmailClientError::Transient("down".to_owned())),
+                ]);
+
+                assert_eq!(
+                        send(&client).await,
+                        Err(EmailClientError::Transient("down".to_owned()))
+                );
+        }
+}
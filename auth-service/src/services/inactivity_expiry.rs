@@ -0,0 +1,64 @@
+// src/services/inactivity_expiry.rs
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::{
+        domain::UserStoreError,
+        utils::constants::{INACTIVITY_EXPIRY_BATCH_SIZE, INACTIVITY_EXPIRY_INTERVAL_SECONDS},
+        UserStoreType,
+};
+
+/// Spawn the daily job that soft-deletes accounts with no login in the last `expiry_days`.
+///
+/// The job stops when `shutdown` receives a value or its sender is dropped.
+pub fn spawn_inactivity_expiry_job(
+        user_store: UserStoreType,
+        expiry_days: u32,
+        mut shutdown: watch::Receiver<()>,
+) -> JoinHandle<()> {
+        tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(
+                        INACTIVITY_EXPIRY_INTERVAL_SECONDS,
+                ));
+
+                loop {
+                        tokio::select! {
+                                _ = interval.tick() => {
+                                        match expire_inactive_users(&user_store, expiry_days).await {
+                                                Ok(expired) => tracing::info!(expired, "Expired inactive accounts"),
+                                                Err(e) => tracing::error!(error = ?e, "Inactivity expiry run failed"),
+                                        }
+                                }
+                                _ = shutdown.changed() => break,
+                        }
+                }
+
+                tracing::info!("Inactivity expiry job stopped");
+        })
+}
+
+/// Run a single expiry pass, returning how many accounts were soft-deleted
+#[tracing::instrument(name = "Expire inactive users", skip(user_store))]
+pub async fn expire_inactive_users(
+        user_store: &UserStoreType,
+        expiry_days: u32,
+) -> Result<u64, UserStoreError> {
+        let cutoff = Utc::now() - chrono::Duration::days(expiry_days.into());
+
+        // Expire in bounded batches, taking the store lock for one batch at a time, so a large
+        // backlog never holds up logins or a long-running lock over the users table
+        let mut expired = 0;
+        loop {
+                let batch = user_store
+                        .write()
+                        .await
+                        .expire_inactive(cutoff, INACTIVITY_EXPIRY_BATCH_SIZE)
+                        .await?;
+                expired += batch;
+                if batch < INACTIVITY_EXPIRY_BATCH_SIZE {
+                        return Ok(expired);
+                }
+        }
+}
@@ -1 +1,7 @@
+pub mod captcha_verifier;
 pub mod data_stores;
+pub mod failover_email_client;
+pub mod inactivity_expiry;
+pub mod mx_resolver;
+pub mod retrying_email_client;
+pub mod webhook_notifier;
@@ -0,0 +1,60 @@
+// src/services/mx_resolver.rs
+use async_trait::async_trait;
+use hickory_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+
+use crate::{
+        domain::{EmailDomain, MxResolver},
+        utils::constants::env::VERIFY_EMAIL_MX_ENV_VAR,
+};
+
+/// Looks up MX records with the system's DNS configuration (`/etc/resolv.conf`)
+pub struct HickoryMxResolver {
+        resolver: TokioAsyncResolver,
+}
+
+impl HickoryMxResolver {
+        pub fn new(resolver: TokioAsyncResolver) -> Self {
+                Self {
+                        resolver,
+                }
+        }
+
+        /// Build a resolver when `VERIFY_EMAIL_MX` is true, or `None` to skip the check, as
+        /// tests and offline development should. Panics when enabled but the system DNS
+        /// configuration cannot be read.
+        pub fn from_env() -> Option<Self> {
+                let enabled = std::env::var(VERIFY_EMAIL_MX_ENV_VAR)
+                        .map(|v| v.trim().parse::<bool>())
+                        .unwrap_or(Ok(false))
+                        .unwrap_or_else(|_| {
+                                panic!("{} has an invalid value", VERIFY_EMAIL_MX_ENV_VAR)
+                        });
+                if !enabled {
+                        return None;
+                }
+
+                let resolver = TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|e| {
+                        panic!(
+                                "{} is true but DNS is not configured: {}",
+                                VERIFY_EMAIL_MX_ENV_VAR, e
+                        )
+                });
+
+                Some(Self::new(resolver))
+        }
+}
+
+#[async_trait]
+impl MxResolver for HickoryMxResolver {
+        async fn has_mx_records(&self, domain: &EmailDomain) -> Result<bool, String> {
+                // The trailing dot makes the name fully qualified, so no search domain is tried
+                match self.resolver.mx_lookup(format!("{}.", domain)).await {
+                        Ok(lookup) => Ok(lookup.iter().next().is_some()),
+                        // Also covers a domain that does not exist (NXDOMAIN)
+                        Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                                Ok(false)
+                        }
+                        Err(e) => Err(format!("MX lookup failed: {e}")),
+                }
+        }
+}
@@ -0,0 +1,123 @@
+// src/services/retrying_email_client.rs
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{
+        domain::{Email, EmailClient, EmailClientError},
+        EmailClientType,
+};
+
+/// Email client that retries transient failures of the client it wraps.
+///
+/// Up to `max_attempts` sends are made, `backoff` apart and growing linearly. Permanent
+/// rejections and rate limiting are returned on the first try, since sending again would
+/// fail the same way.
+pub struct RetryingEmailClient {
+        inner: EmailClientType,
+        max_attempts: u32,
+        backoff: Duration,
+}
+
+impl RetryingEmailClient {
+        pub fn new(inner: EmailClientType, max_attempts: u32, backoff: Duration) -> Self {
+                Self {
+                        inner,
+                        max_attempts: max_attempts.max(1),
+                        backoff,
+                }
+        }
+}
+
+#[async_trait]
+impl EmailClient for RetryingEmailClient {
+        async fn send_email(
+                &self,
+                recipient: &Email,
+                subject: &str,
+                content: &str,
+        ) -> Result<(), EmailClientError> {
+                let mut attempt = 1;
+                loop {
+                        match self.inner.send_email(recipient, subject, content).await {
+                                Err(e) if e.is_retryable() && attempt < self.max_attempts => {
+                                        tracing::warn!(error = %e, attempt, "Retrying email");
+                                        tokio::time::sleep(self.backoff * attempt).await;
+                                        attempt += 1;
+                                }
+                                result => return result,
+                        }
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use std::sync::{Arc, Mutex};
+
+        use super::*;
+
+        /// Fails with each scripted error in turn, then succeeds, counting every send
+        #[derive(Default)]
+        struct ScriptedEmailClient {
+                failures: Mutex<Vec<EmailClientError>>,
+                sends: Mutex<u32>,
+        }
+
+        #[async_trait]
+        impl EmailClient for ScriptedEmailClient {
+                async fn send_email(
+                        &self,
+                        _: &Email,
+                        _: &str,
+                        _: &str,
+                ) -> Result<(), EmailClientError> {
+                        *self.sends.lock().unwrap() += 1;
+                        match self.failures.lock().unwrap().pop() {
+                                Some(e) => Err(e),
+                                None => Ok(()),
+                        }
+                }
+        }
+
+        async fn send_through_retries(
+                failures: Vec<EmailClientError>,
+        ) -> (Result<(), EmailClientError>, u32) {
+                let inner = Arc::new(ScriptedEmailClient {
+                        failures: Mutex::new(failures),
+                        ..Default::default()
+                });
+                let client = RetryingEmailClient::new(inner.clone(), 3, Duration::ZERO);
+                let recipient = Email::parse("test@example.com").unwrap();
+
+                let result = client.send_email(&recipient, "subject", "content").await;
+                let sends = *inner.sends.lock().unwrap();
+                (result, sends)
+        }
+
+        #[tokio::test]
+        async fn test_transient_failures_are_retried() {
+                let transient = EmailClientError::Transient("timeout".to_owned());
+
+                let (result, sends) = send_through_retries(vec![transient.clone()]).await;
+                assert_eq!(result, Ok(()));
+                assert_eq!(sends, 2);
+
+                // Gives up once every attempt has failed
+                let (result, sends) = send_through_retries(vec![transient.clone(); 3]).await;
+                assert_eq!(result, Err(transient));
+                assert_eq!(sends, 3);
+        }
+
+        #[tokio::test]
+        async fn test_permanent_failures_and_rate_limits_are_not_retried() {
+                for error in [
+                        EmailClientError::Permanent("no such mailbox".to_owned()),
+                        EmailClientError::RateLimited,
+                ] {
+                        let (result, sends) = send_through_retries(vec![error.clone()]).await;
+                        assert_eq!(result, Err(error));
+                        assert_eq!(sends, 1);
+                }
+        }
+}
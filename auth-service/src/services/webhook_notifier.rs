@@ -0,0 +1,167 @@
+// src/services/webhook_notifier.rs
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, Client, Url};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+        domain::Email,
+        utils::constants::{
+                env::{WEBHOOK_SECRET_ENV_VAR, WEBHOOK_URL_ENV_VAR},
+                WEBHOOK_SIGNATURE_HEADER, WEBHOOK_TIMEOUT_SECONDS,
+        },
+};
+
+/// Account events reported to the external webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+        #[serde(rename = "user.created")]
+        UserCreated,
+        #[serde(rename = "user.logged_in")]
+        UserLoggedIn,
+        #[serde(rename = "user.password_changed")]
+        PasswordChanged,
+}
+
+/// JSON body POSTed to the webhook; the signature header covers these exact bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+        pub event: WebhookEvent,
+        pub email: String,
+        /// Unix timestamp (seconds) of the event, so receivers can reject stale replays
+        pub timestamp: i64,
+}
+
+/// Posts signed account events to `WEBHOOK_URL`.
+///
+/// Delivery is fire-and-forget: each event is sent on its own task and failures are only
+/// logged, so a slow or unavailable receiver never delays an auth response.
+pub struct WebhookNotifier {
+        client: Client,
+        url: Url,
+        secret: String,
+}
+
+impl WebhookNotifier {
+        /// Fails when the HTTP client cannot be built, e.g. without a usable TLS backend
+        pub fn new(url: Url, secret: impl Into<String>) -> Result<Self, reqwest::Error> {
+                let client = Client::builder()
+                        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECONDS))
+                        .build()?;
+
+                Ok(Self {
+                        client,
+                        url,
+                        secret: secret.into(),
+                })
+        }
+
+        /// Build a notifier from `WEBHOOK_URL` and `WEBHOOK_SECRET`, or `None` when no URL is
+        /// configured. Panics on an invalid URL or a URL without a secret, so a half-configured
+        /// deployment fails at startup instead of sending unsigned events, and returns the error
+        /// of an HTTP client that cannot be built.
+        pub fn from_env() -> Result<Option<Self>, reqwest::Error> {
+                let Some(url) =
+                        std::env::var(WEBHOOK_URL_ENV_VAR).ok().filter(|v| !v.trim().is_empty())
+                else {
+                        return Ok(None);
+                };
+                let url = Url::parse(url.trim())
+                        .unwrap_or_else(|_| panic!("{} has an invalid value", WEBHOOK_URL_ENV_VAR));
+
+                let secret = std::env::var(WEBHOOK_SECRET_ENV_VAR)
+                        .ok()
+                        .filter(|v| !v.is_empty())
+                        .unwrap_or_else(|| {
+                                panic!(
+                                        "{} must be set when {} is set",
+                                        WEBHOOK_SECRET_ENV_VAR, WEBHOOK_URL_ENV_VAR
+                                )
+                        });
+
+                Self::new(url, secret).map(Some)
+        }
+
+        /// Send `event` for `email` in the background
+        pub fn notify(&self, event: WebhookEvent, email: &Email) {
+                let payload = WebhookPayload {
+                        event,
+                        email: email.as_str().to_owned(),
+                        timestamp: Utc::now().timestamp(),
+                };
+                let body = match serde_json::to_vec(&payload) {
+                        Ok(body) => body,
+                        Err(e) => {
+                                tracing::warn!(?event, error = %e, "Failed to serialize webhook payload");
+                                return;
+                        }
+                };
+
+                let request = self
+                        .client
+                        .post(self.url.clone())
+                        .header(CONTENT_TYPE, "application/json")
+                        .header(WEBHOOK_SIGNATURE_HEADER, sign_payload(&self.secret, &body))
+                        .body(body);
+
+                tokio::spawn(async move {
+                        let result = request.send().await.and_then(|res| res.error_for_status());
+                        if let Err(e) = result {
+                                tracing::warn!(?event, error = %e, "Webhook delivery failed");
+                        }
+                });
+        }
+}
+
+impl std::fmt::Debug for WebhookNotifier {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the signing secret
+                f.debug_struct("WebhookNotifier")
+                        .field("url", &self.url.as_str())
+                        .field("secret", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+/// Signature header value for `body`: `sha256=` followed by the hex HMAC-SHA256 of the body
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+        // HMAC accepts keys of any length, so this cannot fail
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC can take a key of any size");
+        mac.update(body);
+
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_sign_payload_matches_known_hmac() {
+                // RFC 4231 test case 2
+                assert_eq!(
+                        sign_payload("Jefe", b"what do ya want for nothing?"),
+                        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+                );
+        }
+
+        #[test]
+        fn test_event_names_on_the_wire() {
+                let payload = WebhookPayload {
+                        event: WebhookEvent::UserCreated,
+                        email: "user@example.com".to_owned(),
+                        timestamp: 0,
+                };
+                let json = serde_json::to_value(&payload).unwrap();
+
+                assert_eq!(json["event"], "user.created");
+                assert_eq!(
+                        serde_json::to_value(WebhookEvent::PasswordChanged).unwrap(),
+                        "user.password_changed"
+                );
+        }
+}
@@ -1,24 +1,42 @@
 use std::sync::Arc;
 
 // src/utils/auth.rs
-use super::constants::{env::JWT_SECRET_ENV_VAR, JWT_COOKIE_NAME, TOKEN_TTL_SECONDS};
-use crate::domain::{BannedTokenStore, Email};
+use super::{
+        constants::{
+                JWT_AUDIENCE, JWT_COOKIE_NAME, JWT_ISSUER, JWT_LEEWAY_SECONDS, TOKEN_TTL_SECONDS,
+        },
+        key_ring::KeyRing,
+};
+use crate::domain::{BannedTokenStore, Email, HashedPassword, LoginAttemptId};
 
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
+use jsonwebtoken::{decode, decode_header, encode, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
 /// Create cookie with a new JWT auth token
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>, GenerateTokenError> {
-        let token = generate_auth_token(email)?;
-        Ok(create_auth_cookie(token))
+pub fn generate_auth_cookie(
+        email: &Email,
+        key_ring: &KeyRing,
+) -> Result<Cookie<'static>, GenerateTokenError> {
+        generate_auth_cookie_with_claims(email, key_ring).map(|(cookie, _)| cookie)
+}
+
+/// Create cookie with a new JWT auth token, along with the token's claims so the caller
+/// can report the expiry to the client and track the session by `jti`
+pub fn generate_auth_cookie_with_claims(
+        email: &Email,
+        key_ring: &KeyRing,
+) -> Result<(Cookie<'static>, Claims), GenerateTokenError> {
+        let (token, claims) = generate_auth_token_with_claims(email, key_ring)?;
+        Ok((create_auth_cookie(token), claims))
 }
 
 /// Create cookie and set the value to the passed-in token string
 pub fn create_auth_cookie(token: String) -> Cookie<'static> {
-        let cookie = Cookie::build((JWT_COOKIE_NAME, token))
+        let cookie = Cookie::build((JWT_COOKIE_NAME.as_str(), token))
                 .path("/") // apply cookie to all URLs on the server
                 .http_only(true) // prevent JavaScript from accessing the cookie
                 .same_site(SameSite::Lax) // send cookie with "same-site" requests, and with "cross-site" top-level navigations
@@ -34,7 +52,18 @@ pub enum GenerateTokenError {
 }
 
 /// Create JWT auth token
-pub fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
+pub fn generate_auth_token(
+        email: &Email,
+        key_ring: &KeyRing,
+) -> Result<String, GenerateTokenError> {
+        generate_auth_token_with_claims(email, key_ring).map(|(token, _)| token)
+}
+
+/// Create JWT auth token, returning it together with its claims
+fn generate_auth_token_with_claims(
+        email: &Email,
+        key_ring: &KeyRing,
+) -> Result<(String, Claims), GenerateTokenError> {
         let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
                 .ok_or(GenerateTokenError::UnexpectedError)?;
 
@@ -49,18 +78,131 @@ pub fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError>
 
         let sub = email.as_ref().to_owned();
 
+        /// Unique per token, so one session can be told apart from another
+        let jti = Uuid::new_v4().to_string();
+
         let claims = Claims {
                 sub,
                 exp,
+                jti,
+                iss: JWT_ISSUER.to_owned(),
+                aud: JWT_AUDIENCE.to_owned(),
         };
 
-        create_token(&claims).map_err(GenerateTokenError::TokenError)
+        let token = create_token(&claims, key_ring).map_err(GenerateTokenError::TokenError)?;
+
+        Ok((token, claims))
 }
 
-/// Check if JWT auth token is valid by decoding it against the JWT secret
+/// `purpose` claim of the token handed out with a 206 login response
+pub const TWO_FA_CHALLENGE_PURPOSE: &str = "2fa";
+
+/// Create the short-lived token that stands for a pending 2FA challenge. It binds the login
+/// attempt to the account and is signed like an auth token, so `/verify-2fa` can trust the
+/// attempt id it carries.
+pub fn generate_challenge_token(
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        ttl_seconds: u64,
+        key_ring: &KeyRing,
+) -> Result<String, GenerateTokenError> {
+        let claims = ChallengeClaims {
+                sub: email.as_ref().to_owned(),
+                exp: expiry_in(ttl_seconds)?,
+                purpose: TWO_FA_CHALLENGE_PURPOSE.to_owned(),
+                login_attempt_id: login_attempt_id.as_ref().to_owned(),
+                iss: JWT_ISSUER.to_owned(),
+                aud: JWT_AUDIENCE.to_owned(),
+        };
+
+        sign_with_current_key(&claims, key_ring)
+}
+
+/// Decode a 2FA challenge token, checking it like an auth token and that it was issued for
+/// 2FA. An auth token is rejected here, and a challenge token is rejected by
+/// `validate_token` since it has no `jti`.
+pub fn validate_challenge_token(
+        token: &str,
+        key_ring: &KeyRing,
+) -> Result<ChallengeClaims, jsonwebtoken::errors::Error> {
+        let claims = decode_with_key_ring::<ChallengeClaims>(token, key_ring, &token_validation())?
+                .claims;
+        if claims.purpose != TWO_FA_CHALLENGE_PURPOSE {
+                return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+        }
+
+        Ok(claims)
+}
+
+/// `purpose` claim of the token handed out when a login must first choose a new password
+pub const PASSWORD_CHANGE_PURPOSE: &str = "password_change";
+
+/// Create the short-lived token a login gets instead of a session when the account must
+/// choose a new password; `/login/change-password` accepts it in place of a session. It
+/// carries the fingerprint of `password`, the temporary password it was issued for, so a
+/// later reset voids it.
+pub fn generate_password_change_token(
+        email: &Email,
+        password: &HashedPassword,
+        ttl_seconds: u64,
+        key_ring: &KeyRing,
+) -> Result<String, GenerateTokenError> {
+        let claims = PasswordChangeClaims {
+                sub: email.as_ref().to_owned(),
+                exp: expiry_in(ttl_seconds)?,
+                purpose: PASSWORD_CHANGE_PURPOSE.to_owned(),
+                password_fingerprint: password.fingerprint(),
+                iss: JWT_ISSUER.to_owned(),
+                aud: JWT_AUDIENCE.to_owned(),
+        };
+
+        sign_with_current_key(&claims, key_ring)
+}
+
+/// Decode a password change token, checking it like an auth token and that it was issued
+/// for a password change
+pub fn validate_password_change_token(
+        token: &str,
+        key_ring: &KeyRing,
+) -> Result<PasswordChangeClaims, jsonwebtoken::errors::Error> {
+        let claims =
+                decode_with_key_ring::<PasswordChangeClaims>(token, key_ring, &token_validation())?
+                        .claims;
+        if claims.purpose != PASSWORD_CHANGE_PURPOSE {
+                return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+        }
+
+        Ok(claims)
+}
+
+/// `exp` claim of a token that lives for `ttl_seconds` from now
+fn expiry_in(ttl_seconds: u64) -> Result<usize, GenerateTokenError> {
+        let ttl = i64::try_from(ttl_seconds).map_err(|_| GenerateTokenError::UnexpectedError)?;
+        Utc::now()
+                .timestamp()
+                .checked_add(ttl)
+                .and_then(|exp| usize::try_from(exp).ok())
+                .ok_or(GenerateTokenError::UnexpectedError)
+}
+
+/// Sign `claims` with the current key, naming it in the `kid` header
+fn sign_with_current_key<T: Serialize>(
+        claims: &T,
+        key_ring: &KeyRing,
+) -> Result<String, GenerateTokenError> {
+        let key = key_ring.current();
+        let header = Header {
+                kid: Some(key.kid().to_owned()),
+                ..Header::default()
+        };
+        encode(&header, claims, &key.encoding_key()).map_err(GenerateTokenError::TokenError)
+}
+
+/// Check if JWT auth token is valid by decoding it against the key named by its `kid`
 pub async fn validate_token(
         banned_token_store: &Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>>,
-        token: &str,
+        key_ring: &KeyRing,
+        token: &Token,
 ) -> Result<Claims, jsonwebtoken::errors::Error> {
         let is_banned = {
                 let store = banned_token_store.read().await;
@@ -74,43 +216,213 @@ pub async fn validate_token(
                 ));
         }
 
-        decode::<Claims>(
-                token,
-                &DecodingKey::from_secret(JWT_SECRET_ENV_VAR.as_bytes()),
-                &Validation::default(),
-        )
-        .map(|data| data.claims)
+        decode_with_key_ring::<Claims>(token.as_str(), key_ring, &token_validation())
+                .map(|data| data.claims)
 }
 
-/// Create JWT auth token by encoding claims using the JWT secret
-fn create_token(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
-        encode(
-                &jsonwebtoken::Header::default(),
-                &claims,
-                &EncodingKey::from_secret(JWT_SECRET_ENV_VAR.as_bytes()),
-        )
+/// Decode `token` with the ring's key for its `kid`. A key no longer on the ring fails the
+/// same way a wrong signature does.
+fn decode_with_key_ring<T: serde::de::DeserializeOwned>(
+        token: &str,
+        key_ring: &KeyRing,
+        validation: &Validation,
+) -> Result<TokenData<T>, jsonwebtoken::errors::Error> {
+        let header = decode_header(token)?;
+        let key = key_ring.find(header.kid.as_deref()).ok_or_else(|| {
+                jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidSignature)
+        })?;
+
+        decode::<T>(token, &key.decoding_key(), validation)
+}
+
+/// Signature and expiry checks, plus `iss`/`aud` matching `JWT_ISSUER`/`JWT_AUDIENCE`, so a
+/// token minted by another service sharing the secret is not accepted here. `exp` and `nbf`
+/// (when present) are checked with `JWT_LEEWAY_SECONDS` of leeway for clock skew.
+fn token_validation() -> Validation {
+        let mut validation = Validation::default();
+        validation.leeway = *JWT_LEEWAY_SECONDS;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[JWT_ISSUER.as_str()]);
+        validation.set_audience(&[JWT_AUDIENCE.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        validation
+}
+
+/// Whether the token's signature matches a key on the ring, ignoring expiry and the banned
+/// token store
+pub fn has_valid_signature(token: &str, key_ring: &KeyRing) -> bool {
+        let mut validation = Validation::default();
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        validation.required_spec_claims.clear();
+
+        decode_with_key_ring::<serde_json::Value>(token, key_ring, &validation).is_ok()
+}
+
+/// Create JWT auth token by encoding claims with the ring's current key
+pub fn create_token(
+        claims: &Claims,
+        key_ring: &KeyRing,
+) -> Result<String, jsonwebtoken::errors::Error> {
+        let key = key_ring.current();
+        let header = Header {
+                kid: Some(key.kid().to_owned()),
+                ..Header::default()
+        };
+
+        encode(&header, &claims, &key.encoding_key())
+}
+
+/// A string shaped like a JWT: three non-empty, dot-separated base64url segments.
+///
+/// Parsing only checks the shape; whether the token is signed, unexpired and not banned is
+/// up to `validate_token`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Token(String);
+
+impl Token {
+        pub fn parse(token: impl Into<String>) -> Result<Self, TokenError> {
+                let token = token.into();
+                let segments: Vec<&str> = token.split('.').collect();
+                let is_base64url = |segment: &&str| {
+                        !segment.is_empty()
+                                && segment.bytes().all(|b| {
+                                        b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+                                })
+                };
+
+                if segments.len() == 3 && segments.iter().all(is_base64url) {
+                        Ok(Self(token))
+                } else {
+                        Err(TokenError::InvalidFormat)
+                }
+        }
+
+        pub fn as_str(&self) -> &str {
+                &self.0
+        }
+}
+
+impl AsRef<str> for Token {
+        fn as_ref(&self) -> &str {
+                &self.0
+        }
+}
+
+impl TryFrom<String> for Token {
+        type Error = TokenError;
+
+        fn try_from(token: String) -> Result<Self, Self::Error> {
+                Self::parse(token)
+        }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+        InvalidFormat,
+}
+
+impl std::fmt::Display for TokenError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                        TokenError::InvalidFormat => write!(f, "Token is not a JWT"),
+                }
+        }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
         pub sub: String,
         pub exp: usize,
+        /// Unique token id
+        pub jti: String,
+        /// Service that minted the token (`JWT_ISSUER`)
+        pub iss: String,
+        /// Service the token is meant for (`JWT_AUDIENCE`)
+        pub aud: String,
+}
+
+/// Claims of a 2FA challenge token; see `generate_challenge_token`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+        pub sub: String,
+        pub exp: usize,
+        /// Always `TWO_FA_CHALLENGE_PURPOSE`
+        pub purpose: String,
+        #[serde(rename = "loginAttemptId")]
+        pub login_attempt_id: String,
+        pub iss: String,
+        pub aud: String,
+}
+
+/// Claims of a password change token; see `generate_password_change_token`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordChangeClaims {
+        pub sub: String,
+        pub exp: usize,
+        /// Always `PASSWORD_CHANGE_PURPOSE`
+        pub purpose: String,
+        /// `HashedPassword::fingerprint` of the password the token was issued for
+        #[serde(rename = "passwordFingerprint")]
+        pub password_fingerprint: String,
+        pub iss: String,
+        pub aud: String,
 }
 
 #[cfg(test)]
 mod tests {
         use super::*;
-        use crate::services::data_stores::HashsetBannedTokenStore;
+        use crate::{
+                services::data_stores::HashsetBannedTokenStore,
+                utils::constants::{env::JWT_SECRET_ENV_VAR, JWT_SECRET},
+        };
+        use jsonwebtoken::{errors::ErrorKind, EncodingKey};
 
         fn create_banned_token_store() -> Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>> {
                 Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::new())))
         }
 
+        fn auth_token(email: &Email, key_ring: &KeyRing) -> Token {
+                Token::parse(generate_auth_token(email, key_ring).unwrap()).unwrap()
+        }
+
+        #[test]
+        fn test_token_parse_accepts_jwt_shapes() {
+                let email = Email::parse("test@example.com").unwrap();
+                let generated = generate_auth_token(&email, &KeyRing::default()).unwrap();
+                assert_eq!(Token::parse(generated.clone()).unwrap().as_str(), generated);
+                assert!(Token::parse("eyJhbGciOiJIUzI1NiJ9.e30.abc-DEF_123").is_ok());
+        }
+
+        #[test]
+        fn test_token_parse_rejects_non_tokens() {
+                for token in [
+                        "",
+                        "invalid_token",
+                        "only.two",
+                        "one.too.many.segments",
+                        "empty..segment",
+                        "padded.with.sig==",
+                        "has a.space.in.it",
+                        "plus+slash/.are.not-base64url",
+                ] {
+                        assert_eq!(Token::parse(token), Err(TokenError::InvalidFormat), "{token}");
+                }
+        }
+
+        #[test]
+        fn test_token_deserializes_only_from_a_jwt_shape() {
+                let token: Token = serde_json::from_str(r#""a.b.c""#).unwrap();
+                assert_eq!(serde_json::to_string(&token).unwrap(), r#""a.b.c""#);
+                assert!(serde_json::from_str::<Token>(r#""not-a-token""#).is_err());
+        }
+
         #[tokio::test]
         async fn test_generate_auth_cookie() {
                 let email = Email::parse("test@example.com").unwrap();
-                let cookie = generate_auth_cookie(&email).unwrap();
-                assert_eq!(cookie.name(), JWT_COOKIE_NAME);
+                let cookie = generate_auth_cookie(&email, &KeyRing::default()).unwrap();
+                assert_eq!(cookie.name(), JWT_COOKIE_NAME.as_str());
                 assert_eq!(cookie.value().split('.').count(), 3);
                 assert_eq!(cookie.path(), Some("/"));
                 assert_eq!(cookie.http_only(), Some(true));
@@ -121,7 +433,7 @@ mod tests {
         async fn test_create_auth_cookie() {
                 let token = "test_token".to_owned();
                 let cookie = create_auth_cookie(token.clone());
-                assert_eq!(cookie.name(), JWT_COOKIE_NAME);
+                assert_eq!(cookie.name(), JWT_COOKIE_NAME.as_str());
                 assert_eq!(cookie.value(), token);
                 assert_eq!(cookie.path(), Some("/"));
                 assert_eq!(cookie.http_only(), Some(true));
@@ -131,16 +443,40 @@ mod tests {
         #[tokio::test]
         async fn test_generate_auth_token() {
                 let email = Email::parse("test@example.com").unwrap();
-                let result = generate_auth_token(&email).unwrap();
+                let result = generate_auth_token(&email, &KeyRing::default()).unwrap();
                 assert_eq!(result.split('.').count(), 3);
         }
 
+        #[tokio::test]
+        async fn test_each_token_gets_a_unique_jti() {
+                let banned_token_store = create_banned_token_store();
+                let email = Email::parse("test@example.com").unwrap();
+
+                let (first, first_claims) =
+                        generate_auth_token_with_claims(&email, &KeyRing::default()).unwrap();
+                let (second, _) =
+                        generate_auth_token_with_claims(&email, &KeyRing::default()).unwrap();
+
+                let (first, second) = (Token::parse(first).unwrap(), Token::parse(second).unwrap());
+
+                let first = validate_token(&banned_token_store, &KeyRing::default(), &first)
+                        .await
+                        .unwrap();
+                let second = validate_token(&banned_token_store, &KeyRing::default(), &second)
+                        .await
+                        .unwrap();
+                assert_eq!(first.jti, first_claims.jti);
+                assert_ne!(first.jti, second.jti);
+        }
+
         #[tokio::test]
         async fn test_validate_token_with_valid_token() {
                 let banned_token_store = create_banned_token_store();
                 let email = Email::parse("test@example.com").unwrap();
-                let token = generate_auth_token(&email).unwrap();
-                let result = validate_token(&banned_token_store, &token).await.unwrap();
+                let token = auth_token(&email, &KeyRing::default());
+                let result = validate_token(&banned_token_store, &KeyRing::default(), &token)
+                        .await
+                        .unwrap();
                 assert_eq!(result.sub, "test@example.com");
 
                 let exp = Utc::now()
@@ -156,8 +492,8 @@ mod tests {
         #[tokio::test]
         async fn test_validate_token_with_invalid_token() {
                 let banned_token_store = create_banned_token_store();
-                let token = "invalid_token".to_owned();
-                let result = validate_token(&banned_token_store, &token).await;
+                let token = Token::parse("not.a.jwt").unwrap();
+                let result = validate_token(&banned_token_store, &KeyRing::default(), &token).await;
                 assert!(result.is_err());
         }
 
@@ -165,7 +501,7 @@ mod tests {
         async fn test_validate_token_with_banned_token() {
                 let banned_token_store = create_banned_token_store();
                 let email = Email::parse("test@example.com").unwrap();
-                let token = generate_auth_token(&email).unwrap();
+                let token = auth_token(&email, &KeyRing::default());
 
                 banned_token_store
                         .write()
@@ -174,10 +510,234 @@ mod tests {
                         .await
                         .expect("token should be banned for test");
 
-                let result = validate_token(&banned_token_store, &token).await;
+                let result = validate_token(&banned_token_store, &KeyRing::default(), &token).await;
                 assert!(result.is_err());
 
                 let error = result.expect_err("banned token must fail validation");
                 assert!(matches!(error.kind(), &jsonwebtoken::errors::ErrorKind::InvalidToken));
         }
+
+        fn claims_for(email: &str) -> Claims {
+                Claims {
+                        sub: email.to_owned(),
+                        exp: (Utc::now().timestamp() + TOKEN_TTL_SECONDS) as usize,
+                        jti: Uuid::new_v4().to_string(),
+                        iss: JWT_ISSUER.to_owned(),
+                        aud: JWT_AUDIENCE.to_owned(),
+                }
+        }
+
+        #[tokio::test]
+        async fn test_validate_token_checks_issuer_and_audience() {
+                let banned_token_store = create_banned_token_store();
+                let email = Email::parse("test@example.com").unwrap();
+                let claims = validate_token(
+                        &banned_token_store,
+                        &KeyRing::default(),
+                        &auth_token(&email, &KeyRing::default()),
+                )
+                .await
+                .unwrap();
+                assert_eq!(claims.iss, *JWT_ISSUER);
+                assert_eq!(claims.aud, *JWT_AUDIENCE);
+
+                let wrong_audience = Claims {
+                        aud: "another-service".to_owned(),
+                        ..claims_for("test@example.com")
+                };
+                let wrong_issuer = Claims {
+                        iss: "another-service".to_owned(),
+                        ..claims_for("test@example.com")
+                };
+                for claims in [wrong_audience, wrong_issuer] {
+                        let token =
+                                Token::parse(create_token(&claims, &KeyRing::default()).unwrap())
+                                        .unwrap();
+                        assert!(validate_token(&banned_token_store, &KeyRing::default(), &token)
+                                .await
+                                .is_err());
+                }
+        }
+
+        #[tokio::test]
+        async fn test_validate_token_requires_issuer_and_audience() {
+                let banned_token_store = create_banned_token_store();
+                let claims = claims_for("test@example.com");
+                let legacy = serde_json::json!({
+                        "sub": claims.sub,
+                        "exp": claims.exp,
+                        "jti": claims.jti,
+                });
+                let token = encode(
+                        &jsonwebtoken::Header::default(),
+                        &legacy,
+                        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+                )
+                .unwrap();
+                let token = Token::parse(token).unwrap();
+
+                assert!(validate_token(&banned_token_store, &KeyRing::default(), &token)
+                        .await
+                        .is_err());
+        }
+
+        #[tokio::test]
+        async fn test_validate_token_rejects_a_token_signed_with_the_env_var_name() {
+                let banned_token_store = create_banned_token_store();
+                let token = encode(
+                        &jsonwebtoken::Header::default(),
+                        &claims_for("test@example.com"),
+                        &EncodingKey::from_secret(JWT_SECRET_ENV_VAR.as_bytes()),
+                )
+                .unwrap();
+                let token = Token::parse(token).unwrap();
+
+                let error = validate_token(&banned_token_store, &KeyRing::default(), &token)
+                        .await
+                        .unwrap_err();
+                assert_eq!(error.kind(), &ErrorKind::InvalidSignature);
+        }
+
+        #[test]
+        fn test_has_valid_signature() {
+                let email = Email::parse("test@example.com").unwrap();
+                let token = generate_auth_token(&email, &KeyRing::default()).unwrap();
+                assert!(has_valid_signature(&token, &KeyRing::default()));
+
+                // Same header and claims, with a signature that was never computed
+                let (unsigned, _) = token.rsplit_once('.').unwrap();
+                let forged = format!("{}.{}", unsigned, "c2lnbmF0dXJl");
+                assert!(!has_valid_signature(&forged, &KeyRing::default()));
+                assert!(!has_valid_signature("invalid_token", &KeyRing::default()));
+        }
+
+        #[test]
+        fn test_challenge_token_round_trips_and_is_not_an_auth_token() {
+                let email = Email::parse("test@example.com").unwrap();
+                let login_attempt_id = LoginAttemptId::default();
+                let token = generate_challenge_token(
+                        &email,
+                        &login_attempt_id,
+                        600,
+                        &KeyRing::default(),
+                )
+                .unwrap();
+
+                let claims = validate_challenge_token(&token, &KeyRing::default()).unwrap();
+                assert_eq!(claims.sub, "test@example.com");
+                assert_eq!(claims.login_attempt_id, login_attempt_id.as_ref());
+
+                // Neither kind of token passes for the other
+                let auth_token = generate_auth_token(&email, &KeyRing::default()).unwrap();
+                assert!(validate_challenge_token(&auth_token, &KeyRing::default()).is_err());
+                let other_purpose = sign_json(serde_json::json!({
+                        "sub": "test@example.com",
+                        "exp": Utc::now().timestamp() + 600,
+                        "purpose": "password-reset",
+                        "loginAttemptId": login_attempt_id.as_ref(),
+                        "iss": JWT_ISSUER.as_str(),
+                        "aud": JWT_AUDIENCE.as_str(),
+                }));
+                assert!(validate_challenge_token(&other_purpose, &KeyRing::default()).is_err());
+        }
+
+        #[tokio::test]
+        async fn test_password_change_token_is_not_a_challenge_token() {
+                let email = Email::parse("test@example.com").unwrap();
+                let password = HashedPassword::parse("TemporaryPassword123").await.unwrap();
+                let token =
+                        generate_password_change_token(&email, &password, 600, &KeyRing::default())
+                                .unwrap();
+
+                let claims = validate_password_change_token(&token, &KeyRing::default()).unwrap();
+                assert_eq!(claims.sub, "test@example.com");
+                assert_eq!(claims.password_fingerprint, password.fingerprint());
+
+                assert!(validate_challenge_token(&token, &KeyRing::default()).is_err());
+                let challenge_token = generate_challenge_token(
+                        &email,
+                        &LoginAttemptId::default(),
+                        600,
+                        &KeyRing::default(),
+                )
+                .unwrap();
+                assert!(validate_password_change_token(&challenge_token, &KeyRing::default())
+                        .is_err());
+                let auth_token = generate_auth_token(&email, &KeyRing::default()).unwrap();
+                assert!(validate_password_change_token(&auth_token, &KeyRing::default()).is_err());
+        }
+
+        /// Sign arbitrary claims the way `create_token` does
+        fn sign_json(claims: serde_json::Value) -> String {
+                let key_ring = KeyRing::default();
+                let key = key_ring.current();
+                let header = Header {
+                        kid: Some(key.kid().to_owned()),
+                        ..Header::default()
+                };
+                encode(&header, &claims, &key.encoding_key()).unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_validate_token_allows_leeway_for_clock_skew() {
+                let banned_token_store = create_banned_token_store();
+                let now = Utc::now().timestamp();
+                let leeway = *JWT_LEEWAY_SECONDS as i64;
+                let validate = |token: String| {
+                        let banned_token_store = banned_token_store.clone();
+                        let token = Token::parse(token).unwrap();
+                        async move {
+                                validate_token(&banned_token_store, &KeyRing::default(), &token)
+                                        .await
+                                        .map_err(|e| e.into_kind())
+                        }
+                };
+
+                let expired_within_leeway = Claims {
+                        exp: (now - leeway + 5) as usize,
+                        ..claims_for("test@example.com")
+                };
+                let token = create_token(&expired_within_leeway, &KeyRing::default()).unwrap();
+                assert!(validate(token).await.is_ok());
+
+                let expired_beyond_leeway = Claims {
+                        exp: (now - leeway - 5) as usize,
+                        ..claims_for("test@example.com")
+                };
+                let token = create_token(&expired_beyond_leeway, &KeyRing::default()).unwrap();
+                assert!(matches!(validate(token).await, Err(ErrorKind::ExpiredSignature)));
+
+                // `nbf` gets the same leeway in the other direction
+                let claims = serde_json::to_value(claims_for("test@example.com")).unwrap();
+                let with_nbf = |nbf: i64| {
+                        let mut claims = claims.clone();
+                        claims["nbf"] = nbf.into();
+                        sign_json(claims)
+                };
+                assert!(validate(with_nbf(now + leeway - 5)).await.is_ok());
+                assert!(matches!(
+                        validate(with_nbf(now + leeway + 5)).await,
+                        Err(ErrorKind::ImmatureSignature)
+                ));
+        }
+
+        #[tokio::test]
+        async fn test_validate_token_follows_key_rotation() {
+                let banned_token_store = create_banned_token_store();
+                let email = Email::parse("test@example.com").unwrap();
+                let original = KeyRing::default();
+                let before = auth_token(&email, &original);
+
+                let rotated = original.rotate("a-newly-pushed-signing-secret");
+                let after = auth_token(&email, &rotated);
+                assert!(validate_token(&banned_token_store, &rotated, &before).await.is_ok());
+                assert!(validate_token(&banned_token_store, &rotated, &after).await.is_ok());
+
+                // The original key has left the ring
+                let rotated_again = rotated.rotate("yet-another-signing-secret");
+                assert!(validate_token(&banned_token_store, &rotated_again, &before)
+                        .await
+                        .is_err());
+                assert!(validate_token(&banned_token_store, &rotated_again, &after).await.is_ok());
+        }
 }
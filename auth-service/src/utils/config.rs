@@ -0,0 +1,564 @@
+// src/utils/config.rs
+use std::{num::NonZeroU32, path::PathBuf, str::FromStr};
+
+use dotenvy::dotenv;
+
+use crate::{
+        domain::{PasswordPolicy, MIN_TEMPORARY_PASSWORD_LEN},
+        utils::constants::{
+                env::{
+                        ASSETS_DIR_ENV_VAR, AUTO_LOGIN_AFTER_SIGNUP_ENV_VAR,
+                        DEBUG_ENDPOINTS_ENV_VAR, FAILED_LOGIN_ALERT_THRESHOLD_ENV_VAR,
+                        HIDE_SIGNUP_CONFLICTS_ENV_VAR, INACTIVITY_EXPIRY_DAYS_ENV_VAR,
+                        LOGIN_ATTEMPT_TTL_SECONDS_ENV_VAR, LOGIN_HISTORY_TRUNCATE_IPS_ENV_VAR,
+                        LOGIN_MAX_DELAY_MS_ENV_VAR, LOGIN_MIN_DELAY_MS_ENV_VAR,
+                        LOGOUT_REVOKES_ALL_ENV_VAR, MAGIC_LINK_LOGIN_ENV_VAR,
+                        MAGIC_LINK_TTL_SECONDS_ENV_VAR, MAINTENANCE_MODE_ENV_VAR,
+                        MAX_2FA_ATTEMPTS_ENV_VAR, MAX_2FA_EMAILS_PER_DAY_ENV_VAR,
+                        MAX_2FA_FAILURES_ENV_VAR, MAX_CONCURRENT_REQUESTS_ENV_VAR,
+                        MAX_EMAIL_CHECKS_PER_IP_ENV_VAR, MAX_FAILED_LOGINS_PER_ACCOUNT_ENV_VAR,
+                        MAX_FAILED_LOGINS_PER_IP_ENV_VAR, MAX_MAGIC_LINKS_PER_EMAIL_ENV_VAR,
+                        MAX_MAGIC_LINKS_PER_IP_ENV_VAR, MAX_REQUEST_BODY_BYTES_ENV_VAR,
+                        MAX_SESSIONS_PER_USER_ENV_VAR, METRICS_ENDPOINT_ENV_VAR,
+                        PASSWORD_HISTORY_DEPTH_ENV_VAR, PASSWORD_MAX_LENGTH_ENV_VAR,
+                        PASSWORD_MIN_LENGTH_ENV_VAR, PASSWORD_REJECT_COMMON_ENV_VAR,
+                        PASSWORD_REJECT_IDENTIFIERS_ENV_VAR, PASSWORD_REQUIRE_DIGIT_ENV_VAR,
+                        PASSWORD_REQUIRE_LOWERCASE_ENV_VAR, PASSWORD_REQUIRE_SYMBOL_ENV_VAR,
+                        PASSWORD_REQUIRE_UPPERCASE_ENV_VAR, PUBLIC_URL_ENV_VAR,
+                        REQUEST_TIMEOUT_SECONDS_ENV_VAR, SERVE_UI_ENV_VAR,
+                        SESSION_EVICTION_POLICY_ENV_VAR, STRICT_EMPTY_TOKEN_ENV_VAR,
+                        TLS_CERT_PATH_ENV_VAR, TLS_ENABLED_ENV_VAR, TLS_KEY_PATH_ENV_VAR,
+                        TRUSTED_PROXY_HEADER_ENV_VAR, TWO_FA_EMAIL_BODY_ENV_VAR,
+                        TWO_FA_EMAIL_BODY_FILE_ENV_VAR, TWO_FA_EMAIL_SUBJECT_ENV_VAR,
+                },
+                DEFAULT_ASSETS_DIR, DEFAULT_FAILED_LOGIN_ALERT_THRESHOLD,
+                DEFAULT_LOGIN_ATTEMPT_TTL_SECONDS, DEFAULT_MAGIC_LINK_TTL_SECONDS,
+                DEFAULT_MAX_2FA_ATTEMPTS, DEFAULT_MAX_2FA_EMAILS_PER_DAY, DEFAULT_MAX_2FA_FAILURES,
+                DEFAULT_MAX_EMAIL_CHECKS_PER_IP, DEFAULT_MAX_FAILED_LOGINS_PER_ACCOUNT,
+                DEFAULT_MAX_FAILED_LOGINS_PER_IP, DEFAULT_MAX_MAGIC_LINKS_PER_EMAIL,
+                DEFAULT_MAX_MAGIC_LINKS_PER_IP, DEFAULT_MAX_REQUEST_BODY_BYTES,
+                DEFAULT_PASSWORD_HISTORY_DEPTH, DEFAULT_PUBLIC_URL,
+                DEFAULT_REQUEST_TIMEOUT_SECONDS,
+        },
+        utils::email_template::{EmailTemplate, CODE_PLACEHOLDER},
+};
+
+/// Tunable runtime settings, read once at startup and shared through `AppState`.
+///
+/// `Default` holds the values used when a variable is unset, which lets tests build
+/// an `AppConfig` directly instead of mutating the process environment.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+        /// Number of previous password hashes kept per user and rejected on password change
+        pub password_history_depth: usize,
+        /// Accounts with no login for this many days are soft-deleted by the inactivity
+        /// expiry job; `None` disables the job. At least 1: with 0 the first run would
+        /// soft-delete every account, admins included
+        pub inactivity_expiry_days: Option<u32>,
+        /// Maximum number of concurrent sessions per user; `None` (or 0) means unlimited
+        pub max_sessions_per_user: Option<usize>,
+        /// What a login beyond `max_sessions_per_user` does
+        pub session_eviction_policy: SessionEvictionPolicy,
+        /// Rules for new passwords, given at signup or password change. Login only checks the
+        /// stored hash, so tightening the rules does not lock out existing accounts.
+        pub password_policy: PasswordPolicy,
+        /// Wrong passwords within an hour before the account owner is emailed about them;
+        /// `None` (or 0) disables the alert
+        pub failed_login_alert_threshold: Option<u32>,
+        /// 2FA emails one address may be sent per day, however many logins ask for one; past
+        /// it a login answers 429. `None` (or 0) removes the cap
+        pub max_2fa_emails_per_day: Option<u32>,
+        /// Checks of one 2FA code, at `/verify-2fa` or `/verify-2fa/check`, before the code is
+        /// discarded and the user has to log in again. `None` (or 0) removes the limit
+        pub max_2fa_attempts: Option<u32>,
+        /// Wrong 2FA codes for one account, across its login attempts, within
+        /// `TWO_FA_FAILURE_WINDOW_SECONDS` before the account is locked for the rest of the
+        /// window, whatever its password. `None` (or 0) turns the lockout off
+        pub max_2fa_failures: Option<u32>,
+        /// Wrong passwords for one account, from any IPs, within `FAILED_LOGIN_WINDOW_SECONDS`
+        /// before the account is locked until enough of them are older than that. The right
+        /// password then gets 403 and a wrong one the usual 401, so the lock does not reveal
+        /// which accounts exist. `None` (or 0) turns the lockout off
+        pub max_failed_logins_per_account: Option<u32>,
+        /// Failed logins from one client IP, for any accounts, within
+        /// `FAILED_LOGIN_WINDOW_SECONDS` before its logins get 429 until enough of them are
+        /// older than that. `None` (or 0) turns the throttle off
+        pub max_failed_logins_per_ip: Option<u32>,
+        /// `/signup/check-email` lookups from one client IP within `CHECK_EMAIL_WINDOW_SECONDS`
+        /// before further ones get 429. `None` (or 0) removes the limit
+        pub max_email_checks_per_ip: Option<u32>,
+        /// Largest request body the API routes accept; bigger bodies get a 413
+        pub max_request_body_bytes: usize,
+        /// Longest an API handler may run before it is abandoned with a 504
+        pub request_timeout_seconds: u64,
+        /// Requests handled at once; past it further requests get an immediate 503 rather
+        /// than queueing for a database connection. `None` (or 0) means unlimited
+        pub max_concurrent_requests: Option<usize>,
+        /// How long the login attempt from a 206 login can be completed with `/verify-2fa`,
+        /// counted from when its 2FA code was issued
+        pub login_attempt_ttl_seconds: u64,
+        /// Start a session on signup for users without 2FA, setting the same cookie a login
+        /// would, so the client need not call `/login` straight after
+        pub auto_login_after_signup: bool,
+        /// Answer a signup for an existing email with the usual 201, changing nothing and
+        /// emailing the account owner instead, so signup cannot be used to find registered
+        /// emails. Also makes `/signup/check-email` report every email available and turns
+        /// off `auto_login_after_signup`, which would otherwise give new accounts away
+        pub hide_signup_conflicts: bool,
+        /// Mount `/magic-link/request` and `/magic-link/verify`, letting users log in with a
+        /// link emailed to them instead of their password
+        pub magic_link_login: bool,
+        /// How long an emailed login link can be used; at least one second
+        pub magic_link_ttl_seconds: u64,
+        /// Login links one email may be sent, or asked for if it has no account, within
+        /// `MAGIC_LINK_REQUEST_WINDOW_SECONDS`; past it requests get 429. `None` (or 0) removes
+        /// the cap
+        pub max_magic_links_per_email: Option<u32>,
+        /// Login links one client IP may ask for, for any emails, within
+        /// `MAGIC_LINK_REQUEST_WINDOW_SECONDS`; past it requests get 429. `None` (or 0) removes
+        /// the cap
+        pub max_magic_links_per_ip: Option<u32>,
+        /// Base URL the auth service is reached at, used to build links sent by email
+        pub public_url: String,
+        /// Email carrying a 2FA code; see `EmailTemplate` for the placeholders
+        pub two_fa_email_template: EmailTemplate,
+        /// Mount the dev-only `/debug/token` route. Off unless `DEBUG_ENDPOINTS=true`; never
+        /// set it in production, the route reports on any token without requiring it be valid
+        pub debug_endpoints: bool,
+        /// Mount `/metrics`, serving this instance's counters in the Prometheus text format
+        pub metrics_endpoint: bool,
+        /// Start in read-only maintenance: logins keep working, signups and other writes get
+        /// a 503. `/admin/maintenance` switches it at runtime.
+        pub maintenance_mode: bool,
+        /// Directory the UI is served from: `index.html` for `/` and the static files behind
+        /// it. `from_env` makes it absolute, so it no longer depends on the working directory.
+        pub assets_dir: PathBuf,
+        /// Serve the UI from `assets_dir` at `/` and for unmatched browser GETs. Off for
+        /// API-only deployments, where every unknown path, `/` included, is a JSON 404
+        pub serve_ui: bool,
+        /// Make every logout end all of the user's sessions, not only the one logging out
+        pub logout_revokes_all: bool,
+        /// Reject an empty `jwt` cookie at `POST /logout` as an invalid token (401). Off by
+        /// default, when it counts as no token at all (400)
+        pub strict_empty_token: bool,
+        /// Show IPs in `/security/login-history` truncated to their network (the last IPv4
+        /// octet zeroed, IPv6 cut to its /48). The full IPs are still stored
+        pub login_history_truncate_ips: bool,
+        /// The header the reverse proxy in front of the service reports the client IP in (see
+        /// `ClientInfo`); no other is read. `None` when clients connect directly, as they
+        /// could then set either header themselves
+        pub trusted_proxy_header: Option<TrustedProxyHeader>,
+        /// Shortest delay added to a login refused for a wrong password or unknown account
+        pub login_min_delay_ms: u64,
+        /// Longest such delay; each refused login waits a random time between the two. A
+        /// maximum below the minimum is raised to it, and 0 for both turns the delay off
+        pub login_max_delay_ms: u64,
+}
+
+/// How a login that would exceed `MAX_SESSIONS_PER_USER` is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionEvictionPolicy {
+        /// Refuse the new login
+        Reject,
+        /// Ban the oldest sessions' tokens to make room for the new one
+        #[default]
+        EvictOldest,
+}
+
+impl FromStr for SessionEvictionPolicy {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                        "reject" => Ok(Self::Reject),
+                        "evict_oldest" => Ok(Self::EvictOldest),
+                        other => Err(format!("Unknown session eviction policy: {other}")),
+                }
+        }
+}
+
+/// Header a reverse proxy reports the client IP in, named as in `TRUSTED_PROXY_HEADER`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustedProxyHeader {
+        /// `x-forwarded-for`: the last hop is the one the proxy appended
+        XForwardedFor,
+        /// `x-real-ip`: the proxy overwrites it with the address it was connected from
+        XRealIp,
+}
+
+impl TrustedProxyHeader {
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        Self::XForwardedFor => "x-forwarded-for",
+                        Self::XRealIp => "x-real-ip",
+                }
+        }
+}
+
+impl FromStr for TrustedProxyHeader {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.to_ascii_lowercase().as_str() {
+                        "x-forwarded-for" => Ok(Self::XForwardedFor),
+                        "x-real-ip" => Ok(Self::XRealIp),
+                        other => Err(format!("Unknown trusted proxy header: {other}")),
+                }
+        }
+}
+
+impl Default for AppConfig {
+        fn default() -> Self {
+                Self {
+                        password_history_depth: DEFAULT_PASSWORD_HISTORY_DEPTH,
+                        inactivity_expiry_days: None,
+                        max_sessions_per_user: None,
+                        session_eviction_policy: SessionEvictionPolicy::default(),
+                        password_policy: PasswordPolicy::default(),
+                        failed_login_alert_threshold: Some(DEFAULT_FAILED_LOGIN_ALERT_THRESHOLD),
+                        max_2fa_emails_per_day: Some(DEFAULT_MAX_2FA_EMAILS_PER_DAY),
+                        max_2fa_attempts: Some(DEFAULT_MAX_2FA_ATTEMPTS),
+                        max_2fa_failures: Some(DEFAULT_MAX_2FA_FAILURES),
+                        max_failed_logins_per_account: Some(DEFAULT_MAX_FAILED_LOGINS_PER_ACCOUNT),
+                        max_failed_logins_per_ip: Some(DEFAULT_MAX_FAILED_LOGINS_PER_IP),
+                        max_email_checks_per_ip: Some(DEFAULT_MAX_EMAIL_CHECKS_PER_IP),
+                        max_request_body_bytes: DEFAULT_MAX_REQUEST_BODY_BYTES,
+                        request_timeout_seconds: DEFAULT_REQUEST_TIMEOUT_SECONDS,
+                        max_concurrent_requests: None,
+                        login_attempt_ttl_seconds: DEFAULT_LOGIN_ATTEMPT_TTL_SECONDS,
+                        auto_login_after_signup: false,
+                        hide_signup_conflicts: false,
+                        magic_link_login: false,
+                        magic_link_ttl_seconds: DEFAULT_MAGIC_LINK_TTL_SECONDS,
+                        max_magic_links_per_email: Some(DEFAULT_MAX_MAGIC_LINKS_PER_EMAIL),
+                        max_magic_links_per_ip: Some(DEFAULT_MAX_MAGIC_LINKS_PER_IP),
+                        public_url: DEFAULT_PUBLIC_URL.to_owned(),
+                        two_fa_email_template: EmailTemplate::default_two_fa(),
+                        debug_endpoints: false,
+                        metrics_endpoint: false,
+                        maintenance_mode: false,
+                        assets_dir: PathBuf::from(DEFAULT_ASSETS_DIR),
+                        serve_ui: true,
+                        logout_revokes_all: false,
+                        strict_empty_token: false,
+                        login_history_truncate_ips: false,
+                        trusted_proxy_header: None,
+                        login_min_delay_ms: 0,
+                        login_max_delay_ms: 0,
+                }
+        }
+}
+
+impl AppConfig {
+        pub fn from_env() -> Self {
+                dotenv().ok();
+                let defaults = Self::default();
+
+                Self {
+                        password_history_depth: parse_env_or(
+                                PASSWORD_HISTORY_DEPTH_ENV_VAR,
+                                defaults.password_history_depth,
+                        ),
+                        // Parsed as non-zero so 0 panics like any other invalid value
+                        inactivity_expiry_days: parse_optional_env(INACTIVITY_EXPIRY_DAYS_ENV_VAR)
+                                .map(NonZeroU32::get)
+                                .or(defaults.inactivity_expiry_days),
+                        max_sessions_per_user: parse_optional_env(MAX_SESSIONS_PER_USER_ENV_VAR)
+                                .filter(|max: &usize| *max > 0)
+                                .or(defaults.max_sessions_per_user),
+                        session_eviction_policy: parse_env_or(
+                                SESSION_EVICTION_POLICY_ENV_VAR,
+                                defaults.session_eviction_policy,
+                        ),
+                        password_policy: password_policy_from_env(defaults.password_policy),
+                        failed_login_alert_threshold: match parse_optional_env(
+                                FAILED_LOGIN_ALERT_THRESHOLD_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(threshold) => Some(threshold),
+                                None => defaults.failed_login_alert_threshold,
+                        },
+                        max_2fa_emails_per_day: match parse_optional_env(
+                                MAX_2FA_EMAILS_PER_DAY_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(cap) => Some(cap),
+                                None => defaults.max_2fa_emails_per_day,
+                        },
+                        max_2fa_attempts: match parse_optional_env(MAX_2FA_ATTEMPTS_ENV_VAR) {
+                                Some(0) => None,
+                                Some(limit) => Some(limit),
+                                None => defaults.max_2fa_attempts,
+                        },
+                        max_2fa_failures: match parse_optional_env(MAX_2FA_FAILURES_ENV_VAR) {
+                                Some(0) => None,
+                                Some(limit) => Some(limit),
+                                None => defaults.max_2fa_failures,
+                        },
+                        max_failed_logins_per_account: match parse_optional_env(
+                                MAX_FAILED_LOGINS_PER_ACCOUNT_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(limit) => Some(limit),
+                                None => defaults.max_failed_logins_per_account,
+                        },
+                        max_failed_logins_per_ip: match parse_optional_env(
+                                MAX_FAILED_LOGINS_PER_IP_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(limit) => Some(limit),
+                                None => defaults.max_failed_logins_per_ip,
+                        },
+                        max_email_checks_per_ip: match parse_optional_env(
+                                MAX_EMAIL_CHECKS_PER_IP_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(limit) => Some(limit),
+                                None => defaults.max_email_checks_per_ip,
+                        },
+                        max_request_body_bytes: parse_env_or(
+                                MAX_REQUEST_BODY_BYTES_ENV_VAR,
+                                defaults.max_request_body_bytes,
+                        ),
+                        request_timeout_seconds: parse_env_or(
+                                REQUEST_TIMEOUT_SECONDS_ENV_VAR,
+                                defaults.request_timeout_seconds,
+                        ),
+                        max_concurrent_requests: parse_optional_env(
+                                MAX_CONCURRENT_REQUESTS_ENV_VAR,
+                        )
+                        .filter(|max: &usize| *max > 0)
+                        .or(defaults.max_concurrent_requests),
+                        login_attempt_ttl_seconds: parse_env_or(
+                                LOGIN_ATTEMPT_TTL_SECONDS_ENV_VAR,
+                                defaults.login_attempt_ttl_seconds,
+                        ),
+                        auto_login_after_signup: parse_env_or(
+                                AUTO_LOGIN_AFTER_SIGNUP_ENV_VAR,
+                                defaults.auto_login_after_signup,
+                        ),
+                        hide_signup_conflicts: parse_env_or(
+                                HIDE_SIGNUP_CONFLICTS_ENV_VAR,
+                                defaults.hide_signup_conflicts,
+                        ),
+                        magic_link_login: parse_env_or(
+                                MAGIC_LINK_LOGIN_ENV_VAR,
+                                defaults.magic_link_login,
+                        ),
+                        magic_link_ttl_seconds: parse_env_or(
+                                MAGIC_LINK_TTL_SECONDS_ENV_VAR,
+                                defaults.magic_link_ttl_seconds,
+                        )
+                        .max(1),
+                        max_magic_links_per_email: match parse_optional_env(
+                                MAX_MAGIC_LINKS_PER_EMAIL_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(cap) => Some(cap),
+                                None => defaults.max_magic_links_per_email,
+                        },
+                        max_magic_links_per_ip: match parse_optional_env(
+                                MAX_MAGIC_LINKS_PER_IP_ENV_VAR,
+                        ) {
+                                Some(0) => None,
+                                Some(cap) => Some(cap),
+                                None => defaults.max_magic_links_per_ip,
+                        },
+                        public_url: parse_env_or(PUBLIC_URL_ENV_VAR, defaults.public_url),
+                        two_fa_email_template: two_fa_email_template_from_env(
+                                defaults.two_fa_email_template,
+                        ),
+                        debug_endpoints: parse_env_or(
+                                DEBUG_ENDPOINTS_ENV_VAR,
+                                defaults.debug_endpoints,
+                        ),
+                        metrics_endpoint: parse_env_or(
+                                METRICS_ENDPOINT_ENV_VAR,
+                                defaults.metrics_endpoint,
+                        ),
+                        maintenance_mode: parse_env_or(
+                                MAINTENANCE_MODE_ENV_VAR,
+                                defaults.maintenance_mode,
+                        ),
+                        assets_dir: assets_dir_from_env(defaults.assets_dir),
+                        serve_ui: parse_env_or(SERVE_UI_ENV_VAR, defaults.serve_ui),
+                        logout_revokes_all: parse_env_or(
+                                LOGOUT_REVOKES_ALL_ENV_VAR,
+                                defaults.logout_revokes_all,
+                        ),
+                        strict_empty_token: parse_env_or(
+                                STRICT_EMPTY_TOKEN_ENV_VAR,
+                                defaults.strict_empty_token,
+                        ),
+                        login_history_truncate_ips: parse_env_or(
+                                LOGIN_HISTORY_TRUNCATE_IPS_ENV_VAR,
+                                defaults.login_history_truncate_ips,
+                        ),
+                        trusted_proxy_header: parse_optional_env(TRUSTED_PROXY_HEADER_ENV_VAR)
+                                .or(defaults.trusted_proxy_header),
+                        login_min_delay_ms: parse_env_or(
+                                LOGIN_MIN_DELAY_MS_ENV_VAR,
+                                defaults.login_min_delay_ms,
+                        ),
+                        login_max_delay_ms: parse_env_or(
+                                LOGIN_MAX_DELAY_MS_ENV_VAR,
+                                defaults.login_max_delay_ms,
+                        ),
+                }
+        }
+}
+
+/// Override each rule of `defaults` whose `PASSWORD_*` variable is set. Panics on lengths no
+/// password can meet, or too short for a temporary password after an admin reset.
+fn password_policy_from_env(defaults: PasswordPolicy) -> PasswordPolicy {
+        let policy = PasswordPolicy {
+                min_len: parse_env_or(PASSWORD_MIN_LENGTH_ENV_VAR, defaults.min_len),
+                max_len: parse_env_or(PASSWORD_MAX_LENGTH_ENV_VAR, defaults.max_len),
+                require_upper: parse_env_or(
+                        PASSWORD_REQUIRE_UPPERCASE_ENV_VAR,
+                        defaults.require_upper,
+                ),
+                require_lower: parse_env_or(
+                        PASSWORD_REQUIRE_LOWERCASE_ENV_VAR,
+                        defaults.require_lower,
+                ),
+                require_digit: parse_env_or(PASSWORD_REQUIRE_DIGIT_ENV_VAR, defaults.require_digit),
+                require_symbol: parse_env_or(
+                        PASSWORD_REQUIRE_SYMBOL_ENV_VAR,
+                        defaults.require_symbol,
+                ),
+                reject_common: parse_env_or(PASSWORD_REJECT_COMMON_ENV_VAR, defaults.reject_common),
+                reject_identifiers: parse_env_or(
+                        PASSWORD_REJECT_IDENTIFIERS_ENV_VAR,
+                        defaults.reject_identifiers,
+                ),
+        };
+
+        if policy.min_len > policy.max_len {
+                panic!(
+                        "{} must not be greater than {}",
+                        PASSWORD_MIN_LENGTH_ENV_VAR, PASSWORD_MAX_LENGTH_ENV_VAR
+                );
+        }
+        if policy.max_len < MIN_TEMPORARY_PASSWORD_LEN {
+                panic!(
+                        "{} must be at least {}",
+                        PASSWORD_MAX_LENGTH_ENV_VAR, MIN_TEMPORARY_PASSWORD_LEN
+                );
+        }
+
+        policy
+}
+
+/// Override the 2FA email subject and body from `TWO_FA_EMAIL_SUBJECT` and either
+/// `TWO_FA_EMAIL_BODY_FILE` (read at startup, takes precedence) or `TWO_FA_EMAIL_BODY`.
+/// Panics on an unreadable file or a body without `{{code}}`, which would send users an
+/// email they cannot log in with.
+fn two_fa_email_template_from_env(defaults: EmailTemplate) -> EmailTemplate {
+        let subject = parse_env_or(TWO_FA_EMAIL_SUBJECT_ENV_VAR, defaults.subject);
+        let body = match parse_optional_env::<PathBuf>(TWO_FA_EMAIL_BODY_FILE_ENV_VAR) {
+                Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+                        panic!("{} could not be read: {}", TWO_FA_EMAIL_BODY_FILE_ENV_VAR, e)
+                }),
+                None => parse_env_or(TWO_FA_EMAIL_BODY_ENV_VAR, defaults.body),
+        };
+
+        if !body.contains(CODE_PLACEHOLDER) {
+                panic!("The 2FA email body must contain {}", CODE_PLACEHOLDER);
+        }
+
+        EmailTemplate::new(subject, body)
+}
+
+/// Read `ASSETS_DIR` and resolve it against the working directory at startup. Panics if
+/// that cannot be done, since the UI would otherwise fail on every request.
+fn assets_dir_from_env(default: PathBuf) -> PathBuf {
+        let dir = parse_env_or(ASSETS_DIR_ENV_VAR, default);
+        std::path::absolute(&dir)
+                .unwrap_or_else(|e| panic!("{} could not be resolved: {}", ASSETS_DIR_ENV_VAR, e))
+}
+
+/// PEM certificate chain and private key used when the service terminates TLS itself
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+        pub cert_path: PathBuf,
+        pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+        /// `Some` when `TLS_ENABLED` is true. Panics if TLS is enabled without both
+        /// `TLS_CERT_PATH` and `TLS_KEY_PATH`, so the service never falls back to plain HTTP
+        /// when HTTPS was asked for.
+        pub fn from_env() -> Option<Self> {
+                dotenv().ok();
+                if !parse_env_or(TLS_ENABLED_ENV_VAR, false) {
+                        return None;
+                }
+
+                let path = |var: &str| -> PathBuf {
+                        parse_optional_env(var).unwrap_or_else(|| {
+                                panic!("{} must be set when {} is true", var, TLS_ENABLED_ENV_VAR)
+                        })
+                };
+
+                Some(Self {
+                        cert_path: path(TLS_CERT_PATH_ENV_VAR),
+                        key_path: path(TLS_KEY_PATH_ENV_VAR),
+                })
+        }
+}
+
+/// Read and parse an optional env var, panicking on a value that does not parse so a
+/// typo in deployment config fails at startup rather than silently using the default.
+fn parse_env_or<T: FromStr>(var: &str, default: T) -> T {
+        parse_optional_env(var).unwrap_or(default)
+}
+
+/// Like `parse_env_or`, for settings whose absence means "disabled"
+fn parse_optional_env<T: FromStr>(var: &str) -> Option<T> {
+        match std::env::var(var) {
+                Ok(value) if !value.trim().is_empty() => Some(value
+                        .trim()
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{} has an invalid value: {}", var, value))),
+                _ => None,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::sync::Mutex;
+
+        /// Held by each test that sets variables `AppConfig::from_env` reads
+        static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+        #[test]
+        fn inactivity_expiry_of_zero_days_is_rejected() {
+                let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+                std::env::set_var(INACTIVITY_EXPIRY_DAYS_ENV_VAR, "0");
+                let zero = std::panic::catch_unwind(AppConfig::from_env);
+                std::env::set_var(INACTIVITY_EXPIRY_DAYS_ENV_VAR, "1");
+                let one = std::panic::catch_unwind(AppConfig::from_env);
+                std::env::remove_var(INACTIVITY_EXPIRY_DAYS_ENV_VAR);
+
+                assert!(zero.is_err());
+                assert_eq!(one.unwrap().inactivity_expiry_days, Some(1));
+        }
+
+        #[test]
+        fn password_policy_no_temporary_password_can_meet_is_rejected() {
+                let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+                for (min_len, max_len) in [("8", "3"), ("30", "20")] {
+                        std::env::set_var(PASSWORD_MIN_LENGTH_ENV_VAR, min_len);
+                        std::env::set_var(PASSWORD_MAX_LENGTH_ENV_VAR, max_len);
+                        let result = std::panic::catch_unwind(|| {
+                                password_policy_from_env(PasswordPolicy::default())
+                        });
+                        std::env::remove_var(PASSWORD_MIN_LENGTH_ENV_VAR);
+                        std::env::remove_var(PASSWORD_MAX_LENGTH_ENV_VAR);
+
+                        assert!(result.is_err(), "{}..={} should be rejected", min_len, max_len);
+                }
+        }
+}
@@ -4,6 +4,7 @@ use crate::utils::constants::env::{
 
 // src/utils/constants.rs
 use super::constants::env::JWT_SECRET_ENV_VAR;
+use crate::domain::{PasswordHasherKind, Peppers};
 use dotenvy::dotenv;
 use lazy_static::lazy_static;
 
@@ -14,14 +15,106 @@ lazy_static! {
         pub static ref DROPLET_URL: String = set_droplet_url();
         pub static ref DATABASE_URL: String = set_db_url();
         pub static ref REDIS_HOST_NAME: String = set_redis_host();
+        pub static ref REDIS_PORT: u16 = set_redis_port();
+        pub static ref JWT_ISSUER: String = set_jwt_issuer();
+        pub static ref JWT_AUDIENCE: String = set_jwt_audience();
+        pub static ref JWT_COOKIE_NAME: String = set_jwt_cookie_name();
+        pub static ref JWT_LEEWAY_SECONDS: u64 =
+                set_numeric_param(env::JWT_LEEWAY_SECONDS_ENV_VAR, DEFAULT_JWT_LEEWAY_SECONDS);
+        pub static ref ARGON2_MEMORY_KIB: u32 =
+                set_numeric_param(env::ARGON2_MEMORY_KIB_ENV_VAR, DEFAULT_ARGON2_MEMORY_KIB);
+        pub static ref ARGON2_ITERATIONS: u32 =
+                set_numeric_param(env::ARGON2_ITERATIONS_ENV_VAR, DEFAULT_ARGON2_ITERATIONS);
+        pub static ref ARGON2_PARALLELISM: u32 =
+                set_numeric_param(env::ARGON2_PARALLELISM_ENV_VAR, DEFAULT_ARGON2_PARALLELISM);
+        pub static ref PASSWORD_HASHER: PasswordHasherKind =
+                set_numeric_param(env::PASSWORD_HASHER_ENV_VAR, PasswordHasherKind::default());
+        pub static ref PASSWORD_PEPPERS: Peppers = Peppers::from_env();
+        pub static ref RUN_MIGRATIONS_ON_STARTUP: bool = set_numeric_param(
+                env::RUN_MIGRATIONS_ON_STARTUP_ENV_VAR,
+                DEFAULT_RUN_MIGRATIONS_ON_STARTUP
+        );
 }
 
 pub mod env {
+        /// Appended to a secret's variable name to give the path of a file holding it instead,
+        /// e.g. `JWT_SECRET_FILE`; see `get_secret`
+        pub const SECRET_FILE_SUFFIX: &str = "_FILE";
         pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
+        pub const JWT_ISSUER_ENV_VAR: &str = "JWT_ISSUER";
+        pub const JWT_AUDIENCE_ENV_VAR: &str = "JWT_AUDIENCE";
+        pub const JWT_COOKIE_NAME_ENV_VAR: &str = "JWT_COOKIE_NAME";
+        pub const JWT_LEEWAY_SECONDS_ENV_VAR: &str = "JWT_LEEWAY_SECONDS";
+        pub const ARGON2_MEMORY_KIB_ENV_VAR: &str = "ARGON2_MEMORY_KIB";
+        pub const ARGON2_ITERATIONS_ENV_VAR: &str = "ARGON2_ITERATIONS";
+        pub const ARGON2_PARALLELISM_ENV_VAR: &str = "ARGON2_PARALLELISM";
+        pub const PASSWORD_HASHER_ENV_VAR: &str = "PASSWORD_HASHER";
+        /// Followed by the version number, e.g. `PASSWORD_PEPPER_V1`
+        pub const PASSWORD_PEPPER_ENV_VAR_PREFIX: &str = "PASSWORD_PEPPER_V";
+        pub const PASSWORD_PEPPER_CURRENT_ENV_VAR: &str = "PASSWORD_PEPPER_CURRENT";
         pub const LOCALHOST_URL_ENV_VAR: &str = "LOCALHOST_URL";
         pub const DROPLET_URL_ENV_VAR: &str = "DROPLET_URL";
         pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+        pub const RUN_MIGRATIONS_ON_STARTUP_ENV_VAR: &str = "RUN_MIGRATIONS_ON_STARTUP";
         pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
+        pub const REDIS_PORT_ENV_VAR: &str = "REDIS_PORT";
+        pub const LOG_FORMAT_ENV_VAR: &str = "LOG_FORMAT";
+        pub const MAX_CONCURRENT_REQUESTS_ENV_VAR: &str = "MAX_CONCURRENT_REQUESTS";
+        pub const OTEL_EXPORTER_OTLP_ENDPOINT_ENV_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+        pub const DEBUG_ENDPOINTS_ENV_VAR: &str = "DEBUG_ENDPOINTS";
+        pub const METRICS_ENDPOINT_ENV_VAR: &str = "METRICS_ENDPOINT";
+        pub const PASSWORD_HISTORY_DEPTH_ENV_VAR: &str = "PASSWORD_HISTORY_DEPTH";
+        pub const INACTIVITY_EXPIRY_DAYS_ENV_VAR: &str = "INACTIVITY_EXPIRY_DAYS";
+        pub const WEBHOOK_URL_ENV_VAR: &str = "WEBHOOK_URL";
+        pub const WEBHOOK_SECRET_ENV_VAR: &str = "WEBHOOK_SECRET";
+        pub const TLS_ENABLED_ENV_VAR: &str = "TLS_ENABLED";
+        pub const TLS_CERT_PATH_ENV_VAR: &str = "TLS_CERT_PATH";
+        pub const TLS_KEY_PATH_ENV_VAR: &str = "TLS_KEY_PATH";
+        pub const CAPTCHA_ENABLED_ENV_VAR: &str = "CAPTCHA_ENABLED";
+        pub const CAPTCHA_SECRET_ENV_VAR: &str = "CAPTCHA_SECRET";
+        pub const CAPTCHA_VERIFY_URL_ENV_VAR: &str = "CAPTCHA_VERIFY_URL";
+        pub const VERIFY_EMAIL_MX_ENV_VAR: &str = "VERIFY_EMAIL_MX";
+        pub const MAX_SESSIONS_PER_USER_ENV_VAR: &str = "MAX_SESSIONS_PER_USER";
+        pub const LOGIN_HISTORY_TRUNCATE_IPS_ENV_VAR: &str = "LOGIN_HISTORY_TRUNCATE_IPS";
+        pub const TRUSTED_PROXY_HEADER_ENV_VAR: &str = "TRUSTED_PROXY_HEADER";
+        pub const SESSION_EVICTION_POLICY_ENV_VAR: &str = "SESSION_EVICTION_POLICY";
+        pub const PASSWORD_MIN_LENGTH_ENV_VAR: &str = "PASSWORD_MIN_LENGTH";
+        pub const PASSWORD_MAX_LENGTH_ENV_VAR: &str = "PASSWORD_MAX_LENGTH";
+        pub const PASSWORD_REQUIRE_UPPERCASE_ENV_VAR: &str = "PASSWORD_REQUIRE_UPPERCASE";
+        pub const PASSWORD_REQUIRE_LOWERCASE_ENV_VAR: &str = "PASSWORD_REQUIRE_LOWERCASE";
+        pub const PASSWORD_REQUIRE_DIGIT_ENV_VAR: &str = "PASSWORD_REQUIRE_DIGIT";
+        pub const PASSWORD_REQUIRE_SYMBOL_ENV_VAR: &str = "PASSWORD_REQUIRE_SYMBOL";
+        pub const PASSWORD_REJECT_COMMON_ENV_VAR: &str = "PASSWORD_REJECT_COMMON";
+        pub const PASSWORD_REJECT_IDENTIFIERS_ENV_VAR: &str = "PASSWORD_REJECT_IDENTIFIERS";
+        pub const FAILED_LOGIN_ALERT_THRESHOLD_ENV_VAR: &str = "FAILED_LOGIN_ALERT_THRESHOLD";
+        pub const MAX_REQUEST_BODY_BYTES_ENV_VAR: &str = "MAX_REQUEST_BODY_BYTES";
+        pub const REQUEST_TIMEOUT_SECONDS_ENV_VAR: &str = "REQUEST_TIMEOUT_SECONDS";
+        pub const LOGIN_ATTEMPT_TTL_SECONDS_ENV_VAR: &str = "LOGIN_ATTEMPT_TTL_SECONDS";
+        pub const AUTO_LOGIN_AFTER_SIGNUP_ENV_VAR: &str = "AUTO_LOGIN_AFTER_SIGNUP";
+        pub const HIDE_SIGNUP_CONFLICTS_ENV_VAR: &str = "HIDE_SIGNUP_CONFLICTS";
+        pub const MAGIC_LINK_LOGIN_ENV_VAR: &str = "MAGIC_LINK_LOGIN";
+        pub const MAGIC_LINK_TTL_SECONDS_ENV_VAR: &str = "MAGIC_LINK_TTL_SECONDS";
+        pub const MAX_MAGIC_LINKS_PER_EMAIL_ENV_VAR: &str = "MAX_MAGIC_LINKS_PER_EMAIL";
+        pub const MAX_MAGIC_LINKS_PER_IP_ENV_VAR: &str = "MAX_MAGIC_LINKS_PER_IP";
+        pub const MAX_2FA_EMAILS_PER_DAY_ENV_VAR: &str = "MAX_2FA_EMAILS_PER_DAY";
+        pub const MAX_2FA_ATTEMPTS_ENV_VAR: &str = "MAX_2FA_ATTEMPTS";
+        pub const MAX_2FA_FAILURES_ENV_VAR: &str = "MAX_2FA_FAILURES";
+        pub const MAX_FAILED_LOGINS_PER_ACCOUNT_ENV_VAR: &str = "MAX_FAILED_LOGINS_PER_ACCOUNT";
+        pub const MAX_FAILED_LOGINS_PER_IP_ENV_VAR: &str = "MAX_FAILED_LOGINS_PER_IP";
+        pub const MAX_EMAIL_CHECKS_PER_IP_ENV_VAR: &str = "MAX_EMAIL_CHECKS_PER_IP";
+        pub const TWO_FA_EMAIL_SUBJECT_ENV_VAR: &str = "TWO_FA_EMAIL_SUBJECT";
+        pub const TWO_FA_EMAIL_BODY_ENV_VAR: &str = "TWO_FA_EMAIL_BODY";
+        pub const TWO_FA_EMAIL_BODY_FILE_ENV_VAR: &str = "TWO_FA_EMAIL_BODY_FILE";
+        pub const PUBLIC_URL_ENV_VAR: &str = "PUBLIC_URL";
+        pub const MAINTENANCE_MODE_ENV_VAR: &str = "MAINTENANCE_MODE";
+        pub const ASSETS_DIR_ENV_VAR: &str = "ASSETS_DIR";
+        pub const SERVE_UI_ENV_VAR: &str = "SERVE_UI";
+        pub const LOGOUT_REVOKES_ALL_ENV_VAR: &str = "LOGOUT_REVOKES_ALL";
+        pub const STRICT_EMPTY_TOKEN_ENV_VAR: &str = "STRICT_EMPTY_TOKEN";
+        pub const LOGIN_MIN_DELAY_MS_ENV_VAR: &str = "LOGIN_MIN_DELAY_MS";
+        pub const LOGIN_MAX_DELAY_MS_ENV_VAR: &str = "LOGIN_MAX_DELAY_MS";
+        pub const ADMIN_EMAIL_ENV_VAR: &str = "ADMIN_EMAIL";
+        pub const ADMIN_PASSWORD_ENV_VAR: &str = "ADMIN_PASSWORD";
 }
 
 pub fn get_env_var<S: Into<String>>(var: S) -> String {
@@ -37,20 +130,85 @@ pub fn get_env_var<S: Into<String>>(var: S) -> String {
         secret
 }
 
-fn set_token() -> String {
+/// A secret read from the file named by `<var>_FILE`, as orchestrators mount secrets, or
+/// else from `var` itself. The file takes precedence when both are set, and its contents are
+/// trimmed so a trailing newline is not part of the secret. Panics when neither is set, or
+/// the file cannot be read or is empty.
+pub fn get_secret(var: &str) -> String {
         dotenv().ok();
-        std::env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET must be set")
+        let file_var = format!("{}{}", var, env::SECRET_FILE_SUFFIX);
+
+        match std::env::var(&file_var) {
+                // Blank counts as unset, as compose passes through unset variables
+                Ok(path) if !path.trim().is_empty() => read_secret_file(&file_var, path.trim()),
+                _ => match std::env::var(var) {
+                        Ok(secret) if !secret.is_empty() => secret,
+                        _ => panic!("{} or {} must be set", var, file_var),
+                },
+        }
+}
+
+/// The trimmed contents of the secret file at `path`, named by `file_var`. Panics when the
+/// file cannot be read or holds only whitespace.
+pub(crate) fn read_secret_file(file_var: &str, path: &str) -> String {
+        let contents = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("{} ({}) could not be read: {}", file_var, path, e));
+        let secret = contents.trim();
+        if secret.is_empty() {
+                panic!("{} ({}) is empty", file_var, path);
+        }
+
+        secret.to_owned()
+}
+
+fn set_token() -> String {
+        get_secret(env::JWT_SECRET_ENV_VAR)
 }
 
 fn set_db_url() -> String {
+        get_secret(env::DATABASE_URL_ENV_VAR)
+}
+
+fn set_jwt_issuer() -> String {
         dotenv().ok();
-        std::env::var(env::DATABASE_URL_ENV_VAR).expect("DATABASE_URL must be set")
+        std::env::var(env::JWT_ISSUER_ENV_VAR).unwrap_or(DEFAULT_JWT_ISSUER.to_owned())
+}
+
+fn set_jwt_audience() -> String {
+        dotenv().ok();
+        std::env::var(env::JWT_AUDIENCE_ENV_VAR).unwrap_or(DEFAULT_JWT_AUDIENCE.to_owned())
+}
+
+fn set_jwt_cookie_name() -> String {
+        dotenv().ok();
+        std::env::var(env::JWT_COOKIE_NAME_ENV_VAR).unwrap_or(DEFAULT_JWT_COOKIE_NAME.to_owned())
+}
+
+fn set_numeric_param<T: std::str::FromStr>(var: &str, default: T) -> T {
+        dotenv().ok();
+        match std::env::var(var) {
+                Ok(value) if !value.trim().is_empty() => value
+                        .trim()
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{} has an invalid value: {}", var, value)),
+                _ => default,
+        }
 }
 
 fn set_redis_host() -> String {
         std::env::var(env::REDIS_HOST_NAME_ENV_VAR).unwrap_or(DEFAULT_REDIS_HOSTNAME.to_owned())
 }
 
+fn set_redis_port() -> u16 {
+        match std::env::var(env::REDIS_PORT_ENV_VAR) {
+                Ok(port) if !port.trim().is_empty() => port
+                        .trim()
+                        .parse()
+                        .unwrap_or_else(|_| panic!("REDIS_PORT has an invalid value: {}", port)),
+                _ => DEFAULT_REDIS_PORT,
+        }
+}
+
 fn set_localhost_url() -> String {
         std::env::var(env::LOCALHOST_URL_ENV_VAR).expect("LOCALHOST_URL must be set")
 }
@@ -59,12 +217,143 @@ fn set_droplet_url() -> String {
         std::env::var(env::DROPLET_URL_ENV_VAR).expect("DROPLET_URL must be set")
 }
 
-pub const JWT_COOKIE_NAME: &str = "jwt";
+/// Name of the auth cookie; override with `JWT_COOKIE_NAME` when services share a domain
+pub const DEFAULT_JWT_COOKIE_NAME: &str = "jwt";
+/// `iss` claim of tokens minted here; a token with any other issuer is rejected
+pub const DEFAULT_JWT_ISSUER: &str = "auth-service";
+/// `aud` claim of tokens minted here; a token for any other audience is rejected
+pub const DEFAULT_JWT_AUDIENCE: &str = "auth-service";
+/// Seconds a token's `exp` and `nbf` may be off by and still be accepted, so clock skew
+/// between nodes does not reject a token as expired or not yet valid
+pub const DEFAULT_JWT_LEEWAY_SECONDS: u64 = 30;
+/// Migrate the database on every boot. Turn it off when several replicas start together and
+/// run `auth-service --migrate` as a separate deploy step instead.
+pub const DEFAULT_RUN_MIGRATIONS_ON_STARTUP: bool = true;
+/// `service.name` of exported spans, and the name of the tracer recording them
+pub const OTEL_SERVICE_NAME: &str = "auth-service";
 pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
+pub const DEFAULT_REDIS_PORT: u16 = 6379;
+/// Connections kept open by the Redis pool shared by every Redis-backed store
+pub const REDIS_POOL_MAX_SIZE: u32 = 10;
+
+/// Argon2id cost of new password hashes. Raising any of them makes each user's stored hash
+/// be recomputed with the new costs at their next login.
+pub const DEFAULT_ARGON2_MEMORY_KIB: u32 = 15_000;
+pub const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+pub const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
 
 /// This value determines how long the JWT auth token is valid for
 pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
 
+/// Shortest signing secret accepted as `JWT_SECRET` at startup and by `/admin/rotate-key`
+/// (256 bits, the size of the HS256 key)
+pub const MIN_JWT_SECRET_BYTES: usize = 32;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// How long a recorded `Idempotency-Key` response is replayed for
+pub const IDEMPOTENCY_KEY_TTL_SECONDS: u64 = 300; // 5 minutes
+
+/// Floor on `GET /signup/check-email` response time, so how long a lookup takes does not tell
+/// a registered email from a free one
+pub const CHECK_EMAIL_MIN_RESPONSE_MILLIS: u64 = 300;
+/// `GET /signup/check-email` lookups one client IP may make within
+/// `CHECK_EMAIL_WINDOW_SECONDS`
+pub const DEFAULT_MAX_EMAIL_CHECKS_PER_IP: u32 = 30;
+/// Sliding window email lookups are counted over
+pub const CHECK_EMAIL_WINDOW_SECONDS: i64 = 600; // 10 minutes
+
+/// Largest request body the API routes accept before answering 413
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 16 * 1024; // 16 KiB
+pub const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 10;
+pub const DEFAULT_LOGIN_ATTEMPT_TTL_SECONDS: u64 = 600; // 10 minutes, like the Redis 2FA code expiry
+
+/// How many previous passwords a user may not reuse when changing their password
+pub const DEFAULT_PASSWORD_HISTORY_DEPTH: usize = 5;
+
+/// Wrong passwords within `FAILED_LOGIN_WINDOW_SECONDS` before the owner is alerted
+pub const DEFAULT_FAILED_LOGIN_ALERT_THRESHOLD: u32 = 5;
+/// Wrong passwords for one account, from any number of IPs, within
+/// `FAILED_LOGIN_WINDOW_SECONDS` before the account is locked
+pub const DEFAULT_MAX_FAILED_LOGINS_PER_ACCOUNT: u32 = 20;
+/// Failed logins from one IP, across any number of accounts, within
+/// `FAILED_LOGIN_WINDOW_SECONDS` before its logins are refused
+pub const DEFAULT_MAX_FAILED_LOGINS_PER_IP: u32 = 100;
+/// How long each failed login keeps counting towards the alert threshold and the login limits
+/// after it happened (a sliding window); a lockout lasts until the count drops below the limit
+pub const FAILED_LOGIN_WINDOW_SECONDS: i64 = 3_600; // 1 hour
+/// Minimum time between two suspicious sign-in alerts to the same account
+pub const FAILED_LOGIN_ALERT_INTERVAL_SECONDS: i64 = 3_600; // 1 hour
+pub const FAILED_LOGIN_ALERT_SUBJECT: &str = "Suspicious sign-in attempts";
+
+/// Sign-in events kept per account for `/security/login-history`; older ones are dropped
+pub const AUDIT_LOG_MAX_EVENTS: usize = 100;
+/// How long an account's sign-in events are kept after its latest one
+pub const AUDIT_LOG_TTL_SECONDS: i64 = 7_776_000; // 90 days
+/// Events returned by `/security/login-history` when no `limit` is given, and the most it
+/// returns at once
+pub const DEFAULT_LOGIN_HISTORY_PAGE_SIZE: usize = 20;
+pub const MAX_LOGIN_HISTORY_PAGE_SIZE: usize = 100;
+/// How far back `/security/summary` counts wrong passwords
+pub const SECURITY_SUMMARY_FAILED_LOGINS_WINDOW_SECONDS: i64 = 604_800; // 7 days
+
+/// 2FA emails one address may be sent within `TWO_FA_EMAIL_WINDOW_SECONDS`
+pub const DEFAULT_MAX_2FA_EMAILS_PER_DAY: u32 = 10;
+/// Window the 2FA email cap is counted over, starting at the first email
+pub const TWO_FA_EMAIL_WINDOW_SECONDS: i64 = 86_400; // 1 day
+/// Code checks allowed against one pending 2FA code before it is discarded
+pub const DEFAULT_MAX_2FA_ATTEMPTS: u32 = 5;
+/// Wrong 2FA codes for one account, across all its login attempts, within
+/// `TWO_FA_FAILURE_WINDOW_SECONDS` before the account is locked
+pub const DEFAULT_MAX_2FA_FAILURES: u32 = 10;
+/// Window wrong 2FA codes are counted over, starting at the first; a lockout lasts until it
+/// ends
+pub const TWO_FA_FAILURE_WINDOW_SECONDS: i64 = 900; // 15 minutes
+
+/// Base URL of the auth service used in emailed links when `PUBLIC_URL` is unset
+pub const DEFAULT_PUBLIC_URL: &str = "http://localhost:8000";
+/// How long the confirmation link sent to a new email address stays valid
+pub const EMAIL_CHANGE_TOKEN_TTL_SECONDS: u64 = 3_600; // 1 hour
+pub const EMAIL_CHANGE_NOTICE_SUBJECT: &str = "Your email address is being changed";
+pub const EMAIL_CHANGE_CONFIRM_SUBJECT: &str = "Confirm your new email address";
+/// How long a magic login link stays valid when `MAGIC_LINK_TTL_SECONDS` is unset
+pub const DEFAULT_MAGIC_LINK_TTL_SECONDS: u64 = 900; // 15 minutes
+pub const MAGIC_LINK_SUBJECT: &str = "Your login link";
+/// `/magic-link/request` requests one email may get within `MAGIC_LINK_REQUEST_WINDOW_SECONDS`
+pub const DEFAULT_MAX_MAGIC_LINKS_PER_EMAIL: u32 = 5;
+/// `/magic-link/request` requests one client IP may make within
+/// `MAGIC_LINK_REQUEST_WINDOW_SECONDS`, for any emails
+pub const DEFAULT_MAX_MAGIC_LINKS_PER_IP: u32 = 20;
+/// Sliding window magic-link requests are counted over
+pub const MAGIC_LINK_REQUEST_WINDOW_SECONDS: i64 = 3_600; // 1 hour
+/// Sent instead of a 409 when `HIDE_SIGNUP_CONFLICTS` is set and an existing email signs up
+pub const SIGNUP_CONFLICT_SUBJECT: &str = "Someone tried to register with your email";
+/// Carries the temporary password set by `/admin/force-reset-password`
+pub const PASSWORD_RESET_BY_ADMIN_SUBJECT: &str = "Your password has been reset";
+
+/// Sends made for one email while the provider keeps failing transiently
+pub const EMAIL_SEND_ATTEMPTS: u32 = 3;
+/// Wait before the first resend, growing by this much on each further one
+pub const EMAIL_RETRY_BACKOFF_MILLIS: u64 = 200;
+
+/// Directory of the UI files when `ASSETS_DIR` is unset, relative to the working directory
+pub const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// How often the inactivity expiry job looks for idle accounts
+pub const INACTIVITY_EXPIRY_INTERVAL_SECONDS: u64 = 86_400; // 1 day
+
+/// Maximum number of accounts the inactivity expiry job soft-deletes per user store call
+pub const INACTIVITY_EXPIRY_BATCH_SIZE: u64 = 500;
+
+/// Header carrying the `sha256=<hex>` HMAC of a webhook body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const WEBHOOK_TIMEOUT_SECONDS: u64 = 5;
+
+/// reCAPTCHA; set `CAPTCHA_VERIFY_URL` to `https://api.hcaptcha.com/siteverify` for hCaptcha
+pub const DEFAULT_CAPTCHA_VERIFY_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+pub const CAPTCHA_TIMEOUT_SECONDS: u64 = 5;
+
 pub mod prod {
         pub const APP_ADDRESS: &str = "0.0.0.0:3000";
 }
@@ -72,3 +361,65 @@ pub mod prod {
 pub mod test {
         pub const APP_ADDRESS: &str = "127.0.0.1:0";
 }
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        /// A variable name no other test touches, so tests can set it in parallel
+        fn unique_var() -> String {
+                format!("TEST_SECRET_{}", uuid::Uuid::new_v4().simple()).to_uppercase()
+        }
+
+        fn write_temp_file(contents: &str) -> std::path::PathBuf {
+                let path = std::env::temp_dir().join(format!("secret-{}", uuid::Uuid::new_v4()));
+                std::fs::write(&path, contents).unwrap();
+                path
+        }
+
+        #[test]
+        fn get_secret_reads_and_trims_the_file() {
+                let var = unique_var();
+                let path = write_temp_file("from-file\n");
+                std::env::set_var(format!("{var}_FILE"), &path);
+
+                assert_eq!(get_secret(&var), "from-file");
+
+                std::fs::remove_file(path).unwrap();
+        }
+
+        #[test]
+        fn get_secret_prefers_the_file_over_the_variable() {
+                let var = unique_var();
+                let path = write_temp_file("from-file");
+                std::env::set_var(&var, "from-env");
+                std::env::set_var(format!("{var}_FILE"), &path);
+
+                assert_eq!(get_secret(&var), "from-file");
+
+                std::fs::remove_file(path).unwrap();
+        }
+
+        #[test]
+        fn get_secret_falls_back_to_the_variable() {
+                let var = unique_var();
+                std::env::set_var(&var, "from-env");
+
+                assert_eq!(get_secret(&var), "from-env");
+        }
+
+        #[test]
+        #[should_panic(expected = "_FILE must be set")]
+        fn get_secret_panics_when_neither_is_set() {
+                get_secret(&unique_var());
+        }
+
+        #[test]
+        #[should_panic(expected = "could not be read")]
+        fn get_secret_panics_on_a_missing_file() {
+                let var = unique_var();
+                std::env::set_var(format!("{var}_FILE"), "/nonexistent/secret");
+
+                get_secret(&var);
+        }
+}
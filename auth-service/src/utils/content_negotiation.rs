@@ -0,0 +1,128 @@
+// src/utils/content_negotiation.rs
+use axum::{
+        body::Body,
+        extract::Request,
+        http::{
+                header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
+                HeaderMap, HeaderValue,
+        },
+        middleware::Next,
+        response::Response,
+};
+
+/// Plain-text form of an `AuthAPIError`, carried as a response extension so the error can
+/// be re-rendered once the request's `Accept` header is known
+#[derive(Debug, Clone)]
+pub struct PlainTextError(pub String);
+
+/// Middleware that swaps an `AuthAPIError`'s JSON body for `text/plain` when the request
+/// prefers it. The status code and every other response is left untouched.
+pub async fn negotiate_error_format(request: Request, next: Next) -> Response {
+        let plain_text = prefers_plain_text(request.headers());
+        let response = next.run(request).await;
+        if !plain_text {
+                return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        match parts.extensions.remove::<PlainTextError>() {
+                Some(PlainTextError(message)) => {
+                        parts.headers.remove(CONTENT_LENGTH);
+                        parts.headers.insert(
+                                CONTENT_TYPE,
+                                HeaderValue::from_static("text/plain; charset=utf-8"),
+                        );
+                        Response::from_parts(parts, Body::from(message))
+                }
+                None => Response::from_parts(parts, body),
+        }
+}
+
+/// Whether `Accept` ranks `text/plain` strictly above JSON. Ties, including `*/*` and a
+/// missing header, keep the JSON default.
+pub fn prefers_plain_text(headers: &HeaderMap) -> bool {
+        let (mut plain, mut json) = (0.0_f32, 0.0_f32);
+
+        for (media_type, quality) in media_ranges(headers) {
+                match media_type.as_str() {
+                        "text/plain" | "text/*" => plain = plain.max(quality),
+                        "application/json" | "application/*" => json = json.max(quality),
+                        "*/*" => {
+                                plain = plain.max(quality);
+                                json = json.max(quality);
+                        }
+                        _ => {}
+                }
+        }
+
+        plain > json
+}
+
+/// Whether `Accept` explicitly lists `text/html`, as a browser navigating to a page does.
+/// A wildcard alone does not count, so API clients sending `*/*` are not served the UI.
+pub fn accepts_html(headers: &HeaderMap) -> bool {
+        media_ranges(headers)
+                .any(|(media_type, quality)| media_type == "text/html" && quality > 0.0)
+}
+
+/// Lowercased media type and `q` value (1.0 when absent) of each range in `Accept`
+fn media_ranges(headers: &HeaderMap) -> impl Iterator<Item = (String, f32)> + '_ {
+        headers.get_all(ACCEPT)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(|range| {
+                        let mut params = range.split(';');
+                        let media_type =
+                                params.next().unwrap_or_default().trim().to_ascii_lowercase();
+                        let quality = params
+                                .filter_map(|param| param.trim().strip_prefix("q="))
+                                .find_map(|q| q.trim().parse::<f32>().ok())
+                                .unwrap_or(1.0);
+                        (media_type, quality)
+                })
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        fn accept(value: &str) -> HeaderMap {
+                let mut headers = HeaderMap::new();
+                headers.insert(ACCEPT, HeaderValue::from_str(value).unwrap());
+                headers
+        }
+
+        #[test]
+        fn test_json_is_the_default() {
+                assert!(!prefers_plain_text(&HeaderMap::new()));
+                assert!(!prefers_plain_text(&accept("*/*")));
+                assert!(!prefers_plain_text(&accept("application/json")));
+                assert!(!prefers_plain_text(&accept("text/html")));
+        }
+
+        #[test]
+        fn test_plain_text_when_ranked_first() {
+                assert!(prefers_plain_text(&accept("text/plain")));
+                assert!(prefers_plain_text(&accept("text/*")));
+                assert!(prefers_plain_text(&accept("text/plain, */*;q=0.8")));
+                assert!(prefers_plain_text(&accept("application/json;q=0.5, text/plain")));
+        }
+
+        #[test]
+        fn test_ties_keep_json() {
+                assert!(!prefers_plain_text(&accept("text/plain, application/json")));
+                assert!(!prefers_plain_text(&accept("text/plain;q=0.9, application/json")));
+        }
+
+        #[test]
+        fn test_accepts_html_only_when_listed() {
+                assert!(accepts_html(&accept(
+                        "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+                )));
+                assert!(!accepts_html(&HeaderMap::new()));
+                assert!(!accepts_html(&accept("*/*")));
+                assert!(!accepts_html(&accept("application/json")));
+                assert!(!accepts_html(&accept("text/html;q=0")));
+        }
+}
@@ -0,0 +1,146 @@
+// src/utils/key_ring.rs
+use jsonwebtoken::{DecodingKey, EncodingKey};
+use sha2::{Digest, Sha256};
+
+use super::constants::JWT_SECRET;
+
+/// Hex characters of the secret's SHA-256 kept as its `kid`
+const KID_LENGTH: usize = 16;
+
+/// An HMAC secret that signs JWTs, named in each token's header by `kid`
+#[derive(Clone)]
+pub struct SigningKey {
+        kid: String,
+        secret: Vec<u8>,
+}
+
+impl SigningKey {
+        /// The `kid` is derived from the secret (a truncated SHA-256), so every instance, and
+        /// the same instance after a restart, names a secret the same way whatever order it
+        /// was loaded in. A truncated hash of a 32-byte or longer secret gives nothing away.
+        fn new(secret: impl Into<Vec<u8>>) -> Self {
+                let secret = secret.into();
+                let mut kid = hex::encode(Sha256::digest(&secret));
+                kid.truncate(KID_LENGTH);
+
+                Self {
+                        kid,
+                        secret,
+                }
+        }
+
+        pub fn kid(&self) -> &str {
+                &self.kid
+        }
+
+        pub fn encoding_key(&self) -> EncodingKey {
+                EncodingKey::from_secret(&self.secret)
+        }
+
+        pub fn decoding_key(&self) -> DecodingKey {
+                DecodingKey::from_secret(&self.secret)
+        }
+}
+
+impl std::fmt::Debug for SigningKey {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // Never display the secret
+                f.debug_struct("SigningKey")
+                        .field("kid", &self.kid)
+                        .field("secret", &"[REDACTED]")
+                        .finish()
+        }
+}
+
+/// The key new tokens are signed with, plus the key it replaced.
+///
+/// Tokens signed with the previous key keep validating until they expire, so rotating the
+/// secret logs nobody out. `AppState` holds the ring behind an `ArcSwap`, so a rotation
+/// replaces it as a whole without blocking requests.
+#[derive(Debug, Clone)]
+pub struct KeyRing {
+        current: SigningKey,
+        previous: Option<SigningKey>,
+}
+
+impl KeyRing {
+        pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+                Self {
+                        current: SigningKey::new(secret),
+                        previous: None,
+                }
+        }
+
+        /// A ring signing with `secret` that demotes the current key to previous. The old
+        /// previous key is dropped, so tokens signed with it stop validating.
+        pub fn rotate(&self, secret: impl Into<Vec<u8>>) -> Self {
+                Self {
+                        current: SigningKey::new(secret),
+                        previous: Some(self.current.clone()),
+                }
+        }
+
+        /// Key new tokens are signed with
+        pub fn current(&self) -> &SigningKey {
+                &self.current
+        }
+
+        /// Key a token with header `kid` was signed with, if it is still on the ring. Tokens
+        /// without a `kid` predate rotation and are checked against the current key.
+        pub fn find(&self, kid: Option<&str>) -> Option<&SigningKey> {
+                let Some(kid) = kid else {
+                        return Some(&self.current);
+                };
+
+                [Some(&self.current), self.previous.as_ref()]
+                        .into_iter()
+                        .flatten()
+                        .find(|key| key.kid == kid)
+        }
+}
+
+impl Default for KeyRing {
+        /// Signs with the configured `JWT_SECRET`
+        fn default() -> Self {
+                Self::new(JWT_SECRET.as_bytes())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_rotation_keeps_exactly_one_previous_key() {
+                let original = KeyRing::new("first-secret");
+                let first = original.current().kid().to_owned();
+
+                let rotated = original.rotate("second-secret");
+                let second = rotated.current().kid().to_owned();
+                assert_ne!(first, second);
+                assert!(rotated.find(Some(&first)).is_some());
+                assert!(rotated.find(Some(&second)).is_some());
+
+                let rotated_again = rotated.rotate("third-secret");
+                assert!(rotated_again.find(Some(&first)).is_none());
+                assert!(rotated_again.find(Some(&second)).is_some());
+        }
+
+        #[test]
+        fn test_kid_depends_only_on_the_secret() {
+                // Two instances rotating to the same secret, in any order, agree on its kid
+                let rotated = KeyRing::new("first-secret").rotate("second-secret");
+                let restarted = KeyRing::new("second-secret");
+                assert_eq!(rotated.current().kid(), restarted.current().kid());
+                assert_eq!(restarted.current().kid().len(), KID_LENGTH);
+        }
+
+        #[test]
+        fn test_token_without_kid_uses_the_current_key() {
+                let ring = KeyRing::new("first-secret").rotate("second-secret");
+                assert_eq!(
+                        ring.find(None).map(SigningKey::kid),
+                        Some(KeyRing::new("second-secret").current().kid())
+                );
+        }
+}
@@ -0,0 +1,70 @@
+// src/utils/maintenance.rs
+use std::sync::atomic::Ordering;
+
+use axum::{
+        extract::{Request, State},
+        http::Method,
+        middleware::Next,
+        response::{IntoResponse, Response},
+};
+
+use crate::{domain::AuthAPIError, AppState};
+
+/// Writes still served during maintenance: signing in and out, checking tokens, and the
+/// admin switch that ends maintenance
+const ALLOWED_WRITES: [&str; 7] = [
+        "/login",
+        "/magic-link/request",
+        "/logout",
+        "/verify-2fa",
+        "/verify-2fa/check",
+        "/verify-token",
+        "/admin/maintenance",
+];
+
+/// Reads that change account data, refused during maintenance like any other write
+const BLOCKED_READS: [&str; 1] = ["/change-email/confirm"];
+
+/// Middleware that answers 503 to every request that would change account data while
+/// `AppState::maintenance_mode` is on. Reads, health checks, and logins pass through.
+pub async fn maintenance_guard(
+        State(state): State<AppState>,
+        request: Request,
+        next: Next,
+) -> Response {
+        if state.maintenance_mode.load(Ordering::Relaxed)
+                && !is_allowed_during_maintenance(request.method(), request.uri().path())
+        {
+                return AuthAPIError::MaintenanceMode.into_response();
+        }
+
+        next.run(request).await
+}
+
+fn is_allowed_during_maintenance(method: &Method, path: &str) -> bool {
+        match *method {
+                Method::GET | Method::HEAD | Method::OPTIONS => !BLOCKED_READS.contains(&path),
+                _ => ALLOWED_WRITES.contains(&path),
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn logins_and_reads_are_allowed() {
+                assert!(is_allowed_during_maintenance(&Method::POST, "/login"));
+                assert!(is_allowed_during_maintenance(&Method::POST, "/verify-token"));
+                assert!(is_allowed_during_maintenance(&Method::GET, "/health/ready"));
+                assert!(is_allowed_during_maintenance(&Method::GET, "/sessions"));
+        }
+
+        #[test]
+        fn writes_are_refused() {
+                assert!(!is_allowed_during_maintenance(&Method::POST, "/signup"));
+                assert!(!is_allowed_during_maintenance(&Method::POST, "/change-password"));
+                assert!(!is_allowed_during_maintenance(&Method::DELETE, "/sessions/abc"));
+                assert!(!is_allowed_during_maintenance(&Method::GET, "/change-email/confirm"));
+        }
+}
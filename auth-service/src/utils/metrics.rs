@@ -0,0 +1,134 @@
+// src/utils/metrics.rs
+use std::{
+        fmt::Write,
+        sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Outcome of a `/verify-2fa` request, the `result` label of `auth_2fa_verifications_total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoFAVerificationResult {
+        Success,
+        /// Wrong code
+        Mismatch,
+        /// Right code, but the login attempt outlived `LOGIN_ATTEMPT_TTL_SECONDS`
+        Expired,
+        /// No code pending for the login attempt
+        NoActiveChallenge,
+        /// Refused because the account is locked, or this wrong code locked it, or the code has
+        /// had too many checks
+        Rejected,
+}
+
+impl TwoFAVerificationResult {
+        const ALL: [Self; 5] = [
+                Self::Success,
+                Self::Mismatch,
+                Self::Expired,
+                Self::NoActiveChallenge,
+                Self::Rejected,
+        ];
+
+        pub fn as_str(&self) -> &'static str {
+                match self {
+                        Self::Success => "success",
+                        Self::Mismatch => "mismatch",
+                        Self::Expired => "expired",
+                        Self::NoActiveChallenge => "no_active_challenge",
+                        Self::Rejected => "rejected",
+                }
+        }
+}
+
+/// Counters served at `/metrics`. They live in the process, so each instance reports its own
+/// and they restart from zero with it.
+#[derive(Debug, Default)]
+pub struct Metrics {
+        two_fa_challenges_started: AtomicU64,
+        two_fa_verifications: [AtomicU64; TwoFAVerificationResult::ALL.len()],
+}
+
+impl Metrics {
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        /// Count a 2FA code issued at login
+        pub fn record_2fa_challenge_started(&self) {
+                self.two_fa_challenges_started.fetch_add(1, Ordering::Relaxed);
+        }
+
+        pub fn record_2fa_verification(&self, result: TwoFAVerificationResult) {
+                self.two_fa_verifications[result as usize].fetch_add(1, Ordering::Relaxed);
+        }
+
+        pub fn two_fa_challenges_started(&self) -> u64 {
+                self.two_fa_challenges_started.load(Ordering::Relaxed)
+        }
+
+        pub fn two_fa_verifications(&self, result: TwoFAVerificationResult) -> u64 {
+                self.two_fa_verifications[result as usize].load(Ordering::Relaxed)
+        }
+
+        /// The counters in the Prometheus text format. `pending_2fa_challenges` is read from the
+        /// 2FA code store at scrape time; the gauge is left out when it is `None`.
+        pub fn render(&self, pending_2fa_challenges: Option<u64>) -> String {
+                let mut out = String::new();
+
+                let _ = writeln!(
+                        out,
+                        "# HELP auth_2fa_challenges_started_total 2FA codes issued at login."
+                );
+                let _ = writeln!(out, "# TYPE auth_2fa_challenges_started_total counter");
+                let _ = writeln!(
+                        out,
+                        "auth_2fa_challenges_started_total {}",
+                        self.two_fa_challenges_started()
+                );
+
+                let _ = writeln!(
+                        out,
+                        "# HELP auth_2fa_verifications_total /verify-2fa requests by outcome."
+                );
+                let _ = writeln!(out, "# TYPE auth_2fa_verifications_total counter");
+                for result in TwoFAVerificationResult::ALL {
+                        let _ = writeln!(
+                                out,
+                                "auth_2fa_verifications_total{{result=\"{}\"}} {}",
+                                result.as_str(),
+                                self.two_fa_verifications(result)
+                        );
+                }
+
+                if let Some(pending) = pending_2fa_challenges {
+                        let _ = writeln!(out, "# HELP auth_2fa_pending_challenges 2FA codes waiting to be verified.");
+                        let _ = writeln!(out, "# TYPE auth_2fa_pending_challenges gauge");
+                        let _ = writeln!(out, "auth_2fa_pending_challenges {}", pending);
+                }
+
+                out
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn render_reports_each_counter() {
+                let metrics = Metrics::new();
+                metrics.record_2fa_challenge_started();
+                metrics.record_2fa_challenge_started();
+                metrics.record_2fa_verification(TwoFAVerificationResult::Mismatch);
+
+                let rendered = metrics.render(Some(1));
+                assert!(rendered.contains("auth_2fa_challenges_started_total 2\n"));
+                assert!(rendered.contains("auth_2fa_verifications_total{result=\"mismatch\"} 1\n"));
+                assert!(rendered.contains("auth_2fa_verifications_total{result=\"success\"} 0\n"));
+                assert!(rendered.contains("auth_2fa_pending_challenges 1\n"));
+        }
+
+        #[test]
+        fn render_leaves_out_an_unknown_gauge() {
+                assert!(!Metrics::new().render(None).contains("auth_2fa_pending_challenges"));
+        }
+}
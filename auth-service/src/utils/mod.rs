@@ -1,10 +1,54 @@
 pub mod auth;
+pub mod config;
 pub mod constants;
+pub mod content_negotiation;
+pub mod email_template;
+pub mod key_ring;
+pub mod maintenance;
+pub mod metrics;
 pub mod tracing;
 
-use axum::routing::{get_service, MethodRouter};
+use std::path::{Path, PathBuf};
+
+use axum::{
+        extract::Request,
+        http::HeaderMap,
+        response::{IntoResponse, Response},
+        routing::{any, get, get_service, MethodRouter},
+};
 use tower_http::services::{ServeDir, ServeFile};
 
-pub fn fetch_assets() -> MethodRouter {
-        get_service(ServeDir::new("assets").not_found_service(ServeFile::new("assets/index.html")))
+use crate::domain::AuthAPIError;
+
+/// Static UI files. A path matching no file or route serves `index.html` only to a browser
+/// GET; any other request gets a JSON 404, so a mistyped API endpoint is reported as such.
+pub fn fetch_assets(assets_dir: &Path) -> MethodRouter {
+        let index = assets_dir.join("index.html");
+        let not_found = get(move |headers: HeaderMap, request: Request| {
+                handle_asset_not_found(index.clone(), headers, request)
+        });
+
+        get_service(ServeDir::new(assets_dir).not_found_service(not_found))
+                .fallback(handle_not_found)
+}
+
+/// Fallback when the UI is not served (`SERVE_UI=false`): every unmatched request, `/`
+/// included, gets a JSON 404 and no file is read from the assets directory
+pub fn api_only_fallback() -> MethodRouter {
+        any(handle_not_found)
+}
+
+async fn handle_asset_not_found(index: PathBuf, headers: HeaderMap, request: Request) -> Response {
+        if !content_negotiation::accepts_html(&headers) {
+                return AuthAPIError::NotFound.into_response();
+        }
+
+        match ServeFile::new(index).try_call(request).await {
+                Ok(response) => response.into_response(),
+                Err(_) => AuthAPIError::UnexpectedError.into_response(),
+        }
+}
+
+async fn handle_not_found() -> AuthAPIError {
+        AuthAPIError::NotFound
 }
@@ -1,11 +1,158 @@
 // src/utils/tracing.rs
-use axum::{body::Body, extract::Request, response::Response};
-use std::time::Duration;
-use tracing::{Level, Span};
-use tracing_subscriber::{fmt::time::UtcTime, EnvFilter};
+use axum::{body::Body, extract::Request, http::HeaderMap, response::Response};
+use opentelemetry::{global, propagation::Extractor, trace::TracerProvider as _};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use std::{str::FromStr, time::Duration};
+use tracing::{Level, Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::{
+        fmt::{time::UtcTime, MakeWriter},
+        layer::SubscriberExt,
+        registry::LookupSpan,
+        util::SubscriberInitExt,
+        EnvFilter,
+};
 
-pub fn init_tracing() {
-        tracing_subscriber::fmt().compact().with_max_level(Level::DEBUG).init();
+use crate::utils::constants::{
+        env::{LOG_FORMAT_ENV_VAR, OTEL_EXPORTER_OTLP_ENDPOINT_ENV_VAR},
+        OTEL_SERVICE_NAME,
+};
+
+/// How log events are written to stdout, chosen with `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+        /// Compact, human-readable lines
+        #[default]
+        Pretty,
+        /// One JSON object per event, for log aggregators
+        Json,
+}
+
+impl FromStr for LogFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                        "pretty" => Ok(Self::Pretty),
+                        "json" => Ok(Self::Json),
+                        other => Err(format!("Unknown log format: {other}")),
+                }
+        }
+}
+
+impl LogFormat {
+        /// `LOG_FORMAT`, or `Pretty` when unset. Panics on an unknown value.
+        pub fn from_env() -> Self {
+                match std::env::var(LOG_FORMAT_ENV_VAR) {
+                        Ok(value) if !value.trim().is_empty() => {
+                                value.trim().parse().unwrap_or_else(|e| {
+                                        panic!("{} has an invalid value: {}", LOG_FORMAT_ENV_VAR, e)
+                                })
+                        }
+                        _ => Self::default(),
+                }
+        }
+}
+
+/// Install the global subscriber. When `OTEL_EXPORTER_OTLP_ENDPOINT` is set, spans are also
+/// exported over OTLP/HTTP, and the returned provider should be shut down on exit so the
+/// last batch is flushed.
+pub fn init_tracing() -> Option<SdkTracerProvider> {
+        let tracer_provider = otlp_tracer_provider_from_env();
+        build_subscriber_with_tracer(
+                LogFormat::from_env(),
+                std::io::stdout,
+                tracer_provider.as_ref(),
+        )
+        .init();
+        tracer_provider
+}
+
+/// Subscriber writing DEBUG and above to `writer` in the given format. JSON events carry
+/// `timestamp`, `level`, `target`, `message` and their own fields at the top level, plus the
+/// enclosing span (e.g. the request ID) under `span`.
+pub fn build_subscriber<W>(format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+        build_subscriber_with_tracer(format, writer, None)
+}
+
+/// `build_subscriber`, also sending every span to `tracer_provider` when one is given
+pub fn build_subscriber_with_tracer<W>(
+        format: LogFormat,
+        writer: W,
+        tracer_provider: Option<&SdkTracerProvider>,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+        let builder = tracing_subscriber::fmt().with_max_level(Level::DEBUG).with_writer(writer);
+
+        match format {
+                LogFormat::Pretty => {
+                        Box::new(builder.compact().finish().with(otel_layer(tracer_provider)))
+                }
+                LogFormat::Json => Box::new(
+                        builder.json()
+                                .flatten_event(true)
+                                .with_current_span(true)
+                                .with_span_list(false)
+                                .finish()
+                                .with(otel_layer(tracer_provider)),
+                ),
+        }
+}
+
+fn otel_layer<S>(
+        tracer_provider: Option<&SdkTracerProvider>,
+) -> Option<OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+{
+        tracer_provider.map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer(OTEL_SERVICE_NAME))
+        })
+}
+
+/// OTLP/HTTP span export to `OTEL_EXPORTER_OTLP_ENDPOINT`, or `None` when it is unset. The
+/// exporter reads the endpoint (and any other `OTEL_EXPORTER_OTLP_*` settings) itself. Also
+/// installs the W3C trace-context propagator, so an incoming `traceparent` header continues
+/// the caller's trace. Panics when the exporter cannot be built.
+fn otlp_tracer_provider_from_env() -> Option<SdkTracerProvider> {
+        std::env::var(OTEL_EXPORTER_OTLP_ENDPOINT_ENV_VAR).ok().filter(|v| !v.trim().is_empty())?;
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .build()
+                .unwrap_or_else(|e| {
+                        panic!(
+                                "{} is set but OTLP export failed to start: {}",
+                                OTEL_EXPORTER_OTLP_ENDPOINT_ENV_VAR, e
+                        )
+                });
+        let tracer_provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(OTEL_SERVICE_NAME).build())
+                .build();
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        global::set_tracer_provider(tracer_provider.clone());
+
+        Some(tracer_provider)
+}
+
+/// Reads trace context (`traceparent`, `tracestate`) from request headers
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+                self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+                self.0.keys().map(|key| key.as_str()).collect()
+        }
 }
 
 // pub fn init_tracing() {
@@ -24,14 +171,23 @@ pub fn init_tracing() {
 pub fn make_span_with_request_id(request: &Request<Body>) -> Span {
         let request_id = uuid::Uuid::new_v4();
 
-        tracing::span!(
+        let span = tracing::span!(
                 Level::INFO,
                 "[REQUEST]",
                 method = tracing::field::display(request.method()),
                 uri = tracing::field::display(request.uri()),
                 version = tracing::field::debug(request.version()),
                 request_id = tracing::field::display(request_id)
-        )
+        );
+
+        // Continue the caller's trace, if it sent one. Without an exporter configured the
+        // propagator is a no-op and so is this.
+        let parent = global::get_text_map_propagator(|propagator| {
+                propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let _ = span.set_parent(parent);
+
+        span
 }
 
 // Logs an event indicating the start of a request
@@ -65,3 +221,132 @@ pub fn on_response(response: &Response, latency: Duration, _span: &Span) {
                 }
         };
 }
+
+/// Log sink shared between a subscriber and a test, e.g.
+/// `build_subscriber(LogFormat::Json, move || writer.clone())`
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl CapturedLogs {
+        pub(crate) fn contents(&self) -> String {
+                String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+}
+
+#[cfg(test)]
+impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+
+        #[test]
+        fn test_log_format_parses_known_values() {
+                assert_eq!("pretty".parse(), Ok(LogFormat::Pretty));
+                assert_eq!("json".parse(), Ok(LogFormat::Json));
+                assert!("xml".parse::<LogFormat>().is_err());
+        }
+
+        #[test]
+        fn test_json_format_writes_one_object_per_event() {
+                let logs = CapturedLogs::default();
+                let writer = logs.clone();
+                let subscriber = build_subscriber(LogFormat::Json, move || writer.clone());
+
+                tracing::subscriber::with_default(subscriber, || {
+                        tracing::info!(status = 200, "[REQUEST END]");
+                });
+
+                let logs = logs.contents();
+                let lines: Vec<&str> = logs.lines().collect();
+                assert_eq!(lines.len(), 1, "expected a single line: {logs}");
+
+                let event: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+                assert_eq!(event["level"], "INFO");
+                assert_eq!(event["message"], "[REQUEST END]");
+                assert_eq!(event["status"], 200);
+        }
+
+        #[test]
+        fn test_payload_debug_output_never_contains_secrets() {
+                let logs = CapturedLogs::default();
+                let writer = logs.clone();
+                let subscriber = build_subscriber(LogFormat::Json, move || writer.clone());
+
+                let login = crate::routes::LoginPayload::new(
+                        "user@example.com".to_owned(),
+                        "SuperSecret123".to_owned(),
+                );
+                let signup = crate::routes::SignupPayload::new(
+                        "user@example.com".to_owned(),
+                        "SuperSecret123".to_owned(),
+                        false,
+                )
+                .with_captcha_token("captcha-token-value");
+                tracing::subscriber::with_default(subscriber, || {
+                        tracing::debug!(?login, ?signup, "payloads");
+                });
+
+                let logs = logs.contents();
+                assert!(logs.contains("user@example.com"));
+                assert!(!logs.contains("SuperSecret123"), "password leaked: {logs}");
+                assert!(!logs.contains("captcha-token-value"), "token leaked: {logs}");
+        }
+
+        #[tokio::test]
+        async fn test_handled_request_exports_a_span_continuing_the_incoming_trace() {
+                use axum::{routing::get, Router};
+                use opentelemetry_sdk::trace::InMemorySpanExporterBuilder;
+                use tower::ServiceExt;
+                use tower_http::trace::TraceLayer;
+
+                let exporter = InMemorySpanExporterBuilder::new().build();
+                let provider =
+                        SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+                global::set_text_map_propagator(TraceContextPropagator::new());
+                let subscriber = build_subscriber_with_tracer(
+                        LogFormat::Json,
+                        std::io::sink,
+                        Some(&provider),
+                );
+                let _guard = tracing::subscriber::set_default(subscriber);
+
+                let app = Router::new()
+                        .route("/", get(|| async { "ok" }))
+                        .layer(TraceLayer::new_for_http()
+                                .make_span_with(make_span_with_request_id));
+                let request = Request::builder()
+                        .uri("/")
+                        .header(
+                                "traceparent",
+                                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                        )
+                        .body(Body::empty())
+                        .unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), 200);
+                drop(response);
+
+                let spans = exporter.get_finished_spans().unwrap();
+                let span = spans
+                        .iter()
+                        .find(|span| span.name == "[REQUEST]")
+                        .unwrap_or_else(|| panic!("no [REQUEST] span exported: {spans:?}"));
+                assert_eq!(
+                        span.span_context.trace_id().to_string(),
+                        "4bf92f3577b34da6a3ce929d0e0e4736"
+                );
+                assert_eq!(span.parent_span_id.to_string(), "00f067aa0ba902b7");
+        }
+}
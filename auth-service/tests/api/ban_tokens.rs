@@ -0,0 +1,77 @@
+use auth_service::routes::{
+        BanStatus, BanTokensPayload, BanTokensResponse, LoginPayload, SignupPayload,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+#[tokio::test]
+async fn should_report_a_status_for_each_item_in_the_batch() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let fresh = app.login(&get_random_email()).await?;
+        let already_banned = app.login(&get_random_email()).await?;
+        assert_eq!(app.post_logout_with_token(&already_banned).await?.status().as_u16(), 200);
+
+        let victim = get_random_email();
+        let victim_first = app.login(&victim).await?;
+        let victim_second = app.login(&victim).await?;
+
+        // After the logout above, which clears the app client's cookie
+        app.login_as_admin().await?;
+        let payload = BanTokensPayload {
+                tokens: vec![fresh.clone(), already_banned, String::new()],
+                emails: vec![victim.clone(), "not-an-email".to_owned()],
+        };
+        let response = app.post_ban_tokens(&payload).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        let body = response.json::<BanTokensResponse>().await?;
+        assert_eq!(
+                body.tokens,
+                vec![BanStatus::Banned, BanStatus::AlreadyBanned, BanStatus::Invalid]
+        );
+        assert_eq!(body.emails.len(), 2);
+        assert_eq!(body.emails[0].email, victim);
+        assert_eq!(body.emails[0].status, BanStatus::Banned);
+        assert_eq!(body.emails[0].sessions_ended, 2);
+        assert_eq!(body.emails[1].status, BanStatus::Invalid);
+
+        for token in [&fresh, &victim_first, &victim_second] {
+                assert_eq!(app.token_status(token).await?, 401);
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_403_for_non_admin() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        app.post_login(&LoginPayload::new(email, PASSWORD.to_owned())).await;
+        let token = app.login(&get_random_email()).await?;
+
+        let payload = BanTokensPayload {
+                tokens: vec![token.clone()],
+                ..BanTokensPayload::default()
+        };
+        let response = app.post_ban_tokens(&payload).await?;
+        assert_eq!(response.status().as_u16(), 403);
+        assert_eq!(app.token_status(&token).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,101 @@
+use auth_service::utils::constants::JWT_COOKIE_NAME;
+use reqwest::header::{AUTHORIZATION, COOKIE};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+async fn get_sessions_with_bearer(app: &TestApp, token: &str) -> TestResult<u16> {
+        let response = reqwest::Client::new()
+                .get(format!("{}/sessions", app.address))
+                .header(AUTHORIZATION, format!("Bearer {token}"))
+                .send()
+                .await?;
+        Ok(response.status().as_u16())
+}
+
+#[tokio::test]
+async fn should_authenticate_with_a_bearer_header_alone() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let token = app.login(&get_random_email()).await?;
+
+        assert_eq!(get_sessions_with_bearer(&app, &token).await?, 200);
+
+        // `/verify-token` takes the header in place of a body
+        let response = reqwest::Client::new()
+                .post(format!("{}/verify-token", app.address))
+                .header(AUTHORIZATION, format!("Bearer {token}"))
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_for_an_invalid_bearer_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        assert_eq!(get_sessions_with_bearer(&app, "invalid").await?, 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_prefer_the_bearer_header_over_the_cookie() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let token = app.login(&get_random_email()).await?;
+
+        let response = reqwest::Client::new()
+                .get(format!("{}/sessions", app.address))
+                .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                .header(AUTHORIZATION, "Bearer invalid")
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_ban_the_bearer_token_on_logout() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let bearer = app.login(&get_random_email()).await?;
+        let cookie = app.login(&get_random_email()).await?;
+
+        let response = reqwest::Client::new()
+                .post(format!("{}/logout", app.address))
+                .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, cookie))
+                .header(AUTHORIZATION, format!("Bearer {bearer}"))
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Only the presented token, the header's, is banned
+        assert_eq!(app.token_status(&bearer).await?, 401);
+        assert_eq!(app.token_status(&cookie).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
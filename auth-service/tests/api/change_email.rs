@@ -0,0 +1,121 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{ChangeEmailPayload, LoginPayload, SignupPayload},
+        utils::constants::{EMAIL_CHANGE_CONFIRM_SUBJECT, EMAIL_CHANGE_NOTICE_SUBJECT},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Sign up and log in a user so the client's cookie jar carries a valid JWT
+async fn signup_and_login(app: &TestApp, email: &str) {
+        let signup = SignupPayload::new(email.to_owned(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let login = LoginPayload::new(email.to_owned(), PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+}
+
+async fn login_status(app: &TestApp, email: &str) -> u16 {
+        let login = LoginPayload::new(email.to_owned(), PASSWORD.to_owned());
+        app.post_login(&login).await.status().as_u16()
+}
+
+/// Token from the confirmation link mailed to `new_email`
+fn confirmation_token(app: &TestApp, new_email: &str) -> String {
+        let sent = app.outbox.sent_to(new_email, EMAIL_CHANGE_CONFIRM_SUBJECT);
+        assert_eq!(sent.len(), 1);
+        sent[0].content.rsplit("token=").next().unwrap().trim().to_owned()
+}
+
+#[tokio::test]
+async fn should_notify_old_email_and_change_only_once_confirmed() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let old_email = get_random_email();
+        let new_email = get_random_email();
+        signup_and_login(&app, &old_email).await;
+
+        let payload = ChangeEmailPayload {
+                new_email: new_email.clone(),
+        };
+        assert_eq!(app.post_change_email(&payload).await?.status().as_u16(), 202);
+
+        // The old address is told immediately, before anything has changed
+        assert_eq!(app.outbox.sent_to(&old_email, EMAIL_CHANGE_NOTICE_SUBJECT).len(), 1);
+        assert_eq!(login_status(&app, &old_email).await, 200);
+        assert_ne!(login_status(&app, &new_email).await, 200);
+
+        let token = confirmation_token(&app, &new_email);
+        assert_eq!(app.get_confirm_email_change(&token).await?.status().as_u16(), 200);
+
+        // Every session of the old address was ended
+        let retry = app.post_change_email(&payload).await?;
+        assert_eq!(retry.status().as_u16(), 401);
+
+        assert_ne!(login_status(&app, &old_email).await, 200);
+        assert_eq!(login_status(&app, &new_email).await, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_for_an_unknown_or_used_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let new_email = get_random_email();
+        signup_and_login(&app, &get_random_email()).await;
+
+        let response = app.get_confirm_email_change("not-a-real-token").await?;
+        assert_eq!(response.status().as_u16(), 400);
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.error, "Invalid or expired email change token");
+
+        let payload = ChangeEmailPayload {
+                new_email: new_email.clone(),
+        };
+        assert_eq!(app.post_change_email(&payload).await?.status().as_u16(), 202);
+        let token = confirmation_token(&app, &new_email);
+        assert_eq!(app.get_confirm_email_change(&token).await?.status().as_u16(), 200);
+
+        // A token can be redeemed once
+        assert_eq!(app.get_confirm_email_change(&token).await?.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_409_if_new_email_is_taken() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let taken_email = get_random_email();
+        let signup = SignupPayload::new(taken_email.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+        let old_email = get_random_email();
+        signup_and_login(&app, &old_email).await;
+
+        let payload = ChangeEmailPayload {
+                new_email: taken_email.clone(),
+        };
+        assert_eq!(app.post_change_email(&payload).await?.status().as_u16(), 409);
+        assert!(app.outbox.sent_to(&old_email, EMAIL_CHANGE_NOTICE_SUBJECT).is_empty());
+        assert!(app.outbox.sent_to(&taken_email, EMAIL_CHANGE_CONFIRM_SUBJECT).is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,132 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{ChangePasswordPayload, LoginPayload, SignupPayload},
+        utils::config::AppConfig,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+/// Sign up and log in a user so the client's cookie jar carries a valid JWT
+async fn signup_and_login(app: &TestApp, email: &str, password: &str) {
+        let signup = SignupPayload::new(email.to_owned(), password.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let login = LoginPayload::new(email.to_owned(), password.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+}
+
+async fn change_password(app: &TestApp, current: &str, new: &str) -> TestResult<u16> {
+        let payload = ChangePasswordPayload::new(current.to_owned(), new.to_owned());
+        Ok(app.post_change_password(&payload).await?.status().as_u16())
+}
+
+#[tokio::test]
+async fn should_return_200_and_allow_login_with_new_password() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        signup_and_login(&app, &email, "FirstPassword1").await;
+
+        assert_eq!(change_password(&app, "FirstPassword1", "SecondPassword1").await?, 200);
+
+        let old_login = LoginPayload::new(email.clone(), "FirstPassword1".to_owned());
+        assert_eq!(app.post_login(&old_login).await.status().as_u16(), 401);
+
+        let new_login = LoginPayload::new(email, "SecondPassword1".to_owned());
+        assert_eq!(app.post_login(&new_login).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_password_was_used_recently() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                password_history_depth: 1,
+                ..AppConfig::default()
+        })
+        .await?;
+        signup_and_login(&app, &get_random_email(), "FirstPassword1").await;
+
+        assert_eq!(change_password(&app, "FirstPassword1", "SecondPassword1").await?, 200);
+
+        // The immediately-previous password is in the history
+        let payload = ChangePasswordPayload::new(
+                "SecondPassword1".to_owned(),
+                "FirstPassword1".to_owned(),
+        );
+        let response = app.post_change_password(&payload).await?;
+        assert_eq!(response.status().as_u16(), 400);
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.error, "Password was used recently");
+
+        // Re-using the current password is rejected as well
+        assert_eq!(change_password(&app, "SecondPassword1", "SecondPassword1").await?, 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_allow_password_older_than_history_depth() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                password_history_depth: 1,
+                ..AppConfig::default()
+        })
+        .await?;
+        signup_and_login(&app, &get_random_email(), "FirstPassword1").await;
+
+        assert_eq!(change_password(&app, "FirstPassword1", "SecondPassword1").await?, 200);
+        assert_eq!(change_password(&app, "SecondPassword1", "ThirdPassword1").await?, 200);
+
+        // "FirstPassword1" has been trimmed from a history of depth 1
+        assert_eq!(change_password(&app, "ThirdPassword1", "FirstPassword1").await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_current_password_is_wrong() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        signup_and_login(&app, &get_random_email(), "FirstPassword1").await;
+
+        assert_eq!(change_password(&app, "WrongPassword1", "SecondPassword1").await?, 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        assert_eq!(change_password(&app, "FirstPassword1", "SecondPassword1").await?, 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
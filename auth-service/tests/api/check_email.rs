@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use auth_service::{
+        domain::ErrorResponse,
+        routes::CheckEmailResponse,
+        utils::{config::AppConfig, constants::CHECK_EMAIL_MIN_RESPONSE_MILLIS},
+};
+
+use crate::{get_random_email, SignupPayload, TestApp, TestResult};
+
+#[tokio::test]
+async fn should_report_unregistered_email_as_available() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let started = Instant::now();
+        let response = app.get_check_email(&get_random_email()).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+                response.json::<CheckEmailResponse>().await?,
+                CheckEmailResponse {
+                        available: true
+                }
+        );
+
+        // The response is padded whether or not the email exists
+        assert!(started.elapsed() >= Duration::from_millis(CHECK_EMAIL_MIN_RESPONSE_MILLIS));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_registered_email_as_taken() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), "ValidPassword123".to_owned(), false))
+                .await;
+
+        let started = Instant::now();
+        let response = app.get_check_email(&email).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+                response.json::<CheckEmailResponse>().await?,
+                CheckEmailResponse {
+                        available: false
+                }
+        );
+        assert!(started.elapsed() >= Duration::from_millis(CHECK_EMAIL_MIN_RESPONSE_MILLIS));
+
+        // The same address in another case belongs to the same account
+        let response = app.get_check_email(&email.to_uppercase()).await?;
+        assert_eq!(
+                response.json::<CheckEmailResponse>().await?,
+                CheckEmailResponse {
+                        available: false
+                }
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_registered_email_as_available_when_conflicts_are_hidden() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                hide_signup_conflicts: true,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), "ValidPassword123".to_owned(), false))
+                .await;
+
+        let started = Instant::now();
+        let response = app.get_check_email(&email).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+                response.json::<CheckEmailResponse>().await?,
+                CheckEmailResponse {
+                        available: true
+                }
+        );
+        assert!(started.elapsed() >= Duration::from_millis(CHECK_EMAIL_MIN_RESPONSE_MILLIS));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_email_is_malformed() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_check_email("not an email").await?;
+        assert_eq!(response.status().as_u16(), 400);
+        assert_eq!(response.json::<ErrorResponse>().await?.error, "Invalid credentials");
+
+        // Missing parameter
+        let response =
+                app.http_client.get(format!("{}/signup/check-email", app.address)).send().await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_429_past_the_per_ip_lookup_limit() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                max_email_checks_per_ip: Some(2),
+                ..AppConfig::default()
+        })
+        .await?;
+
+        for _ in 0..2 {
+                let response = app.get_check_email(&get_random_email()).await?;
+                assert_eq!(response.status().as_u16(), 200);
+        }
+
+        // Whatever email is asked about next, the IP has used up its lookups
+        let response = app.get_check_email(&get_random_email()).await?;
+        assert_eq!(response.status().as_u16(), 429);
+        assert_eq!(response.json::<ErrorResponse>().await?.code.as_deref(), Some("rate_limited"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
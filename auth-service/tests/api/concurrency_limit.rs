@@ -0,0 +1,41 @@
+use auth_service::utils::config::AppConfig;
+
+use crate::{TestApp, TestResult};
+
+#[tokio::test]
+async fn should_return_503_when_more_requests_arrive_than_the_limit() -> TestResult<()> {
+        // Refused logins are held for the login delay, keeping both slots busy
+        let app = TestApp::with_config(AppConfig {
+                max_concurrent_requests: Some(2),
+                login_min_delay_ms: 1000,
+                login_max_delay_ms: 1000,
+                ..AppConfig::default()
+        })
+        .await?;
+        let body = serde_json::json!({
+                "email": "nobody@example.com",
+                "password": "WrongPassword123"
+        });
+
+        let (a, b, c, d) = tokio::join!(
+                app.post_login(&body),
+                app.post_login(&body),
+                app.post_login(&body),
+                app.post_login(&body)
+        );
+        let statuses: Vec<u16> = [a, b, c, d].iter().map(|res| res.status().as_u16()).collect();
+        assert!(statuses.contains(&503), "No request was shed: {statuses:?}");
+        assert!(statuses.iter().all(|status| [401, 503].contains(status)), "{statuses:?}");
+
+        // Once the slow requests finish, the next one is served again
+        let res = app.post_login(&body).await;
+        assert_eq!(res.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
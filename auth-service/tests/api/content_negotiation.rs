@@ -0,0 +1,74 @@
+use auth_service::{domain::ErrorResponse, routes::SignupPayload};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+async fn post_logout_accepting(
+        app: &TestApp,
+        accept: Option<&str>,
+) -> TestResult<reqwest::Response> {
+        let mut request = app.http_client.post(format!("{}/logout", app.address));
+        if let Some(accept) = accept {
+                request = request.header(ACCEPT, accept);
+        }
+        Ok(request.send().await?)
+}
+
+fn content_type(response: &reqwest::Response) -> String {
+        response.headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_owned()
+}
+
+#[tokio::test]
+async fn should_return_json_errors_by_default() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        for accept in [None, Some("*/*"), Some("application/json")] {
+                let res = post_logout_accepting(&app, accept).await?;
+                assert_eq!(res.status().as_u16(), 400);
+                assert!(content_type(&res).starts_with("application/json"), "Accept: {accept:?}");
+                assert_eq!(res.json::<ErrorResponse>().await?.error, "Missing JWT auth token");
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_plain_text_errors_when_preferred() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let res = post_logout_accepting(&app, Some("text/plain")).await?;
+        assert_eq!(res.status().as_u16(), 400);
+        assert!(content_type(&res).starts_with("text/plain"));
+        assert_eq!(res.text().await?, "Missing JWT auth token");
+
+        // Validation errors list each failing field on its own line
+        let res = app
+                .http_client
+                .post(format!("{}/signup", app.address))
+                .header(ACCEPT, "text/plain")
+                .json(&SignupPayload::new(get_random_email(), "short".to_owned(), false))
+                .send()
+                .await?;
+        assert_eq!(res.status().as_u16(), 400);
+        assert!(content_type(&res).starts_with("text/plain"));
+        let body = res.text().await?;
+        assert!(body.starts_with("Invalid credentials\npassword: "), "{body}");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,65 @@
+use auth_service::{
+        domain::AuditEventKind,
+        routes::{DataExport, LoginPayload, SignupPayload},
+};
+use reqwest::header::CONTENT_DISPOSITION;
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+#[tokio::test]
+async fn should_export_the_account_and_its_login_without_password_material() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        let response = app.post_login(&LoginPayload::new(email.clone(), PASSWORD.to_owned())).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        let response = app.get_data_export().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response
+                .headers()
+                .get(CONTENT_DISPOSITION)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.starts_with("attachment")));
+
+        let body = response.text().await?;
+        assert!(!body.contains("$argon2"), "password hash exported: {body}");
+        assert!(!body.contains(PASSWORD), "password exported: {body}");
+
+        let export = serde_json::from_str::<DataExport>(&body)?;
+        assert_eq!(export.account.email, email);
+        assert_eq!(
+                export.login_history
+                        .iter()
+                        .filter(|event| event.kind == AuditEventKind::LoginSuccess)
+                        .count(),
+                1
+        );
+        assert_eq!(export.sessions.len(), 1);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_without_a_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_data_export().await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
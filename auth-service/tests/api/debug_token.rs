@@ -0,0 +1,130 @@
+use auth_service::{
+        domain::Email,
+        routes::TokenIntrospection,
+        utils::{auth::generate_auth_token, config::AppConfig, constants::JWT_COOKIE_NAME},
+};
+use reqwest::header::{AUTHORIZATION, COOKIE};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+fn debug_config() -> AppConfig {
+        AppConfig {
+                debug_endpoints: true,
+                ..AppConfig::default()
+        }
+}
+
+#[tokio::test]
+async fn should_return_404_when_debug_endpoints_are_disabled() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let token = generate_auth_token(
+                &Email::parse(&get_random_email()).expect("valid email"),
+                &app.key_ring.load(),
+        )
+        .expect("token");
+
+        let res = app
+                .http_client
+                .get(format!("{}/debug/token", app.address))
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .send()
+                .await?;
+        assert_eq!(res.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_decoded_claims_for_a_bearer_token() -> TestResult<()> {
+        let app = TestApp::with_config(debug_config()).await?;
+        let email = get_random_email();
+        let token = generate_auth_token(
+                &Email::parse(&email).expect("valid email"),
+                &app.key_ring.load(),
+        )
+        .expect("token");
+
+        let res = app
+                .http_client
+                .get(format!("{}/debug/token", app.address))
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .send()
+                .await?;
+        assert_eq!(res.status().as_u16(), 200);
+
+        let body = res.json::<TokenIntrospection>().await?;
+        assert_eq!(body.claims["sub"], email);
+        assert!(body.claims["jti"].is_string());
+        assert_eq!(body.algorithm, "HS256");
+        assert!(body.signature_valid);
+        assert_eq!(body.expired, Some(false));
+        assert!(!body.banned);
+
+        // The scheme is read like on every other route, whatever its case
+        let res = app
+                .http_client
+                .get(format!("{}/debug/token", app.address))
+                .header(AUTHORIZATION, format!("bearer {}", token))
+                .send()
+                .await?;
+        assert_eq!(res.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_a_bad_signature_instead_of_returning_401() -> TestResult<()> {
+        let app = TestApp::with_config(debug_config()).await?;
+        let token = generate_auth_token(
+                &Email::parse(&get_random_email()).expect("valid email"),
+                &app.key_ring.load(),
+        )
+        .expect("token");
+        let (unsigned, _) = token.rsplit_once('.').expect("three-part token");
+        let forged = format!("{}.c2lnbmF0dXJl", unsigned);
+
+        let res = app
+                .http_client
+                .get(format!("{}/debug/token", app.address))
+                .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, forged))
+                .send()
+                .await?;
+        assert_eq!(res.status().as_u16(), 200);
+        assert!(!res.json::<TokenIntrospection>().await?.signature_valid);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_without_a_token() -> TestResult<()> {
+        let app = TestApp::with_config(debug_config()).await?;
+
+        let res = app.http_client.get(format!("{}/debug/token", app.address)).send().await?;
+        assert_eq!(res.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,98 @@
+use auth_service::utils::{config::AppConfig, constants::FAILED_LOGIN_ALERT_SUBJECT};
+
+use crate::{get_random_email, LoginPayload, SignupPayload, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+const WRONG_PASSWORD: &str = "WrongPassword123";
+
+async fn spawn_with_alert_threshold(threshold: u32) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                failed_login_alert_threshold: Some(threshold),
+                ..AppConfig::default()
+        })
+        .await
+}
+
+async fn fail_login(app: &TestApp, email: &str) {
+        let response = app
+                .post_login(&LoginPayload::new(email.to_owned(), WRONG_PASSWORD.to_owned()))
+                .await;
+        assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn crossing_the_threshold_sends_exactly_one_alert() -> TestResult<()> {
+        let app = spawn_with_alert_threshold(3).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        for _ in 0..2 {
+                fail_login(&app, &email).await;
+        }
+        assert!(
+                app.outbox.sent_to(&email, FAILED_LOGIN_ALERT_SUBJECT).is_empty(),
+                "No alert below the threshold"
+        );
+
+        fail_login(&app, &email).await;
+        let alerts = app.outbox.sent_to(&email, FAILED_LOGIN_ALERT_SUBJECT);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].content.contains("3 failed attempts"));
+
+        // Further failures within the hour are throttled
+        for _ in 0..3 {
+                fail_login(&app, &email).await;
+        }
+        assert_eq!(app.outbox.sent_to(&email, FAILED_LOGIN_ALERT_SUBJECT).len(), 1);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn successful_login_resets_the_failure_count() -> TestResult<()> {
+        let app = spawn_with_alert_threshold(3).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        for _ in 0..2 {
+                fail_login(&app, &email).await;
+        }
+        let response = app.post_login(&LoginPayload::new(email.clone(), PASSWORD.to_owned())).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        for _ in 0..2 {
+                fail_login(&app, &email).await;
+        }
+        assert!(app.outbox.sent_to(&email, FAILED_LOGIN_ALERT_SUBJECT).is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn unknown_accounts_are_never_alerted() -> TestResult<()> {
+        let app = spawn_with_alert_threshold(1).await?;
+        let email = get_random_email();
+
+        fail_login(&app, &email).await;
+        assert!(app.outbox.sent_to(&email, FAILED_LOGIN_ALERT_SUBJECT).is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
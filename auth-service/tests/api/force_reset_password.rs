@@ -0,0 +1,204 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{
+                ForceResetPasswordPayload, LoginPayload, PasswordChangeRequiredResponse,
+                RequiredPasswordChangePayload, SignupPayload, VerifyTokenPayload,
+                PASSWORD_CHANGE_REQUIRED_CODE,
+        },
+        utils::constants::{JWT_COOKIE_NAME, PASSWORD_RESET_BY_ADMIN_SUBJECT},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+const NEW_PASSWORD: &str = "FreshPassword456";
+
+#[tokio::test]
+async fn force_reset_user_must_change_password_at_next_login() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        app.login_as_admin().await?;
+        let payload = ForceResetPasswordPayload::new(email.clone());
+        let response = app.post_force_reset_password(&payload).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        let sent = app.outbox.sent_to(&email, PASSWORD_RESET_BY_ADMIN_SUBJECT);
+        assert_eq!(sent.len(), 1, "The temporary password should be emailed");
+        let temporary_password = sent[0].content.lines().last().unwrap_or_default().to_owned();
+
+        // The old password no longer works
+        let login = LoginPayload::new(email.clone(), PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 401);
+
+        // The temporary one only gets a prompt to change it
+        let login = LoginPayload::new(email.clone(), temporary_password.clone());
+        let response = app.post_login(&login).await;
+        assert_eq!(response.status().as_u16(), 403);
+        assert!(
+                response.cookies().all(|cookie| cookie.value().is_empty()),
+                "No session should be issued before the password is changed"
+        );
+        let prompt = response.json::<PasswordChangeRequiredResponse>().await?;
+        assert_eq!(prompt.code, PASSWORD_CHANGE_REQUIRED_CODE);
+
+        // Keeping the temporary password is refused
+        let change =
+                RequiredPasswordChangePayload::new(prompt.change_token.clone(), temporary_password);
+        let response = app.post_required_password_change(&change).await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        let change = RequiredPasswordChangePayload::new(
+                prompt.change_token.clone(),
+                NEW_PASSWORD.to_owned(),
+        );
+        let response = app.post_required_password_change(&change).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // The change token is spent once the password is changed
+        let response = app.post_required_password_change(&change).await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        let login = LoginPayload::new(email, NEW_PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn change_token_is_void_after_another_reset() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        app.login_as_admin().await?;
+        let mut change_tokens = Vec::new();
+        for reset in 1..=2 {
+                let payload = ForceResetPasswordPayload::new(email.clone());
+                assert_eq!(app.post_force_reset_password(&payload).await?.status().as_u16(), 200);
+
+                let sent = app.outbox.sent_to(&email, PASSWORD_RESET_BY_ADMIN_SUBJECT);
+                assert_eq!(sent.len(), reset);
+                let temporary_password =
+                        sent[reset - 1].content.lines().last().unwrap_or_default().to_owned();
+
+                let login = LoginPayload::new(email.clone(), temporary_password);
+                let response = app.post_login(&login).await;
+                assert_eq!(response.status().as_u16(), 403);
+                let prompt = response.json::<PasswordChangeRequiredResponse>().await?;
+                change_tokens.push(prompt.change_token);
+        }
+
+        // The token from the first reset is still unexpired, but its password is gone
+        let change = RequiredPasswordChangePayload::new(
+                change_tokens[0].clone(),
+                NEW_PASSWORD.to_owned(),
+        );
+        let response = app.post_required_password_change(&change).await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        let change = RequiredPasswordChangePayload::new(
+                change_tokens[1].clone(),
+                NEW_PASSWORD.to_owned(),
+        );
+        let response = app.post_required_password_change(&change).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn force_reset_should_end_the_accounts_sessions() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let response = app.post_login(&LoginPayload::new(email.clone(), PASSWORD.to_owned())).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let token = response
+                .cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie should be set")
+                .value()
+                .to_owned();
+
+        app.login_as_admin().await?;
+        let payload = ForceResetPasswordPayload::new(email);
+        assert_eq!(app.post_force_reset_password(&payload).await?.status().as_u16(), 200);
+
+        let response = app.post_verify_token(&VerifyTokenPayload::new(token)).await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_403_if_caller_is_not_admin() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let target = get_random_email();
+        let signup = SignupPayload::new(target.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        let _ = app.post_signup(&signup).await;
+        let _ = app.post_login(&LoginPayload::new(email, PASSWORD.to_owned())).await;
+
+        let payload = ForceResetPasswordPayload::new(target.clone());
+        let response = app.post_force_reset_password(&payload).await?;
+        assert_eq!(response.status().as_u16(), 403);
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.error, "Forbidden");
+
+        // The target's password is untouched
+        let login = LoginPayload::new(target, PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_404_if_account_does_not_exist() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        app.login_as_admin().await?;
+
+        let payload = ForceResetPasswordPayload::new(get_random_email());
+        let response = app.post_force_reset_password(&payload).await?;
+        assert_eq!(response.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,52 @@
+use auth_service::{
+        domain::UserStore, routes::ReadinessResponse,
+        services::data_stores::postgres_user_store::PostgresUserStore,
+        utils::constants::DATABASE_URL,
+};
+use sqlx::postgres::PgPoolOptions;
+
+use crate::{TestApp, TestResult};
+
+#[tokio::test]
+async fn should_return_200_when_user_store_is_reachable() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let res = app.get_readiness().await?;
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.json::<ReadinessResponse>().await?.status, "ready");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn postgres_ping_succeeds_against_a_live_pool() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // TestApp always runs against PostgreSQL
+        assert!(app.user_store.read().await.ping().await.is_ok());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn postgres_ping_fails_against_a_closed_pool() -> TestResult<()> {
+        let pool = PgPoolOptions::new().connect_lazy(&DATABASE_URL)?;
+        pool.close().await;
+
+        let store = PostgresUserStore::new(pool);
+        assert!(store.ping().await.is_err());
+
+        Ok(())
+}
@@ -1,18 +1,35 @@
+use async_trait::async_trait;
 use auth_service::{
-        domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore},
-        get_two_fa_code_store,
+        domain::{
+                BannedTokenStore, Email, EmailClient, EmailClientError, HashedPassword, Role,
+                TwoFACodeStore, User, UserStore,
+        },
+        get_redis_pool, get_two_fa_code_store,
         routes::{LoginPayload, SignupPayload, Verify2FAPayload, VerifyTokenPayload},
-        services::data_stores::{
-                postgres_user_store::PostgresUserStore, HashmapTwoFACodeStore,
-                HashsetBannedTokenStore, MockEmailClient,
+        services::{
+                data_stores::{
+                        postgres_user_store::PostgresUserStore, HashmapAuditLogStore,
+                        HashmapFailedLoginStore, HashmapIdempotencyStore, HashmapMagicLinkStore,
+                        HashmapPendingEmailChangeStore, HashmapRateLimitStore, HashmapSessionStore,
+                        HashmapTwoFACodeStore, HashsetBannedTokenStore,
+                },
+                webhook_notifier::WebhookNotifier,
+        },
+        utils::{
+                config::AppConfig,
+                constants::{DATABASE_URL, JWT_COOKIE_NAME},
         },
-        utils::constants::DATABASE_URL,
-        AppState, AppStateBuilder, Application, BannedTokenStoreType, EmailClientType,
-        TwoFACodeStoreType,
+        AppState, AppStateBuilder, Application, AuditLogStoreType, BannedTokenStoreType,
+        CaptchaVerifierType, EmailClientType, FailedLoginStoreType, IdempotencyStoreType,
+        KeyRingType, MxResolverType, PendingEmailChangeStoreType, SessionStoreType,
+        TwoFACodeStoreType, UserStoreType,
 };
 use axum_extra::extract::CookieJar;
 use core::panic;
-use reqwest::cookie::Jar;
+use reqwest::{
+        cookie::Jar,
+        header::{COOKIE, USER_AGENT},
+};
 use sqlx::{
         postgres::{PgConnectOptions, PgPoolOptions},
         Connection, Executor, PgConnection,
@@ -24,19 +41,63 @@ type TestAppResult = core::result::Result<reqwest::Response, Box<dyn std::error:
 
 pub struct TestApp {
         pub address: String,
-        pub test_db_name: String,
+        /// `None` when the test supplied its own user store
+        pub test_db: Option<TestDb>,
         pub cookie_jar: Arc<Jar>,
+        pub user_store: UserStoreType,
         pub banned_token_store: BannedTokenStoreType,
         pub two_fa_code_store: TwoFACodeStoreType,
         pub email_client: EmailClientType,
+        /// The app's JWT signing keys, for minting tokens it will accept
+        pub key_ring: KeyRingType,
+        /// Every email the app has sent
+        pub outbox: RecordingEmailClient,
         pub http_client: reqwest::Client,
-        pub clean_up_called: bool,
 }
 
-impl Drop for TestApp {
+/// A uniquely named Postgres database for one test. Dropping the guard drops the database,
+/// along with any connections still open to it, so no test leaves a database behind.
+#[derive(Debug)]
+pub struct TestDb {
+        name: String,
+}
+
+impl TestDb {
+        pub async fn create() -> Self {
+                let name = uuid::Uuid::new_v4().to_string();
+                create_database(&DATABASE_URL, &name).await;
+                Self {
+                        name,
+                }
+        }
+
+        pub fn name(&self) -> &str {
+                &self.name
+        }
+
+        /// Connection pool to the database, with migrations applied
+        pub async fn pool(&self) -> sqlx::PgPool {
+                get_test_db_pool(&DATABASE_URL, &self.name).await
+        }
+}
+
+impl Drop for TestDb {
         fn drop(&mut self) {
-                if !self.clean_up_called {
-                        panic!("Test Database must be cleaned before dropped");
+                // `Drop` cannot await, and blocking on the test's own runtime would deadlock,
+                // so the database is dropped from a runtime on a separate thread
+                let name = std::mem::take(&mut self.name);
+                let result = std::thread::spawn(move || {
+                        tokio::runtime::Builder::new_current_thread()
+                                .enable_all()
+                                .build()
+                                .expect("Failed to build a runtime to drop the test database")
+                                .block_on(delete_database(&name))
+                })
+                .join();
+
+                // Don't panic while already unwinding from a failed test
+                if result.is_err() && !std::thread::panicking() {
+                        panic!("Failed to drop the test database");
                 }
         }
 }
@@ -73,24 +134,79 @@ async fn create_database(postgresql_conn_url: &str, db_name: &str) {
 
 impl TestApp {
         pub async fn new() -> Result<Self, Box<dyn Error>> {
-                let test_db_name = uuid::Uuid::new_v4().to_string();
-                let clean_up_called = false;
-                let postgresql_conn_url: String = DATABASE_URL.to_owned();
-                create_database(&postgresql_conn_url, &test_db_name).await;
-                let test_db_pool = get_test_db_pool(&postgresql_conn_url, &test_db_name).await;
-                let user_store: Arc<RwLock<Box<dyn UserStore + Send + Sync>>> =
-                        Arc::new(RwLock::new(Box::new(PostgresUserStore::new(test_db_pool))));
-                let banned_token_store: Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>> =
-                        Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::new())));
-                let two_fa_code_store = get_two_fa_code_store();
-                let email_client: Arc<dyn EmailClient + Send + Sync> = Arc::new(MockEmailClient);
-
-                let app_state = AppStateBuilder::new()
-                        .user_store(user_store)
+                Self::with_config(AppConfig::default()).await
+        }
+
+        /// Spawn the app with explicit runtime settings instead of the defaults
+        pub async fn with_config(config: AppConfig) -> Result<Self, Box<dyn Error>> {
+                Self::builder().config(config).build().await
+        }
+
+        /// Spawn the app with any store, email client, or setting swapped out
+        pub fn builder() -> TestAppBuilder {
+                TestAppBuilder::default()
+        }
+
+        async fn spawn(builder: TestAppBuilder) -> Result<Self, Box<dyn Error>> {
+                // A fresh Postgres database per test, unless the test brings its own user store
+                let (user_store, test_db) = match builder.user_store {
+                        Some(user_store) => (user_store, None),
+                        None => {
+                                let test_db = TestDb::create().await;
+                                let user_store: UserStoreType = Arc::new(RwLock::new(Box::new(
+                                        PostgresUserStore::new(test_db.pool().await),
+                                )));
+                                (user_store, Some(test_db))
+                        }
+                };
+                let banned_token_store = builder.banned_token_store.unwrap_or_else(|| {
+                        Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::new())))
+                });
+                let two_fa_code_store = builder
+                        .two_fa_code_store
+                        .unwrap_or_else(|| get_two_fa_code_store(get_redis_pool()));
+                let idempotency_store: IdempotencyStoreType =
+                        Arc::new(RwLock::new(Box::new(HashmapIdempotencyStore::new())));
+                let session_store: SessionStoreType =
+                        Arc::new(RwLock::new(Box::new(HashmapSessionStore::new())));
+                let failed_login_store: FailedLoginStoreType =
+                        Arc::new(RwLock::new(Box::new(HashmapFailedLoginStore::new())));
+                let audit_log_store: AuditLogStoreType =
+                        Arc::new(RwLock::new(Box::new(HashmapAuditLogStore::new())));
+                let pending_email_change_store: PendingEmailChangeStoreType =
+                        Arc::new(RwLock::new(Box::new(HashmapPendingEmailChangeStore::new())));
+                let outbox = RecordingEmailClient::default();
+                let email_client: EmailClientType =
+                        builder.email_client.unwrap_or_else(|| Arc::new(outbox.clone()));
+
+                let mut app_state_builder = AppStateBuilder::new()
+                        .user_store(Arc::clone(&user_store))
                         .banned_token_store(Arc::clone(&banned_token_store))
                         .two_fa_code_store(Arc::clone(&two_fa_code_store))
+                        .idempotency_store(idempotency_store)
+                        .session_store(session_store)
+                        .failed_login_store(failed_login_store)
+                        .audit_log_store(audit_log_store)
+                        .pending_email_change_store(pending_email_change_store)
+                        .magic_link_store(Arc::new(RwLock::new(Box::new(
+                                HashmapMagicLinkStore::new(),
+                        ))))
+                        .rate_limit_store(Arc::new(RwLock::new(Box::new(
+                                HashmapRateLimitStore::new(),
+                        ))))
                         .email_client(Arc::clone(&email_client))
-                        .build();
+                        .config(builder.config.unwrap_or_default());
+                if let Some(webhook_notifier) = builder.webhook_notifier {
+                        app_state_builder = app_state_builder.webhook_notifier(webhook_notifier);
+                }
+                if let Some(captcha_verifier) = builder.captcha_verifier {
+                        app_state_builder = app_state_builder.captcha_verifier(captcha_verifier);
+                }
+                if let Some(mx_resolver) = builder.mx_resolver {
+                        app_state_builder = app_state_builder.mx_resolver(mx_resolver);
+                }
+                let app_state = app_state_builder.build();
+                let key_ring = app_state.key_ring.clone();
 
                 let app = Application::build(app_state, "127.0.0.1:0").await?;
 
@@ -108,26 +224,37 @@ impl TestApp {
 
                 Ok(TestApp {
                         address,
-                        test_db_name,
+                        test_db,
                         cookie_jar,
+                        user_store,
                         banned_token_store,
                         two_fa_code_store,
                         email_client,
+                        key_ring,
+                        outbox,
                         http_client,
-                        clean_up_called,
                 })
         }
 
+        /// Drop the test database now rather than when the `TestApp` goes out of scope
         pub async fn clean_up(&mut self) {
-                if self.clean_up_called {
-                        return;
-                }
-                // Mark as cleaned before teardown so Drop never double-panics if cleanup work itself fails.
-                self.clean_up_called = true;
-                if self.test_db_name.is_empty() {
-                        return;
-                }
-                delete_database(&self.test_db_name).await;
+                self.test_db.take();
+        }
+
+        /// Seed an admin account directly through the store and log it in, so the
+        /// client's cookie jar carries an admin JWT.
+        pub async fn login_as_admin(&self) -> Result<(), Box<dyn Error>> {
+                let password = "ValidPassword123";
+                let email = Email::parse(&get_random_email()).expect("Valid email");
+                let hashed_password = HashedPassword::parse(password).await?;
+                let admin = User::new(email.clone(), hashed_password, false).with_role(Role::Admin);
+                self.user_store.write().await.add_user(admin).await.expect("Failed to seed admin");
+
+                let login = LoginPayload::new(email.as_str().to_owned(), password.to_owned());
+                let response = self.post_login(&login).await;
+                assert_eq!(response.status().as_u16(), 200, "Admin login should succeed");
+
+                Ok(())
         }
 
         pub async fn get_login_or_signup(&self) -> TestAppResult {
@@ -148,6 +275,19 @@ impl TestApp {
                 Ok(response)
         }
 
+        pub async fn post_verify_2fa_check<Body>(&self, payload: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/verify-2fa/check", &self.address))
+                        .json(&payload)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
         pub async fn post_signup<Body>(&self, body: &Body) -> reqwest::Response
         where
                 Body: serde::Serialize,
@@ -160,6 +300,70 @@ impl TestApp {
                         .expect("Failed to execute request")
         }
 
+        /// POST /signup as `application/x-www-form-urlencoded`, like a plain HTML form
+        pub async fn post_signup_form<Body>(&self, body: &Body) -> reqwest::Response
+        where
+                Body: serde::Serialize,
+        {
+                self.http_client
+                        .post(format!("{}/signup", &self.address))
+                        .form(body)
+                        .send()
+                        .await
+                        .expect("Failed to execute request")
+        }
+
+        pub async fn post_signup_with_idempotency_key<Body>(
+                &self,
+                body: &Body,
+                idempotency_key: &str,
+        ) -> reqwest::Response
+        where
+                Body: serde::Serialize,
+        {
+                self.http_client
+                        .post(format!("{}/signup", &self.address))
+                        .header("Idempotency-Key", idempotency_key)
+                        .json(body)
+                        .send()
+                        .await
+                        .expect("Failed to execute request")
+        }
+
+        pub async fn get_check_email(&self, email: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/signup/check-email", &self.address))
+                        .query(&[("email", email)])
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_password_policy(&self) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/password/policy", &self.address))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_readiness(&self) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/health/ready", &self.address))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_metrics(&self) -> TestAppResult {
+                let response =
+                        self.http_client.get(format!("{}/metrics", &self.address)).send().await?;
+                Ok(response)
+        }
+
         pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
         where
                 Body: serde::Serialize,
@@ -172,12 +376,131 @@ impl TestApp {
                         .expect("Failed to execute request")
         }
 
+        /// POST /login as `application/x-www-form-urlencoded`, like a plain HTML form
+        pub async fn post_login_form<Body>(&self, body: &Body) -> reqwest::Response
+        where
+                Body: serde::Serialize,
+        {
+                self.http_client
+                        .post(format!("{}/login", &self.address))
+                        .form(body)
+                        .send()
+                        .await
+                        .expect("Failed to execute request")
+        }
+
+        /// POST /login from a client that does not share `cookie_jar`, like a second device.
+        /// Logging in through `post_login` again would supersede the jar's current session.
+        pub async fn post_login_from_new_device<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = reqwest::Client::new()
+                        .post(format!("{}/login", &self.address))
+                        .json(body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// Sign up `email` with the password `ValidPassword123` unless it exists, then log it in
+        /// from a new device (see `post_login_from_new_device`) and return the issued JWT
+        pub async fn login(&self, email: &str) -> Result<String, Box<dyn Error>> {
+                let request = reqwest::Client::new().post(format!("{}/login", &self.address));
+                self.login_with(email, request).await
+        }
+
+        /// Like `login`, from a device sending `user_agent`
+        pub async fn login_with_user_agent(
+                &self,
+                email: &str,
+                user_agent: &str,
+        ) -> Result<String, Box<dyn Error>> {
+                let request = reqwest::Client::new()
+                        .post(format!("{}/login", &self.address))
+                        .header(USER_AGENT, user_agent);
+                self.login_with(email, request).await
+        }
+
+        async fn login_with(
+                &self,
+                email: &str,
+                request: reqwest::RequestBuilder,
+        ) -> Result<String, Box<dyn Error>> {
+                let password = "ValidPassword123";
+                self.post_signup(&SignupPayload::new(email.to_owned(), password.to_owned(), false))
+                        .await;
+
+                let response = request
+                        .json(&LoginPayload::new(email.to_owned(), password.to_owned()))
+                        .send()
+                        .await?;
+                assert_eq!(response.status().as_u16(), 200, "Login should succeed");
+
+                let token = response
+                        .cookies()
+                        .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                        .expect("JWT cookie should be set")
+                        .value()
+                        .to_owned();
+
+                Ok(token)
+        }
+
+        /// Status `/verify-token` answers for `token`: 200 while its session is valid
+        pub async fn token_status(&self, token: &str) -> Result<u16, Box<dyn Error>> {
+                let response =
+                        self.post_verify_token(&VerifyTokenPayload::new(token.to_owned())).await?;
+                Ok(response.status().as_u16())
+        }
+
+        /// POST /logout authenticated as `token` rather than the cookie jar's session
+        pub async fn post_logout_with_token(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .post(format!("{}/logout", &self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
         pub async fn post_logout(&self) -> TestAppResult {
                 let response =
                         self.http_client.post(format!("{}/logout", &self.address)).send().await?;
                 Ok(response)
         }
 
+        /// GET /logout without following the redirect, so the 303 itself can be inspected
+        pub async fn get_logout(&self) -> TestAppResult {
+                let client = reqwest::Client::builder()
+                        .cookie_provider(self.cookie_jar.clone())
+                        .redirect(reqwest::redirect::Policy::none())
+                        .build()?;
+                let response = client.get(format!("{}/logout", &self.address)).send().await?;
+                Ok(response)
+        }
+
+        pub async fn get_session_status(&self) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/session/status", &self.address))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// GET /session/status presenting `token` as the auth cookie instead of the jar's
+        pub async fn get_session_status_with_token(&self, token: &str) -> TestAppResult {
+                let client = reqwest::Client::new();
+                let response = client
+                        .get(format!("{}/session/status", &self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
         pub async fn post_verify_token<Body>(&self, body: &Body) -> TestAppResult
         where
                 Body: serde::Serialize,
@@ -190,6 +513,345 @@ impl TestApp {
                         .await?;
                 Ok(response)
         }
+
+        pub async fn post_change_password<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/change-password", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_change_email<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/change-email", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_confirm_email_change(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/change-email/confirm", self.address))
+                        .query(&[("token", token)])
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_magic_link_request(&self, email: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .post(format!("{}/magic-link/request", self.address))
+                        .json(&serde_json::json!({ "email": email }))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_verify_magic_link(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/magic-link/verify", self.address))
+                        .query(&[("token", token)])
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_ban_tokens<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/admin/ban-tokens", &self.address))
+                        .json(body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_rotate_key<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/admin/rotate-key", &self.address))
+                        .json(body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_set_maintenance_mode<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/admin/maintenance", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_reactivate_account<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/reactivate-account", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_force_reset_password<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/admin/force-reset-password", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn post_required_password_change<Body>(&self, body: &Body) -> TestAppResult
+        where
+                Body: serde::Serialize,
+        {
+                let response = self
+                        .http_client
+                        .post(format!("{}/login/change-password", self.address))
+                        .json(&body)
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        pub async fn get_me(&self) -> TestAppResult {
+                let response = self.http_client.get(format!("{}/me", &self.address)).send().await?;
+                Ok(response)
+        }
+
+        pub async fn get_data_export(&self) -> TestAppResult {
+                let response =
+                        self.http_client.get(format!("{}/me/export", &self.address)).send().await?;
+                Ok(response)
+        }
+
+        /// GET /sessions authenticated as `token`, regardless of what the cookie jar holds
+        pub async fn get_sessions(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/sessions", self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// DELETE /sessions/{jti} authenticated as `token`
+        pub async fn delete_session(&self, token: &str, jti: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .delete(format!("{}/sessions/{}", self.address, jti))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// GET /security/sessions authenticated as `token`
+        pub async fn get_security_sessions(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/security/sessions", self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// GET /security/login-history with the given query string, authenticated as `token`
+        pub async fn get_login_history(&self, token: &str, query: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/security/login-history?{}", self.address, query))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// GET /security/summary authenticated as `token`
+        pub async fn get_security_summary(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .get(format!("{}/security/summary", self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+
+        /// POST /security/sign-out-others authenticated as `token`
+        pub async fn post_sign_out_others(&self, token: &str) -> TestAppResult {
+                let response = self
+                        .http_client
+                        .post(format!("{}/security/sign-out-others", self.address))
+                        .header(COOKIE, format!("{}={}", *JWT_COOKIE_NAME, token))
+                        .send()
+                        .await?;
+                Ok(response)
+        }
+}
+
+/// An email captured by `RecordingEmailClient`
+#[derive(Debug, Clone)]
+pub struct SentEmail {
+        pub recipient: String,
+        pub subject: String,
+        pub content: String,
+}
+
+/// Spawns a `TestApp` the way `AppStateBuilder` builds an `AppState`. Anything not supplied
+/// gets the `TestApp::new` default: a fresh Postgres database for users, Redis for 2FA codes,
+/// in-memory stores otherwise, and `outbox` for email.
+#[derive(Default)]
+pub struct TestAppBuilder {
+        config: Option<AppConfig>,
+        user_store: Option<UserStoreType>,
+        banned_token_store: Option<BannedTokenStoreType>,
+        two_fa_code_store: Option<TwoFACodeStoreType>,
+        email_client: Option<EmailClientType>,
+        webhook_notifier: Option<WebhookNotifier>,
+        captcha_verifier: Option<CaptchaVerifierType>,
+        mx_resolver: Option<MxResolverType>,
+}
+
+impl TestAppBuilder {
+        pub fn config(mut self, config: AppConfig) -> Self {
+                self.config = Some(config);
+                self
+        }
+
+        /// No test database is created when a user store is supplied
+        pub fn user_store(mut self, user_store: UserStoreType) -> Self {
+                self.user_store = Some(user_store);
+                self
+        }
+
+        pub fn banned_token_store(mut self, banned_token_store: BannedTokenStoreType) -> Self {
+                self.banned_token_store = Some(banned_token_store);
+                self
+        }
+
+        pub fn two_fa_code_store(mut self, two_fa_code_store: TwoFACodeStoreType) -> Self {
+                self.two_fa_code_store = Some(two_fa_code_store);
+                self
+        }
+
+        /// `TestApp::outbox` stays empty when an email client is supplied
+        pub fn email_client(mut self, email_client: EmailClientType) -> Self {
+                self.email_client = Some(email_client);
+                self
+        }
+
+        /// Report account events to the given webhook
+        pub fn webhook_notifier(mut self, webhook_notifier: WebhookNotifier) -> Self {
+                self.webhook_notifier = Some(webhook_notifier);
+                self
+        }
+
+        /// Require signups to pass the given CAPTCHA verifier
+        pub fn captcha_verifier(mut self, captcha_verifier: CaptchaVerifierType) -> Self {
+                self.captcha_verifier = Some(captcha_verifier);
+                self
+        }
+
+        /// Check signup email domains for MX records with the given resolver
+        pub fn mx_resolver(mut self, mx_resolver: MxResolverType) -> Self {
+                self.mx_resolver = Some(mx_resolver);
+                self
+        }
+
+        pub async fn build(self) -> Result<TestApp, Box<dyn Error>> {
+                TestApp::spawn(self).await
+        }
+}
+
+/// Email client that keeps every email instead of sending it, so tests can assert on them
+#[derive(Debug, Clone, Default)]
+pub struct RecordingEmailClient {
+        sent: Arc<std::sync::Mutex<Vec<SentEmail>>>,
+}
+
+impl RecordingEmailClient {
+        /// Every email sent so far, oldest first
+        pub fn sent_messages(&self) -> Vec<SentEmail> {
+                self.sent.lock().unwrap().clone()
+        }
+
+        /// Emails sent to `recipient` with the given subject, oldest first
+        pub fn sent_to(&self, recipient: &str, subject: &str) -> Vec<SentEmail> {
+                self.sent
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|email| email.recipient == recipient && email.subject == subject)
+                        .cloned()
+                        .collect()
+        }
+
+        /// Like `sent_to`, but for emails sent in the background after the response: waits
+        /// up to five seconds for at least one to arrive
+        pub async fn wait_for(&self, recipient: &str, subject: &str) -> Vec<SentEmail> {
+                for _ in 0..100 {
+                        let sent = self.sent_to(recipient, subject);
+                        if !sent.is_empty() {
+                                return sent;
+                        }
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+                Vec::new()
+        }
+}
+
+#[async_trait]
+impl EmailClient for RecordingEmailClient {
+        async fn send_email(
+                &self,
+                recipient: &Email,
+                subject: &str,
+                content: &str,
+        ) -> Result<(), EmailClientError> {
+                self.sent.lock().unwrap().push(SentEmail {
+                        recipient: recipient.as_str().to_owned(),
+                        subject: subject.to_owned(),
+                        content: content.to_owned(),
+                });
+                Ok(())
+        }
 }
 
 async fn delete_database(db_name: &str) {
@@ -203,26 +865,9 @@ async fn delete_database(db_name: &str) {
                 .await
                 .expect("Failed to connect to Postgres");
 
-        // Kill any active connections to the database
-        connection
-                .execute(
-                        format!(
-                                r#"
-                SELECT pg_terminate_backend(pg_stat_activity.pid)
-                FROM pg_stat_activity
-                WHERE pg_stat_activity.datname = '{}'
-                  AND pid <> pg_backend_pid();
-        "#,
-                                db_name
-                        )
-                        .as_str(),
-                )
-                .await
-                .expect("Failed to drop the database.");
-
-        // Drop the database
+        // FORCE also terminates connections still open to it, such as the app's pool
         connection
-                .execute(format!(r#"DROP DATABASE IF EXISTS "{}";"#, db_name).as_str())
+                .execute(format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE);"#, db_name).as_str())
                 .await
                 .expect("Failed to drop the database.");
 }
@@ -0,0 +1,76 @@
+use auth_service::{
+        domain::{Email, HashedPassword, User, UserStoreError},
+        routes::LoginPayload,
+        services::inactivity_expiry::expire_inactive_users,
+};
+use chrono::{Duration, Utc};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Seed a user directly through the store with the given last login time
+async fn seed_user(app: &TestApp, last_login_days_ago: i64) -> TestResult<Email> {
+        let email = Email::parse(&get_random_email()).expect("Valid email");
+        let password = HashedPassword::parse(PASSWORD).await?;
+        let user = User::new(email.clone(), password, false)
+                .with_last_login_at(Utc::now() - Duration::days(last_login_days_ago));
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        Ok(email)
+}
+
+#[tokio::test]
+async fn expiry_job_soft_deletes_only_inactive_users() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let inactive = seed_user(&app, 120).await?;
+        let recently_active = seed_user(&app, 5).await?;
+
+        let expired = expire_inactive_users(&app.user_store, 90).await.expect("Expiry run failed");
+        assert_eq!(expired, 1);
+
+        {
+                let store = app.user_store.read().await;
+                assert_eq!(store.get_user(&inactive).await, Err(UserStoreError::UserNotFound));
+                assert!(store.get_user_including_deleted(&inactive).await.unwrap().is_deleted());
+                assert!(store.get_user(&recently_active).await.is_ok());
+        }
+
+        // The expired account can no longer log in; the active one still can
+        let login = LoginPayload::new(inactive.as_str().to_owned(), PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 401);
+        let login = LoginPayload::new(recently_active.as_str().to_owned(), PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn login_restarts_the_inactivity_window() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = seed_user(&app, 60).await?;
+
+        let login = LoginPayload::new(email.as_str().to_owned(), PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        let last_login_at =
+                app.user_store.read().await.get_user(&email).await.unwrap().last_login_at();
+        assert!(Utc::now() - last_login_at < Duration::minutes(1));
+
+        let expired = expire_inactive_users(&app.user_store, 30).await.expect("Expiry run failed");
+        assert_eq!(expired, 0);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
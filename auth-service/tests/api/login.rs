@@ -1,10 +1,49 @@
-use crate::{get_random_email, TestApp, TestResult};
+use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+};
+
+use chrono::{Duration, Utc};
+use tokio::sync::RwLock;
+
+use crate::{get_random_email, helpers::RecordingEmailClient, TestApp, TestResult};
+use async_trait::async_trait;
 use auth_service::{
-        domain::{Email, ErrorResponse},
-        routes::TwoFactorAuthResponse,
-        utils::constants::JWT_COOKIE_NAME,
+        domain::{
+                Email, EmailClient, EmailClientError, ErrorResponse, FieldError, HashedPassword,
+                LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError, TwoFAMethod, User,
+                ValidationErrorResponse,
+        },
+        routes::{RegularAuthResponse, TwoFactorAuthResponse, VerifyTokenPayload},
+        services::{
+                data_stores::{HashmapTwoFACodeStore, HashmapUserStore},
+                retrying_email_client::RetryingEmailClient,
+        },
+        utils::{
+                auth::validate_challenge_token,
+                config::AppConfig,
+                constants::{JWT_COOKIE_NAME, TOKEN_TTL_SECONDS},
+                email_template::EmailTemplate,
+        },
 };
 
+/// The login attempt ID carried by the challenge token of a 206 login response
+fn attempt_id_of(app: &TestApp, response: &TwoFactorAuthResponse) -> LoginAttemptId {
+        let claims = validate_challenge_token(&response.challenge_token, &app.key_ring.load())
+                .expect("Challenge token should be valid");
+        LoginAttemptId::parse(claims.login_attempt_id).expect("Login attempt ID should be valid")
+}
+
+/// The code pending for the login attempt of a 206 login response
+async fn pending_code(
+        app: &TestApp,
+        email: &Email,
+        response: &TwoFactorAuthResponse,
+) -> Result<TwoFACode, TwoFACodeStoreError> {
+        let login_attempt_id = attempt_id_of(app, response);
+        app.two_fa_code_store.read().await.get_code(email, &login_attempt_id).await
+}
+
 #[tokio::test]
 async fn should_return_201_if_valid_credentials_and_2fa_disabled() -> TestResult<()> {
         let app = TestApp::new().await?;
@@ -28,7 +67,7 @@ async fn should_return_201_if_valid_credentials_and_2fa_disabled() -> TestResult
 
         let auth_token = res
                 .cookies()
-                .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
                 .expect("Failed to find jwt token cookie");
 
         assert!(!auth_token.name().is_empty());
@@ -43,7 +82,49 @@ async fn should_return_201_if_valid_credentials_and_2fa_disabled() -> TestResult
 }
 
 #[tokio::test]
-async fn should_return_206_on_repeated_login_if_2fa_code_already_exists() -> TestResult<()> {
+async fn should_return_token_expiry_if_2fa_disabled() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": false
+        });
+        let res = app.post_signup(&signup_payload).await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 200);
+
+        let body = res
+                .json::<RegularAuthResponse>()
+                .await
+                .expect("Could not deserialize response body to RegularAuthResponse");
+
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = body.expires_at as i64;
+        assert!(expires_at > now, "expires_at should be in the future");
+        assert!(
+                (expires_at - (now + TOKEN_TTL_SECONDS)).abs() <= 5,
+                "expires_at should be roughly now + TOKEN_TTL_SECONDS"
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_keep_earlier_2fa_challenges_pending_on_repeated_login() -> TestResult<()> {
         let app = TestApp::new().await?;
 
         // Create and signup a user with 2FA enabled
@@ -77,18 +158,15 @@ async fn should_return_206_on_repeated_login_if_2fa_code_already_exists() -> Tes
                 .expect("Could not deserialize second 2FA response");
 
         assert_eq!(second_json.message, "2FA required");
-        assert_ne!(first_json.login_attempt_id, second_json.login_attempt_id);
+        assert_ne!(attempt_id_of(&app, &first_json), attempt_id_of(&app, &second_json));
 
-        // The store should contain the most recent login attempt id.
+        // Both login attempts have a code pending, e.g. for two devices
         let email = Email::parse(&random_email).expect("Invalid Email");
-        let (stored_login_attempt_id, _) = app
-                .two_fa_code_store
-                .read()
-                .await
-                .get_code(&email)
-                .await
-                .expect("Email must have an active 2FA code after repeated login");
-        assert_eq!(stored_login_attempt_id.as_ref(), second_json.login_attempt_id);
+        for json in [&first_json, &second_json] {
+                pending_code(&app, &email, json)
+                        .await
+                        .expect("Each login attempt must have an active 2FA code");
+        }
 
         // Mutable re-bind for teardown
         {
@@ -127,16 +205,10 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() -> TestResult<
 
         assert_eq!(json_body.message, "2FA required".to_owned());
 
-        // TODO: assert that `json_body.login_attempt_id` is stored inside `app.two_fa_code_store`
         let email = Email::parse(&random_email).expect("Invalid Email");
-        let (login_attempt_id, _) = app
-                .two_fa_code_store
-                .read()
-                .await
-                .get_code(&email)
+        pending_code(&app, &email, &json_body)
                 .await
                 .expect("Email must be added to 2FA code store during login attempt");
-        assert_eq!(login_attempt_id.as_ref(), json_body.login_attempt_id);
 
         // Mutable re-bind for teardown
         {
@@ -148,36 +220,91 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() -> TestResult<
 }
 
 #[tokio::test]
-async fn should_return_400_if_invalid_input() -> TestResult<()> {
+async fn logging_in_again_bans_the_previous_token() -> TestResult<()> {
         let app = TestApp::new().await?;
 
-        let test_cases = [
-                serde_json::json!({
-                        "email": "valid@mail.com",
-                        "password": "2short"
-                }),
-                serde_json::json!({
-                        "email": "invalid email",
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": false
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        let jwt_from = |res: &reqwest::Response| {
+                res.cookies()
+                        .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                        .expect("JWT cookie must be set.")
+                        .value()
+                        .to_owned()
+        };
+
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 200);
+        let first_token = jwt_from(&res);
+
+        // The cookie jar sends the first token along with the second login
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 200);
+        let second_token = jwt_from(&res);
+        assert_ne!(first_token, second_token);
+
+        let res = app.post_verify_token(&VerifyTokenPayload::new(first_token)).await?;
+        assert_eq!(res.status().as_u16(), 401);
+        let res = app.post_verify_token(&VerifyTokenPayload::new(second_token)).await?;
+        assert_eq!(res.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_resolve_username_and_email_to_the_same_user() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true,
+                "username": "Login_Tester"
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        for identifier in ["login_tester", random_email.as_str()] {
+                let login_payload = serde_json::json!({
+                        "identifier": identifier,
                         "password": "ValidPassword123"
-                }),
-                serde_json::json!({
-                        "email": "invalid email",
-                        "password": "2short"
-                }),
-        ];
+                });
+                let res = app.post_login(&login_payload).await;
+                assert_eq!(res.status().as_u16(), 206);
+                let json_body = res.json::<TwoFactorAuthResponse>().await?;
 
-        for test_case in test_cases.iter() {
-                let response = app.post_login(test_case).await;
-                assert_eq!(response.status().as_u16(), 400);
-                assert_eq!(
-                        response.json::<ErrorResponse>()
-                                .await
-                                .expect("Could not deserialize response body to ErrorResponse")
-                                .error,
-                        "Invalid credentials"
-                );
+                // The pending 2FA code is keyed by the account's email either way
+                pending_code(&app, &email, &json_body)
+                        .await
+                        .expect("Email must be added to 2FA code store during login attempt");
         }
 
+        // An unknown username is treated like an unknown email
+        let res = app
+                .post_login(&serde_json::json!({
+                        "identifier": "nobody_here",
+                        "password": "ValidPassword123"
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 401);
+
         // Mutable re-bind for teardown
         {
                 let mut app = app;
@@ -188,34 +315,161 @@ async fn should_return_400_if_invalid_input() -> TestResult<()> {
 }
 
 #[tokio::test]
-async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
+async fn should_report_email_2fa_method_in_206_response() -> TestResult<()> {
         let app = TestApp::new().await?;
-        let email = "valid@mail.com".to_string();
-        let password = "ValidPassword123".to_string();
-        let other_password = "ValidPassword456".to_string();
 
+        let random_email = get_random_email();
         let signup_payload = serde_json::json!({
-                "email": email.clone(),
-                "password": password.clone(),
-                "requires2FA": false
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true
         });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
 
-        let _ = app.post_signup(&signup_payload).await;
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
 
-        let login = serde_json::json!({
-                "email": email,
-                "password": other_password
+        let body = res.json::<serde_json::Value>().await?;
+        assert_eq!(body["method"], "email");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_email_the_stored_2fa_code() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true
         });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
 
-        let res = app.post_login(&login).await;
+        let login_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
+        let json_body = res.json::<TwoFactorAuthResponse>().await?;
 
-        assert_eq!(res.status().as_u16(), 401);
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let code = pending_code(&app, &email, &json_body)
+                .await
+                .expect("Email must be added to 2FA code store during login attempt");
+
+        let sent = app.outbox.sent_messages();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, random_email);
+        assert_eq!(sent[0].content, code.as_ref());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_capture_the_2fa_email_with_injected_stores() -> TestResult<()> {
+        // In-memory stores throughout, so this test needs neither Postgres nor Redis
+        let outbox = RecordingEmailClient::default();
+        let app = TestApp::builder()
+                .user_store(Arc::new(RwLock::new(Box::new(HashmapUserStore::default()))))
+                .two_fa_code_store(Arc::new(RwLock::new(
+                        Box::new(HashmapTwoFACodeStore::default()),
+                )))
+                .email_client(Arc::new(outbox.clone()))
+                .build()
+                .await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
+        let json_body = res.json::<TwoFactorAuthResponse>().await?;
+
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let code = pending_code(&app, &email, &json_body)
+                .await
+                .expect("Email must be added to 2FA code store during login attempt");
+
+        let sent = outbox.sent_to(&random_email, "2FA: Verify Email");
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].content, code.as_ref());
+        assert!(app.outbox.sent_messages().is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_send_2fa_code_with_configured_email_template() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                two_fa_email_template: EmailTemplate::new(
+                        "Votre code : {{code}}",
+                        "Bonjour {{email}}, votre code est {{code}}.",
+                ),
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
+        let json_body = res.json::<TwoFactorAuthResponse>().await?;
+
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let code = pending_code(&app, &email, &json_body)
+                .await
+                .expect("Email must be added to 2FA code store during login attempt");
+
+        let subject = format!("Votre code : {}", code.as_ref());
+        let sent = app.outbox.sent_to(&random_email, &subject);
+        assert_eq!(sent.len(), 1);
         assert_eq!(
-                res.json::<ErrorResponse>()
-                        .await
-                        .expect("Could not deserialize response body to ErrorResponse")
-                        .error,
-                "Unauthorized"
+                sent[0].content,
+                format!("Bonjour {}, votre code est {}.", random_email, code.as_ref())
         );
 
         // Mutable re-bind for teardown
@@ -228,14 +482,684 @@ async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
 }
 
 #[tokio::test]
-async fn should_return_422_if_malformed_credentials() -> TestResult<()> {
-        let app = TestApp::new().await?;
+async fn should_return_429_once_the_daily_2fa_email_cap_is_reached() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                max_2fa_emails_per_day: Some(2),
+                ..AppConfig::default()
+        })
+        .await?;
 
-        let payload = serde_json::json!({
-                "wrong field": "wrong value"
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123",
+                "requires2FA": true
         });
-        let response = app.post_login(&payload).await;
-        assert_eq!(response.status().as_u16(), 422);
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 206);
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 206);
+
+        let response = app.post_login(&login_payload).await;
+        assert_eq!(response.status().as_u16(), 429);
+        assert_eq!(
+                response.json::<ErrorResponse>().await?.error,
+                "Too many 2FA emails sent to this address today"
+        );
+
+        // The third code was never emailed
+        assert_eq!(app.outbox.sent_to(&random_email, "2FA: Verify Email").len(), 2);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_totp_2fa_method_in_206_response() -> TestResult<()> {
+        // Postgres refuses TOTP accounts until their codes can be verified, so this one lives
+        // in an in-memory store
+        let app = TestApp::builder()
+                .user_store(Arc::new(RwLock::new(Box::new(HashmapUserStore::default()))))
+                .build()
+                .await?;
+
+        // There is no enrollment endpoint yet, so seed a TOTP user directly
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let password = HashedPassword::parse("ValidPassword123").await?;
+        let user = User::new(email, password, true).with_two_fa_method(TwoFAMethod::Totp);
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email.clone(),
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
+
+        let body = res.json::<serde_json::Value>().await?;
+        assert_eq!(body["method"], "totp");
+        // The code comes from the authenticator app, not an email
+        assert!(app.outbox.sent_to(&random_email, "2FA: Verify Email").is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_log_in_a_legacy_bcrypt_user_and_upgrade_their_hash() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // Seed a user imported from a legacy system that hashed with bcrypt
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let bcrypt_hash = bcrypt::hash("ValidPassword123", 4)?;
+        let password = HashedPassword::parse_password_hash(bcrypt_hash.clone())?;
+        let user = User::new(email.clone(), password, false);
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 200);
+
+        let stored = app.user_store.read().await.get_user(&email).await.expect("User must exist");
+        assert!(!stored.password().needs_rehash());
+        assert!(stored.password_str().starts_with("$argon2id$"));
+        let history = app.user_store.read().await.get_password_history(&email).await;
+        assert_eq!(history, Ok(vec![]));
+
+        // The upgraded hash still accepts the same password
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_log_in_with_a_password_the_policy_no_longer_allows() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // An account from before the policy was tightened, stored without a policy check
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let password = HashedPassword::rehash("2short").await?;
+        let user = User::new(email, password, false);
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "2short"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 200);
+
+        // A password that breaks the policy is only a wrong password, not a format error
+        let login_payload = serde_json::json!({
+                "email": get_random_email(),
+                "password": "2short"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_log_in_a_legacy_user_whose_password_is_common() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // Imported accounts predate the common-passwords list
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let password = HashedPassword::parse_password_hash(bcrypt::hash("Password1", 4)?)?;
+        let user = User::new(email, password, false);
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "Password1"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+/// Puts a freshly created account into some state
+type AccountState = fn(User) -> User;
+
+/// Seed an account in the given state and return the status and error code of logging
+/// into it
+async fn login_code_for(app: &TestApp, state: AccountState) -> TestResult<(u16, Option<String>)> {
+        login_code_with_password_for(app, state, "ValidPassword123").await
+}
+
+/// Like `login_code_for`, but logs in with `password` rather than the account's own
+async fn login_code_with_password_for(
+        app: &TestApp,
+        state: AccountState,
+        password: &str,
+) -> TestResult<(u16, Option<String>)> {
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let user = state(User::new(email, HashedPassword::parse("ValidPassword123").await?, false));
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": password
+        });
+        let response = app.post_login(&login_payload).await;
+        let status = response.status().as_u16();
+        let code = match status {
+                403 => response.json::<ErrorResponse>().await?.code,
+                _ => None,
+        };
+        Ok((status, code))
+}
+
+#[tokio::test]
+async fn should_return_403_with_a_distinct_code_per_account_state() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let test_cases: [(AccountState, &str); 4] = [
+                (|user| user.with_email_verified(false), "email_not_verified"),
+                (|user| user.with_locked_until(Utc::now() + Duration::hours(1)), "account_locked"),
+                (|user| user.with_banned_at(Utc::now()), "account_banned"),
+                // A ban is reported over a lock and an unverified email
+                (
+                        |user| {
+                                user.with_email_verified(false)
+                                        .with_locked_until(Utc::now() + Duration::hours(1))
+                                        .with_banned_at(Utc::now())
+                        },
+                        "account_banned",
+                ),
+        ];
+        for (state, expected) in test_cases {
+                let (status, code) = login_code_for(&app, state).await?;
+                assert_eq!(status, 403);
+                assert_eq!(code.as_deref(), Some(expected));
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_for_a_wrong_password_whatever_the_account_state() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // The account state is only revealed to someone who knows the password
+        let test_cases: [AccountState; 3] = [
+                |user| user.with_email_verified(false),
+                |user| user.with_locked_until(Utc::now() + Duration::hours(1)),
+                |user| user.with_banned_at(Utc::now()),
+        ];
+        for state in test_cases {
+                let result = login_code_with_password_for(&app, state, "WrongPassword123").await?;
+                assert_eq!(result, (401, None));
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_how_long_a_locked_account_stays_locked() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let random_email = get_random_email();
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let user = User::new(email, HashedPassword::parse("ValidPassword123").await?, false)
+                .with_locked_until(Utc::now() + Duration::minutes(10));
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        let retry_after = || async {
+                let response = app.post_login(&login_payload).await;
+                assert_eq!(response.status().as_u16(), 403);
+                let header: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+                let body = response.json::<ErrorResponse>().await?;
+                assert_eq!(body.code.as_deref(), Some("account_locked"));
+                assert_eq!(body.retry_after_seconds, Some(header));
+                TestResult::Ok(header)
+        };
+
+        let first = retry_after().await?;
+        assert!(first > 0 && first <= 600, "unexpected retry_after_seconds: {first}");
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let second = retry_after().await?;
+        assert!(second < first, "{second} should be below {first}");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_log_in_an_account_in_good_standing() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        assert_eq!(login_code_for(&app, |user| user).await?, (200, None));
+        // A lock that has run out no longer applies
+        let expired_lock = |user: User| user.with_locked_until(Utc::now() - Duration::minutes(1));
+        assert_eq!(login_code_for(&app, expired_lock).await?, (200, None));
+        // 2FA accounts go on to the code challenge
+        let with_2fa = |user: User| User {
+                requires_2fa: true,
+                ..user
+        };
+        assert_eq!(login_code_for(&app, with_2fa).await?.0, 206);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let test_cases = [
+                serde_json::json!({
+                        "email": "invalid email",
+                        "password": "ValidPassword123"
+                }),
+                serde_json::json!({
+                        "email": "invalid email",
+                        "password": "2short"
+                }),
+        ];
+
+        for test_case in test_cases.iter() {
+                let response = app.post_login(test_case).await;
+                assert_eq!(response.status().as_u16(), 400);
+                assert_eq!(
+                        response.json::<ErrorResponse>()
+                                .await
+                                .expect("Could not deserialize response body to ErrorResponse")
+                                .error,
+                        "Invalid credentials"
+                );
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = "valid@mail.com".to_string();
+        let password = "ValidPassword123".to_string();
+        let other_password = "ValidPassword456".to_string();
+
+        let signup_payload = serde_json::json!({
+                "email": email.clone(),
+                "password": password.clone(),
+                "requires2FA": false
+        });
+
+        let _ = app.post_signup(&signup_payload).await;
+
+        let login = serde_json::json!({
+                "email": email,
+                "password": other_password
+        });
+
+        let res = app.post_login(&login).await;
+
+        assert_eq!(res.status().as_u16(), 401);
+        assert_eq!(
+                res.json::<ErrorResponse>()
+                        .await
+                        .expect("Could not deserialize response body to ErrorResponse")
+                        .error,
+                "Unauthorized"
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn failed_logins_wait_at_least_the_minimum_delay() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                login_min_delay_ms: 300,
+                login_max_delay_ms: 400,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        app.post_signup(&serde_json::json!({
+                "email": email,
+                "password": "ValidPassword123",
+                "requires2FA": false
+        }))
+        .await;
+
+        // Wrong password, unknown email, and unknown username are all delayed
+        for identifier in [email.as_str(), "nobody@example.com", "nobody"] {
+                let started = std::time::Instant::now();
+                let response = app
+                        .post_login(&serde_json::json!({
+                                "email": identifier,
+                                "password": "WrongPassword123"
+                        }))
+                        .await;
+                assert_eq!(response.status().as_u16(), 401);
+                assert!(
+                        started.elapsed() >= std::time::Duration::from_millis(300),
+                        "Failed login for {identifier} returned before the minimum delay"
+                );
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_422_if_credentials_are_blank() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app
+                .post_login(&serde_json::json!({
+                        "email": "",
+                        "password": "   "
+                }))
+                .await;
+        assert_eq!(response.status().as_u16(), 422);
+
+        let body = response.json::<ValidationErrorResponse>().await?;
+        assert_eq!(body.error, "Unprocessable payload");
+        assert_eq!(
+                body.fields,
+                [
+                        FieldError {
+                                field: "identifier".to_owned(),
+                                message: "identifier is required".to_owned(),
+                        },
+                        FieldError {
+                                field: "password".to_owned(),
+                                message: "password is required".to_owned(),
+                        },
+                ]
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_credentials() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let payload = serde_json::json!({
+                "wrong field": "wrong value"
+        });
+        let response = app.post_login(&payload).await;
+        assert_eq!(response.status().as_u16(), 422);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+/// Email provider whose first `failures` sends fail transiently
+#[derive(Default)]
+struct FlakyEmailClient {
+        failures: AtomicUsize,
+        sends: AtomicUsize,
+}
+
+#[async_trait]
+impl EmailClient for FlakyEmailClient {
+        async fn send_email(&self, _: &Email, _: &str, _: &str) -> Result<(), EmailClientError> {
+                self.sends.fetch_add(1, Ordering::SeqCst);
+                match self
+                        .failures
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                {
+                        Ok(_) => Err(EmailClientError::Transient(
+                                "Email provider is down".to_owned(),
+                        )),
+                        Err(_) => Ok(()),
+                }
+        }
+}
+
+#[tokio::test]
+async fn should_retry_a_transient_2fa_email_failure() -> TestResult<()> {
+        let flaky = Arc::new(FlakyEmailClient {
+                failures: AtomicUsize::new(1),
+                ..Default::default()
+        });
+        let email_client = RetryingEmailClient::new(flaky.clone(), 3, std::time::Duration::ZERO);
+        let app = TestApp::builder().email_client(Arc::new(email_client)).build().await?;
+
+        let random_email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123",
+                "requires2FA": true
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        // The first send fails, the resend goes through, and the login never notices
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 206);
+        assert_eq!(flaky.sends.load(Ordering::SeqCst), 2);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+/// In-memory 2FA code store that also remembers every login attempt a code was added for,
+/// since a failed login never hands its attempt ID to the client
+#[derive(Default)]
+struct AttemptRecordingStore {
+        inner: HashmapTwoFACodeStore,
+        added: Arc<std::sync::Mutex<Vec<LoginAttemptId>>>,
+}
+
+#[async_trait]
+impl TwoFACodeStore for AttemptRecordingStore {
+        async fn add_code(
+                &mut self,
+                email: Email,
+                login_attempt_id: LoginAttemptId,
+                code: TwoFACode,
+        ) -> Result<(), TwoFACodeStoreError> {
+                self.added.lock().unwrap().push(login_attempt_id.clone());
+                self.inner.add_code(email, login_attempt_id, code).await
+        }
+
+        async fn remove_code(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<(), TwoFACodeStoreError> {
+                self.inner.remove_code(email, login_attempt_id).await
+        }
+
+        async fn get_code(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<TwoFACode, TwoFACodeStoreError> {
+                self.inner.get_code(email, login_attempt_id).await
+        }
+
+        async fn get_issued_at(
+                &self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<i64, TwoFACodeStoreError> {
+                self.inner.get_issued_at(email, login_attempt_id).await
+        }
+
+        async fn record_email_sent(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                self.inner.record_email_sent(email).await
+        }
+
+        async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                self.inner.record_failure(email).await
+        }
+
+        async fn get_failures(&self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+                self.inner.get_failures(email).await
+        }
+
+        async fn get_failures_reset_at(
+                &self,
+                email: &Email,
+        ) -> Result<Option<i64>, TwoFACodeStoreError> {
+                self.inner.get_failures_reset_at(email).await
+        }
+
+        async fn record_attempt(
+                &mut self,
+                email: &Email,
+                login_attempt_id: &LoginAttemptId,
+        ) -> Result<u32, TwoFACodeStoreError> {
+                self.inner.record_attempt(email, login_attempt_id).await
+        }
+
+        async fn count_pending_codes(&self) -> Result<u64, TwoFACodeStoreError> {
+                self.inner.count_pending_codes().await
+        }
+}
+
+#[tokio::test]
+async fn should_discard_the_2fa_code_if_the_email_fails_to_send() -> TestResult<()> {
+        let email_client = Arc::new(FlakyEmailClient {
+                failures: AtomicUsize::new(1),
+                ..Default::default()
+        });
+        let two_fa_code_store = AttemptRecordingStore::default();
+        let added = Arc::clone(&two_fa_code_store.added);
+        let app = TestApp::builder()
+                .email_client(email_client)
+                .two_fa_code_store(Arc::new(RwLock::new(Box::new(two_fa_code_store))))
+                .build()
+                .await?;
+
+        let random_email = get_random_email();
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": random_email,
+                        "password": "ValidPassword123",
+                        "requires2FA": true
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        let login_payload = serde_json::json!({
+                "email": random_email,
+                "password": "ValidPassword123"
+        });
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 500);
+
+        // The code the user never received is not left behind
+        let email = Email::parse(&random_email).expect("Invalid Email");
+        let unsent_login_attempt_id = added.lock().unwrap()[0].clone();
+        assert_eq!(
+                app.two_fa_code_store.read().await.get_code(&email, &unsent_login_attempt_id).await,
+                Err(TwoFACodeStoreError::NoActiveChallenge)
+        );
+
+        // Once the provider recovers, the retry gets a fresh 2FA challenge
+        let res = app.post_login(&login_payload).await;
+        assert_eq!(res.status().as_u16(), 206);
+        let json = res.json::<TwoFactorAuthResponse>().await?;
+        pending_code(&app, &email, &json)
+                .await
+                .expect("Email must have an active 2FA code after the retry");
 
         // Mutable re-bind for teardown
         {
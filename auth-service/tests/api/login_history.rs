@@ -0,0 +1,163 @@
+use auth_service::{
+        domain::AuditEventKind,
+        routes::{LoginHistoryResponse, LoginPayload, SignupPayload},
+        utils::{
+                config::{AppConfig, TrustedProxyHeader},
+                constants::JWT_COOKIE_NAME,
+        },
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Log in as if from `ip` behind a proxy, returning the response
+async fn login_from(
+        app: &TestApp,
+        email: &str,
+        password: &str,
+        ip: &str,
+) -> TestResult<reqwest::Response> {
+        let response = reqwest::Client::new()
+                .post(format!("{}/login", app.address))
+                .header("X-Forwarded-For", ip)
+                .json(&LoginPayload::new(email.to_owned(), password.to_owned()))
+                .send()
+                .await?;
+        Ok(response)
+}
+
+fn jwt(response: &reqwest::Response) -> String {
+        response.cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie should be set")
+                .value()
+                .to_owned()
+}
+
+async fn login_history(
+        app: &TestApp,
+        token: &str,
+        query: &str,
+) -> TestResult<LoginHistoryResponse> {
+        let response = app.get_login_history(token, query).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        Ok(response.json::<LoginHistoryResponse>().await?)
+}
+
+#[tokio::test]
+async fn should_list_logins_from_each_ip_newest_first() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                trusted_proxy_header: Some(TrustedProxyHeader::XForwardedFor),
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let first = login_from(&app, &email, PASSWORD, "203.0.113.7").await?;
+        assert_eq!(first.status().as_u16(), 200);
+        let failed = login_from(&app, &email, "WrongPassword123", "198.51.100.1").await?;
+        assert_eq!(failed.status().as_u16(), 401);
+        let second = login_from(&app, &email, PASSWORD, "198.51.100.23").await?;
+        assert_eq!(second.status().as_u16(), 200);
+
+        let events = login_history(&app, &jwt(&second), "").await?.events;
+        let listed: Vec<_> = events.iter().map(|event| (event.kind, event.ip.as_deref())).collect();
+        assert_eq!(
+                listed,
+                vec![
+                        (AuditEventKind::LoginSuccess, Some("198.51.100.23")),
+                        (AuditEventKind::LoginFailed, Some("198.51.100.1")),
+                        (AuditEventKind::LoginSuccess, Some("203.0.113.7")),
+                ]
+        );
+        assert!(events[0].occurred_at >= events[2].occurred_at);
+
+        // Paged: the second event only
+        let page = login_history(&app, &jwt(&first), "limit=1&offset=1").await?;
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].kind, AuditEventKind::LoginFailed);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_only_list_the_callers_own_events() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                trusted_proxy_header: Some(TrustedProxyHeader::XForwardedFor),
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        let other = get_random_email();
+        for account in [&email, &other] {
+                app.post_signup(&SignupPayload::new(account.clone(), PASSWORD.to_owned(), false))
+                        .await;
+        }
+
+        login_from(&app, &other, PASSWORD, "198.51.100.1").await?;
+        let response = login_from(&app, &email, PASSWORD, "203.0.113.7").await?;
+
+        let events = login_history(&app, &jwt(&response), "").await?.events;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].ip.as_deref(), Some("203.0.113.7"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_truncate_ips_when_configured() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                login_history_truncate_ips: true,
+                trusted_proxy_header: Some(TrustedProxyHeader::XForwardedFor),
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let response = login_from(&app, &email, PASSWORD, "203.0.113.7").await?;
+
+        let events = login_history(&app, &jwt(&response), "").await?.events;
+        assert_eq!(events[0].ip.as_deref(), Some("203.0.113.0"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_without_a_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = reqwest::Client::new()
+                .get(format!("{}/security/login-history", app.address))
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,173 @@
+use auth_service::utils::config::{AppConfig, TrustedProxyHeader};
+
+use crate::{get_random_email, LoginPayload, SignupPayload, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+const WRONG_PASSWORD: &str = "WrongPassword123";
+
+/// An app that takes the client IP from `X-Forwarded-For`, so each login can come from a
+/// different address
+async fn spawn_with_limits(per_account: Option<u32>, per_ip: Option<u32>) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                trusted_proxy_header: Some(TrustedProxyHeader::XForwardedFor),
+                max_failed_logins_per_account: per_account,
+                max_failed_logins_per_ip: per_ip,
+                ..AppConfig::default()
+        })
+        .await
+}
+
+async fn signup(app: &TestApp) -> String {
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        email
+}
+
+async fn login_from(
+        app: &TestApp,
+        ip: &str,
+        email: &str,
+        password: &str,
+) -> TestResult<reqwest::Response> {
+        Ok(reqwest::Client::new()
+                .post(format!("{}/login", app.address))
+                .header("X-Forwarded-For", ip)
+                .json(&LoginPayload::new(email.to_owned(), password.to_owned()))
+                .send()
+                .await?)
+}
+
+#[tokio::test]
+async fn failures_from_one_ip_across_many_accounts_throttle_the_ip() -> TestResult<()> {
+        let app = spawn_with_limits(None, Some(3)).await?;
+        let attacker = "203.0.113.7";
+
+        for _ in 0..3 {
+                let email = signup(&app).await;
+                let response = login_from(&app, attacker, &email, WRONG_PASSWORD).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+
+        // Even a correct password is refused from the throttled IP
+        let email = signup(&app).await;
+        let response = login_from(&app, attacker, &email, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 429);
+        assert!(response.headers().contains_key("retry-after"));
+        let body = response.json::<serde_json::Value>().await?;
+        assert_eq!(body["code"], "login_throttled");
+
+        // The accounts themselves are not locked
+        let response = login_from(&app, "198.51.100.1", &email, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn unknown_usernames_count_against_the_ip() -> TestResult<()> {
+        let app = spawn_with_limits(None, Some(3)).await?;
+        let attacker = "203.0.113.7";
+
+        for username in ["nobody_one", "nobody_two", "nobody_three"] {
+                let response = login_from(&app, attacker, username, WRONG_PASSWORD).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+
+        let email = signup(&app).await;
+        let response = login_from(&app, attacker, &email, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 429);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn failures_on_one_account_from_many_ips_lock_the_account() -> TestResult<()> {
+        let app = spawn_with_limits(Some(3), None).await?;
+        let email = signup(&app).await;
+
+        for ip in ["203.0.113.1", "203.0.113.2", "203.0.113.3"] {
+                let response = login_from(&app, ip, &email, WRONG_PASSWORD).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+
+        // Locked whatever the address, even with the right password
+        let response = login_from(&app, "198.51.100.1", &email, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 403);
+        assert!(response.headers().contains_key("retry-after"));
+        let body = response.json::<serde_json::Value>().await?;
+        assert_eq!(body["code"], "account_locked");
+
+        // Other accounts can still log in from the same addresses
+        let other = signup(&app).await;
+        let response = login_from(&app, "203.0.113.1", &other, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn locked_account_answers_a_wrong_password_like_an_unknown_email() -> TestResult<()> {
+        let app = spawn_with_limits(Some(3), None).await?;
+        let email = signup(&app).await;
+        let unknown = get_random_email();
+
+        for ip in ["203.0.113.1", "203.0.113.2", "203.0.113.3"] {
+                let response = login_from(&app, ip, &email, WRONG_PASSWORD).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+
+        // Past the limit, a wrong password cannot tell the locked account from no account
+        let locked = login_from(&app, "198.51.100.1", &email, WRONG_PASSWORD).await?;
+        let missing = login_from(&app, "198.51.100.1", &unknown, WRONG_PASSWORD).await?;
+        assert_eq!(locked.status().as_u16(), 401);
+        assert_eq!(missing.status().as_u16(), 401);
+        assert!(!locked.headers().contains_key("retry-after"));
+        assert_eq!(locked.text().await?, missing.text().await?);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn limits_can_be_turned_off() -> TestResult<()> {
+        let app = spawn_with_limits(None, None).await?;
+        let email = signup(&app).await;
+
+        for _ in 0..5 {
+                let response = login_from(&app, "203.0.113.7", &email, WRONG_PASSWORD).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+        let response = login_from(&app, "203.0.113.7", &email, PASSWORD).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
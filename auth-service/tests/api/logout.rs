@@ -2,7 +2,7 @@ use auth_service::{
         domain::BannedTokenStore,
         domain::ErrorResponse,
         routes::{LoginPayload, SignupPayload},
-        utils::constants::JWT_COOKIE_NAME,
+        utils::{auth::Token, config::AppConfig, constants::JWT_COOKIE_NAME},
 };
 use reqwest::Url;
 
@@ -26,9 +26,9 @@ async fn should_return_200_if_valid_jwt_cookie() -> TestResult<()> {
         // Extract JWT token from cookie
         let jwt_cookie = login_response
                 .cookies()
-                .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
                 .expect("JWT cookie must be set.");
-        let jwt_token = jwt_cookie.value().to_string();
+        let jwt_token = Token::parse(jwt_cookie.value()).expect("JWT cookie must hold a token");
 
         // Verify token is not banned before logout
         assert!(
@@ -42,7 +42,7 @@ async fn should_return_200_if_valid_jwt_cookie() -> TestResult<()> {
 
         // Verify JWT cookie is removed
         let jwt_cookie_after_logout =
-                logout_response.cookies().find(|cookie| cookie.name() == JWT_COOKIE_NAME);
+                logout_response.cookies().find(|cookie| cookie.name() == *JWT_COOKIE_NAME);
         assert!(
                 jwt_cookie_after_logout.is_none()
                         || jwt_cookie_after_logout.unwrap().value().is_empty(),
@@ -146,9 +146,9 @@ async fn should_return_401_if_banned_token() -> TestResult<()> {
         // Extract JWT token and pre-ban it
         let jwt_cookie = login_response
                 .cookies()
-                .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
                 .expect("JWT cookie must be set.");
-        let jwt_token = jwt_cookie.value().to_string();
+        let jwt_token = Token::parse(jwt_cookie.value()).expect("JWT cookie must hold a token");
 
         app.banned_token_store
                 .write()
@@ -186,7 +186,7 @@ async fn should_return_401_if_invalid_token() -> TestResult<()> {
         app.cookie_jar.add_cookie_str(
                 &format!(
                         "{}=invalid_token; HttpOnly; SameSite=Lax; Secure; Path=/",
-                        JWT_COOKIE_NAME
+                        *JWT_COOKIE_NAME
                 ),
                 &Url::parse(&app.address).expect("Failed to parse URL"),
         );
@@ -211,3 +211,109 @@ async fn should_return_401_if_invalid_token() -> TestResult<()> {
 
         Ok(())
 }
+
+#[tokio::test]
+async fn should_return_400_for_empty_cookie_since_it_carries_no_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // A blanked cookie means the client is not logged in, like no cookie at all
+        let response = app.post_logout_with_token("").await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        let error_response = response.json::<ErrorResponse>().await?;
+        assert_eq!(error_response.error, "Missing JWT auth token");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_for_empty_cookie_when_strict_empty_token_is_set() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                strict_empty_token: true,
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let response = app.post_logout_with_token("").await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        let error_response = response.json::<ErrorResponse>().await?;
+        assert_eq!(error_response.error, "Invalid JWT auth token");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn get_logout_bans_token_and_redirects() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = "get_logout@example.com".to_string();
+        let password = "ValidPassword123".to_string();
+        let signup = SignupPayload::new(email.clone(), password.clone(), false);
+        let _ = app.post_signup(&signup).await;
+
+        let login = LoginPayload::new(email, password);
+        let login_response = app.post_login(&login).await;
+        assert_eq!(login_response.status().as_u16(), 200, "Login should succeed");
+        let jwt_token = login_response
+                .cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie must be set.")
+                .value()
+                .to_string();
+        let jwt_token = Token::parse(jwt_token).expect("JWT cookie must hold a token");
+
+        let response = app.get_logout().await?;
+        assert_eq!(response.status().as_u16(), 303, "GET /logout should redirect");
+        assert_eq!(response.headers()["location"], "/");
+
+        let jwt_cookie_after_logout =
+                response.cookies().find(|cookie| cookie.name() == *JWT_COOKIE_NAME);
+        assert!(
+                jwt_cookie_after_logout.is_none()
+                        || jwt_cookie_after_logout.unwrap().value().is_empty(),
+                "JWT cookie should be removed or emptied"
+        );
+
+        assert!(
+                app.banned_token_store.read().await.is_banned(&jwt_token).await.unwrap(),
+                "Token should be banned after GET /logout"
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn get_logout_without_cookie_still_redirects() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_logout().await?;
+        assert_eq!(response.status().as_u16(), 303, "GET /logout should redirect without a cookie");
+        assert_eq!(response.headers()["location"], "/");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
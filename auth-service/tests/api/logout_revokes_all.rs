@@ -0,0 +1,73 @@
+use auth_service::{routes::SignupPayload, utils::config::AppConfig};
+use reqwest::header::AUTHORIZATION;
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+async fn spawn_with_logout_revokes_all(logout_revokes_all: bool) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                logout_revokes_all,
+                ..AppConfig::default()
+        })
+        .await
+}
+
+async fn logout(app: &TestApp, token: &str) -> TestResult<u16> {
+        let response = reqwest::Client::new()
+                .post(format!("{}/logout", app.address))
+                .header(AUTHORIZATION, format!("Bearer {token}"))
+                .send()
+                .await?;
+        Ok(response.status().as_u16())
+}
+
+#[tokio::test]
+async fn logout_ends_every_session_when_enabled() -> TestResult<()> {
+        let app = spawn_with_logout_revokes_all(true).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        let other_email = get_random_email();
+        app.post_signup(&SignupPayload::new(other_email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let laptop = app.login(&email).await?;
+        let phone = app.login(&email).await?;
+        let someone_else = app.login(&other_email).await?;
+
+        assert_eq!(logout(&app, &laptop).await?, 200);
+
+        assert_eq!(app.token_status(&laptop).await?, 401);
+        assert_eq!(app.token_status(&phone).await?, 401, "Sibling session should be ended");
+        assert_eq!(app.token_status(&someone_else).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn logout_ends_only_its_own_session_by_default() -> TestResult<()> {
+        let app = spawn_with_logout_revokes_all(false).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let laptop = app.login(&email).await?;
+        let phone = app.login(&email).await?;
+
+        assert_eq!(logout(&app, &laptop).await?, 200);
+
+        assert_eq!(app.token_status(&laptop).await?, 401);
+        assert_eq!(app.token_status(&phone).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
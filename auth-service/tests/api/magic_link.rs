@@ -0,0 +1,197 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{
+                ForceResetPasswordPayload, MagicLinkResponse, PasswordChangeRequiredResponse,
+                SignupPayload, PASSWORD_CHANGE_REQUIRED_CODE,
+        },
+        utils::{
+                config::AppConfig,
+                constants::{JWT_COOKIE_NAME, MAGIC_LINK_SUBJECT},
+        },
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+async fn magic_link_app(config: AppConfig) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                magic_link_login: true,
+                ..config
+        })
+        .await
+}
+
+/// Token of the single login link mailed to `email`
+async fn magic_link_token(app: &TestApp, email: &str) -> String {
+        let sent = app.outbox.wait_for(email, MAGIC_LINK_SUBJECT).await;
+        assert_eq!(sent.len(), 1);
+        sent[0].content.rsplit("token=").next().unwrap().trim().to_owned()
+}
+
+#[tokio::test]
+async fn should_log_in_once_with_a_magic_link() -> TestResult<()> {
+        let app = magic_link_app(AppConfig::default()).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let response = app.post_magic_link_request(&email).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let token = magic_link_token(&app, &email).await;
+
+        let response = app.get_verify_magic_link(&token).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response
+                .cookies()
+                .any(|cookie| cookie.name() == *JWT_COOKIE_NAME && !cookie.value().is_empty()));
+
+        let reused = app.get_verify_magic_link(&token).await?;
+        assert_eq!(reused.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_require_a_password_change_after_an_admin_reset() -> TestResult<()> {
+        let app = magic_link_app(AppConfig::default()).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        app.login_as_admin().await?;
+        let payload = ForceResetPasswordPayload::new(email.clone());
+        assert_eq!(app.post_force_reset_password(&payload).await?.status().as_u16(), 200);
+
+        app.post_magic_link_request(&email).await?;
+        let token = magic_link_token(&app, &email).await;
+
+        // The link stands in for the temporary password, so it gets the same prompt
+        let response = app.get_verify_magic_link(&token).await?;
+        assert_eq!(response.status().as_u16(), 403);
+        assert!(response
+                .cookies()
+                .all(|cookie| cookie.name() != *JWT_COOKIE_NAME || cookie.value().is_empty()));
+        let prompt = response.json::<PasswordChangeRequiredResponse>().await?;
+        assert_eq!(prompt.code, PASSWORD_CHANGE_REQUIRED_CODE);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_for_an_expired_magic_link() -> TestResult<()> {
+        let app = magic_link_app(AppConfig {
+                magic_link_ttl_seconds: 1,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        app.post_magic_link_request(&email).await?;
+        let token = magic_link_token(&app, &email).await;
+        tokio::time::sleep(std::time::Duration::from_millis(1_100)).await;
+
+        let response = app.get_verify_magic_link(&token).await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_answer_an_unknown_email_like_a_known_one() -> TestResult<()> {
+        let app = magic_link_app(AppConfig::default()).await?;
+        let known = get_random_email();
+        app.post_signup(&SignupPayload::new(known.clone(), PASSWORD.to_owned(), false)).await;
+        let unknown = get_random_email();
+
+        let known_response = app.post_magic_link_request(&known).await?;
+        let unknown_response = app.post_magic_link_request(&unknown).await?;
+        assert_eq!(unknown_response.status().as_u16(), 200);
+        assert_eq!(
+                known_response.json::<MagicLinkResponse>().await?,
+                unknown_response.json::<MagicLinkResponse>().await?
+        );
+        // Once the known account's link has gone out, none has for the unknown one
+        assert_eq!(app.outbox.wait_for(&known, MAGIC_LINK_SUBJECT).await.len(), 1);
+        assert!(app.outbox.sent_to(&unknown, MAGIC_LINK_SUBJECT).is_empty());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_cap_requests_per_email_and_per_ip() -> TestResult<()> {
+        let app = magic_link_app(AppConfig {
+                max_magic_links_per_email: Some(2),
+                max_magic_links_per_ip: Some(6),
+                ..AppConfig::default()
+        })
+        .await?;
+        let known = get_random_email();
+        app.post_signup(&SignupPayload::new(known.clone(), PASSWORD.to_owned(), false)).await;
+        let unknown = get_random_email();
+
+        // An email without an account is capped like one with, so the 429 gives nothing away
+        for email in [&known, &unknown] {
+                for _ in 0..2 {
+                        let response = app.post_magic_link_request(email).await?;
+                        assert_eq!(response.status().as_u16(), 200);
+                }
+                let response = app.post_magic_link_request(email).await?;
+                assert_eq!(response.status().as_u16(), 429);
+                assert_eq!(
+                        response.json::<ErrorResponse>().await?.code.as_deref(),
+                        Some("rate_limited")
+                );
+        }
+
+        // Refused requests count too, so the IP has used up its six and even a fresh email is
+        // refused
+        let response = app.post_magic_link_request(&get_random_email()).await?;
+        assert_eq!(response.status().as_u16(), 429);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_mount_magic_link_routes_unless_enabled() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.post_magic_link_request(&get_random_email()).await?;
+        assert_eq!(response.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
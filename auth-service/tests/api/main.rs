@@ -1,10 +1,47 @@
+mod ban_tokens;
+mod bearer_token;
+mod change_email;
+mod change_password;
+mod check_email;
+mod concurrency_limit;
+mod content_negotiation;
+mod data_export;
+mod debug_token;
+mod failed_login_alert;
+mod force_reset_password;
+mod health;
 mod helpers;
+mod inactivity_expiry;
 mod login;
+mod login_history;
+mod login_throttling;
 mod logout;
+mod logout_revokes_all;
+mod magic_link;
+mod maintenance_mode;
+mod metrics;
+mod migrations;
+mod password_policy;
+mod postgres_user_store;
+mod reactivate_account;
+mod redis_failed_login_store;
+mod redis_pool;
+mod request_timeout;
 mod root;
+mod rotate_key;
+mod security_summary;
+mod seed_admin;
+mod session_limit;
+mod session_status;
+mod sessions;
 mod signup;
+mod test_db;
+#[cfg(feature = "tls")]
+mod tls;
+mod trusted_proxy;
 mod verify_2fa;
 mod verify_token;
+mod webhook;
 
 pub use crate::helpers::{get_random_email, TestApp};
 pub use auth_service::routes::{LoginPayload, SignupPayload, Verify2FAPayload, VerifyTokenPayload};
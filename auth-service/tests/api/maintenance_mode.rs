@@ -0,0 +1,103 @@
+use auth_service::{
+        domain::{Email, ErrorResponse, HashedPassword, User},
+        routes::{LoginPayload, MaintenanceModePayload, SignupPayload},
+        utils::config::AppConfig,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Add a user through the store, since signup itself is closed during maintenance
+async fn seed_user(app: &TestApp) -> TestResult<String> {
+        let email = get_random_email();
+        let user = User::new(
+                Email::parse(&email).expect("Invalid Email"),
+                HashedPassword::parse(PASSWORD).await?,
+                false,
+        );
+        app.user_store.write().await.add_user(user).await.expect("Failed to seed user");
+        Ok(email)
+}
+
+async fn signup_status(app: &TestApp) -> u16 {
+        let signup = SignupPayload::new(get_random_email(), PASSWORD.to_owned(), false);
+        app.post_signup(&signup).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn should_refuse_signup_but_allow_login_in_maintenance_mode() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                maintenance_mode: true,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = seed_user(&app).await?;
+
+        let signup = SignupPayload::new(get_random_email(), PASSWORD.to_owned(), false);
+        let response = app.post_signup(&signup).await;
+        assert_eq!(response.status().as_u16(), 503);
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.code.as_deref(), Some("maintenance_mode"));
+
+        let login = LoginPayload::new(email, PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+        assert_eq!(app.get_readiness().await?.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_let_an_admin_toggle_maintenance_mode_at_runtime() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        app.login_as_admin().await?;
+
+        let enable = MaintenanceModePayload {
+                enabled: true,
+        };
+        assert_eq!(app.post_set_maintenance_mode(&enable).await?.status().as_u16(), 200);
+        assert_eq!(signup_status(&app).await, 503);
+
+        // The switch itself stays reachable, so maintenance can be ended
+        let disable = MaintenanceModePayload {
+                enabled: false,
+        };
+        assert_eq!(app.post_set_maintenance_mode(&disable).await?.status().as_u16(), 200);
+        assert_eq!(signup_status(&app).await, 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_403_if_a_non_admin_toggles_maintenance_mode() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = seed_user(&app).await?;
+        let login = LoginPayload::new(email, PASSWORD.to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        let enable = MaintenanceModePayload {
+                enabled: true,
+        };
+        assert_eq!(app.post_set_maintenance_mode(&enable).await?.status().as_u16(), 403);
+        assert_eq!(signup_status(&app).await, 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,97 @@
+use auth_service::{
+        domain::{Email, LoginAttemptId},
+        routes::TwoFactorAuthResponse,
+        utils::{auth::validate_challenge_token, config::AppConfig},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// The value of the sample named `sample` (metric name and labels) in a Prometheus text body
+fn sample_value(body: &str, sample: &str) -> Option<u64> {
+        body.lines()
+                .filter(|line| !line.starts_with('#'))
+                .find_map(|line| line.strip_prefix(sample)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[tokio::test]
+async fn should_count_a_started_and_successfully_verified_2fa_challenge() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                metrics_endpoint: true,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        let signup_payload = serde_json::json!({
+                "email": email,
+                "password": PASSWORD,
+                "requires2FA": true
+        });
+        assert_eq!(app.post_signup(&signup_payload).await.status().as_u16(), 201);
+
+        let body = app.get_metrics().await?.text().await?;
+        assert_eq!(sample_value(&body, "auth_2fa_challenges_started_total"), Some(0));
+        assert_eq!(
+                sample_value(&body, "auth_2fa_verifications_total{result=\"success\"}"),
+                Some(0)
+        );
+
+        let login_payload = serde_json::json!({ "email": email, "password": PASSWORD });
+        let response = app.post_login(&login_payload).await;
+        assert_eq!(response.status().as_u16(), 206);
+        let challenge_token = response.json::<TwoFactorAuthResponse>().await?.challenge_token;
+
+        let claims = validate_challenge_token(&challenge_token, &app.key_ring.load())?;
+        let parsed_email = Email::parse(&email).expect("Email should be valid in test setup");
+        let login_attempt_id = LoginAttemptId::parse(claims.login_attempt_id)?;
+        let code = app
+                .two_fa_code_store
+                .read()
+                .await
+                .get_code(&parsed_email, &login_attempt_id)
+                .await
+                .expect("2FA code should be pending after login");
+        let verify_payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code.as_ref()
+        });
+        assert_eq!(app.post_verify_2fa(&verify_payload).await?.status().as_u16(), 200);
+
+        let response = app.get_metrics().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let body = response.text().await?;
+        assert_eq!(sample_value(&body, "auth_2fa_challenges_started_total"), Some(1));
+        assert_eq!(
+                sample_value(&body, "auth_2fa_verifications_total{result=\"success\"}"),
+                Some(1)
+        );
+        assert_eq!(
+                sample_value(&body, "auth_2fa_verifications_total{result=\"mismatch\"}"),
+                Some(0)
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_404_when_the_metrics_endpoint_is_disabled() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        assert_eq!(app.get_metrics().await?.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,41 @@
+use std::str::FromStr;
+
+use auth_service::{run_migrations, utils::constants::DATABASE_URL};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use crate::{helpers::TestDb, TestResult};
+
+async fn applied_migrations(pool: &sqlx::PgPool) -> TestResult<i64> {
+        let count =
+                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM _sqlx_migrations WHERE success")
+                        .fetch_one(pool)
+                        .await?;
+        Ok(count)
+}
+
+#[tokio::test]
+async fn migrate_applies_pending_migrations_and_is_idempotent() -> TestResult<()> {
+        // A fresh database, connected to without the migrations `TestDb::pool` would run
+        let test_db = TestDb::create().await;
+        let options = PgConnectOptions::from_str(&DATABASE_URL)?.database(test_db.name());
+        let pool = PgPoolOptions::new().max_connections(1).connect_with(options).await?;
+
+        run_migrations(&pool).await?;
+
+        let expected = sqlx::migrate!()
+                .iter()
+                .filter(|migration| migration.migration_type.is_up_migration())
+                .count() as i64;
+        assert_eq!(applied_migrations(&pool).await?, expected);
+        let users =
+                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users").fetch_one(&pool).await?;
+        assert_eq!(users, 0);
+
+        // Nothing is pending the second time, so nothing changes
+        run_migrations(&pool).await?;
+        assert_eq!(applied_migrations(&pool).await?, expected);
+
+        pool.close().await;
+
+        Ok(())
+}
@@ -0,0 +1,57 @@
+use auth_service::{
+        domain::PasswordPolicy,
+        utils::{
+                config::AppConfig,
+                constants::env::{PASSWORD_MIN_LENGTH_ENV_VAR, PASSWORD_REQUIRE_SYMBOL_ENV_VAR},
+        },
+};
+
+use crate::{TestApp, TestResult};
+
+#[tokio::test]
+async fn should_return_the_default_password_policy() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_password_policy().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.json::<PasswordPolicy>().await?, PasswordPolicy::default());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_the_policy_loaded_from_the_environment() -> TestResult<()> {
+        // No other test reads these variables, so setting them here cannot race
+        std::env::set_var(PASSWORD_MIN_LENGTH_ENV_VAR, "14");
+        std::env::set_var(PASSWORD_REQUIRE_SYMBOL_ENV_VAR, "true");
+        let config = AppConfig::from_env();
+        std::env::remove_var(PASSWORD_MIN_LENGTH_ENV_VAR);
+        std::env::remove_var(PASSWORD_REQUIRE_SYMBOL_ENV_VAR);
+
+        let expected = PasswordPolicy {
+                min_len: 14,
+                require_symbol: true,
+                ..PasswordPolicy::default()
+        };
+        assert_eq!(config.password_policy, expected);
+
+        let app = TestApp::with_config(config).await?;
+
+        let response = app.get_password_policy().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.json::<PasswordPolicy>().await?, expected);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
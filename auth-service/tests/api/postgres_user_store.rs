@@ -0,0 +1,231 @@
+use std::{
+        error::Error,
+        sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+        },
+};
+
+use argon2::{
+        password_hash::{rand_core::OsRng, SaltString},
+        Algorithm, Argon2, Params, PasswordHash, PasswordHasher, Version,
+};
+use async_trait::async_trait;
+use auth_service::{
+        domain::{
+                Argon2PasswordVerifier, Email, HashedPassword, PasswordHashVerifier, TwoFAMethod,
+                User, UserStore, UserStoreError, Username,
+        },
+        services::data_stores::{postgres_user_store::PostgresUserStore, InMemoryPgUserStore},
+        utils::constants::{ARGON2_ITERATIONS, ARGON2_MEMORY_KIB, ARGON2_PARALLELISM},
+};
+
+use crate::{get_random_email, helpers::TestDb, TestResult};
+
+/// Counts verifications while delegating to argon2, so the timing work really happens
+#[derive(Default)]
+struct CountingVerifier {
+        calls: AtomicUsize,
+}
+
+#[async_trait]
+impl PasswordHashVerifier for CountingVerifier {
+        async fn verify(
+                &self,
+                hash: &HashedPassword,
+                password_candidate: &str,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Argon2PasswordVerifier.verify(hash, password_candidate).await
+        }
+}
+
+#[tokio::test]
+async fn validate_user_verifies_a_password_whether_or_not_the_user_exists() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let verifier = Arc::new(CountingVerifier::default());
+        let mut store = PostgresUserStore::with_verifier(test_db.pool().await, verifier.clone());
+
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        let password = HashedPassword::parse("ValidPassword123").await?;
+        store.add_user(User::new(email.clone(), password, false))
+                .await
+                .expect("Failed to add user");
+
+        assert_eq!(
+                store.validate_user(&email, "WrongPassword123").await,
+                Err(UserStoreError::InvalidCredentials)
+        );
+        assert_eq!(verifier.calls.load(Ordering::SeqCst), 1);
+
+        let unknown = Email::parse(&get_random_email()).expect("valid email");
+        assert_eq!(
+                store.validate_user(&unknown, "WrongPassword123").await,
+                Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(verifier.calls.load(Ordering::SeqCst), 2);
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn validate_user_rehashes_a_password_stored_with_lower_argon2_costs() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let mut store = PostgresUserStore::new(test_db.pool().await);
+
+        let raw_password = "ValidPassword123";
+        let weak_hash =
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(4096, 1, 1, None)?)
+                        .hash_password(raw_password.as_bytes(), &SaltString::generate(&mut OsRng))?
+                        .to_string();
+        let weak = HashedPassword::parse_password_hash(weak_hash.clone())?;
+        assert!(weak.needs_rehash());
+
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        store.add_user(User::new(email.clone(), weak, false)).await.expect("Failed to add user");
+
+        // A wrong password leaves the stored hash alone
+        assert_eq!(
+                store.validate_user(&email, "WrongPassword123").await,
+                Err(UserStoreError::InvalidCredentials)
+        );
+        assert_eq!(store.get_user(&email).await.expect("user").password_str(), weak_hash);
+
+        assert_eq!(store.validate_user(&email, raw_password).await, Ok(()));
+
+        let stored = store.get_user(&email).await.expect("user").password_to_owned();
+        assert!(!stored.needs_rehash());
+        let params = Params::try_from(&PasswordHash::new(stored.as_ref())?)?;
+        assert_eq!(params.m_cost(), *ARGON2_MEMORY_KIB);
+        assert_eq!(params.t_cost(), *ARGON2_ITERATIONS);
+        assert_eq!(params.p_cost(), *ARGON2_PARALLELISM);
+
+        // The verification result is unchanged: the same password still validates
+        assert_eq!(store.validate_user(&email, raw_password).await, Ok(()));
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn pepper_version_is_stored_with_the_hash_and_its_history() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let mut store = PostgresUserStore::new(test_db.pool().await);
+
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        let peppered = HashedPassword::parse("ValidPassword123").await?.with_pepper_version(3);
+        store.add_user(User::new(email.clone(), peppered, false))
+                .await
+                .expect("Failed to add user");
+        assert_eq!(store.get_user(&email).await.expect("user").password().pepper_version(), 3);
+
+        // The replaced hash keeps its version in the history
+        let replacement = HashedPassword::parse("OtherPassword123").await?;
+        store.update_password(&email, replacement, 5).await.expect("Failed to update password");
+        assert_eq!(store.get_user(&email).await.expect("user").password().pepper_version(), 0);
+        let history = store.get_password_history(&email).await.expect("history");
+        assert_eq!(history.iter().map(HashedPassword::pepper_version).collect::<Vec<_>>(), [3]);
+
+        Ok(())
+}
+
+/// A user with a freshly salted hash, so it only clashes on the fields it is meant to
+async fn new_user(email: &Email) -> TestResult<User> {
+        let password = HashedPassword::parse("ValidPassword123").await?;
+        Ok(User::new(email.clone(), password, false))
+}
+
+/// Duplicate and missing-user cases whose results `InMemoryPgUserStore` must reproduce
+async fn constraint_and_missing_user_results(
+        store: &mut dyn UserStore,
+        [existing, deleted, missing]: &[Email; 3],
+) -> TestResult<Vec<Result<(), UserStoreError>>> {
+        let username = Username::parse("duplicate").expect("valid username");
+        let first = new_user(existing).await?.with_username(username.clone());
+        let existing_hash = first.password_to_owned();
+
+        let results = vec![
+                store.add_user(first).await,
+                store.add_user(new_user(deleted).await?).await,
+                store.soft_delete(deleted).await,
+                // An unsupported 2FA method
+                store.add_user(new_user(missing).await?.with_two_fa_method(TwoFAMethod::Totp))
+                        .await,
+                // Duplicates: email, soft-deleted email, username, password hash
+                store.add_user(new_user(existing).await?).await,
+                store.add_user(new_user(deleted).await?).await,
+                store.add_user(new_user(missing).await?.with_username(username)).await,
+                store.add_user(User::new(missing.clone(), existing_hash, false)).await,
+                // Missing or soft-deleted users
+                store.get_user(deleted).await.map(|_| ()),
+                store.get_user(missing).await.map(|_| ()),
+                store.soft_delete(missing).await,
+                store.record_login(deleted).await,
+                store.update_password(missing, new_user(missing).await?.password_to_owned(), 5)
+                        .await,
+                store.get_password_history(missing).await.map(|_| ()),
+                store.validate_user(missing, "ValidPassword123").await,
+                // Email changes: a missing row wins over a taken address
+                store.update_email(missing, existing).await,
+                store.update_email(existing, deleted).await,
+                store.update_email(deleted, missing).await,
+                store.update_email(existing, existing).await,
+        ];
+
+        Ok(results)
+}
+
+#[tokio::test]
+async fn in_memory_fake_returns_the_same_results_as_postgres() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let mut postgres = PostgresUserStore::new(test_db.pool().await);
+        let mut fake = InMemoryPgUserStore::new();
+
+        let emails = [get_random_email(), get_random_email(), get_random_email()]
+                .map(|email| Email::parse(&email).expect("valid email"));
+        let expected = constraint_and_missing_user_results(&mut postgres, &emails).await?;
+        let actual = constraint_and_missing_user_results(&mut fake, &emails).await?;
+
+        assert_eq!(actual, expected);
+        // Guard against both stores agreeing on a run that tested nothing
+        assert!(expected.contains(&Err(UserStoreError::UserAlreadyExists)));
+        assert!(expected.contains(&Err(UserStoreError::UserNotFound)));
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn add_user_rejects_an_email_differing_only_in_case() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let pool = test_db.pool().await;
+        let mut store = PostgresUserStore::new(pool.clone());
+
+        // `Email::parse` lowercases, so only a row written some other way, like one stored
+        // before it did, can differ in case
+        let lower = Email::parse(&get_random_email()).expect("valid email");
+        let password = new_user(&lower).await?.password_to_owned();
+        sqlx::query("INSERT INTO users (email, password_hash) VALUES ($1, $2)")
+                .bind(lower.as_str().to_uppercase())
+                .bind(password.as_ref())
+                .execute(&pool)
+                .await?;
+
+        assert_eq!(
+                store.add_user(new_user(&lower).await?).await,
+                Err(UserStoreError::UserAlreadyExists)
+        );
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn add_user_refuses_a_totp_user() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let mut store = PostgresUserStore::new(test_db.pool().await);
+
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        let user = new_user(&email).await?.with_two_fa_method(TwoFAMethod::Totp);
+        assert_eq!(store.add_user(user).await, Err(UserStoreError::UnexpectedError));
+        assert_eq!(store.get_user(&email).await.map(|_| ()), Err(UserStoreError::UserNotFound));
+
+        Ok(())
+}
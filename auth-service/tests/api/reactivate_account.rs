@@ -0,0 +1,131 @@
+use auth_service::{
+        domain::{Email, ErrorResponse},
+        routes::{LoginPayload, ReactivateAccountPayload, SignupPayload},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Sign up a regular user and soft-delete it, returning its email.
+async fn signup_and_soft_delete(app: &TestApp) -> TestResult<Email> {
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let email = Email::parse(&email).expect("Valid email");
+        app.user_store.write().await.soft_delete(&email).await.expect("Failed to soft-delete");
+
+        Ok(email)
+}
+
+#[tokio::test]
+async fn soft_deleted_user_cannot_login_but_row_is_kept() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = signup_and_soft_delete(&app).await?;
+
+        let login = LoginPayload::new(email.as_str().to_owned(), PASSWORD.to_owned());
+        let response = app.post_login(&login).await;
+        assert_eq!(response.status().as_u16(), 401, "Soft-deleted user must not log in");
+
+        let user = app
+                .user_store
+                .read()
+                .await
+                .get_user_including_deleted(&email)
+                .await
+                .expect("Row should still exist");
+        assert!(user.is_deleted());
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_200_and_allow_login_after_admin_reactivates() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = signup_and_soft_delete(&app).await?;
+        app.login_as_admin().await?;
+
+        let payload = ReactivateAccountPayload::new(email.as_str().to_owned());
+        let response = app.post_reactivate_account(&payload).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        let login = LoginPayload::new(email.as_str().to_owned(), PASSWORD.to_owned());
+        let response = app.post_login(&login).await;
+        assert_eq!(response.status().as_u16(), 200, "Reactivated user should log in");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_403_if_caller_is_not_admin() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let deleted = signup_and_soft_delete(&app).await?;
+
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), PASSWORD.to_owned(), false);
+        let _ = app.post_signup(&signup).await;
+        let _ = app.post_login(&LoginPayload::new(email, PASSWORD.to_owned())).await;
+
+        let payload = ReactivateAccountPayload::new(deleted.as_str().to_owned());
+        let response = app.post_reactivate_account(&payload).await?;
+        assert_eq!(response.status().as_u16(), 403);
+
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.error, "Forbidden");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let payload = ReactivateAccountPayload::new(get_random_email());
+        let response = app.post_reactivate_account(&payload).await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_404_if_account_does_not_exist() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        app.login_as_admin().await?;
+
+        let payload = ReactivateAccountPayload::new(get_random_email());
+        let response = app.post_reactivate_account(&payload).await?;
+        assert_eq!(response.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,41 @@
+use chrono::Utc;
+
+use auth_service::{
+        domain::{Email, FailedLoginStore},
+        get_redis_pool,
+        services::data_stores::redis_failed_login_store::RedisFailedLoginStore,
+        utils::constants::FAILED_LOGIN_WINDOW_SECONDS,
+};
+
+use crate::get_random_email;
+
+#[tokio::test]
+async fn failures_are_counted_over_a_sliding_window() {
+        let mut store = RedisFailedLoginStore::new(get_redis_pool());
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        // A made-up IP keeps the count apart from other test runs
+        let ip = format!("test-ip-{}", uuid::Uuid::new_v4());
+
+        let before = Utc::now().timestamp();
+        assert_eq!(store.record_failure(&email).await, Ok(1));
+        assert_eq!(store.record_failure(&email).await, Ok(2));
+        assert_eq!(store.record_ip_failure(&ip).await, Ok(1));
+        let after = Utc::now().timestamp();
+
+        assert_eq!(store.get_failures(&email).await, Ok(2));
+        assert_eq!(store.get_ip_failures(&ip).await, Ok(1));
+
+        // The count drops once the oldest failure is a full window old
+        let reset_at = store
+                .get_failures_reset_at(&email)
+                .await
+                .expect("get_failures_reset_at")
+                .expect("a failure is counted");
+        assert!(reset_at >= before + FAILED_LOGIN_WINDOW_SECONDS);
+        assert!(reset_at <= after + FAILED_LOGIN_WINDOW_SECONDS + 1);
+
+        store.clear_failures(&email).await.expect("clear_failures");
+        assert_eq!(store.get_failures(&email).await, Ok(0));
+        assert_eq!(store.get_failures_reset_at(&email).await, Ok(None));
+        assert_eq!(store.get_ip_failures(&ip).await, Ok(1));
+}
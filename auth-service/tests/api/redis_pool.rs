@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use auth_service::{
+        domain::{
+                BannedTokenStore, Email, LoginAttemptId, TwoFACode, TwoFACodeStore,
+                TwoFACodeStoreError,
+        },
+        get_redis_pool,
+        services::data_stores::{
+                redis_banned_token_store::RedisBannedTokenStore,
+                redis_two_fa_code_store::RedisTwoFACodeStore,
+        },
+        utils::auth::Token,
+};
+
+use crate::{get_random_email, TestResult};
+
+#[tokio::test]
+async fn stores_sharing_one_pool_both_work() -> TestResult<()> {
+        let pool = get_redis_pool();
+        let mut banned_token_store = RedisBannedTokenStore::new(Arc::clone(&pool));
+        let mut two_fa_code_store = RedisTwoFACodeStore::new(Arc::clone(&pool));
+
+        let token = Token::parse(format!("header.payload.{}", uuid::Uuid::new_v4()))
+                .expect("valid token");
+        assert!(!banned_token_store.is_banned(&token).await.expect("is_banned"));
+        banned_token_store.ban_token(token.clone()).await.expect("ban_token");
+
+        let email = Email::parse(&get_random_email()).expect("valid email");
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        two_fa_code_store
+                .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+                .await
+                .expect("add_code");
+
+        // Each store checks out its own connection from the same pool
+        assert!(banned_token_store.is_banned(&token).await.expect("is_banned"));
+        assert_eq!(
+                two_fa_code_store.get_code(&email, &login_attempt_id).await.expect("get_code"),
+                code
+        );
+
+        two_fa_code_store.remove_code(&email, &login_attempt_id).await.expect("remove_code");
+        assert_eq!(
+                two_fa_code_store.get_code(&email, &login_attempt_id).await,
+                Err(TwoFACodeStoreError::NoActiveChallenge)
+        );
+        assert!(banned_token_store.is_banned(&token).await.expect("is_banned"));
+
+        Ok(())
+}
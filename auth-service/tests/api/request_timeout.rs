@@ -0,0 +1,61 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use auth_service::{
+        domain::{Email, EmailClient, EmailClientError},
+        utils::config::AppConfig,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+/// Email provider that answers only after `delay`
+struct SlowEmailClient {
+        delay: Duration,
+}
+
+#[async_trait]
+impl EmailClient for SlowEmailClient {
+        async fn send_email(&self, _: &Email, _: &str, _: &str) -> Result<(), EmailClientError> {
+                tokio::time::sleep(self.delay).await;
+                Ok(())
+        }
+}
+
+#[tokio::test]
+async fn should_return_504_if_a_handler_outlives_the_request_timeout() -> TestResult<()> {
+        let config = AppConfig {
+                request_timeout_seconds: 1,
+                ..AppConfig::default()
+        };
+        let email_client = Arc::new(SlowEmailClient {
+                delay: Duration::from_secs(5),
+        });
+        let app = TestApp::builder().config(config).email_client(email_client).build().await?;
+
+        let email = get_random_email();
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": email,
+                        "password": "ValidPassword123",
+                        "requires2FA": true
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // A 2FA login waits on the email provider, which takes longer than the timeout
+        let res = app
+                .post_login(&serde_json::json!({
+                        "email": email,
+                        "password": "ValidPassword123"
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 504);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
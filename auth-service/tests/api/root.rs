@@ -1,3 +1,6 @@
+use auth_service::{domain::ErrorResponse, utils::config::AppConfig};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+
 use crate::{TestApp, TestResult};
 
 #[tokio::test]
@@ -14,3 +17,142 @@ async fn root_returns_auth_ui() -> TestResult<()> {
 
         Ok(())
 }
+
+#[tokio::test]
+async fn root_returns_html() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let response = app.get_login_or_signup().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let content_type = response.headers()[CONTENT_TYPE].to_str()?.to_owned();
+        assert!(content_type.starts_with("text/html"), "got {content_type}");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn root_serves_index_html_from_the_configured_assets_dir() -> TestResult<()> {
+        let assets_dir = std::env::temp_dir().join(format!("assets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&assets_dir)?;
+        std::fs::write(assets_dir.join("index.html"), "<h1>Custom assets</h1>")?;
+        std::fs::write(assets_dir.join("app.js"), "// custom")?;
+
+        let app = TestApp::with_config(AppConfig {
+                assets_dir: assets_dir.clone(),
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let response = app.get_login_or_signup().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.text().await?, "<h1>Custom assets</h1>");
+
+        // Static files come from the same directory
+        let response = app.http_client.get(format!("{}/app.js", app.address)).send().await?;
+        assert_eq!(response.text().await?, "// custom");
+
+        std::fs::remove_dir_all(&assets_dir)?;
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn unknown_api_path_returns_json_404() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app
+                .http_client
+                .post(format!("{}/does-not-exist", app.address))
+                .json(&serde_json::json!({}))
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 404);
+        assert_eq!(response.json::<ErrorResponse>().await?.error, "Not found");
+
+        // A GET from an API client is not a page load either
+        let response = app
+                .http_client
+                .get(format!("{}/singup", app.address))
+                .header(ACCEPT, "application/json")
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 404);
+        assert_eq!(response.json::<ErrorResponse>().await?.error, "Not found");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn unknown_path_from_a_browser_returns_the_ui() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app
+                .http_client
+                .get(format!("{}/some/client-side/route", app.address))
+                .header(ACCEPT, "text/html,application/xhtml+xml,*/*;q=0.8")
+                .send()
+                .await?;
+        // Still the UI's 404 page, as `ServeDir` answers it: `index.html` with a 404 status
+        assert_eq!(response.status().as_u16(), 404);
+        assert!(response.headers()[CONTENT_TYPE].to_str()?.starts_with("text/html"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn root_is_a_json_404_when_the_ui_is_not_served() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                serve_ui: false,
+                ..AppConfig::default()
+        })
+        .await?;
+
+        // Even a browser asking for HTML gets no page, at `/` or a path the UI would handle
+        for path in ["/", "/app.js", "/some/page"] {
+                let response = app
+                        .http_client
+                        .get(format!("{}{}", app.address, path))
+                        .header(ACCEPT, "text/html")
+                        .send()
+                        .await?;
+                assert_eq!(response.status().as_u16(), 404, "Failed for {path}");
+                let content_type = response.headers()[CONTENT_TYPE].to_str()?.to_owned();
+                assert!(content_type.starts_with("application/json"), "got {content_type}");
+                assert_eq!(response.json::<ErrorResponse>().await?.error, "Not found");
+        }
+
+        // The API itself is unaffected
+        let response = app.get_password_policy().await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,99 @@
+use auth_service::{
+        domain::ValidationErrorResponse,
+        routes::{LoginPayload, RotateKeyPayload, RotateKeyResponse, SignupPayload},
+        utils::key_ring::KeyRing,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+async fn rotate(app: &TestApp, secret: &str) -> TestResult<String> {
+        let response = app.post_rotate_key(&RotateKeyPayload::new(secret)).await?;
+        assert_eq!(response.status().as_u16(), 200, "Rotation should succeed");
+        Ok(response.json::<RotateKeyResponse>().await?.kid)
+}
+
+/// The `kid` any instance gives `secret`
+fn kid_of(secret: &str) -> String {
+        KeyRing::new(secret).current().kid().to_owned()
+}
+
+#[tokio::test]
+async fn should_keep_accepting_tokens_signed_with_the_previous_key() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        app.login_as_admin().await?;
+        let email = get_random_email();
+
+        let before = app.login(&email).await?;
+        let secret = "a-newly-pushed-signing-secret-0001";
+        assert_eq!(rotate(&app, secret).await?, kid_of(secret));
+        let after = app.login(&email).await?;
+
+        assert_eq!(app.token_status(&before).await?, 200);
+        assert_eq!(app.token_status(&after).await?, 200);
+
+        // The original key is dropped when the second key is demoted in turn
+        let secret = "a-newly-pushed-signing-secret-0002";
+        assert_eq!(rotate(&app, secret).await?, kid_of(secret));
+        assert_eq!(app.token_status(&before).await?, 401);
+        assert_eq!(app.token_status(&after).await?, 200);
+        assert_eq!(app.token_status(&app.login(&email).await?).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_for_a_short_secret() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        app.login_as_admin().await?;
+        let email = get_random_email();
+        let token = app.login(&email).await?;
+        let kid = app.key_ring.load().current().kid().to_owned();
+
+        let response = app.post_rotate_key(&RotateKeyPayload::new("too-short")).await?;
+        assert_eq!(response.status().as_u16(), 400);
+        let body = response.json::<ValidationErrorResponse>().await?;
+        assert_eq!(body.fields[0].field, "secret");
+
+        // The key was not rotated
+        assert_eq!(app.key_ring.load().current().kid(), kid);
+        assert_eq!(app.token_status(&token).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_403_for_non_admin() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        app.post_login(&LoginPayload::new(email, PASSWORD.to_owned())).await;
+        let kid = app.key_ring.load().current().kid().to_owned();
+
+        let response = app
+                .post_rotate_key(&RotateKeyPayload::new("a-newly-pushed-signing-secret-0001"))
+                .await?;
+        assert_eq!(response.status().as_u16(), 403);
+        assert_eq!(app.key_ring.load().current().kid(), kid);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
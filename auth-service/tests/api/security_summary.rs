@@ -0,0 +1,64 @@
+use auth_service::{
+        routes::{LoginPayload, SecuritySummaryResponse, SignupPayload},
+        utils::constants::JWT_COOKIE_NAME,
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+#[tokio::test]
+async fn should_summarize_sessions_2fa_and_failed_logins_after_a_login() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let failed = app
+                .post_login(&LoginPayload::new(email.clone(), "WrongPassword123".to_owned()))
+                .await;
+        assert_eq!(failed.status().as_u16(), 401);
+        let response = app.post_login(&LoginPayload::new(email.clone(), PASSWORD.to_owned())).await;
+        assert_eq!(response.status().as_u16(), 200);
+        let token = response
+                .cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie should be set")
+                .value()
+                .to_owned();
+
+        let response = app.get_security_summary(&token).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let summary = response.json::<SecuritySummaryResponse>().await?;
+        assert_eq!(summary.active_session_count, Some(1));
+        assert!(!summary.two_factor_enabled);
+        assert_eq!(summary.two_factor_method, None);
+        assert_eq!(summary.recent_failed_logins, Some(1));
+        assert!(summary.last_login_at > 0);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_without_a_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = reqwest::Client::new()
+                .get(format!("{}/security/summary", app.address))
+                .send()
+                .await?;
+        assert_eq!(response.status().as_u16(), 400);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,61 @@
+use auth_service::{
+        domain::{Email, Role},
+        seed_admin,
+        utils::config::AppConfig,
+        SeedAdminOutcome,
+};
+
+use crate::{get_random_email, LoginPayload, SignupPayload, TestApp, TestResult};
+
+const PASSWORD: &str = "AdminPassword123";
+
+#[tokio::test]
+async fn seeding_twice_creates_exactly_one_admin() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = Email::parse(&get_random_email()).expect("Valid email");
+        let config = AppConfig::default();
+
+        let first = seed_admin(&app.user_store, &email, PASSWORD, &config).await?;
+        let second = seed_admin(&app.user_store, &email, "OtherPassword123", &config).await?;
+        assert_eq!(first, SeedAdminOutcome::Created);
+        assert_eq!(second, SeedAdminOutcome::AlreadyExists);
+
+        let admin = app.user_store.read().await.get_user(&email).await.expect("User should exist");
+        assert_eq!(admin.role(), Role::Admin);
+
+        // The password is hashed and the second run did not replace it
+        let response = app
+                .post_login(&LoginPayload::new(email.as_str().to_owned(), PASSWORD.to_owned()))
+                .await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn seeding_never_promotes_an_existing_user() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+        let email = Email::parse(&email).expect("Valid email");
+
+        let outcome = seed_admin(&app.user_store, &email, PASSWORD, &AppConfig::default()).await?;
+        assert_eq!(outcome, SeedAdminOutcome::AlreadyExists);
+
+        let user = app.user_store.read().await.get_user(&email).await.expect("User should exist");
+        assert_eq!(user.role(), Role::User);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,74 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{LoginPayload, SignupPayload},
+        utils::config::{AppConfig, SessionEvictionPolicy},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+async fn spawn_with_session_limit(
+        max_sessions: usize,
+        policy: SessionEvictionPolicy,
+) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                max_sessions_per_user: Some(max_sessions),
+                session_eviction_policy: policy,
+                ..AppConfig::default()
+        })
+        .await
+}
+
+#[tokio::test]
+async fn third_login_evicts_the_oldest_session() -> TestResult<()> {
+        let app = spawn_with_session_limit(2, SessionEvictionPolicy::EvictOldest).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let first = app.login(&email).await?;
+        let second = app.login(&email).await?;
+        let third = app.login(&email).await?;
+
+        assert_eq!(app.token_status(&first).await?, 401, "Oldest session should be evicted");
+        assert_eq!(app.token_status(&second).await?, 200);
+        assert_eq!(app.token_status(&third).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn third_login_is_rejected_under_reject_policy() -> TestResult<()> {
+        let app = spawn_with_session_limit(2, SessionEvictionPolicy::Reject).await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let first = app.login(&email).await?;
+        let second = app.login(&email).await?;
+
+        let response = app.post_login(&LoginPayload::new(email.clone(), PASSWORD.to_owned())).await;
+        assert_eq!(response.status().as_u16(), 409);
+        assert_eq!(response.json::<ErrorResponse>().await?.error, "Too many active sessions");
+
+        // Existing sessions are untouched
+        assert_eq!(app.token_status(&first).await?, 200);
+        assert_eq!(app.token_status(&second).await?, 200);
+
+        // Logging out frees a slot
+        assert_eq!(app.post_logout_with_token(&second).await?.status().as_u16(), 200);
+        app.login(&email).await?;
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,72 @@
+use auth_service::{domain::BannedTokenStore, routes::SessionStatusResponse, utils::auth::Token};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+#[tokio::test]
+async fn should_report_a_valid_session_as_active() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let token = app.login(&get_random_email()).await?;
+
+        let response = app.get_session_status_with_token(&token).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let status = response.json::<SessionStatusResponse>().await?;
+        assert!(status.active);
+        assert!(status.expires_at.is_some_and(|exp| exp as i64 > chrono::Utc::now().timestamp()));
+
+        // Checking the status neither bans nor replaces the token
+        let token = Token::parse(token).expect("JWT cookie must hold a token");
+        assert!(!app.banned_token_store.read().await.is_banned(&token).await.unwrap());
+        let response = app.get_session_status_with_token(token.as_str()).await?;
+        assert!(response.cookies().next().is_none(), "No cookie should be set");
+        assert!(response.json::<SessionStatusResponse>().await?.active);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_a_logged_out_token_as_inactive() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let token = app.login(&get_random_email()).await?;
+        assert_eq!(app.post_logout_with_token(&token).await?.status().as_u16(), 200);
+
+        let response = app.get_session_status_with_token(&token).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let status = response.json::<SessionStatusResponse>().await?;
+        assert!(!status.active);
+        assert_eq!(status.expires_at, None);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_inactive_without_a_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_session_status().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(!response.json::<SessionStatusResponse>().await?.active);
+
+        let response = app.get_session_status_with_token("not-a-jwt").await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(!response.json::<SessionStatusResponse>().await?.active);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,184 @@
+use auth_service::{
+        domain::ErrorResponse,
+        routes::{SessionsResponse, SignOutOthersResponse, SignupPayload},
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+async fn list_sessions(app: &TestApp, token: &str) -> TestResult<SessionsResponse> {
+        let response = app.get_sessions(token).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        Ok(response.json::<SessionsResponse>().await?)
+}
+
+#[tokio::test]
+async fn should_list_each_login_as_a_session() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let first = app.login_with_user_agent(&email, "laptop").await?;
+        let second = app.login_with_user_agent(&email, "phone").await?;
+
+        // Either token sees the same list, oldest first
+        for token in [&first, &second] {
+                let sessions = list_sessions(&app, token).await?.sessions;
+                assert_eq!(sessions.len(), 2);
+                assert_eq!(sessions[0].user_agent.as_deref(), Some("laptop"));
+                assert_eq!(sessions[1].user_agent.as_deref(), Some("phone"));
+                assert_eq!(sessions[0].ip.as_deref(), Some("127.0.0.1"));
+                assert_ne!(sessions[0].jti, sessions[1].jti);
+                assert!(sessions[0].created_at > 0);
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_listing_without_a_valid_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.get_sessions("invalid").await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn revoking_a_session_bans_only_that_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let first = app.login_with_user_agent(&email, "laptop").await?;
+        let second = app.login_with_user_agent(&email, "phone").await?;
+
+        let sessions = list_sessions(&app, &second).await?.sessions;
+        let first_jti = sessions[0].jti.clone();
+
+        let response = app.delete_session(&second, &first_jti).await?;
+        assert_eq!(response.status().as_u16(), 204);
+
+        assert_eq!(app.token_status(&first).await?, 401, "Revoked token should be banned");
+        assert_eq!(app.token_status(&second).await?, 200);
+
+        let sessions = list_sessions(&app, &second).await?.sessions;
+        assert_eq!(sessions.len(), 1);
+        assert_ne!(sessions[0].jti, first_jti);
+
+        // The session is gone, so revoking it again is a 404
+        let response = app.delete_session(&second, &first_jti).await?;
+        assert_eq!(response.status().as_u16(), 404);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_revoke_another_users_session() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let victim_email = get_random_email();
+        let other_email = get_random_email();
+        for email in [&victim_email, &other_email] {
+                app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false))
+                        .await;
+        }
+
+        let victim = app.login_with_user_agent(&victim_email, "laptop").await?;
+        let other = app.login_with_user_agent(&other_email, "laptop").await?;
+
+        let victim_jti = list_sessions(&app, &victim).await?.sessions[0].jti.clone();
+
+        // The other user cannot see the victim's sessions...
+        let sessions = list_sessions(&app, &other).await?.sessions;
+        assert!(sessions.iter().all(|session| session.jti != victim_jti));
+
+        // ...or revoke them
+        let response = app.delete_session(&other, &victim_jti).await?;
+        assert_eq!(response.status().as_u16(), 404);
+        assert_eq!(response.json::<ErrorResponse>().await?.error, "Session not found");
+
+        assert_eq!(app.token_status(&victim).await?, 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn signing_out_others_keeps_only_the_current_session() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        let other_email = get_random_email();
+        for email in [&email, &other_email] {
+                app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false))
+                        .await;
+        }
+
+        let laptop = app.login_with_user_agent(&email, "laptop").await?;
+        let phone = app.login_with_user_agent(&email, "phone").await?;
+        let current = app.login_with_user_agent(&email, "tablet").await?;
+        let other_user = app.login_with_user_agent(&other_email, "laptop").await?;
+
+        let response = app.post_sign_out_others(&current).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.json::<SignOutOthersResponse>().await?.sessions_ended, 2);
+
+        assert_eq!(app.token_status(&laptop).await?, 401, "Other sessions should be banned");
+        assert_eq!(app.token_status(&phone).await?, 401, "Other sessions should be banned");
+        assert_eq!(app.token_status(&current).await?, 200, "Current session should still work");
+        assert_eq!(app.token_status(&other_user).await?, 200, "Other users are unaffected");
+
+        let response = app.get_security_sessions(&current).await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let sessions = response.json::<SessionsResponse>().await?.sessions;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].user_agent.as_deref(), Some("tablet"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_signing_out_others_without_a_valid_token() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let response = app.post_sign_out_others("invalid").await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
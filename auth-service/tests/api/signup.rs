@@ -1,5 +1,17 @@
-use auth_service::{domain::ErrorResponse, routes::SignupResponse};
+use auth_service::{
+        domain::{
+                Email, ErrorResponse, FieldError, PasswordPolicy, PublicUser,
+                ValidationErrorResponse,
+        },
+        routes::{LoginPayload, SignupResponse, VerifyTokenPayload},
+        services::data_stores::{MockCaptchaVerifier, MockMxResolver},
+        utils::{
+                config::AppConfig,
+                constants::{JWT_COOKIE_NAME, SIGNUP_CONFLICT_SUBJECT},
+        },
+};
 use axum::response;
+use std::sync::Arc;
 
 use crate::{get_random_email, SignupPayload, TestApp, TestResult};
 
@@ -7,7 +19,7 @@ use crate::{get_random_email, SignupPayload, TestApp, TestResult};
 async fn should_return_201_if_valid_input() -> TestResult<()> {
         let app = TestApp::new().await?;
         let valid_input = serde_json::json!({
-                "email": "valid@mail.com",
+                "email": "new.user@mail.com",
                 "password": "ValidPassword123",
                 "requires2FA": false
         });
@@ -33,6 +45,68 @@ async fn should_return_201_if_valid_input() -> TestResult<()> {
         Ok(())
 }
 
+#[tokio::test]
+async fn form_encoded_signup_matches_json_signup() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let json_email = get_random_email();
+        let form_email = get_random_email();
+
+        let json_res = app
+                .post_signup(&serde_json::json!({
+                        "email": json_email,
+                        "password": "ValidPassword123",
+                        "requires2FA": false
+                }))
+                .await;
+        let form_res = app
+                .post_signup_form(&[
+                        ("email", form_email.as_str()),
+                        ("password", "ValidPassword123"),
+                        ("requires2FA", "false"),
+                ])
+                .await;
+        assert_eq!(json_res.status().as_u16(), 201);
+        assert_eq!(form_res.status().as_u16(), 201);
+        assert_eq!(
+                json_res.json::<SignupResponse>().await?,
+                form_res.json::<SignupResponse>().await?
+        );
+
+        // Both accounts were created alike: each can log in, by JSON or by form
+        let res = app
+                .post_login_form(&[
+                        ("email", json_email.as_str()),
+                        ("password", "ValidPassword123"),
+                ])
+                .await;
+        assert_eq!(res.status().as_u16(), 200);
+        let res = app
+                .post_login(&serde_json::json!({
+                        "email": form_email,
+                        "password": "ValidPassword123"
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 200);
+
+        // Form bodies are validated like JSON ones
+        let res = app
+                .post_signup_form(&[
+                        ("email", get_random_email().as_str()),
+                        ("password", ""),
+                        ("requires2FA", "false"),
+                ])
+                .await;
+        assert_eq!(res.status().as_u16(), 422);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
 #[tokio::test]
 async fn should_return_422_if_malformed_input() -> TestResult<()> {
         let app = TestApp::new().await?;
@@ -138,6 +212,126 @@ async fn should_return_400_if_invalid_input() -> TestResult<()> {
         Ok(())
 }
 
+#[tokio::test]
+async fn should_return_400_listing_every_invalid_field() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let invalid_input = serde_json::json!({
+                "email": "no at symbol and no dot",
+                "password": "2short",
+                "requires2FA": false,
+        });
+        let res = app.post_signup(&invalid_input).await;
+        assert_eq!(res.status().as_u16(), 400);
+
+        let body = res
+                .json::<ValidationErrorResponse>()
+                .await
+                .expect("Could not deserialize response body to ValidationErrorResponse");
+        assert_eq!(body.error, "Invalid credentials");
+
+        let failing_fields: Vec<(&str, &str)> =
+                body.fields.iter().map(|e| (e.field.as_str(), e.message.as_str())).collect();
+        assert!(failing_fields.contains(&("email", "Email has an invalid format")));
+        assert!(failing_fields.contains(&("password", "Password must be at least 8 characters")));
+        assert!(failing_fields
+                .contains(&("password", "Password must contain at least one uppercase letter")));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_enforce_the_configured_password_policy() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                password_policy: PasswordPolicy {
+                        min_len: 12,
+                        require_symbol: true,
+                        ..PasswordPolicy::default()
+                },
+                ..AppConfig::default()
+        })
+        .await?;
+
+        // Valid under the default policy, but too short and without a symbol here
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "Sunflower12".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 400);
+
+        let body = res.json::<ValidationErrorResponse>().await?;
+        let messages: Vec<&str> = body.fields.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+                messages,
+                vec![
+                        "Password must be at least 12 characters",
+                        "Password must contain at least one special character",
+                ]
+        );
+
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "Sunflower12!".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_413_if_body_is_too_large() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                max_request_body_bytes: 1024,
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let oversized = serde_json::json!({
+                "email": get_random_email(),
+                "password": "ValidPassword123",
+                "requires2FA": false,
+                "padding": "a".repeat(2048),
+        });
+        let res = app.post_signup(&oversized).await;
+        assert_eq!(res.status().as_u16(), 413);
+
+        // A normal signup still fits
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "ValidPassword123".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
 #[tokio::test]
 async fn should_return_409_if_email_already_exists() -> TestResult<()> {
         // Call the signup route twice. The second request should fail with a 409 HTTP status code
@@ -172,3 +366,501 @@ async fn should_return_409_if_email_already_exists() -> TestResult<()> {
 
         Ok(())
 }
+
+#[tokio::test]
+async fn should_return_201_and_notify_the_owner_when_conflicts_are_hidden() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                hide_signup_conflicts: true,
+                ..AppConfig::default()
+        })
+        .await?;
+        let email = get_random_email();
+        let first = app
+                .post_signup(&SignupPayload::new(
+                        email.clone(),
+                        "ValidPassword123".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(first.status().as_u16(), 201);
+        let first = first.json::<SignupResponse>().await?;
+
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        email.clone(),
+                        "OtherPassword456".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+        assert_eq!(res.json::<SignupResponse>().await?, first);
+        assert_eq!(app.outbox.wait_for(&email, SIGNUP_CONFLICT_SUBJECT).await.len(), 1);
+
+        // The existing account keeps its password
+        let login = app
+                .post_login(&LoginPayload::new(email.clone(), "ValidPassword123".to_owned()))
+                .await;
+        assert_eq!(login.status().as_u16(), 200);
+        let login = app.post_login(&LoginPayload::new(email, "OtherPassword456".to_owned())).await;
+        assert_eq!(login.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_hide_conflicts_with_case_variant_and_soft_deleted_emails() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                hide_signup_conflicts: true,
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let email = get_random_email();
+        assert_eq!(signup_status(&app, email.clone()).await, 201);
+        assert_eq!(signup_status(&app, email.to_uppercase()).await, 201);
+        assert_eq!(app.outbox.wait_for(&email, SIGNUP_CONFLICT_SUBJECT).await.len(), 1);
+
+        // Only the insert itself finds a soft-deleted account's email taken
+        let deleted = get_random_email();
+        assert_eq!(signup_status(&app, deleted.clone()).await, 201);
+        let parsed = Email::parse(&deleted).expect("valid email");
+        app.user_store.write().await.soft_delete(&parsed).await.expect("Failed to soft-delete");
+        assert_eq!(signup_status(&app, deleted.clone()).await, 201);
+        assert_eq!(app.outbox.wait_for(&deleted, SIGNUP_CONFLICT_SUBJECT).await.len(), 1);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+async fn signup_status(app: &TestApp, email: String) -> u16 {
+        let payload = SignupPayload::new(email, "ValidPassword123".to_owned(), false);
+        app.post_signup(&payload).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn should_return_409_if_username_already_taken() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let res = app
+                .post_signup(
+                        &SignupPayload::new(
+                                get_random_email(),
+                                "ValidPassword123".to_owned(),
+                                false,
+                        )
+                        .with_username("taken_name"),
+                )
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // Usernames are compared case-insensitively
+        let res = app
+                .post_signup(
+                        &SignupPayload::new(
+                                get_random_email(),
+                                "ValidPassword123".to_owned(),
+                                false,
+                        )
+                        .with_username("Taken_Name"),
+                )
+                .await;
+        assert_eq!(res.status().as_u16(), 409);
+
+        let res = app
+                .post_signup(
+                        &SignupPayload::new(
+                                get_random_email(),
+                                "ValidPassword123".to_owned(),
+                                false,
+                        )
+                        .with_username("not a username"),
+                )
+                .await;
+        assert_eq!(res.status().as_u16(), 400);
+        let body = res.json::<ValidationErrorResponse>().await?;
+        assert_eq!(body.fields[0].field, "username");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_display_name_from_me_after_signup() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+
+        let signup = SignupPayload::new(email.clone(), "ValidPassword123".to_owned(), false)
+                .with_display_name("  Zoë O'Brien ");
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        let login = LoginPayload::new(email.clone(), "ValidPassword123".to_owned());
+        assert_eq!(app.post_login(&login).await.status().as_u16(), 200);
+
+        let res = app.get_me().await?;
+        assert_eq!(res.status().as_u16(), 200);
+        let me = res.json::<PublicUser>().await?;
+        assert_eq!(me.email, email);
+        assert_eq!(me.display_name.as_deref(), Some("Zoë O'Brien"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_display_name_is_invalid() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        for display_name in ["   ".to_owned(), "x".repeat(65), "Bob\nSmith".to_owned()] {
+                let signup = SignupPayload::new(
+                        get_random_email(),
+                        "ValidPassword123".to_owned(),
+                        false,
+                )
+                .with_display_name(display_name.clone());
+                let res = app.post_signup(&signup).await;
+                assert_eq!(res.status().as_u16(), 400, "{display_name:?}");
+                let body = res.json::<ValidationErrorResponse>().await?;
+                assert_eq!(body.fields[0].field, "display_name");
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_201_on_retry_with_same_idempotency_key() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let signup_payload = serde_json::json!({
+                "email": get_random_email(),
+                "password": "ValidPassword123",
+                "requires2FA": false
+        });
+        let key = uuid::Uuid::new_v4().to_string();
+
+        let first = app.post_signup_with_idempotency_key(&signup_payload, &key).await;
+        assert_eq!(first.status().as_u16(), 201);
+        let first_body = first.json::<SignupResponse>().await?;
+
+        // The client retries because it never saw the first response
+        let retry = app.post_signup_with_idempotency_key(&signup_payload, &key).await;
+        assert_eq!(retry.status().as_u16(), 201);
+        assert_eq!(retry.json::<SignupResponse>().await?, first_body);
+
+        // A new key is a new request, so the duplicate email is reported
+        let other_key = uuid::Uuid::new_v4().to_string();
+        let res = app.post_signup_with_idempotency_key(&signup_payload, &other_key).await;
+        assert_eq!(res.status().as_u16(), 409);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_idempotency_key_is_empty() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let signup_payload = serde_json::json!({
+                "email": get_random_email(),
+                "password": "ValidPassword123",
+                "requires2FA": false
+        });
+        let res = app.post_signup_with_idempotency_key(&signup_payload, "").await;
+        assert_eq!(res.status().as_u16(), 400);
+        assert_eq!(res.json::<ErrorResponse>().await?.error, "Invalid Idempotency-Key header");
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_422_if_a_required_field_is_blank() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        // Well-formed JSON with every field present, but nothing to validate in the email
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": "",
+                        "password": "ValidPassword123",
+                        "requires2FA": false
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 422);
+
+        let body = res.json::<ValidationErrorResponse>().await?;
+        assert_eq!(body.error, "Unprocessable payload");
+        assert_eq!(
+                body.fields,
+                [FieldError {
+                        field: "email".to_owned(),
+                        message: "email is required".to_owned(),
+                }]
+        );
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_check_email_domain_mx_records_when_enabled() -> TestResult<()> {
+        let app = TestApp::builder()
+                .mx_resolver(Arc::new(MockMxResolver::new(["example.com"])))
+                .build()
+                .await?;
+
+        // The domain is matched case-insensitively
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": format!("{}@Example.COM", uuid::Uuid::new_v4()),
+                        "password": "ValidPassword123",
+                        "requires2FA": false
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": "user@no-mx.example.org",
+                        "password": "ValidPassword123",
+                        "requires2FA": false
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 400);
+        let body = res.json::<ValidationErrorResponse>().await?;
+        let failing_fields: Vec<(&str, &str)> =
+                body.fields.iter().map(|e| (e.field.as_str(), e.message.as_str())).collect();
+        assert_eq!(failing_fields, [("email", "Email domain cannot receive mail")]);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_201_if_captcha_passes() -> TestResult<()> {
+        let app = TestApp::builder()
+                .captcha_verifier(Arc::new(MockCaptchaVerifier::new("human")))
+                .build()
+                .await?;
+
+        let signup_payload = serde_json::json!({
+                "email": get_random_email(),
+                "password": "ValidPassword123",
+                "requires2FA": false,
+                "captchaToken": "human"
+        });
+        let res = app.post_signup(&signup_payload).await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_400_if_captcha_fails_or_is_missing() -> TestResult<()> {
+        let app = TestApp::builder()
+                .captcha_verifier(Arc::new(MockCaptchaVerifier::new("human")))
+                .build()
+                .await?;
+        let email = get_random_email();
+
+        let test_cases = [
+                serde_json::json!({
+                        "email": email,
+                        "password": "ValidPassword123",
+                        "requires2FA": false,
+                        "captchaToken": "bot"
+                }),
+                serde_json::json!({
+                        "email": email,
+                        "password": "ValidPassword123",
+                        "requires2FA": false
+                }),
+        ];
+
+        for test_case in test_cases {
+                let res = app.post_signup(&test_case).await;
+                assert_eq!(res.status().as_u16(), 400, "Failed for input: {:?}", test_case);
+                assert_eq!(res.json::<ErrorResponse>().await?.error, "CAPTCHA verification failed");
+        }
+
+        // No account was created by the rejected attempts
+        let res = app
+                .post_signup(&serde_json::json!({
+                        "email": email,
+                        "password": "ValidPassword123",
+                        "requires2FA": false,
+                        "captchaToken": "human"
+                }))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+fn auto_login_config() -> AppConfig {
+        AppConfig {
+                auto_login_after_signup: true,
+                ..AppConfig::default()
+        }
+}
+
+#[tokio::test]
+async fn should_set_an_auth_cookie_when_auto_login_is_enabled() -> TestResult<()> {
+        let app = TestApp::with_config(auto_login_config()).await?;
+
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "ValidPassword123".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+
+        let token = res
+                .cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie should be set")
+                .value()
+                .to_owned();
+        let res = app.post_verify_token(&VerifyTokenPayload::new(token)).await?;
+        assert_eq!(res.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_auto_login_a_2fa_signup() -> TestResult<()> {
+        let app = TestApp::with_config(auto_login_config()).await?;
+
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "ValidPassword123".to_owned(),
+                        true,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+        assert!(res.cookies().all(|cookie| cookie.name() != *JWT_COOKIE_NAME));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_auto_login_by_default() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let res = app
+                .post_signup(&SignupPayload::new(
+                        get_random_email(),
+                        "ValidPassword123".to_owned(),
+                        false,
+                ))
+                .await;
+        assert_eq!(res.status().as_u16(), 201);
+        assert!(res.cookies().all(|cookie| cookie.name() != *JWT_COOKIE_NAME));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_auto_login_when_conflicts_are_hidden() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                hide_signup_conflicts: true,
+                ..auto_login_config()
+        })
+        .await?;
+        let email = get_random_email();
+
+        // A new account and a repeat of it get the same answer, neither logged in
+        for password in ["ValidPassword123", "OtherPassword456"] {
+                let res = app
+                        .post_signup(&SignupPayload::new(email.clone(), password.to_owned(), false))
+                        .await;
+                assert_eq!(res.status().as_u16(), 201);
+                assert!(res.cookies().all(|cookie| cookie.name() != *JWT_COOKIE_NAME));
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
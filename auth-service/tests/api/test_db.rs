@@ -0,0 +1,47 @@
+use std::str::FromStr;
+
+use auth_service::utils::constants::DATABASE_URL;
+use sqlx::{postgres::PgConnectOptions, Connection, PgConnection};
+
+use crate::{helpers::TestDb, TestApp, TestResult};
+
+async fn database_exists(name: &str) -> TestResult<bool> {
+        let options = PgConnectOptions::from_str(&DATABASE_URL)?.database("postgres");
+        let mut connection = PgConnection::connect_with(&options).await?;
+        let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM pg_database WHERE datname = $1)",
+        )
+        .bind(name)
+        .fetch_one(&mut connection)
+        .await?;
+        Ok(exists)
+}
+
+#[tokio::test]
+async fn dropping_the_guard_drops_the_database() -> TestResult<()> {
+        let test_db = TestDb::create().await;
+        let name = test_db.name().to_owned();
+        assert!(database_exists(&name).await?);
+
+        // An open pool must not keep the database alive
+        let pool = test_db.pool().await;
+        drop(test_db);
+
+        assert!(!database_exists(&name).await?);
+        drop(pool);
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn test_app_drops_its_database_without_clean_up() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let name = app.test_db.as_ref().expect("TestApp::new creates a database").name().to_owned();
+        assert!(database_exists(&name).await?);
+
+        drop(app);
+
+        assert!(!database_exists(&name).await?);
+
+        Ok(())
+}
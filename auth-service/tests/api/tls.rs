@@ -0,0 +1,61 @@
+use std::{path::PathBuf, sync::Arc};
+
+use auth_service::{
+        services::data_stores::{
+                HashmapAuditLogStore, HashmapFailedLoginStore, HashmapIdempotencyStore,
+                HashmapMagicLinkStore, HashmapPendingEmailChangeStore, HashmapRateLimitStore,
+                HashmapSessionStore, HashmapTwoFACodeStore, HashmapUserStore,
+                HashsetBannedTokenStore, MockEmailClient,
+        },
+        utils::config::TlsConfig,
+        AppStateBuilder, Application,
+};
+use tokio::sync::RwLock;
+
+use crate::TestResult;
+
+fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[tokio::test]
+async fn should_serve_requests_over_https() -> TestResult<()> {
+        // In-memory stores: this only exercises the transport, so no database is needed
+        let app_state = AppStateBuilder::new()
+                .user_store(Arc::new(RwLock::new(Box::new(HashmapUserStore::new()))))
+                .banned_token_store(Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::new()))))
+                .two_fa_code_store(Arc::new(RwLock::new(Box::new(HashmapTwoFACodeStore::new()))))
+                .idempotency_store(Arc::new(RwLock::new(Box::new(HashmapIdempotencyStore::new()))))
+                .session_store(Arc::new(RwLock::new(Box::new(HashmapSessionStore::new()))))
+                .failed_login_store(Arc::new(RwLock::new(Box::new(HashmapFailedLoginStore::new()))))
+                .audit_log_store(Arc::new(RwLock::new(Box::new(HashmapAuditLogStore::new()))))
+                .pending_email_change_store(Arc::new(RwLock::new(Box::new(
+                        HashmapPendingEmailChangeStore::new(),
+                ))))
+                .magic_link_store(Arc::new(RwLock::new(Box::new(HashmapMagicLinkStore::new()))))
+                .rate_limit_store(Arc::new(RwLock::new(Box::new(HashmapRateLimitStore::new()))))
+                .email_client(Arc::new(MockEmailClient))
+                .build();
+
+        // Self-signed certificate for `localhost`, trusted explicitly by the client below
+        let tls = TlsConfig {
+                cert_path: fixture("tls_cert.pem"),
+                key_path: fixture("tls_key.pem"),
+        };
+
+        let app = Application::build_tls(app_state, "127.0.0.1:0", &tls).await?;
+        let port = app.address.rsplit(':').next().expect("address should include a port");
+        let address = format!("https://localhost:{}", port);
+
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::spawn(app.run());
+
+        let cert = reqwest::Certificate::from_pem(&std::fs::read(&tls.cert_path)?)?;
+        let client = reqwest::Client::builder().add_root_certificate(cert).build()?;
+
+        let response = client.get(format!("{}/", address)).send().await?;
+
+        assert_eq!(response.status().as_u16(), 200);
+
+        Ok(())
+}
@@ -0,0 +1,126 @@
+use auth_service::{
+        routes::{LoginHistoryResponse, LoginPayload, SignupPayload},
+        utils::{
+                config::{AppConfig, TrustedProxyHeader},
+                constants::JWT_COOKIE_NAME,
+        },
+};
+
+use crate::{get_random_email, TestApp, TestResult};
+
+const PASSWORD: &str = "ValidPassword123";
+
+/// Sign up, log in with `headers` set, and return the IP recorded for that login
+async fn recorded_login_ip(app: &TestApp, headers: &[(&str, &str)]) -> TestResult<Option<String>> {
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), PASSWORD.to_owned(), false)).await;
+
+        let mut request = reqwest::Client::new()
+                .post(format!("{}/login", app.address))
+                .json(&LoginPayload::new(email, PASSWORD.to_owned()));
+        for (name, value) in headers {
+                request = request.header(*name, *value);
+        }
+        let response = request.send().await?;
+        assert_eq!(response.status().as_u16(), 200);
+        let token = response
+                .cookies()
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+                .expect("JWT cookie should be set")
+                .value()
+                .to_owned();
+
+        let history =
+                app.get_login_history(&token, "").await?.json::<LoginHistoryResponse>().await?;
+        Ok(history.events.into_iter().next().and_then(|event| event.ip))
+}
+
+async fn spawn_behind(header: TrustedProxyHeader) -> TestResult<TestApp> {
+        TestApp::with_config(AppConfig {
+                trusted_proxy_header: Some(header),
+                ..AppConfig::default()
+        })
+        .await
+}
+
+#[tokio::test]
+async fn should_record_the_hop_added_by_a_trusted_proxy() -> TestResult<()> {
+        let app = spawn_behind(TrustedProxyHeader::XForwardedFor).await?;
+
+        // The first hop came from the client and is ignored
+        let ip = recorded_login_ip(&app, &[("X-Forwarded-For", "198.51.100.1, 203.0.113.7")])
+                .await?;
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+
+        // The proxy only vouches for X-Forwarded-For
+        let ip = recorded_login_ip(&app, &[("X-Real-IP", "203.0.113.8")]).await?;
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_ignore_a_forged_forwarded_for_behind_a_real_ip_proxy() -> TestResult<()> {
+        let app = spawn_behind(TrustedProxyHeader::XRealIp).await?;
+
+        let ip = recorded_login_ip(
+                &app,
+                &[("X-Forwarded-For", "198.51.100.1"), ("X-Real-IP", "203.0.113.8")],
+        )
+        .await?;
+        assert_eq!(ip.as_deref(), Some("203.0.113.8"));
+
+        // A proxy that did not set the header leaves only the forged one
+        let ip = recorded_login_ip(&app, &[("X-Forwarded-For", "198.51.100.1")]).await?;
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_fall_back_to_the_peer_for_a_malformed_forwarded_ip() -> TestResult<()> {
+        let app = spawn_behind(TrustedProxyHeader::XForwardedFor).await?;
+
+        let ip = recorded_login_ip(&app, &[("X-Forwarded-For", "203.0.113.7, not-an-ip")]).await?;
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_ignore_forwarded_headers_without_a_trusted_proxy() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let ip = recorded_login_ip(
+                &app,
+                &[("X-Forwarded-For", "203.0.113.7"), ("X-Real-IP", "203.0.113.8")],
+        )
+        .await?;
+        assert_eq!(ip.as_deref(), Some("127.0.0.1"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -1,7 +1,11 @@
 use auth_service::{
-        domain::{Email, ErrorResponse},
-        routes::TwoFactorAuthResponse,
-        utils::constants::JWT_COOKIE_NAME,
+        domain::{Email, ErrorResponse, FieldError, LoginAttemptId, ValidationErrorResponse},
+        routes::{RegularAuthResponse, TwoFactorAuthResponse, Verify2FACheckResponse},
+        utils::{
+                auth::validate_challenge_token,
+                config::AppConfig,
+                constants::{JWT_COOKIE_NAME, TOKEN_TTL_SECONDS},
+        },
 };
 
 use crate::{get_random_email, TestApp, TestResult};
@@ -19,6 +23,15 @@ async fn signup_and_login_with_2fa(
         let signup_response = app.post_signup(&signup_payload).await;
         assert_eq!(signup_response.status().as_u16(), 201, "Signup should succeed");
 
+        login_with_2fa(app, email, password).await
+}
+
+/// Log in to an account with 2FA, returning the challenge token and the code sent for it
+async fn login_with_2fa(
+        app: &TestApp,
+        email: &str,
+        password: &str,
+) -> TestResult<(String, String)> {
         let login_payload = serde_json::json!({
                 "email": email,
                 "password": password
@@ -31,16 +44,30 @@ async fn signup_and_login_with_2fa(
                 .await
                 .expect("Could not deserialize response body to TwoFactorAuthResponse");
 
+        let code = pending_code(app, email, &two_fa_response.challenge_token).await;
+
+        Ok((two_fa_response.challenge_token, code))
+}
+
+/// The code stored for the login attempt named by a challenge token
+async fn pending_code(app: &TestApp, email: &str, challenge_token: &str) -> String {
         let parsed_email = Email::parse(email).expect("Email should be valid in test setup");
-        let (_, code) = app
+        let login_attempt_id = attempt_id_of(app, challenge_token);
+        let code = app
                 .two_fa_code_store
                 .read()
                 .await
-                .get_code(&parsed_email)
+                .get_code(&parsed_email, &login_attempt_id)
                 .await
                 .expect("2FA code should be present in store after login");
 
-        Ok((two_fa_response.login_attempt_id, code.as_ref().to_owned()))
+        code.as_ref().to_owned()
+}
+
+fn attempt_id_of(app: &TestApp, challenge_token: &str) -> LoginAttemptId {
+        let claims = validate_challenge_token(challenge_token, &app.key_ring.load())
+                .expect("Challenge token should be valid");
+        LoginAttemptId::parse(claims.login_attempt_id).expect("Login attempt ID should be valid")
 }
 
 #[tokio::test]
@@ -50,11 +77,11 @@ async fn should_return_200_if_correct_code() -> TestResult<()> {
 
         let email = get_random_email();
         let password = "ValidPassword123";
-        let (login_attempt_id, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let (challenge_token, code) = signup_and_login_with_2fa(&app, &email, password).await?;
 
         let payload = serde_json::json!({
                 "email": email,
-                "loginAttemptId": login_attempt_id,
+                "challengeToken": challenge_token,
                 "code": code
         });
         let response = app.post_verify_2fa(&payload).await?;
@@ -63,10 +90,267 @@ async fn should_return_200_if_correct_code() -> TestResult<()> {
 
         let auth_cookie = response
                 .cookies()
-                .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
                 .expect("JWT cookie should be set after successful 2FA verification");
         assert!(!auth_cookie.value().is_empty(), "JWT cookie value should not be empty");
 
+        let body = response
+                .json::<RegularAuthResponse>()
+                .await
+                .expect("Could not deserialize response body to RegularAuthResponse");
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = body.expires_at as i64;
+        assert!(expires_at > now, "expires_at should be in the future");
+        assert!(
+                (expires_at - (now + TOKEN_TTL_SECONDS)).abs() <= 5,
+                "expires_at should be roughly now + TOKEN_TTL_SECONDS"
+        );
+        assert_eq!(body.email, email);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+fn wrong_code(code: &str) -> &'static str {
+        if code == "000000" {
+                "111111"
+        } else {
+                "000000"
+        }
+}
+
+#[tokio::test]
+async fn should_precheck_a_valid_code_without_consuming_it() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        let response = app.post_verify_2fa_check(&payload).await?;
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.cookies().all(|cookie| cookie.name() != *JWT_COOKIE_NAME));
+        assert!(response.json::<Verify2FACheckResponse>().await?.valid);
+
+        // The real verification still accepts the code
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_precheck_an_invalid_code_as_not_valid() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": wrong_code(&code)
+        });
+        let response = app.post_verify_2fa_check(&payload).await?;
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(!response.json::<Verify2FACheckResponse>().await?.valid);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_count_prechecks_toward_the_attempt_limit() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                max_2fa_attempts: Some(2),
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        let wrong_payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": wrong_code(&code)
+        });
+        for _ in 0..2 {
+                let response = app.post_verify_2fa_check(&wrong_payload).await?;
+                assert_eq!(response.status().as_u16(), 200);
+        }
+
+        let response = app.post_verify_2fa_check(&wrong_payload).await?;
+        assert_eq!(response.status().as_u16(), 429);
+        assert_eq!(
+                response.json::<ErrorResponse>().await?.error,
+                "Too many 2FA attempts; log in again"
+        );
+
+        // The code was discarded, so even the right one no longer works
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_report_no_active_challenge_if_the_code_store_was_emptied() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        // As if a restart wiped the store between the 206 login and the verification
+        let parsed_email = Email::parse(&email).expect("Invalid Email");
+        let login_attempt_id = attempt_id_of(&app, &challenge_token);
+        app.two_fa_code_store
+                .write()
+                .await
+                .remove_code(&parsed_email, &login_attempt_id)
+                .await
+                .unwrap();
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        let response = app.post_verify_2fa(&payload).await?;
+        assert_eq!(response.status().as_u16(), 401);
+        let body = response.json::<ErrorResponse>().await?;
+        assert_eq!(body.code.as_deref(), Some("no_active_2fa_challenge"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_not_report_no_active_challenge_for_a_wrong_code() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": wrong_code(&code)
+        });
+        let response = app.post_verify_2fa(&payload).await?;
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(response.json::<ErrorResponse>().await?.code, None);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_challenge_token_is_tampered_with() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let (challenge_token, code) =
+                signup_and_login_with_2fa(&app, &email, "ValidPassword123").await?;
+
+        // Swap the first character of the signature
+        let (unsigned, signature) =
+                challenge_token.rsplit_once('.').expect("token should have a signature");
+        let replacement = if signature.starts_with('A') {
+                'B'
+        } else {
+                'A'
+        };
+        let tampered = format!("{unsigned}.{replacement}{}", &signature[1..]);
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": tampered,
+                "code": code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 401);
+        assert_eq!(app.post_verify_2fa_check(&payload).await?.status().as_u16(), 401);
+
+        // The untouched token still completes the login
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 200);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_challenge_token_names_another_account() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let password = "ValidPassword123";
+
+        let email = get_random_email();
+        let (_, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let (other_challenge_token, _) =
+                signup_and_login_with_2fa(&app, &get_random_email(), password).await?;
+
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": other_challenge_token,
+                "code": code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 401);
+
         // Mutable re-bind for teardown
         {
                 let mut app = app;
@@ -83,17 +367,12 @@ async fn should_return_400_if_invalid_input() -> TestResult<()> {
         let test_cases = [
                 serde_json::json!({
                         "email": "invalid-email",
-                        "loginAttemptId": "550e8400-e29b-41d4-a716-446655440000",
+                        "challengeToken": "550e8400-e29b-41d4-a716-446655440000",
                         "code": "123456"
                 }),
                 serde_json::json!({
                         "email": "valid@mail.com",
-                        "loginAttemptId": "not-a-valid-uuid",
-                        "code": "123456"
-                }),
-                serde_json::json!({
-                        "email": "valid@mail.com",
-                        "loginAttemptId": "550e8400-e29b-41d4-a716-446655440000",
+                        "challengeToken": "550e8400-e29b-41d4-a716-446655440000",
                         "code": "12ab56"
                 }),
         ];
@@ -127,11 +406,11 @@ async fn should_return_401_if_same_code_twice() -> TestResult<()> {
 
         let email = get_random_email();
         let password = "ValidPassword123";
-        let (login_attempt_id, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let (challenge_token, code) = signup_and_login_with_2fa(&app, &email, password).await?;
 
         let payload = serde_json::json!({
                 "email": email.clone(),
-                "loginAttemptId": login_attempt_id.clone(),
+                "challengeToken": challenge_token.clone(),
                 "code": code.clone()
         });
 
@@ -160,7 +439,7 @@ async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
 
         let email = get_random_email();
         let password = "ValidPassword123";
-        let (login_attempt_id, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let (challenge_token, code) = signup_and_login_with_2fa(&app, &email, password).await?;
 
         let wrong_code = if code == "000000" {
                 "111111".to_owned()
@@ -170,7 +449,7 @@ async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
 
         let payload = serde_json::json!({
                 "email": email,
-                "loginAttemptId": login_attempt_id,
+                "challengeToken": challenge_token,
                 "code": wrong_code
         });
         let response = app.post_verify_2fa(&payload).await?;
@@ -186,6 +465,108 @@ async fn should_return_401_if_incorrect_credentials() -> TestResult<()> {
         Ok(())
 }
 
+#[tokio::test]
+async fn should_verify_concurrent_logins_independently() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let email = get_random_email();
+        let password = "ValidPassword123";
+
+        // Two devices log in before either enters its code
+        let (first_token, first_code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let (second_token, second_code) = login_with_2fa(&app, &email, password).await?;
+
+        // Each code only answers its own challenge
+        if first_code != second_code {
+                let crossed = serde_json::json!({
+                        "email": email,
+                        "challengeToken": first_token,
+                        "code": second_code
+                });
+                assert_eq!(app.post_verify_2fa(&crossed).await?.status().as_u16(), 401);
+        }
+
+        // Verifying the later login leaves the earlier one pending
+        for (challenge_token, code) in [(second_token, second_code), (first_token, first_code)] {
+                let payload = serde_json::json!({
+                        "email": email,
+                        "challengeToken": challenge_token,
+                        "code": code
+                });
+                assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 200);
+        }
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
+#[tokio::test]
+async fn should_lock_the_account_after_too_many_wrong_codes_across_logins() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                max_2fa_attempts: Some(2),
+                max_2fa_failures: Some(3),
+                ..AppConfig::default()
+        })
+        .await?;
+
+        let email = get_random_email();
+        let password = "ValidPassword123";
+        let wrong = |challenge_token: &str, code: &str| {
+                serde_json::json!({
+                        "email": email,
+                        "challengeToken": challenge_token,
+                        "code": wrong_code(code)
+                })
+        };
+
+        // Two wrong codes on the first login stay under both limits
+        let (first_token, first_code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        for _ in 0..2 {
+                let response = app.post_verify_2fa(&wrong(&first_token, &first_code)).await?;
+                assert_eq!(response.status().as_u16(), 401);
+        }
+
+        // A fresh login has its own per-attempt cap, but the third wrong code overall locks
+        let (second_token, second_code) = login_with_2fa(&app, &email, password).await?;
+        let response = app.post_verify_2fa(&wrong(&second_token, &second_code)).await?;
+        assert_eq!(response.status().as_u16(), 403);
+        let body = response.json::<ErrorResponse>().await?;
+        assert_eq!(body.code.as_deref(), Some("account_locked"));
+        // Locked until the 15-minute window that started at the first wrong code ends
+        let retry_after = body.retry_after_seconds.expect("lock should say when it ends");
+        assert!(retry_after > 0 && retry_after <= 900, "unexpected retry_after: {retry_after}");
+
+        // The right code no longer completes the first login
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": first_token,
+                "code": first_code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 403);
+
+        // Nor does the right password start a new one
+        let login_payload = serde_json::json!({
+                "email": email,
+                "password": password
+        });
+        let response = app.post_login(&login_payload).await;
+        assert_eq!(response.status().as_u16(), 403);
+        assert_eq!(response.json::<ErrorResponse>().await?.code.as_deref(), Some("account_locked"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
 #[tokio::test]
 async fn should_return_401_if_old_code() -> TestResult<()> {
         // Call login twice. Then, attempt to call verify-fa with the 2FA code from the first login requet. This should fail.
@@ -194,12 +575,12 @@ async fn should_return_401_if_old_code() -> TestResult<()> {
         let email = get_random_email();
         let password = "ValidPassword123";
 
-        let (old_login_attempt_id, old_code) =
+        let (old_challenge_token, old_code) =
                 signup_and_login_with_2fa(&app, &email, password).await?;
 
         let first_verify_payload = serde_json::json!({
                 "email": email.clone(),
-                "loginAttemptId": old_login_attempt_id.clone(),
+                "challengeToken": old_challenge_token.clone(),
                 "code": old_code.clone()
         });
         let first_verify_response = app.post_verify_2fa(&first_verify_payload).await?;
@@ -223,7 +604,7 @@ async fn should_return_401_if_old_code() -> TestResult<()> {
 
         let old_verify_payload = serde_json::json!({
                 "email": email,
-                "loginAttemptId": old_login_attempt_id,
+                "challengeToken": old_challenge_token,
                 "code": old_code
         });
         let old_code_response = app.post_verify_2fa(&old_verify_payload).await?;
@@ -239,6 +620,49 @@ async fn should_return_401_if_old_code() -> TestResult<()> {
         Ok(())
 }
 
+#[tokio::test]
+async fn should_return_401_once_the_login_attempt_expires() -> TestResult<()> {
+        let app = TestApp::with_config(AppConfig {
+                login_attempt_ttl_seconds: 1,
+                ..AppConfig::default()
+        })
+        .await?;
+        let password = "ValidPassword123";
+
+        // Verified within the TTL
+        let email = get_random_email();
+        let (challenge_token, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        assert_eq!(app.post_verify_2fa(&payload).await?.status().as_u16(), 200);
+
+        // Correct attempt ID and code, but verified after the TTL
+        let email = get_random_email();
+        let (challenge_token, code) = signup_and_login_with_2fa(&app, &email, password).await?;
+        tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+        let payload = serde_json::json!({
+                "email": email,
+                "challengeToken": challenge_token,
+                "code": code
+        });
+        let response = app.post_verify_2fa(&payload).await?;
+        assert_eq!(response.status().as_u16(), 401);
+        assert!(response.cookies().all(|cookie| cookie.name() != *JWT_COOKIE_NAME));
+        let error = response.json::<ErrorResponse>().await?;
+        assert_eq!(error.code.as_deref(), Some("2fa_challenge_expired"));
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
+
 #[tokio::test]
 async fn should_return_422_if_malformed_input() -> TestResult<()> {
         let app = TestApp::new().await?;
@@ -249,11 +673,11 @@ async fn should_return_422_if_malformed_input() -> TestResult<()> {
                 }),
                 serde_json::json!({
                         "email": "valid@mail.com",
-                        "loginAttemptId": "550e8400-e29b-41d4-a716-446655440000"
+                        "challengeToken": "550e8400-e29b-41d4-a716-446655440000"
                 }),
                 serde_json::json!({
                         "email": 123,
-                        "loginAttemptId": 123,
+                        "challengeToken": 123,
                         "code": 123
                 }),
         ];
@@ -276,3 +700,29 @@ async fn should_return_422_if_malformed_input() -> TestResult<()> {
 
         Ok(())
 }
+
+#[tokio::test]
+async fn should_return_422_listing_blank_fields() -> TestResult<()> {
+        let app = TestApp::new().await?;
+
+        let payload = serde_json::json!({
+                "email": "valid@mail.com",
+                "challengeToken": " ",
+                "code": ""
+        });
+        let response = app.post_verify_2fa(&payload).await?;
+        assert_eq!(response.status().as_u16(), 422);
+
+        let body = response.json::<ValidationErrorResponse>().await?;
+        assert_eq!(body.error, "Unprocessable payload");
+        let fields: Vec<&str> = body.fields.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["challengeToken", "code"]);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
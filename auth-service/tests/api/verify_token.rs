@@ -1,9 +1,13 @@
 use auth_service::{
-        routes::{LoginPayload, SignupPayload, VerifyTokenPayload},
-        utils::constants::JWT_COOKIE_NAME,
+        domain::{ErrorResponse, Role},
+        routes::{LoginPayload, SignupPayload, VerifyTokenPayload, VerifyTokenResponse},
+        utils::{
+                auth::{create_token, Claims},
+                constants::{JWT_AUDIENCE, JWT_COOKIE_NAME, JWT_ISSUER, TOKEN_TTL_SECONDS},
+        },
 };
 
-use crate::{TestApp, TestResult};
+use crate::{get_random_email, TestApp, TestResult};
 
 #[tokio::test]
 async fn should_return_200_valid_token() -> TestResult<()> {
@@ -16,14 +20,14 @@ async fn should_return_200_valid_token() -> TestResult<()> {
         let _ = app.post_signup(&signup).await;
 
         // Login to get a valid JWT token
-        let login = LoginPayload::new(email, password);
+        let login = LoginPayload::new(email.clone(), password);
         let response = app.post_login(&login).await;
         assert_eq!(response.status().as_u16(), 200, "Login should succeed");
 
         // Extract the JWT token from the cookie
         let auth_cookie = response
                 .cookies()
-                .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+                .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
                 .expect("JWT cookie should be present");
 
         let token = auth_cookie.value().to_string();
@@ -34,6 +38,14 @@ async fn should_return_200_valid_token() -> TestResult<()> {
 
         assert_eq!(response.status().as_u16(), 200, "Valid token should return 200");
 
+        let body = response
+                .json::<VerifyTokenResponse>()
+                .await
+                .expect("Could not deserialize response body to VerifyTokenResponse");
+        assert_eq!(body.email, email);
+        assert_eq!(body.role, Role::User);
+        assert!(body.expires_at as i64 > chrono::Utc::now().timestamp());
+
         // Mutable re-bind for teardown
         {
                 let mut app = app;
@@ -54,6 +66,11 @@ async fn should_return_401_if_invalid_token() -> TestResult<()> {
 
         assert_eq!(response.status().as_u16(), 401, "Invalid token should return 401");
 
+        // Only the error body is returned; no claims leak on the 401 path
+        let body = response.json::<serde_json::Value>().await?;
+        assert!(body.get("email").is_none());
+        assert!(serde_json::from_value::<ErrorResponse>(body).is_ok());
+
         // Mutable re-bind for teardown
         {
                 let mut app = app;
@@ -81,3 +98,38 @@ async fn should_return_422_if_malformed_input() -> TestResult<()> {
 
         Ok(())
 }
+
+#[tokio::test]
+async fn should_only_accept_tokens_for_this_issuer_and_audience() -> TestResult<()> {
+        let app = TestApp::new().await?;
+        let email = get_random_email();
+        app.post_signup(&SignupPayload::new(email.clone(), "ValidPassword123".to_owned(), false))
+                .await;
+
+        let claims = |iss: &str, aud: &str| Claims {
+                sub: email.clone(),
+                exp: (chrono::Utc::now().timestamp() + TOKEN_TTL_SECONDS) as usize,
+                jti: uuid::Uuid::new_v4().to_string(),
+                iss: iss.to_owned(),
+                aud: aud.to_owned(),
+        };
+
+        let token = create_token(&claims(&JWT_ISSUER, &JWT_AUDIENCE), &app.key_ring.load())
+                .expect("token");
+        let response = app.post_verify_token(&VerifyTokenPayload::new(token)).await?;
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Same secret and user, but minted for another service
+        let token = create_token(&claims(&JWT_ISSUER, "another-service"), &app.key_ring.load())
+                .expect("token");
+        let response = app.post_verify_token(&VerifyTokenPayload::new(token)).await?;
+        assert_eq!(response.status().as_u16(), 401);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
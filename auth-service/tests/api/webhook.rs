@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use auth_service::{
+        services::webhook_notifier::{WebhookEvent, WebhookNotifier, WebhookPayload},
+        utils::constants::WEBHOOK_SIGNATURE_HEADER,
+};
+use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+use crate::{get_random_email, SignupPayload, TestApp, TestResult};
+
+const WEBHOOK_SECRET: &str = "test-webhook-secret";
+
+/// Start a local HTTP endpoint that forwards every webhook delivery to the returned channel
+async fn spawn_webhook_receiver() -> TestResult<(Url, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>)>
+{
+        let (tx, rx) = mpsc::unbounded_channel();
+        let router = Router::new().route(
+                "/hooks",
+                post(move |headers: HeaderMap, body: Bytes| {
+                        let tx = tx.clone();
+                        async move {
+                                let _ = tx.send((headers, body));
+                        }
+                }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}/hooks", listener.local_addr()?))?;
+
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Ok((url, rx))
+}
+
+#[tokio::test]
+async fn signup_sends_signed_user_created_event() -> TestResult<()> {
+        let (url, mut deliveries) = spawn_webhook_receiver().await?;
+        let app = TestApp::builder()
+                .webhook_notifier(WebhookNotifier::new(url, WEBHOOK_SECRET)?)
+                .build()
+                .await?;
+
+        let email = get_random_email();
+        let signup = SignupPayload::new(email.clone(), "ValidPassword123".to_owned(), false);
+        assert_eq!(app.post_signup(&signup).await.status().as_u16(), 201);
+
+        // Delivery happens on a background task after the response is sent
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), deliveries.recv())
+                .await?
+                .expect("Webhook receiver closed");
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_SECRET.as_bytes())?;
+        mac.update(&body);
+        let expected_signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        assert_eq!(headers[WEBHOOK_SIGNATURE_HEADER].to_str()?, expected_signature);
+
+        let payload: WebhookPayload = serde_json::from_slice(&body)?;
+        assert_eq!(payload.event, WebhookEvent::UserCreated);
+        assert_eq!(payload.email, email);
+        assert!(payload.timestamp > 0);
+
+        // Mutable re-bind for teardown
+        {
+                let mut app = app;
+                app.clean_up().await;
+        }
+
+        Ok(())
+}
@@ -0,0 +1,75 @@
+// The auth cookie name is read from the environment once per process, so this test gets a
+// binary of its own instead of living in `tests/api` alongside tests using the default name.
+use std::sync::Arc;
+
+use auth_service::{
+        domain::{Email, HashedPassword, User, UserStore},
+        services::data_stores::{
+                HashmapAuditLogStore, HashmapFailedLoginStore, HashmapIdempotencyStore,
+                HashmapMagicLinkStore, HashmapPendingEmailChangeStore, HashmapRateLimitStore,
+                HashmapSessionStore, HashmapTwoFACodeStore, HashmapUserStore,
+                HashsetBannedTokenStore, MockEmailClient,
+        },
+        utils::constants::{env::JWT_COOKIE_NAME_ENV_VAR, JWT_COOKIE_NAME},
+        AppStateBuilder, Application,
+};
+use tokio::sync::RwLock;
+
+const CUSTOM_COOKIE_NAME: &str = "auth_service_session";
+
+#[tokio::test]
+async fn should_set_and_clear_a_cookie_with_the_configured_name() {
+        std::env::set_var(JWT_COOKIE_NAME_ENV_VAR, CUSTOM_COOKIE_NAME);
+        assert_eq!(*JWT_COOKIE_NAME, CUSTOM_COOKIE_NAME);
+
+        let mut user_store = HashmapUserStore::new();
+        let email = Email::parse("cookie@example.com").unwrap();
+        let password = HashedPassword::parse("ValidPassword123").await.unwrap();
+        user_store.add_user(User::new(email, password, false)).await.unwrap();
+
+        let app_state = AppStateBuilder::new()
+                .user_store(Arc::new(RwLock::new(Box::new(user_store))))
+                .banned_token_store(Arc::new(RwLock::new(Box::new(HashsetBannedTokenStore::new()))))
+                .two_fa_code_store(Arc::new(RwLock::new(Box::new(HashmapTwoFACodeStore::new()))))
+                .idempotency_store(Arc::new(RwLock::new(Box::new(HashmapIdempotencyStore::new()))))
+                .session_store(Arc::new(RwLock::new(Box::new(HashmapSessionStore::new()))))
+                .failed_login_store(Arc::new(RwLock::new(Box::new(HashmapFailedLoginStore::new()))))
+                .audit_log_store(Arc::new(RwLock::new(Box::new(HashmapAuditLogStore::new()))))
+                .pending_email_change_store(Arc::new(RwLock::new(Box::new(
+                        HashmapPendingEmailChangeStore::new(),
+                ))))
+                .magic_link_store(Arc::new(RwLock::new(Box::new(HashmapMagicLinkStore::new()))))
+                .rate_limit_store(Arc::new(RwLock::new(Box::new(HashmapRateLimitStore::new()))))
+                .email_client(Arc::new(MockEmailClient))
+                .build();
+        let app = Application::build(app_state, "127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", app.address);
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::spawn(app.run());
+
+        let http_client = reqwest::Client::builder().cookie_store(true).build().unwrap();
+
+        let login_response = http_client
+                .post(format!("{}/login", address))
+                .json(&serde_json::json!({
+                        "email": "cookie@example.com",
+                        "password": "ValidPassword123"
+                }))
+                .send()
+                .await
+                .unwrap();
+        assert_eq!(login_response.status().as_u16(), 200);
+        let login_cookies: Vec<_> = login_response.cookies().collect();
+        assert!(login_cookies
+                .iter()
+                .any(|cookie| cookie.name() == CUSTOM_COOKIE_NAME && !cookie.value().is_empty()));
+        assert!(login_cookies.iter().all(|cookie| cookie.name() != "jwt"));
+
+        let logout_response = http_client.post(format!("{}/logout", address)).send().await.unwrap();
+        assert_eq!(logout_response.status().as_u16(), 200);
+        let cleared = logout_response
+                .cookies()
+                .find(|cookie| cookie.name() == CUSTOM_COOKIE_NAME)
+                .expect("Logout should clear the custom-named cookie");
+        assert!(cleared.value().is_empty());
+}